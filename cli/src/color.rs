@@ -1,15 +1,81 @@
-//! Color output utilities respecting NO_COLOR environment variable.
+//! Color output utilities respecting NO_COLOR and FORCE_COLOR.
 //!
 //! When the NO_COLOR environment variable is present (regardless of value),
-//! all color formatting is disabled per https://no-color.org/
+//! all color formatting is disabled per https://no-color.org/. Otherwise
+//! FORCE_COLOR turns color on (or off when set to `0` or `false`), and without
+//! either, color is used only when stdout is a terminal. The `--color` and
+//! `--no-color` flags override all of these via `set_enabled`.
 
 use std::env;
-use std::sync::OnceLock;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicU8, Ordering};
 
-/// Returns true if color output is enabled (NO_COLOR is NOT set)
+/// Color on/off, detected lazily on first use and overridable afterwards.
+struct ColorSetting(AtomicU8);
+
+const UNSET: u8 = 0;
+const OFF: u8 = 1;
+const ON: u8 = 2;
+
+impl ColorSetting {
+    const fn new() -> Self {
+        Self(AtomicU8::new(UNSET))
+    }
+
+    fn get(&self, detect: impl FnOnce() -> bool) -> bool {
+        match self.0.load(Ordering::Relaxed) {
+            UNSET => {
+                let detected = if detect() { ON } else { OFF };
+                // A concurrent `set` wins over detection
+                match self
+                    .0
+                    .compare_exchange(UNSET, detected, Ordering::Relaxed, Ordering::Relaxed)
+                {
+                    Ok(_) => detected == ON,
+                    Err(current) => current == ON,
+                }
+            }
+            value => value == ON,
+        }
+    }
+
+    fn set(&self, enabled: bool) {
+        self.0
+            .store(if enabled { ON } else { OFF }, Ordering::Relaxed);
+    }
+}
+
+static COLORS_ENABLED: ColorSetting = ColorSetting::new();
+
+/// Returns true if color output is enabled
 pub fn is_enabled() -> bool {
-    static COLORS_ENABLED: OnceLock<bool> = OnceLock::new();
-    *COLORS_ENABLED.get_or_init(|| env::var("NO_COLOR").is_err())
+    COLORS_ENABLED.get(detect)
+}
+
+/// Turns color on or off for the rest of the process, replacing the detected
+/// setting even if something (such as a flag warning) was already colored.
+pub fn set_enabled(enabled: bool) {
+    COLORS_ENABLED.set(enabled);
+}
+
+/// Default color setting from the environment and whether stdout is a TTY
+pub fn detect() -> bool {
+    from_env(
+        env::var_os("NO_COLOR").is_some(),
+        env::var("FORCE_COLOR").ok().as_deref(),
+        io::stdout().is_terminal(),
+    )
+}
+
+fn from_env(no_color: bool, force_color: Option<&str>, is_tty: bool) -> bool {
+    if no_color {
+        return false;
+    }
+    match force_color {
+        Some("0" | "false") => false,
+        Some(_) => true,
+        None => is_tty,
+    }
 }
 
 /// Format text in red (errors)
@@ -39,6 +105,15 @@ pub fn yellow(text: &str) -> String {
     }
 }
 
+/// Format text in blue (diff hunk headers)
+pub fn blue(text: &str) -> String {
+    if is_enabled() {
+        format!("\x1b[34m{}\x1b[0m", text)
+    } else {
+        text.to_string()
+    }
+}
+
 /// Format text in cyan (info/progress)
 pub fn cyan(text: &str) -> String {
     if is_enabled() {
@@ -68,38 +143,29 @@ pub fn dim(text: &str) -> String {
 
 /// Red X error indicator
 pub fn error_indicator() -> &'static str {
-    static INDICATOR: OnceLock<String> = OnceLock::new();
-    INDICATOR.get_or_init(|| {
-        if is_enabled() {
-            "\x1b[31m✗\x1b[0m".to_string()
-        } else {
-            "✗".to_string()
-        }
-    })
+    if is_enabled() {
+        "\x1b[31m✗\x1b[0m"
+    } else {
+        "✗"
+    }
 }
 
 /// Green checkmark success indicator
 pub fn success_indicator() -> &'static str {
-    static INDICATOR: OnceLock<String> = OnceLock::new();
-    INDICATOR.get_or_init(|| {
-        if is_enabled() {
-            "\x1b[32m✓\x1b[0m".to_string()
-        } else {
-            "✓".to_string()
-        }
-    })
+    if is_enabled() {
+        "\x1b[32m✓\x1b[0m"
+    } else {
+        "✓"
+    }
 }
 
 /// Yellow warning indicator
 pub fn warning_indicator() -> &'static str {
-    static INDICATOR: OnceLock<String> = OnceLock::new();
-    INDICATOR.get_or_init(|| {
-        if is_enabled() {
-            "\x1b[33m⚠\x1b[0m".to_string()
-        } else {
-            "⚠".to_string()
-        }
-    })
+    if is_enabled() {
+        "\x1b[33m⚠\x1b[0m"
+    } else {
+        "⚠"
+    }
 }
 
 /// Get console log color prefix by level
@@ -148,6 +214,43 @@ mod tests {
         assert!(console_level_prefix("log").contains("log"));
     }
 
+    #[test]
+    fn test_from_env_follows_tty_by_default() {
+        assert!(from_env(false, None, true));
+        assert!(!from_env(false, None, false));
+    }
+
+    #[test]
+    fn test_from_env_no_color_disables() {
+        assert!(!from_env(true, None, true));
+        assert!(!from_env(true, Some("1"), true));
+    }
+
+    #[test]
+    fn test_from_env_force_color() {
+        assert!(from_env(false, Some("1"), false));
+        assert!(from_env(false, Some(""), false));
+        assert!(!from_env(false, Some("0"), true));
+        assert!(!from_env(false, Some("false"), true));
+    }
+
+    #[test]
+    fn test_color_setting_detects_once() {
+        let setting = ColorSetting::new();
+        assert!(setting.get(|| true));
+        assert!(setting.get(|| false));
+    }
+
+    #[test]
+    fn test_color_setting_set_overrides_detected_value() {
+        let setting = ColorSetting::new();
+        assert!(setting.get(|| true));
+        setting.set(false);
+        assert!(!setting.get(|| true));
+        setting.set(true);
+        assert!(setting.get(|| false));
+    }
+
     #[test]
     fn test_indicators_contain_symbols() {
         // Regardless of color state, symbols should be present
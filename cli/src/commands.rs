@@ -1,13 +1,21 @@
 use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Serialize;
 use serde_json::{json, Value};
+use std::fs;
 use std::io::{self, BufRead};
 
 use crate::color;
-use crate::flags::Flags;
+use crate::flags::{Flags, WAIT_UNTIL_STATES};
 use crate::validation::{is_valid_session_name, session_name_error};
 
-/// Error type for command parsing with contextual information
-#[derive(Debug)]
+/// Error type for command parsing with contextual information. Serializes as
+/// `{"type": "<snake_case variant>", ...fields}` for `--json` output.
+#[derive(Debug, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
 pub enum ParseError {
     /// Command does not exist
     UnknownCommand { command: String },
@@ -60,18 +68,48 @@ impl ParseError {
     }
 }
 
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.format())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Request ID sent with every command: `r` followed by 6 random bytes in hex.
+/// Timestamp-based IDs collided when commands were built back to back (batch
+/// mode), which mixed up daemon responses.
 pub fn gen_id() -> String {
-    format!(
-        "r{}",
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_micros()
-            % 1000000
-    )
+    let mut bytes = [0u8; 6];
+    getrandom::getrandom(&mut bytes).expect("failed to read OS random source");
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("r{}", hex)
 }
 
 pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError> {
+    let mut cmd = parse_command_action(args, flags)?;
+
+    // Global --timeout applies to every command unless the command set its own
+    if let Some(ref timeout_str) = flags.timeout {
+        let timeout = timeout_str
+            .parse::<u64>()
+            .ok()
+            .filter(|&ms| ms > 0)
+            .ok_or_else(|| ParseError::InvalidValue {
+                message: format!(
+                    "Invalid --timeout value: {}. Expected a positive number of milliseconds",
+                    timeout_str
+                ),
+                usage: "<command> [args...] --timeout <ms>",
+            })?;
+        if cmd.get("timeout").is_none() {
+            cmd["timeout"] = json!(timeout);
+        }
+    }
+    Ok(cmd)
+}
+
+fn parse_command_action(args: &[String], flags: &Flags) -> Result<Value, ParseError> {
     if args.is_empty() {
         return Err(ParseError::MissingArguments {
             context: "".to_string(),
@@ -109,8 +147,16 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
                 format!("https://{}", url)
             };
             let mut nav_cmd = json!({ "id": id, "action": "navigate", "url": url });
-            // If --headers flag is set, include headers (scoped to this origin)
-            if let Some(ref headers_json) = flags.headers {
+            // Include headers (scoped to this origin). Repeated --header
+            // flags replace the --headers JSON when both are given.
+            if !flags.header_pairs.is_empty() {
+                let headers: serde_json::Map<String, serde_json::Value> = flags
+                    .header_pairs
+                    .iter()
+                    .map(|(name, value)| (name.clone(), json!(value)))
+                    .collect();
+                nav_cmd["headers"] = json!(headers);
+            } else if let Some(ref headers_json) = flags.headers {
                 let headers = serde_json::from_str::<serde_json::Value>(headers_json)
                     .map_err(|_| ParseError::InvalidValue {
                         message: format!("Invalid JSON for --headers: {}", headers_json),
@@ -118,6 +164,12 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
                     })?;
                 nav_cmd["headers"] = headers;
             }
+            if let Some(ref state) = flags.default_wait_until {
+                nav_cmd["waitUntil"] = json!(state);
+            }
+            if let Some(ms) = flags.default_timeout {
+                nav_cmd["timeout"] = json!(ms);
+            }
             // Include iOS device info if specified (needed for auto-launch with existing daemon)
             if flags.provider.as_deref() == Some("ios") {
                 if let Some(ref device) = flags.device {
@@ -126,46 +178,88 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
             }
             Ok(nav_cmd)
         }
-        "back" => Ok(json!({ "id": id, "action": "back" })),
-        "forward" => Ok(json!({ "id": id, "action": "forward" })),
-        "reload" => Ok(json!({ "id": id, "action": "reload" })),
+        "back" | "forward" => {
+            let mut nav_cmd = json!({ "id": id, "action": cmd });
+            let usage = if cmd == "back" {
+                "back [--wait-until <load|domcontentloaded|networkidle>]"
+            } else {
+                "forward [--wait-until <load|domcontentloaded|networkidle>]"
+            };
+            if let Some(state) = parse_wait_until(&rest, usage)? {
+                nav_cmd["waitUntil"] = json!(state);
+            }
+            Ok(nav_cmd)
+        }
+        "reload" => {
+            let mut cmd = json!({ "id": id, "action": "reload" });
+            if rest.contains(&"--hard") {
+                cmd["hard"] = json!(true);
+            }
+            if let Some(state) = parse_wait_until(
+                &rest,
+                "reload [--hard] [--wait-until <load|domcontentloaded|networkidle>]",
+            )? {
+                cmd["waitUntil"] = json!(state);
+            }
+            Ok(cmd)
+        }
 
         // === Core Actions ===
         "click" => {
-            let new_tab = rest.iter().any(|arg| *arg == "--new-tab");
+            let (no_wait_after, rest) = take_bool_flag(&rest, "--no-wait-after");
+            let new_tab = rest.contains(&"--new-tab");
             let sel = rest
                 .iter()
                 .find(|arg| **arg != "--new-tab")
                 .ok_or_else(|| ParseError::MissingArguments {
                     context: "click".to_string(),
-                    usage: "click <selector> [--new-tab]",
+                    usage: "click <selector> [--new-tab] [--no-wait-after]",
                 })?;
+            let mut cmd = json!({ "id": id, "action": "click", "selector": sel });
             if new_tab {
-                Ok(json!({ "id": id, "action": "click", "selector": sel, "newTab": true }))
-            } else {
-                Ok(json!({ "id": id, "action": "click", "selector": sel }))
+                cmd["newTab"] = json!(true);
             }
+            if no_wait_after {
+                cmd["noWaitAfter"] = json!(true);
+            }
+            Ok(cmd)
         }
         "dblclick" => {
+            let (no_wait_after, rest) = take_bool_flag(&rest, "--no-wait-after");
             let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
                 context: "dblclick".to_string(),
-                usage: "dblclick <selector>",
+                usage: "dblclick <selector> [--no-wait-after]",
             })?;
-            Ok(json!({ "id": id, "action": "dblclick", "selector": sel }))
+            let mut cmd = json!({ "id": id, "action": "dblclick", "selector": sel });
+            if no_wait_after {
+                cmd["noWaitAfter"] = json!(true);
+            }
+            Ok(cmd)
         }
         "fill" => {
+            let (no_wait_after, rest) = take_bool_flag(&rest, "--no-wait-after");
             let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
                 context: "fill".to_string(),
-                usage: "fill <selector> <text>",
+                usage: "fill <selector> <text> [--no-wait-after]",
             })?;
-            Ok(json!({ "id": id, "action": "fill", "selector": sel, "value": rest[1..].join(" ") }))
+            let mut cmd = json!({ "id": id, "action": "fill", "selector": sel, "value": rest[1..].join(" ") });
+            if no_wait_after {
+                cmd["noWaitAfter"] = json!(true);
+            }
+            Ok(cmd)
         }
         "type" => {
+            let (no_wait_after, rest) = take_bool_flag(&rest, "--no-wait-after");
             let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
                 context: "type".to_string(),
-                usage: "type <selector> <text>",
+                usage: "type <selector> <text> [--no-wait-after]",
             })?;
-            Ok(json!({ "id": id, "action": "type", "selector": sel, "text": rest[1..].join(" ") }))
+            let mut cmd =
+                json!({ "id": id, "action": "type", "selector": sel, "text": rest[1..].join(" ") });
+            if no_wait_after {
+                cmd["noWaitAfter"] = json!(true);
+            }
+            Ok(cmd)
         }
         "hover" => {
             let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
@@ -195,22 +289,7 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
             })?;
             Ok(json!({ "id": id, "action": "uncheck", "selector": sel }))
         }
-        "select" => {
-            let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "select".to_string(),
-                usage: "select <selector> <value...>",
-            })?;
-            let _val = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "select".to_string(),
-                usage: "select <selector> <value...>",
-            })?;
-            let values = &rest[1..];
-            if values.len() == 1 {
-                Ok(json!({ "id": id, "action": "select", "selector": sel, "values": values[0] }))
-            } else {
-                Ok(json!({ "id": id, "action": "select", "selector": sel, "values": values }))
-            }
-        }
+        "select" => parse_select(&rest, &id),
         "drag" => {
             let src = rest.first().ok_or_else(|| ParseError::MissingArguments {
                 context: "drag".to_string(),
@@ -222,33 +301,11 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
             })?;
             Ok(json!({ "id": id, "action": "drag", "source": src, "target": tgt }))
         }
-        "upload" => {
-            let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "upload".to_string(),
-                usage: "upload <selector> <files...>",
-            })?;
-            Ok(json!({ "id": id, "action": "upload", "selector": sel, "files": &rest[1..] }))
-        }
-        "download" => {
-            let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "download".to_string(),
-                usage: "download <selector> <path>",
-            })?;
-            let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "download".to_string(),
-                usage: "download <selector> <path>",
-            })?;
-            Ok(json!({ "id": id, "action": "download", "selector": sel, "path": path }))
-        }
+        "upload" => parse_upload(&rest, &id),
+        "download" => parse_download(&rest, &id),
 
         // === Keyboard ===
-        "press" | "key" => {
-            let key = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "press".to_string(),
-                usage: "press <key>",
-            })?;
-            Ok(json!({ "id": id, "action": "press", "key": key }))
-        }
+        "press" | "key" => parse_press(&rest, &id),
         "keydown" => {
             let key = rest.first().ok_or_else(|| ParseError::MissingArguments {
                 context: "keydown".to_string(),
@@ -351,97 +408,45 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
 
         // === Wait ===
         "wait" => {
-            // Check for --url flag: wait --url "**/dashboard"
-            if let Some(idx) = rest.iter().position(|&s| s == "--url" || s == "-u") {
-                let url = rest
-                    .get(idx + 1)
-                    .ok_or_else(|| ParseError::MissingArguments {
-                        context: "wait --url".to_string(),
-                        usage: "wait --url <pattern>",
-                    })?;
-                return Ok(json!({ "id": id, "action": "waitforurl", "url": url }));
-            }
-
-            // Check for --load flag: wait --load networkidle
-            if let Some(idx) = rest.iter().position(|&s| s == "--load" || s == "-l") {
-                let state = rest
-                    .get(idx + 1)
-                    .ok_or_else(|| ParseError::MissingArguments {
-                        context: "wait --load".to_string(),
-                        usage: "wait --load <state>",
-                    })?;
-                return Ok(json!({ "id": id, "action": "waitforloadstate", "state": state }));
-            }
-
-            // Check for --fn flag: wait --fn "window.ready === true"
-            if let Some(idx) = rest.iter().position(|&s| s == "--fn" || s == "-f") {
-                let expr = rest
-                    .get(idx + 1)
-                    .ok_or_else(|| ParseError::MissingArguments {
-                        context: "wait --fn".to_string(),
-                        usage: "wait --fn <expression>",
-                    })?;
-                return Ok(json!({ "id": id, "action": "waitforfunction", "expression": expr }));
-            }
-
-            // Check for --text flag: wait --text "Welcome"
-            if let Some(idx) = rest.iter().position(|&s| s == "--text" || s == "-t") {
-                let text = rest
-                    .get(idx + 1)
-                    .ok_or_else(|| ParseError::MissingArguments {
-                        context: "wait --text".to_string(),
-                        usage: "wait --text <text>",
-                    })?;
-                // Use getByText locator to wait for text to appear
-                return Ok(
-                    json!({ "id": id, "action": "wait", "selector": format!("text={}", text) }),
-                );
-            }
-
-            // Check for --download flag: wait --download [path] [--timeout ms]
-            if rest.iter().any(|&s| s == "--download" || s == "-d") {
-                let mut cmd = json!({ "id": id, "action": "waitfordownload" });
-                // Check for optional path (first non-flag argument after --download)
-                let download_idx = rest
-                    .iter()
-                    .position(|&s| s == "--download" || s == "-d")
-                    .unwrap();
-                if let Some(path) = rest.get(download_idx + 1) {
-                    if !path.starts_with("--") {
-                        cmd["path"] = json!(path);
-                    }
-                }
-                // Check for optional timeout
-                if let Some(idx) = rest.iter().position(|&s| s == "--timeout") {
-                    if let Some(timeout_str) = rest.get(idx + 1) {
-                        if let Ok(timeout) = timeout_str.parse::<u64>() {
-                            cmd["timeout"] = json!(timeout);
+            const USAGE: &str = "wait <condition> [--polling-interval <ms>]";
+            let polling_interval = match rest.iter().position(|&s| s == "--polling-interval") {
+                Some(idx) => {
+                    let value = rest
+                        .get(idx + 1)
+                        .ok_or_else(|| ParseError::MissingArguments {
+                            context: "wait --polling-interval".to_string(),
+                            usage: USAGE,
+                        })?;
+                    match value.parse::<u64>() {
+                        Ok(ms) if ms > 0 => Some(ms),
+                        _ => {
+                            return Err(ParseError::InvalidValue {
+                                message: format!(
+                                    "Invalid --polling-interval value: {}. Expected a positive number of milliseconds",
+                                    value
+                                ),
+                                usage: USAGE,
+                            })
                         }
                     }
                 }
-                return Ok(cmd);
-            }
-
-            // Default: selector or timeout
-            if let Some(arg) = rest.first() {
-                if let Ok(timeout) = arg.parse::<u64>() {
-                    Ok(json!({ "id": id, "action": "wait", "timeout": timeout }))
-                } else {
-                    Ok(json!({ "id": id, "action": "wait", "selector": arg }))
-                }
-            } else {
-                Err(ParseError::MissingArguments {
-                    context: "wait".to_string(),
-                    usage: "wait <selector|ms|--url|--load|--fn|--text>",
-                })
+                None => None,
+            };
+            let rest = positional_args(&rest, &["--polling-interval"]);
+            let mut cmd = parse_wait(&rest, &id)?;
+            if let Some(ms) = polling_interval {
+                cmd["pollingInterval"] = json!(ms);
             }
+            Ok(cmd)
         }
 
         // === Screenshot/PDF ===
         "screenshot" => {
-            // screenshot [selector] [path]
+            // screenshot [selector] [path] [--mask <sel>]...
             // selector: @ref or CSS selector
             // path: file path (contains / or . or ends with known extension)
+            let masks = parse_mask_flags(&rest, "screenshot [selector] [path] [--mask <sel>]...")?;
+            let rest = positional_args(&rest, &["--mask"]);
             let (selector, path) = match (rest.first(), rest.get(1)) {
                 (Some(first), Some(second)) => {
                     // Two args: first is selector, second is path
@@ -467,9 +472,25 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
                 }
                 _ => (None, None),
             };
-            Ok(
-                json!({ "id": id, "action": "screenshot", "path": path, "selector": selector, "fullPage": flags.full, "annotate": flags.annotate }),
-            )
+            let mut cmd = json!({ "id": id, "action": "screenshot", "path": path, "selector": selector, "fullPage": flags.full, "annotate": flags.annotate });
+            if !masks.is_empty() {
+                cmd["mask"] = json!(masks);
+            }
+            // Config defaults; a path's own image extension decides its format
+            let has_image_ext = path.is_some_and(|p| {
+                [".png", ".jpg", ".jpeg", ".webp"]
+                    .iter()
+                    .any(|ext| p.to_lowercase().ends_with(ext))
+            });
+            if let Some(ref format) = flags.default_screenshot_format {
+                if !has_image_ext {
+                    cmd["format"] = json!(format);
+                }
+            }
+            if let Some(ms) = flags.default_timeout {
+                cmd["timeout"] = json!(ms);
+            }
+            Ok(cmd)
         }
         "pdf" => {
             let path = rest.first().ok_or_else(|| ParseError::MissingArguments {
@@ -509,6 +530,31 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
                             i += 1;
                         }
                     }
+                    "--only-roles" => {
+                        const USAGE: &str = "snapshot --only-roles <role,...>";
+                        let list = rest
+                            .get(i + 1)
+                            .ok_or_else(|| ParseError::MissingArguments {
+                                context: "snapshot --only-roles".to_string(),
+                                usage: USAGE,
+                            })?;
+                        let roles: Vec<&str> = list
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|r| !r.is_empty())
+                            .collect();
+                        if roles.is_empty() {
+                            return Err(ParseError::InvalidValue {
+                                message: format!(
+                                    "--only-roles needs at least one role, got: {:?}",
+                                    list
+                                ),
+                                usage: USAGE,
+                            });
+                        }
+                        obj.insert("onlyRoles".to_string(), json!(roles));
+                        i += 1;
+                    }
                     _ => {}
                 }
                 i += 1;
@@ -611,7 +657,13 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
         "get" => parse_get(&rest, &id),
 
         // === Is (state checks) ===
-        "is" => parse_is(&rest, &id),
+        "is" => parse_is(&rest, &id, flags),
+
+        // === Assert (test assertions) ===
+        "assert" => parse_assert(&rest, &id),
+
+        // === Validate (element existence) ===
+        "validate" => parse_validate(&rest, &id),
 
         // === Find (locators) ===
         "find" => parse_find(&rest, &id),
@@ -623,7 +675,7 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
         "set" => parse_set(&rest, &id),
 
         // === Network ===
-        "network" => parse_network(&rest, &id),
+        "network" => parse_network(&rest, &id, flags),
 
         // === Storage ===
         "storage" => parse_storage(&rest, &id),
@@ -739,69 +791,155 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
                     Ok(json!({ "id": id, "action": "cookies_set", "cookies": [cookie] }))
                 }
                 "clear" => Ok(json!({ "id": id, "action": "cookies_clear" })),
+                "import" => {
+                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                        context: "cookies import".to_string(),
+                        usage: "cookies import <file>",
+                    })?;
+                    let cookies = read_cookies_file(path)?;
+                    Ok(json!({ "id": id, "action": "cookies_set", "cookies": cookies }))
+                }
+                "export" => {
+                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                        context: "cookies export".to_string(),
+                        usage: "cookies export <file>",
+                    })?;
+                    Ok(json!({ "id": id, "action": "cookies_export", "path": path }))
+                }
+                "delete" => {
+                    const USAGE: &str = "cookies delete <name> [--domain <domain>] [--path <path>]";
+                    let name = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                        context: "cookies delete".to_string(),
+                        usage: USAGE,
+                    })?;
+                    let mut cmd = json!({ "id": id, "action": "cookies_delete", "name": name });
+                    for (flag, key) in [("--domain", "domain"), ("--path", "path")] {
+                        if let Some(i) = rest.iter().position(|&s| s == flag) {
+                            let value =
+                                rest.get(i + 1)
+                                    .ok_or_else(|| ParseError::MissingArguments {
+                                        context: format!("cookies delete {}", flag),
+                                        usage: USAGE,
+                                    })?;
+                            cmd[key] = json!(value);
+                        }
+                    }
+                    Ok(cmd)
+                }
+                "get" => {
+                    let mut cmd = json!({ "id": id, "action": "cookies_get" });
+                    if let Some(name) = rest.get(1) {
+                        cmd["name"] = json!(name);
+                    }
+                    Ok(cmd)
+                }
                 _ => Ok(json!({ "id": id, "action": "cookies_get" })),
             }
         }
 
         // === Tabs ===
-        "tab" => match rest.first().copied() {
-            Some("new") => {
-                let mut cmd = json!({ "id": id, "action": "tab_new" });
-                if let Some(url) = rest.get(1) {
-                    cmd["url"] = json!(url);
+        "tab" => parse_tab(&rest, &id),
+
+        // === Window ===
+        "window" => parse_window(&rest, &id),
+
+        // === Accessibility ===
+        "accessibility" => parse_accessibility(&rest, &id),
+
+        // === Clipboard ===
+        "clipboard" => parse_clipboard(&rest, &id),
+
+        // === Performance ===
+        "performance" => parse_performance(&rest, &id),
+
+        // === Mock ===
+        "mock" => parse_mock(&rest, &id),
+
+        // === Inject ===
+        "inject" => parse_inject(&rest, &id),
+
+        // === Protocol schema ===
+        "schema" => Ok(json!({ "id": id, "action": "schema" })),
+
+        // === Shell completions (printed by main.rs, never sent to the daemon) ===
+        "completions" => {
+            const VALID: &[&str] = &["bash", "zsh", "fish", "powershell"];
+            match rest.first().copied() {
+                Some(shell) if VALID.contains(&shell) => {
+                    Ok(json!({ "id": id, "action": "completions", "shell": shell }))
                 }
-                Ok(cmd)
+                Some(shell) => Err(ParseError::UnknownSubcommand {
+                    subcommand: shell.to_string(),
+                    valid_options: VALID,
+                }),
+                None => Err(ParseError::MissingArguments {
+                    context: "completions".to_string(),
+                    usage: "completions <bash|zsh|fish|powershell>",
+                }),
             }
-            Some("list") => Ok(json!({ "id": id, "action": "tab_list" })),
-            Some("close") => {
-                let mut cmd = json!({ "id": id, "action": "tab_close" });
-                if let Some(index) = rest.get(1).and_then(|s| s.parse::<i32>().ok()) {
-                    cmd["index"] = json!(index);
+        }
+
+        // === Config (handled locally, like completions) ===
+        "config" => {
+            const VALID: &[&str] = &["validate"];
+            match rest.first().copied() {
+                Some("validate") => {
+                    let strict = rest[1..].contains(&"--strict");
+                    Ok(json!({ "id": id, "action": "config_validate", "strict": strict }))
                 }
-                Ok(cmd)
-            }
-            Some(n) if n.parse::<i32>().is_ok() => {
-                let index = n.parse::<i32>().expect("already checked parse succeeds");
-                Ok(json!({ "id": id, "action": "tab_switch", "index": index }))
+                Some(sub) => Err(ParseError::UnknownSubcommand {
+                    subcommand: sub.to_string(),
+                    valid_options: VALID,
+                }),
+                None => Err(ParseError::MissingArguments {
+                    context: "config".to_string(),
+                    usage: "config validate [--strict]",
+                }),
             }
-            _ => Ok(json!({ "id": id, "action": "tab_list" })),
-        },
+        }
 
-        // === Window ===
-        "window" => {
-            const VALID: &[&str] = &["new"];
+        // === Daemon ===
+        "daemon" => {
+            const VALID: &[&str] = &["start", "stop", "status", "restart", "logs"];
             match rest.first().copied() {
-                Some("new") => Ok(json!({ "id": id, "action": "window_new" })),
+                Some(sub @ ("start" | "stop" | "status" | "restart" | "logs")) => {
+                    Ok(json!({ "id": id, "action": "daemon", "subcommand": sub }))
+                }
                 Some(sub) => Err(ParseError::UnknownSubcommand {
                     subcommand: sub.to_string(),
                     valid_options: VALID,
                 }),
                 None => Err(ParseError::MissingArguments {
-                    context: "window".to_string(),
-                    usage: "window <new>",
+                    context: "daemon".to_string(),
+                    usage: "daemon <start|stop|status|restart|logs>",
                 }),
             }
         }
 
-        // === Frame ===
-        "frame" => {
-            if rest.first().copied() == Some("main") {
-                Ok(json!({ "id": id, "action": "mainframe" }))
-            } else {
-                let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                    context: "frame".to_string(),
-                    usage: "frame <selector|main>",
-                })?;
-                Ok(json!({ "id": id, "action": "frame", "selector": sel }))
-            }
+        // === Foreach (repeat a command per matching element) ===
+        "foreach" => parse_foreach(&rest, &id, flags),
+
+        // === Run (batch script) ===
+        "run" => {
+            let path = rest.first().ok_or_else(|| ParseError::MissingArguments {
+                context: "run".to_string(),
+                usage: "run <script-file>",
+            })?;
+            let commands = read_batch_file(path, flags)?;
+            Ok(json!({ "id": id, "action": "batch", "commands": commands }))
         }
 
+        // === Frame ===
+        "frame" => parse_frame(&rest, &id),
+
         // === Dialog ===
         "dialog" => {
-            const VALID: &[&str] = &["accept", "dismiss"];
+            const VALID: &[&str] = &["accept", "dismiss", "auto", "get"];
             match rest.first().copied() {
-                Some("accept") => {
-                    let mut cmd = json!({ "id": id, "action": "dialog", "response": "accept" });
+                Some("auto") => parse_dialog_auto(&rest, &id),
+                Some("get") => Ok(json!({ "id": id, "action": "dialog_get" })),
+                Some(response @ ("accept" | "dismiss")) => {
+                    let mut cmd = json!({ "id": id, "action": "dialog", "response": response });
                     if let Some(prompt_text) = rest.get(1) {
                         cmd["promptText"] = json!(prompt_text);
                     }
@@ -813,16 +951,24 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
                 }),
                 None => Err(ParseError::MissingArguments {
                     context: "dialog".to_string(),
-                    usage: "dialog <accept|dismiss> [text]",
+                    usage: "dialog <accept|dismiss|auto|get> [text]",
                 }),
             }
         }
 
         // === Debug ===
         "trace" => {
-            const VALID: &[&str] = &["start", "stop"];
+            const VALID: &[&str] = &["start", "stop", "open", "status"];
             match rest.first().copied() {
                 Some("start") => Ok(json!({ "id": id, "action": "trace_start" })),
+                Some("open") => {
+                    let mut cmd = json!({ "id": id, "action": "trace_open" });
+                    if let Some(path) = rest.get(1) {
+                        cmd["path"] = json!(path);
+                    }
+                    Ok(cmd)
+                }
+                Some("status") => Ok(json!({ "id": id, "action": "trace_status" })),
                 Some("stop") => {
                     let mut cmd = json!({ "id": id, "action": "trace_stop" });
                     if let Some(path) = rest.get(1) {
@@ -836,14 +982,14 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
                 }),
                 None => Err(ParseError::MissingArguments {
                     context: "trace".to_string(),
-                    usage: "trace <start|stop> [path]",
+                    usage: "trace <start|stop|open|status> [path]",
                 }),
             }
         }
 
         // === Profiler (CDP Tracing / Chromium profiling) ===
         "profiler" => {
-            const VALID: &[&str] = &["start", "stop"];
+            const VALID: &[&str] = &["start", "stop", "snapshot", "status"];
             match rest.first().copied() {
                 Some("start") => {
                     let mut cmd = json!({ "id": id, "action": "profiler_start" });
@@ -867,13 +1013,25 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
                     }
                     Ok(cmd)
                 }
+                Some("snapshot") => match rest.get(1) {
+                    Some(path) => Ok(json!({
+                        "id": id,
+                        "action": "profiler_heapsnapshot",
+                        "path": path,
+                    })),
+                    None => Err(ParseError::MissingArguments {
+                        context: "profiler snapshot".to_string(),
+                        usage: "profiler snapshot <path>",
+                    }),
+                },
+                Some("status") => Ok(json!({ "id": id, "action": "profiler_status" })),
                 Some(sub) => Err(ParseError::UnknownSubcommand {
                     subcommand: sub.to_string(),
                     valid_options: VALID,
                 }),
                 None => Err(ParseError::MissingArguments {
                     context: "profiler".to_string(),
-                    usage: "profiler <start|stop> [options]",
+                    usage: "profiler <start|stop|snapshot|status> [options]",
                 }),
             }
         }
@@ -931,39 +1089,43 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
                 }),
             }
         }
-        "console" => {
-            let clear = rest.contains(&"--clear");
-            Ok(json!({ "id": id, "action": "console", "clear": clear }))
-        }
-        "errors" => {
-            let clear = rest.contains(&"--clear");
-            Ok(json!({ "id": id, "action": "errors", "clear": clear }))
-        }
-        "highlight" => {
-            let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "highlight".to_string(),
-                usage: "highlight <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "highlight", "selector": sel }))
-        }
+        "console" => parse_console(&rest, &id),
+        "errors" => parse_errors(&rest, &id),
+        "highlight" => parse_highlight(&rest, &id),
 
         // === State ===
         "state" => {
-            const VALID: &[&str] = &["save", "load", "list", "clear", "show", "clean", "rename"];
+            const VALID: &[&str] = &[
+                "save", "load", "list", "clear", "show", "clean", "rename", "diff",
+            ];
             match rest.first().copied() {
                 Some("save") => {
+                    const USAGE: &str = "state save <path> [--encrypt [password]]";
                     let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
                         context: "state save".to_string(),
-                        usage: "state save <path>",
+                        usage: USAGE,
                     })?;
-                    Ok(json!({ "id": id, "action": "state_save", "path": path }))
+                    let mut cmd = json!({ "id": id, "action": "state_save", "path": path });
+                    if let Some(password) = parse_state_password(&rest, "--encrypt", flags, USAGE)?
+                    {
+                        cmd["encrypt"] = json!(true);
+                        cmd["password"] = json!(password);
+                    }
+                    Ok(cmd)
                 }
                 Some("load") => {
+                    const USAGE: &str = "state load <path> [--decrypt [password]]";
                     let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
                         context: "state load".to_string(),
-                        usage: "state load <path>",
+                        usage: USAGE,
                     })?;
-                    Ok(json!({ "id": id, "action": "state_load", "path": path }))
+                    let mut cmd = json!({ "id": id, "action": "state_load", "path": path });
+                    if let Some(password) = parse_state_password(&rest, "--decrypt", flags, USAGE)?
+                    {
+                        cmd["decrypt"] = json!(true);
+                        cmd["password"] = json!(password);
+                    }
+                    Ok(cmd)
                 }
                 Some("list") => {
                     Ok(json!({ "id": id, "action": "state_list" }))
@@ -1050,13 +1212,36 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
 
                     Ok(json!({ "id": id, "action": "state_rename", "oldName": old_name, "newName": new_name }))
                 }
+                Some("diff") => {
+                    const USAGE: &str = "state diff <baseline-file> <current-file>";
+                    let (Some(baseline), Some(current)) = (rest.get(1), rest.get(2)) else {
+                        return Err(ParseError::MissingArguments {
+                            context: "state diff".to_string(),
+                            usage: USAGE,
+                        });
+                    };
+                    // Bare names refer to saved state files, so hold them to the
+                    // same naming rules as `state rename`; paths are passed through.
+                    for file in [baseline, current] {
+                        let is_path = file.contains('/') || file.contains('\\');
+                        let name = file.trim_end_matches(".json");
+                        if !is_path && !is_valid_session_name(name) {
+                            return Err(ParseError::InvalidSessionName {
+                                name: name.to_string(),
+                            });
+                        }
+                    }
+                    Ok(
+                        json!({ "id": id, "action": "state_diff", "baseline": baseline, "current": current }),
+                    )
+                }
                 Some(sub) => Err(ParseError::UnknownSubcommand {
                     subcommand: sub.to_string(),
                     valid_options: VALID,
                 }),
                 None => Err(ParseError::MissingArguments {
                     context: "state".to_string(),
-                    usage: "state <save|load|list|clear|show|clean|rename> ...",
+                    usage: "state <save|load|list|clear|show|clean|rename|diff> ...",
                 }),
             }
         }
@@ -1073,23 +1258,71 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
         "swipe" => {
             let direction = rest.first().ok_or_else(|| ParseError::MissingArguments {
                 context: "swipe".to_string(),
-                usage: "swipe <up|down|left|right> [distance]",
+                usage: SWIPE_USAGE,
             })?;
             let valid_directions = ["up", "down", "left", "right"];
             if !valid_directions.contains(direction) {
                 return Err(ParseError::InvalidValue {
                     message: format!("Invalid swipe direction: {}", direction),
-                    usage: "swipe <up|down|left|right> [distance]",
+                    usage: SWIPE_USAGE,
                 });
             }
+            let selector = parse_selector_flag(&rest, SWIPE_USAGE)?;
             let mut cmd = json!({ "id": id, "action": "swipe", "direction": direction });
-            if let Some(distance) = rest.get(1) {
+            let positional = positional_args(&rest, &["--selector"]);
+            if let Some(distance) = positional.get(1) {
                 if let Ok(d) = distance.parse::<u32>() {
                     cmd.as_object_mut()
                         .unwrap()
                         .insert("distance".to_string(), json!(d));
                 }
             }
+            if let Some(sel) = selector {
+                cmd["selector"] = json!(sel);
+            }
+            Ok(cmd)
+        }
+        "pinch" => {
+            const VALID: &[&str] = &["in", "out"];
+            let direction = match rest.first().copied() {
+                Some(d) if VALID.contains(&d) => d,
+                Some(d) => {
+                    return Err(ParseError::InvalidValue {
+                        message: format!("Invalid pinch direction: {}", d),
+                        usage: PINCH_USAGE,
+                    })
+                }
+                None => {
+                    return Err(ParseError::MissingArguments {
+                        context: "pinch".to_string(),
+                        usage: PINCH_USAGE,
+                    })
+                }
+            };
+            let mut cmd = json!({ "id": id, "action": "pinch", "direction": direction });
+            if let Some(sel) = parse_selector_flag(&rest, PINCH_USAGE)? {
+                cmd["selector"] = json!(sel);
+            }
+            if let Some(idx) = rest.iter().position(|&s| s == "--scale") {
+                let value = rest
+                    .get(idx + 1)
+                    .ok_or_else(|| ParseError::MissingArguments {
+                        context: "--scale".to_string(),
+                        usage: PINCH_USAGE,
+                    })?;
+                let scale = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|s| s.is_finite() && *s > 0.0)
+                    .ok_or_else(|| ParseError::InvalidValue {
+                        message: format!(
+                            "Invalid --scale value: {}. Expected a positive number",
+                            value
+                        ),
+                        usage: PINCH_USAGE,
+                    })?;
+                cmd["scale"] = json!(scale);
+            }
             Ok(cmd)
         }
         "device" => {
@@ -1113,1470 +1346,9371 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
     }
 }
 
-fn parse_diff(rest: &[&str], id: &str, flags: &Flags) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["snapshot", "screenshot", "url"];
+/// Parses the `wait` conditions; `--polling-interval` is handled by the caller.
+fn parse_wait(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    // Check for --element-hidden flag: wait --element-hidden "#spinner"
+    if let Some(idx) = rest.iter().position(|&s| s == "--element-hidden") {
+        let sel = rest
+            .get(idx + 1)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: "wait --element-hidden".to_string(),
+                usage: "wait --element-hidden <selector>",
+            })?;
+        return Ok(json!({ "id": id, "action": "waitforhidden", "selector": sel }));
+    }
 
-    match rest.first().copied() {
-        Some("snapshot") => {
-            let mut cmd = json!({ "id": id, "action": "diff_snapshot" });
-            let obj = cmd.as_object_mut().unwrap();
-            let mut i = 1;
-            while i < rest.len() {
-                match rest[i] {
-                    "-b" | "--baseline" => {
-                        if let Some(path) = rest.get(i + 1) {
-                            obj.insert("baseline".to_string(), json!(path));
-                            i += 1;
-                        } else {
-                            return Err(ParseError::MissingArguments {
-                                context: "diff snapshot --baseline".to_string(),
-                                usage: "diff snapshot --baseline <file>",
-                            });
-                        }
-                    }
-                    "-s" | "--selector" => {
-                        if let Some(s) = rest.get(i + 1) {
-                            obj.insert("selector".to_string(), json!(s));
-                            i += 1;
-                        } else {
-                            return Err(ParseError::MissingArguments {
-                                context: "diff snapshot --selector".to_string(),
-                                usage: "diff snapshot --selector <sel>",
-                            });
-                        }
-                    }
-                    "-c" | "--compact" => {
-                        obj.insert("compact".to_string(), json!(true));
-                    }
-                    "-d" | "--depth" => {
-                        if let Some(d) = rest.get(i + 1) {
-                            match d.parse::<u32>() {
-                                Ok(n) => {
-                                    obj.insert("maxDepth".to_string(), json!(n));
-                                    i += 1;
-                                }
-                                Err(_) => {
-                                    return Err(ParseError::InvalidValue {
-                                        message: format!("Depth must be a non-negative integer, got: {}", d),
-                                        usage: "diff snapshot --depth <n>",
-                                    });
-                                }
-                            }
-                        } else {
-                            return Err(ParseError::MissingArguments {
-                                context: "diff snapshot --depth".to_string(),
-                                usage: "diff snapshot --depth <n>",
-                            });
-                        }
-                    }
-                    other if other.starts_with('-') => {
-                        return Err(ParseError::InvalidValue {
-                            message: format!("Unknown flag: {}", other),
-                            usage: "diff snapshot [--baseline <file>] [--selector <sel>] [--compact] [--depth <n>]",
-                        });
-                    }
-                    other => {
-                        return Err(ParseError::InvalidValue {
-                            message: format!("Unexpected argument: {}", other),
-                            usage: "diff snapshot [--baseline <file>] [--selector <sel>] [--compact] [--depth <n>]",
-                        });
-                    }
-                }
-                i += 1;
-            }
-            Ok(cmd)
+    // Check for --element-detached flag: wait --element-detached "#modal"
+    if let Some(idx) = rest.iter().position(|&s| s == "--element-detached") {
+        let sel = rest
+            .get(idx + 1)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: "wait --element-detached".to_string(),
+                usage: "wait --element-detached <selector>",
+            })?;
+        return Ok(json!({ "id": id, "action": "waitfordetached", "selector": sel }));
+    }
+
+    // Check for --navigation flag: wait --navigation [--wait-until <state>]
+    if rest.contains(&"--navigation") {
+        let mut cmd = json!({ "id": id, "action": "waitfornavigation" });
+        if let Some(state) = parse_wait_until(
+            rest,
+            "wait --navigation [--wait-until <load|domcontentloaded|networkidle>]",
+        )? {
+            cmd["waitUntil"] = json!(state);
         }
-        Some("screenshot") => {
-            let mut cmd = json!({ "id": id, "action": "diff_screenshot" });
-            let obj = cmd.as_object_mut().unwrap();
-            let mut i = 1;
-            while i < rest.len() {
-                match rest[i] {
-                    "-b" | "--baseline" => {
-                        if let Some(path) = rest.get(i + 1) {
-                            obj.insert("baseline".to_string(), json!(path));
-                            i += 1;
-                        } else {
-                            return Err(ParseError::MissingArguments {
-                                context: "diff screenshot --baseline".to_string(),
-                                usage: "diff screenshot --baseline <file>",
-                            });
-                        }
-                    }
-                    "-o" | "--output" => {
-                        if let Some(path) = rest.get(i + 1) {
-                            obj.insert("output".to_string(), json!(path));
-                            i += 1;
-                        } else {
-                            return Err(ParseError::MissingArguments {
-                                context: "diff screenshot --output".to_string(),
-                                usage: "diff screenshot --output <file>",
-                            });
-                        }
-                    }
-                    "-t" | "--threshold" => {
-                        if let Some(t) = rest.get(i + 1) {
-                            match t.parse::<f64>() {
-                                Ok(n) if (0.0..=1.0).contains(&n) => {
-                                    obj.insert("threshold".to_string(), json!(n));
-                                    i += 1;
-                                }
-                                Ok(n) => {
-                                    return Err(ParseError::InvalidValue {
-                                        message: format!("Threshold must be between 0 and 1, got {}", n),
-                                        usage: "diff screenshot --threshold <0-1>",
-                                    });
-                                }
-                                Err(_) => {
-                                    return Err(ParseError::InvalidValue {
-                                        message: format!("Invalid threshold value: {}", t),
-                                        usage: "diff screenshot --threshold <0-1>",
-                                    });
-                                }
-                            }
-                        } else {
-                            return Err(ParseError::MissingArguments {
-                                context: "diff screenshot --threshold".to_string(),
-                                usage: "diff screenshot --threshold <0-1>",
-                            });
-                        }
-                    }
-                    "-s" | "--selector" => {
-                        if let Some(s) = rest.get(i + 1) {
-                            obj.insert("selector".to_string(), json!(s));
-                            i += 1;
-                        } else {
-                            return Err(ParseError::MissingArguments {
-                                context: "diff screenshot --selector".to_string(),
-                                usage: "diff screenshot --selector <sel>",
-                            });
-                        }
-                    }
-                    "--full" => {
-                        obj.insert("fullPage".to_string(), json!(true));
-                    }
-                    other if other.starts_with('-') => {
-                        return Err(ParseError::InvalidValue {
-                            message: format!("Unknown flag: {}", other),
-                            usage: "diff screenshot --baseline <file> [--output <file>] [--threshold <0-1>] [--selector <sel>] [--full]",
-                        });
-                    }
-                    other => {
-                        return Err(ParseError::InvalidValue {
-                            message: format!("Unexpected argument: {}", other),
-                            usage: "diff screenshot --baseline <file> [--output <file>] [--threshold <0-1>] [--selector <sel>] [--full]",
-                        });
-                    }
-                }
-                i += 1;
-            }
-            if flags.full {
-                obj.insert("fullPage".to_string(), json!(true));
-            }
-            if !obj.contains_key("baseline") {
+        return Ok(cmd);
+    }
+
+    // Check for --request/--response flags: wait --request "**/api/users"
+    if let Some(idx) = rest
+        .iter()
+        .position(|&s| s == "--request" || s == "--response")
+    {
+        let (action, usage) = if rest[idx] == "--request" {
+            (
+                "waitforrequest",
+                "wait --request <url-pattern> [--timeout <ms>]",
+            )
+        } else {
+            (
+                "waitforresponse",
+                "wait --response <url-pattern> [--timeout <ms>]",
+            )
+        };
+        let url = rest
+            .get(idx + 1)
+            .filter(|s| !s.starts_with("--"))
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: format!("wait {}", rest[idx]),
+                usage,
+            })?;
+        return Ok(json!({ "id": id, "action": action, "url": url }));
+    }
+
+    // Check for --console flag: wait --console "App ready" [--type error]
+    if let Some(idx) = rest.iter().position(|&s| s == "--console") {
+        const USAGE: &str = "wait --console <pattern> [--type log|error|warn|info]";
+        const TYPES: &[&str] = &["log", "error", "warn", "info"];
+        let pattern = rest
+            .get(idx + 1)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: "wait --console".to_string(),
+                usage: USAGE,
+            })?;
+        let mut cmd = json!({ "id": id, "action": "waitforconsole", "pattern": pattern });
+        if let Some(type_idx) = rest.iter().position(|&s| s == "--type") {
+            let Some(message_type) = rest.get(type_idx + 1) else {
                 return Err(ParseError::MissingArguments {
-                    context: "diff screenshot".to_string(),
-                    usage: "diff screenshot --baseline <file>",
+                    context: "wait --console --type".to_string(),
+                    usage: USAGE,
+                });
+            };
+            if !TYPES.contains(message_type) {
+                return Err(ParseError::InvalidValue {
+                    message: format!(
+                        "Invalid console message type: {}. Expected one of: {}",
+                        message_type,
+                        TYPES.join(", ")
+                    ),
+                    usage: USAGE,
                 });
             }
-            Ok(cmd)
+            cmd["messageType"] = json!(message_type);
         }
-        Some("url") => {
-            let url1 = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "diff url".to_string(),
-                usage: "diff url <url1> <url2>",
-            })?;
-            let url2 = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "diff url".to_string(),
-                usage: "diff url <url1> <url2>",
+        return Ok(cmd);
+    }
+
+    // Check for --url flag: wait --url "**/dashboard"
+    if let Some(idx) = rest.iter().position(|&s| s == "--url" || s == "-u") {
+        let url = rest
+            .get(idx + 1)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: "wait --url".to_string(),
+                usage: "wait --url <pattern>",
             })?;
-            let mut cmd = json!({
-                "id": id,
-                "action": "diff_url",
-                "url1": url1,
-                "url2": url2,
-            });
-            let obj = cmd.as_object_mut().unwrap();
-            let mut i = 3;
-            while i < rest.len() {
-                match rest[i] {
-                    "--screenshot" => {
-                        obj.insert("screenshot".to_string(), json!(true));
-                    }
-                    "--full" => {
-                        obj.insert("fullPage".to_string(), json!(true));
-                    }
-                    "--wait-until" => {
-                        if let Some(val) = rest.get(i + 1) {
-                            obj.insert("waitUntil".to_string(), json!(val));
-                            i += 1;
-                        } else {
-                            return Err(ParseError::MissingArguments {
-                                context: "diff url --wait-until".to_string(),
-                                usage: "diff url <url1> <url2> --wait-until <load|domcontentloaded|networkidle>",
-                            });
-                        }
-                    }
-                    "-s" | "--selector" => {
-                        if let Some(s) = rest.get(i + 1) {
-                            obj.insert("selector".to_string(), json!(s));
-                            i += 1;
-                        } else {
-                            return Err(ParseError::MissingArguments {
-                                context: "diff url --selector".to_string(),
-                                usage: "diff url <url1> <url2> --selector <sel>",
-                            });
-                        }
-                    }
-                    "-c" | "--compact" => {
-                        obj.insert("compact".to_string(), json!(true));
-                    }
-                    "-d" | "--depth" => {
-                        if let Some(d) = rest.get(i + 1) {
-                            match d.parse::<u32>() {
-                                Ok(n) => {
-                                    obj.insert("maxDepth".to_string(), json!(n));
-                                    i += 1;
-                                }
-                                Err(_) => {
-                                    return Err(ParseError::InvalidValue {
-                                        message: format!("Depth must be a non-negative integer, got: {}", d),
-                                        usage: "diff url <url1> <url2> --depth <n>",
-                                    });
-                                }
-                            }
-                        } else {
-                            return Err(ParseError::MissingArguments {
-                                context: "diff url --depth".to_string(),
-                                usage: "diff url <url1> <url2> --depth <n>",
-                            });
-                        }
-                    }
-                    other if other.starts_with('-') => {
-                        return Err(ParseError::InvalidValue {
-                            message: format!("Unknown flag: {}", other),
-                            usage: "diff url <url1> <url2> [--screenshot] [--full] [--wait-until <strategy>] [--selector <sel>] [--compact] [--depth <n>]",
-                        });
-                    }
-                    other => {
-                        return Err(ParseError::InvalidValue {
-                            message: format!("Unexpected argument: {}", other),
-                            usage: "diff url <url1> <url2> [--screenshot] [--full] [--wait-until <strategy>] [--selector <sel>] [--compact] [--depth <n>]",
-                        });
-                    }
-                }
-                i += 1;
-            }
-            if flags.full {
-                obj.insert("fullPage".to_string(), json!(true));
-            }
-            Ok(cmd)
-        }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "diff".to_string(),
-            usage: "diff <snapshot|screenshot|url>",
-        }),
+        return Ok(json!({ "id": id, "action": "waitforurl", "url": url }));
     }
-}
 
-fn parse_get(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &[
-        "text", "html", "value", "attr", "url", "title", "count", "box", "styles",
-    ];
+    // Check for --load flag: wait --load networkidle
+    if let Some(idx) = rest.iter().position(|&s| s == "--load" || s == "-l") {
+        let state = rest
+            .get(idx + 1)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: "wait --load".to_string(),
+                usage: "wait --load <state>",
+            })?;
+        return Ok(json!({ "id": id, "action": "waitforloadstate", "state": state }));
+    }
 
-    match rest.first().copied() {
-        Some("text") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get text".to_string(),
-                usage: "get text <selector>",
+    // Check for --fn flag: wait --fn "window.ready === true"
+    if let Some(idx) = rest.iter().position(|&s| s == "--fn" || s == "-f") {
+        let expr = rest
+            .get(idx + 1)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: "wait --fn".to_string(),
+                usage: "wait --fn <expression>",
             })?;
-            Ok(json!({ "id": id, "action": "gettext", "selector": sel }))
-        }
-        Some("html") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get html".to_string(),
-                usage: "get html <selector>",
+        return Ok(json!({ "id": id, "action": "waitforfunction", "expression": expr }));
+    }
+
+    // Check for --text flag: wait --text "Welcome"
+    if let Some(idx) = rest.iter().position(|&s| s == "--text" || s == "-t") {
+        let text = rest
+            .get(idx + 1)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: "wait --text".to_string(),
+                usage: "wait --text <text>",
             })?;
-            Ok(json!({ "id": id, "action": "innerhtml", "selector": sel }))
+        // Use getByText locator to wait for text to appear
+        return Ok(json!({ "id": id, "action": "wait", "selector": format!("text={}", text) }));
+    }
+
+    // Check for --download flag: wait --download [path] [--timeout ms]
+    if rest.iter().any(|&s| s == "--download" || s == "-d") {
+        let mut cmd = json!({ "id": id, "action": "waitfordownload" });
+        // Check for optional path (first non-flag argument after --download)
+        let download_idx = rest
+            .iter()
+            .position(|&s| s == "--download" || s == "-d")
+            .unwrap();
+        if let Some(path) = rest.get(download_idx + 1) {
+            if !path.starts_with("--") {
+                cmd["path"] = json!(path);
+            }
         }
-        Some("value") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get value".to_string(),
-                usage: "get value <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "inputvalue", "selector": sel }))
+        // Check for optional timeout
+        if let Some(idx) = rest.iter().position(|&s| s == "--timeout") {
+            if let Some(timeout_str) = rest.get(idx + 1) {
+                if let Ok(timeout) = timeout_str.parse::<u64>() {
+                    cmd["timeout"] = json!(timeout);
+                }
+            }
         }
-        Some("attr") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get attr".to_string(),
-                usage: "get attr <selector> <attribute>",
-            })?;
-            let attr = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "get attr".to_string(),
-                usage: "get attr <selector> <attribute>",
-            })?;
-            Ok(json!({ "id": id, "action": "getattribute", "selector": sel, "attribute": attr }))
+        return Ok(cmd);
+    }
+
+    // Default: selector or timeout
+    if let Some(arg) = rest.first() {
+        if let Ok(timeout) = arg.parse::<u64>() {
+            Ok(json!({ "id": id, "action": "wait", "timeout": timeout }))
+        } else {
+            Ok(json!({ "id": id, "action": "wait", "selector": arg }))
         }
-        Some("url") => Ok(json!({ "id": id, "action": "url" })),
-        Some("title") => Ok(json!({ "id": id, "action": "title" })),
-        Some("count") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get count".to_string(),
-                usage: "get count <selector>",
+    } else {
+        Err(ParseError::MissingArguments {
+            context: "wait".to_string(),
+            usage: "wait <selector|ms|--url|--load|--fn|--text|--element-hidden|--element-detached|--navigation|--request|--response|--console>",
+        })
+    }
+}
+
+const SWIPE_USAGE: &str = "swipe <up|down|left|right> [distance] [--selector <sel>]";
+const PINCH_USAGE: &str = "pinch <in|out> [--selector <sel>] [--scale <n>]";
+
+/// Collects every `--mask <sel>` for screenshot commands, in order.
+fn parse_mask_flags<'a>(rest: &[&'a str], usage: &'static str) -> Result<Vec<&'a str>, ParseError> {
+    let mut masks = Vec::new();
+    let mut iter = rest.iter();
+    while let Some(&arg) = iter.next() {
+        if arg == "--mask" {
+            let sel = iter.next().ok_or_else(|| ParseError::MissingArguments {
+                context: "--mask".to_string(),
+                usage,
             })?;
-            Ok(json!({ "id": id, "action": "count", "selector": sel }))
+            masks.push(*sel);
         }
-        Some("box") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get box".to_string(),
-                usage: "get box <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "boundingbox", "selector": sel }))
+    }
+    Ok(masks)
+}
+
+/// Parses an optional `--selector <sel>` flag for gesture commands.
+fn parse_selector_flag<'a>(
+    rest: &[&'a str],
+    usage: &'static str,
+) -> Result<Option<&'a str>, ParseError> {
+    let Some(idx) = rest.iter().position(|&s| s == "--selector") else {
+        return Ok(None);
+    };
+    rest.get(idx + 1)
+        .copied()
+        .map(Some)
+        .ok_or_else(|| ParseError::MissingArguments {
+            context: "--selector".to_string(),
+            usage,
+        })
+}
+
+/// Removes every occurrence of a boolean flag, reporting whether it was present.
+fn take_bool_flag<'a>(rest: &[&'a str], flag: &str) -> (bool, Vec<&'a str>) {
+    let remaining: Vec<&str> = rest.iter().copied().filter(|&s| s != flag).collect();
+    (remaining.len() != rest.len(), remaining)
+}
+
+/// Returns the arguments left after removing the given flags and their values.
+fn positional_args<'a>(rest: &[&'a str], value_flags: &[&str]) -> Vec<&'a str> {
+    let mut positional = Vec::new();
+    let mut iter = rest.iter();
+    while let Some(&arg) = iter.next() {
+        if value_flags.contains(&arg) {
+            iter.next();
+        } else {
+            positional.push(arg);
         }
-        Some("styles") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get styles".to_string(),
-                usage: "get styles <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "styles", "selector": sel }))
+    }
+    positional
+}
+
+/// Named viewports for `set viewport --preset`:
+/// (name, width, height, deviceScaleFactor, isMobile).
+const VIEWPORT_PRESETS: &[(&str, i32, i32, f64, bool)] = &[
+    ("iPhoneSE", 375, 667, 2.0, true),
+    ("iPhone15", 393, 852, 3.0, true),
+    ("iPhone15Pro", 393, 852, 3.0, true),
+    ("iPhone15ProMax", 430, 932, 3.0, true),
+    ("Pixel7", 412, 915, 2.625, true),
+    ("GalaxyS23", 360, 780, 3.0, true),
+    ("iPad", 820, 1180, 2.0, true),
+    ("iPadPro", 1024, 1366, 2.0, true),
+    ("Laptop", 1366, 768, 1.0, false),
+    ("Desktop720", 1280, 720, 1.0, false),
+    ("Desktop1080", 1920, 1080, 1.0, false),
+    ("Desktop1440", 2560, 1440, 1.0, false),
+];
+
+/// Looks up a viewport preset, ignoring case and `-`, `_` or spaces. Unknown
+/// names suggest the closest preset when one is near enough.
+fn find_viewport_preset(
+    name: &str,
+    usage: &'static str,
+) -> Result<&'static (&'static str, i32, i32, f64, bool), ParseError> {
+    let normalize = |s: &str| -> String {
+        s.chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    };
+    let wanted = normalize(name);
+    if let Some(preset) = VIEWPORT_PRESETS.iter().find(|p| normalize(p.0) == wanted) {
+        return Ok(preset);
+    }
+
+    let suggestion = VIEWPORT_PRESETS
+        .iter()
+        .map(|p| (edit_distance(&normalize(p.0), &wanted), p.0))
+        .min()
+        .filter(|(distance, _)| *distance <= 3)
+        .map(|(_, name)| format!(" (did you mean {}?)", name))
+        .unwrap_or_default();
+    let names: Vec<&str> = VIEWPORT_PRESETS.iter().map(|p| p.0).collect();
+    Err(ParseError::InvalidValue {
+        message: format!(
+            "Unknown viewport preset: {}{}. Available presets: {}",
+            name,
+            suggestion,
+            names.join(", ")
+        ),
+        usage,
+    })
+}
+
+/// Levenshtein distance between two strings, counted in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            curr.push(substitution.min(prev[j + 1] + 1).min(curr[j] + 1));
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
+/// Parses `upload`, expanding glob patterns in file arguments and collecting
+/// `--base64 <name> <content>` inline files.
+fn parse_upload(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "upload <selector> <files...> [--base64 <name> <content>]";
+
+    let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
+        context: "upload".to_string(),
+        usage: USAGE,
+    })?;
+    let mut files = Vec::new();
+    let mut inline_files = Vec::new();
+    let mut i = 1;
+    while i < rest.len() {
+        if rest[i] == "--base64" {
+            let (Some(name), Some(content)) = (rest.get(i + 1), rest.get(i + 2)) else {
+                return Err(ParseError::MissingArguments {
+                    context: "--base64".to_string(),
+                    usage: USAGE,
+                });
+            };
+            if STANDARD.decode(content).is_err() {
+                return Err(ParseError::InvalidValue {
+                    message: format!("Invalid base64 content for {}", name),
+                    usage: USAGE,
+                });
+            }
+            inline_files.push(json!({ "name": name, "content": content }));
+            i += 3;
+            continue;
+        }
+        files.extend(expand_upload_path(rest[i])?);
+        i += 1;
+    }
+
+    let mut cmd = json!({ "id": id, "action": "upload", "selector": sel, "files": files });
+    if !inline_files.is_empty() {
+        cmd["inlineFiles"] = json!(inline_files);
+    }
+    Ok(cmd)
+}
+
+/// Expands a glob pattern into matching paths; plain paths, and existing files
+/// whose names happen to contain glob characters, pass through as-is.
+fn expand_upload_path(path: &str) -> Result<Vec<String>, ParseError> {
+    const USAGE: &str = "upload <selector> <files...>";
+
+    if !path.contains(['*', '?', '[']) || std::path::Path::new(path).exists() {
+        return Ok(vec![path.to_string()]);
+    }
+    let entries = glob::glob(path).map_err(|e| ParseError::InvalidValue {
+        message: format!("Invalid glob pattern {}: {}", path, e.msg),
+        usage: USAGE,
+    })?;
+    let matches: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|p| p.is_file())
+        .map(|p| p.display().to_string())
+        .collect();
+    if matches.is_empty() {
+        return Err(ParseError::InvalidValue {
+            message: format!("No files match {}", path),
+            usage: USAGE,
+        });
+    }
+    Ok(matches)
+}
+
+/// Parses `press <key> [--selector <sel>]`. A key argument containing spaces
+/// (e.g. "Tab Tab Enter") is sent as a `keys` sequence.
+fn parse_press(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "press <key> [--selector <sel>]";
+
+    let selector = parse_selector_flag(rest, USAGE)?;
+    let positional = positional_args(rest, &["--selector"]);
+    let key = positional
+        .first()
+        .map(|k| k.trim())
+        .filter(|k| !k.is_empty())
+        .ok_or_else(|| ParseError::MissingArguments {
+            context: "press".to_string(),
+            usage: USAGE,
+        })?;
+
+    let mut cmd = json!({ "id": id, "action": "press" });
+    if key.contains(char::is_whitespace) {
+        let keys: Vec<&str> = key.split_whitespace().collect();
+        if let Some(bad) = keys
+            .iter()
+            .find(|k| !k.chars().all(|c| c.is_ascii_alphanumeric() || c == '+'))
+        {
+            return Err(ParseError::InvalidValue {
+                message: format!("Invalid key in sequence: {}", bad),
+                usage: USAGE,
+            });
+        }
+        cmd["keys"] = json!(keys);
+    } else {
+        cmd["key"] = json!(key);
+    }
+    if let Some(sel) = selector {
+        cmd["selector"] = json!(sel);
+    }
+    Ok(cmd)
+}
+
+/// Parses `select <selector>` with either positional values, `--by-index <n>`
+/// or `--by-label <text>`.
+fn parse_select(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "select <selector> <value...> | --by-index <n> | --by-label <text>";
+
+    let flag_value = |flag: &str| -> Result<Option<&str>, ParseError> {
+        let Some(idx) = rest.iter().position(|&s| s == flag) else {
+            return Ok(None);
+        };
+        rest.get(idx + 1)
+            .copied()
+            .map(Some)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: flag.to_string(),
+                usage: USAGE,
+            })
+    };
+    let by_index = flag_value("--by-index")?
+        .map(|n| {
+            n.parse::<u32>().map_err(|_| ParseError::InvalidValue {
+                message: format!(
+                    "Invalid --by-index value: {}. Expected a non-negative integer",
+                    n
+                ),
+                usage: USAGE,
+            })
+        })
+        .transpose()?;
+    let by_label = flag_value("--by-label")?;
+
+    let positional = positional_args(rest, &["--by-index", "--by-label"]);
+    let missing = || ParseError::MissingArguments {
+        context: "select".to_string(),
+        usage: USAGE,
+    };
+    let sel = positional.first().ok_or_else(missing)?;
+    let values = &positional[1..];
+
+    let modes = [!values.is_empty(), by_index.is_some(), by_label.is_some()];
+    match modes.iter().filter(|&&m| m).count() {
+        0 => return Err(missing()),
+        1 => {}
+        _ => {
+            return Err(ParseError::InvalidValue {
+                message: "Use only one of a value, --by-index or --by-label".to_string(),
+                usage: USAGE,
+            })
+        }
+    }
+
+    let mut cmd = json!({ "id": id, "action": "select", "selector": sel });
+    if let Some(index) = by_index {
+        cmd["byIndex"] = json!(index);
+    } else if let Some(label) = by_label {
+        cmd["byLabel"] = json!(label);
+    } else if values.len() == 1 {
+        cmd["values"] = json!(values[0]);
+    } else {
+        cmd["values"] = json!(values);
+    }
+    Ok(cmd)
+}
+
+/// Parses `download <selector> <path> [--wait] [--timeout <ms>]`. The path may
+/// also be given as `--path <path>`.
+fn parse_download(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "download <selector> <path> [--wait] [--timeout <ms>]";
+    // `--wait-for-download` is a longer alias of `--wait`.
+    const WAIT_FLAGS: &[&str] = &["--wait", "--wait-for-download"];
+
+    let positional: Vec<&str> = positional_args(rest, &["--path"])
+        .into_iter()
+        .filter(|s| !WAIT_FLAGS.contains(s))
+        .collect();
+    let missing = || ParseError::MissingArguments {
+        context: "download".to_string(),
+        usage: USAGE,
+    };
+    let sel = positional.first().ok_or_else(missing)?;
+    let path = match rest.iter().position(|&s| s == "--path") {
+        Some(idx) => rest
+            .get(idx + 1)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: "--path".to_string(),
+                usage: USAGE,
+            })?,
+        None => positional.get(1).ok_or_else(missing)?,
+    };
+    let mut cmd = json!({ "id": id, "action": "download", "selector": sel, "path": path });
+    if rest.iter().any(|s| WAIT_FLAGS.contains(s)) {
+        cmd["waitForDownload"] = json!(true);
+    }
+    Ok(cmd)
+}
+
+/// Parses an optional `--wait-until <state>` flag, validating the load state.
+fn parse_wait_until<'a>(
+    rest: &[&'a str],
+    usage: &'static str,
+) -> Result<Option<&'a str>, ParseError> {
+    let Some(idx) = rest.iter().position(|&s| s == "--wait-until") else {
+        return Ok(None);
+    };
+    let state = rest
+        .get(idx + 1)
+        .ok_or_else(|| ParseError::MissingArguments {
+            context: "--wait-until".to_string(),
+            usage,
+        })?;
+    if !WAIT_UNTIL_STATES.contains(state) {
+        return Err(ParseError::InvalidValue {
+            message: format!(
+                "Invalid --wait-until state: {}. Expected one of: {}",
+                state,
+                WAIT_UNTIL_STATES.join(", ")
+            ),
+            usage,
+        });
+    }
+    Ok(Some(state))
+}
+
+/// Reads a cookie file for `cookies import`: a JSON array of cookie objects,
+/// each with at least a `name` and `value`.
+fn read_cookies_file(path: &str) -> Result<Vec<Value>, ParseError> {
+    const USAGE: &str = "cookies import <file>";
+    let invalid = |message: String| ParseError::InvalidValue {
+        message,
+        usage: USAGE,
+    };
+    let content =
+        fs::read_to_string(path).map_err(|e| invalid(format!("Failed to read {}: {}", path, e)))?;
+    let value: Value = serde_json::from_str(&content)
+        .map_err(|e| invalid(format!("Invalid JSON in {}: {}", path, e)))?;
+    let Value::Array(cookies) = value else {
+        return Err(invalid(format!(
+            "Expected a JSON array of cookies in {}",
+            path
+        )));
+    };
+    for (i, cookie) in cookies.iter().enumerate() {
+        let has_fields = cookie.get("name").is_some_and(Value::is_string)
+            && cookie.get("value").is_some_and(Value::is_string);
+        if !has_fields {
+            return Err(invalid(format!(
+                "Cookie at index {} in {} must be an object with string \"name\" and \"value\"",
+                i, path
+            )));
+        }
+    }
+    Ok(cookies)
+}
+
+/// Commands `foreach` can run per element. For `get` and `is` the element
+/// goes after the property (`get text <el>`); for the rest it comes first.
+const FOREACH_COMMANDS: &[&str] = &[
+    "click",
+    "dblclick",
+    "fill",
+    "type",
+    "hover",
+    "focus",
+    "check",
+    "uncheck",
+    "select",
+    "scrollintoview",
+    "screenshot",
+    "highlight",
+    "get",
+    "is",
+];
+
+/// Parses `foreach <selector> <subcommand> [args...] [--output <template>]`.
+/// The subcommand is parsed like a normal command, with the `{{ref}}`
+/// placeholder as its target; the daemon substitutes each match's ref (and
+/// `{{index}}`/`{{text}}`) before running it.
+fn parse_foreach(rest: &[&str], id: &str, flags: &Flags) -> Result<Value, ParseError> {
+    const USAGE: &str = "foreach <selector> <subcommand> [args...] [--output <template>]";
+    let (Some(selector), Some(sub)) = (rest.first(), rest.get(1)) else {
+        return Err(ParseError::MissingArguments {
+            context: "foreach".to_string(),
+            usage: USAGE,
+        });
+    };
+
+    let mut sub_args: Vec<String> = Vec::new();
+    let mut output = None;
+    let mut i = 2;
+    while i < rest.len() {
+        if rest[i] == "--output" {
+            output = Some(
+                *rest
+                    .get(i + 1)
+                    .ok_or_else(|| ParseError::MissingArguments {
+                        context: "foreach --output".to_string(),
+                        usage: USAGE,
+                    })?,
+            );
+            i += 2;
+        } else {
+            sub_args.push(rest[i].to_string());
+            i += 1;
+        }
+    }
+
+    if !FOREACH_COMMANDS.contains(sub) {
+        // Surface typos as unknown commands; reject real commands that do not
+        // act on an element.
+        if let Err(e @ ParseError::UnknownCommand { .. }) = parse_command(&[sub.to_string()], flags)
+        {
+            return Err(e);
+        }
+        return Err(ParseError::InvalidValue {
+            message: format!(
+                "foreach cannot run {}. Supported commands: {}",
+                sub,
+                FOREACH_COMMANDS.join(", ")
+            ),
+            usage: USAGE,
+        });
+    }
+
+    let target_at = if matches!(*sub, "get" | "is") {
+        sub_args.len().min(1)
+    } else {
+        0
+    };
+    sub_args.insert(target_at, "{{ref}}".to_string());
+    sub_args.insert(0, sub.to_string());
+    let mut args = parse_command(&sub_args, flags)?;
+    if let Some(obj) = args.as_object_mut() {
+        obj.remove("id");
+    }
+
+    let mut cmd = json!({
+        "id": id,
+        "action": "foreach",
+        "selector": selector,
+        "command": args["action"].clone(),
+        "args": args,
+    });
+    if let Some(template) = output {
+        cmd["outputTemplate"] = json!(template);
+    }
+    Ok(cmd)
+}
+
+/// Parses every command in a `run` script. Blank lines and `#` comments are
+/// skipped; each remaining line is parsed like its own CLI invocation.
+fn read_batch_file(path: &str, flags: &Flags) -> Result<Vec<Value>, ParseError> {
+    const USAGE: &str = "run <script-file>";
+    let invalid = |message: String| ParseError::InvalidValue {
+        message,
+        usage: USAGE,
+    };
+    let content =
+        fs::read_to_string(path).map_err(|e| invalid(format!("Failed to read {}: {}", path, e)))?;
+
+    let mut commands = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_no = i + 1;
+        let args = split_script_line(line)
+            .map_err(|e| invalid(format!("{} line {}: {}", path, line_no, e)))?;
+        if args[0] == "run" {
+            return Err(invalid(format!(
+                "{} line {}: run cannot be nested inside a script",
+                path, line_no
+            )));
+        }
+        let cmd = parse_command(&args, flags)
+            .map_err(|e| invalid(format!("{} line {}: {}", path, line_no, e)))?;
+        commands.push(cmd);
+    }
+    if commands.is_empty() {
+        return Err(invalid(format!("No commands found in {}", path)));
+    }
+    Ok(commands)
+}
+
+/// Splits a script line into arguments the way a shell would for simple
+/// cases: whitespace separates words, and single or double quotes group them.
+pub(crate) fn split_script_line(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => match chars.next() {
+                Some(next) => current.push(next),
+                None => return Err("unterminated escape".to_string()),
+            },
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => match chars.next() {
+                Some(next) => {
+                    current.push(next);
+                    in_word = true;
+                }
+                None => return Err("unterminated escape".to_string()),
+            },
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err("unterminated quote".to_string());
+    }
+    if in_word {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Resolves the password for `state save --encrypt` / `state load --decrypt`.
+/// An explicit value wins; a bare flag falls back to AGENT_BROWSER_STATE_PASSWORD
+/// (via `flags`), which keeps the password out of shell history and process lists.
+/// Without the flag the state file is saved or loaded unencrypted.
+fn parse_state_password(
+    rest: &[&str],
+    flag: &str,
+    flags: &Flags,
+    usage: &'static str,
+) -> Result<Option<String>, ParseError> {
+    let Some(i) = rest.iter().position(|&s| s == flag) else {
+        return Ok(None);
+    };
+    match rest.get(i + 1) {
+        Some(password) if !password.starts_with("--") => Ok(Some(password.to_string())),
+        _ => flags
+            .state_password
+            .clone()
+            .map(Some)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: format!("state {}", flag),
+                usage,
+            }),
+    }
+}
+
+fn parse_dialog_auto(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["accept", "dismiss", "off"];
+    const USAGE: &str = "dialog auto <accept|dismiss|off> [--text <value>]";
+    let text = match rest.iter().position(|&s| s == "--text") {
+        Some(i) => Some(
+            *rest
+                .get(i + 1)
+                .ok_or_else(|| ParseError::MissingArguments {
+                    context: "dialog auto --text".to_string(),
+                    usage: USAGE,
+                })?,
+        ),
+        None => None,
+    };
+    let mode = rest.get(1).copied();
+    if text.is_some() && mode != Some("accept") {
+        return Err(ParseError::InvalidValue {
+            message: "--text can only be used with dialog auto accept".to_string(),
+            usage: USAGE,
+        });
+    }
+    match mode {
+        Some("accept") => {
+            let mut cmd = json!({ "id": id, "action": "dialog_auto", "response": "accept" });
+            if let Some(text) = text {
+                cmd["promptText"] = json!(text);
+            }
+            Ok(cmd)
         }
+        Some("dismiss") => Ok(json!({ "id": id, "action": "dialog_auto", "response": "dismiss" })),
+        Some("off") => Ok(json!({ "id": id, "action": "dialog_auto", "enabled": false })),
         Some(sub) => Err(ParseError::UnknownSubcommand {
             subcommand: sub.to_string(),
             valid_options: VALID,
         }),
         None => Err(ParseError::MissingArguments {
-            context: "get".to_string(),
-            usage: "get <text|html|value|attr|url|title|count|box|styles> [args...]",
+            context: "dialog auto".to_string(),
+            usage: USAGE,
         }),
     }
 }
 
-fn parse_is(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["visible", "enabled", "checked"];
+/// Returns the value following `flag` in `rest`, if the flag is present.
+fn log_flag_value<'a>(
+    rest: &[&'a str],
+    flag: &str,
+    command: &str,
+    usage: &'static str,
+) -> Result<Option<&'a str>, ParseError> {
+    match rest.iter().position(|&s| s == flag) {
+        Some(i) => rest
+            .get(i + 1)
+            .copied()
+            .filter(|v| !v.starts_with("--"))
+            .map(Some)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: format!("{} {}", command, flag),
+                usage,
+            }),
+        None => Ok(None),
+    }
+}
+
+fn parse_console(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str =
+        "console [--clear] [--follow] [--filter log|info|warn|error|debug] [--filter-text <regex>]";
+    const LEVELS: &[&str] = &["log", "info", "warn", "error", "debug"];
+
+    let clear = rest.contains(&"--clear");
+    let mut cmd = json!({ "id": id, "action": "console", "clear": clear });
+    if rest.contains(&"--follow") {
+        cmd["follow"] = json!(true);
+    }
+    if let Some(level) = log_flag_value(rest, "--filter", "console", USAGE)? {
+        if !LEVELS.contains(&level) {
+            return Err(ParseError::InvalidValue {
+                message: format!(
+                    "Invalid console level: {}. Expected one of: {}",
+                    level,
+                    LEVELS.join(", ")
+                ),
+                usage: USAGE,
+            });
+        }
+        cmd["filter"] = json!(level);
+    }
+    if let Some(pattern) = log_flag_value(rest, "--filter-text", "console", USAGE)? {
+        cmd["filterText"] = json!(pattern);
+    }
+    Ok(cmd)
+}
+
+fn parse_errors(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "errors [--clear] [--follow] [--type <type>]";
+
+    let clear = rest.contains(&"--clear");
+    let mut cmd = json!({ "id": id, "action": "errors", "clear": clear });
+    if rest.contains(&"--follow") {
+        cmd["follow"] = json!(true);
+    }
+    if let Some(error_type) = log_flag_value(rest, "--type", "errors", USAGE)? {
+        cmd["errorType"] = json!(error_type);
+    }
+    Ok(cmd)
+}
 
+fn parse_highlight(rest: &[&str], id: &str) -> Result<Value, ParseError> {
     match rest.first().copied() {
-        Some("visible") => {
+        Some("clear") => Ok(json!({ "id": id, "action": "highlight_clear" })),
+        Some("all") => parse_highlight_target(
+            &rest[1..],
+            id,
+            "highlight_all",
+            "highlight all",
+            "highlight all <selector> [--highlight-color <#RRGGBB>] [--label <text>] [--duration <ms>]",
+        ),
+        _ => parse_highlight_target(
+            rest,
+            id,
+            "highlight",
+            "highlight",
+            "highlight <selector> [--highlight-color <#RRGGBB>] [--label <text>] [--duration <ms>] | highlight <all|clear>",
+        ),
+    }
+}
+
+/// Parses `<selector> [--highlight-color] [--label] [--duration]` for the highlight commands.
+fn parse_highlight_target(
+    rest: &[&str],
+    id: &str,
+    action: &str,
+    context: &str,
+    usage: &'static str,
+) -> Result<Value, ParseError> {
+    let mut selector: Option<&str> = None;
+    let mut color = "#FF0000";
+    let mut label: Option<&str> = None;
+    let mut duration: Option<u64> = None;
+    let mut i = 0;
+    while i < rest.len() {
+        match rest[i] {
+            flag @ ("--highlight-color" | "--label" | "--duration") => {
+                let value = *rest
+                    .get(i + 1)
+                    .ok_or_else(|| ParseError::MissingArguments {
+                        context: format!("{} {}", context, flag),
+                        usage,
+                    })?;
+                match flag {
+                    "--highlight-color" => {
+                        if !is_hex_color(value) {
+                            return Err(ParseError::InvalidValue {
+                                message: format!(
+                                    "Invalid color: {} (expected #RRGGBB, e.g. #FF0000)",
+                                    value
+                                ),
+                                usage,
+                            });
+                        }
+                        color = value;
+                    }
+                    "--label" => label = Some(value),
+                    _ => {
+                        duration = Some(value.parse::<u64>().ok().filter(|&ms| ms > 0).ok_or_else(
+                            || ParseError::InvalidValue {
+                                message: format!(
+                                    "Invalid duration: {} (expected a positive number of milliseconds)",
+                                    value
+                                ),
+                                usage,
+                            },
+                        )?);
+                    }
+                }
+                i += 2;
+            }
+            arg => {
+                if selector.is_none() {
+                    selector = Some(arg);
+                }
+                i += 1;
+            }
+        }
+    }
+
+    let selector = selector.ok_or_else(|| ParseError::MissingArguments {
+        context: context.to_string(),
+        usage,
+    })?;
+    let mut cmd = json!({
+        "id": id,
+        "action": action,
+        "selector": selector,
+        "color": color,
+    });
+    if let Some(label) = label {
+        cmd["label"] = json!(label);
+    }
+    if let Some(duration) = duration {
+        cmd["duration"] = json!(duration);
+    }
+    Ok(cmd)
+}
+
+/// Returns true for a `#RRGGBB` hex color.
+fn is_hex_color(value: &str) -> bool {
+    value.len() == 7 && value.starts_with('#') && value[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn parse_accessibility(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["snapshot", "check"];
+    const STANDARDS: &[&str] = &["wcag2a", "wcag2aa", "wcag21a", "wcag21aa"];
+    const USAGE: &str = "accessibility [snapshot] | accessibility check [--standard <standard>]";
+    match rest.first().copied() {
+        None | Some("snapshot") => Ok(json!({ "id": id, "action": "accessibility_snapshot" })),
+        Some("check") => {
+            let mut cmd = json!({ "id": id, "action": "accessibility_check" });
+            if let Some(idx) = rest.iter().position(|&s| s == "--standard") {
+                let standard = rest
+                    .get(idx + 1)
+                    .ok_or_else(|| ParseError::MissingArguments {
+                        context: "accessibility check --standard".to_string(),
+                        usage: USAGE,
+                    })?;
+                if !STANDARDS.contains(standard) {
+                    return Err(ParseError::InvalidValue {
+                        message: format!(
+                            "Invalid standard: {}. Expected one of: {}",
+                            standard,
+                            STANDARDS.join(", ")
+                        ),
+                        usage: USAGE,
+                    });
+                }
+                cmd["standard"] = json!(standard);
+            }
+            Ok(cmd)
+        }
+        Some(sub) => Err(ParseError::UnknownSubcommand {
+            subcommand: sub.to_string(),
+            valid_options: VALID,
+        }),
+    }
+}
+
+fn parse_clipboard(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["copy", "paste", "get"];
+    match rest.first().copied() {
+        Some("copy") => {
+            if rest.len() < 2 {
+                return Err(ParseError::MissingArguments {
+                    context: "clipboard copy".to_string(),
+                    usage: "clipboard copy <text>",
+                });
+            }
+            Ok(json!({ "id": id, "action": "clipboard_copy", "text": rest[1..].join(" ") }))
+        }
+        Some("paste") => {
             let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "is visible".to_string(),
-                usage: "is visible <selector>",
+                context: "clipboard paste".to_string(),
+                usage: "clipboard paste <selector>",
             })?;
-            Ok(json!({ "id": id, "action": "isvisible", "selector": sel }))
+            Ok(json!({ "id": id, "action": "clipboard_paste", "selector": sel }))
         }
-        Some("enabled") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "is enabled".to_string(),
-                usage: "is enabled <selector>",
+        Some("get") => Ok(json!({ "id": id, "action": "clipboard_get" })),
+        Some(sub) => Err(ParseError::UnknownSubcommand {
+            subcommand: sub.to_string(),
+            valid_options: VALID,
+        }),
+        None => Err(ParseError::MissingArguments {
+            context: "clipboard".to_string(),
+            usage: "clipboard <copy|paste|get> [args]",
+        }),
+    }
+}
+
+fn parse_performance(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["metrics", "mark", "measure"];
+    match rest.first().copied() {
+        Some("metrics") => Ok(json!({ "id": id, "action": "performance_metrics" })),
+        Some("mark") => {
+            let name = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "performance mark".to_string(),
+                usage: "performance mark <name>",
             })?;
-            Ok(json!({ "id": id, "action": "isenabled", "selector": sel }))
+            Ok(json!({ "id": id, "action": "performance_mark", "name": name }))
         }
-        Some("checked") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "is checked".to_string(),
-                usage: "is checked <selector>",
+        Some("measure") => {
+            let name = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "performance measure".to_string(),
+                usage: "performance measure <name> [start-mark] [end-mark]",
             })?;
-            Ok(json!({ "id": id, "action": "ischecked", "selector": sel }))
+            let mut cmd = json!({ "id": id, "action": "performance_measure", "name": name });
+            if let Some(start) = rest.get(2) {
+                cmd["startMark"] = json!(start);
+            }
+            if let Some(end) = rest.get(3) {
+                cmd["endMark"] = json!(end);
+            }
+            Ok(cmd)
         }
         Some(sub) => Err(ParseError::UnknownSubcommand {
             subcommand: sub.to_string(),
             valid_options: VALID,
         }),
         None => Err(ParseError::MissingArguments {
-            context: "is".to_string(),
-            usage: "is <visible|enabled|checked> <selector>",
+            context: "performance".to_string(),
+            usage: "performance <metrics|mark|measure> [args]",
         }),
     }
 }
 
-fn parse_find(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &[
-        "role",
-        "text",
-        "label",
-        "placeholder",
-        "alt",
-        "title",
-        "testid",
-        "first",
-        "last",
-        "nth",
-    ];
+fn parse_mock(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["load", "clear", "status"];
+    match rest.first().copied() {
+        Some("load") => {
+            const USAGE: &str = "mock load <fixture-dir>";
+            let dir = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "mock load".to_string(),
+                usage: USAGE,
+            })?;
+            let invalid = |message: String| ParseError::InvalidValue {
+                message,
+                usage: USAGE,
+            };
+            // The daemon runs in its own working directory, so send an absolute path.
+            let path = fs::canonicalize(dir)
+                .ok()
+                .filter(|p| p.is_dir())
+                .ok_or_else(|| invalid(format!("Fixture directory not found: {}", dir)))?;
+            Ok(json!({ "id": id, "action": "mock_load", "dir": path.display().to_string() }))
+        }
+        Some("clear") => Ok(json!({ "id": id, "action": "mock_clear" })),
+        Some("status") => Ok(json!({ "id": id, "action": "mock_status" })),
+        Some(sub) => Err(ParseError::UnknownSubcommand {
+            subcommand: sub.to_string(),
+            valid_options: VALID,
+        }),
+        None => Err(ParseError::MissingArguments {
+            context: "mock".to_string(),
+            usage: "mock <load|clear|status> [args]",
+        }),
+    }
+}
 
-    let locator = rest.first().ok_or_else(|| ParseError::MissingArguments {
-        context: "find".to_string(),
-        usage: "find <locator> <value> [action] [text]",
+fn parse_inject(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["script", "style"];
+    let (action, field, usage) = match rest.first().copied() {
+        Some("script") => (
+            "inject_script",
+            "script",
+            "inject script <path> [--url <pattern>]",
+        ),
+        Some("style") => (
+            "inject_style",
+            "css",
+            "inject style <path> [--url <pattern>]",
+        ),
+        Some(sub) => {
+            return Err(ParseError::UnknownSubcommand {
+                subcommand: sub.to_string(),
+                valid_options: VALID,
+            })
+        }
+        None => {
+            return Err(ParseError::MissingArguments {
+                context: "inject".to_string(),
+                usage: "inject <script|style> <path> [--url <pattern>]",
+            })
+        }
+    };
+    let context = format!("inject {}", rest[0]);
+
+    let mut path = None;
+    let mut url = None;
+    let mut i = 1;
+    while i < rest.len() {
+        if rest[i] == "--url" {
+            url = Some(
+                *rest
+                    .get(i + 1)
+                    .ok_or_else(|| ParseError::MissingArguments {
+                        context: format!("{} --url", context),
+                        usage,
+                    })?,
+            );
+            i += 2;
+        } else {
+            path = path.or(Some(rest[i]));
+            i += 1;
+        }
+    }
+    let path = path.ok_or_else(|| ParseError::MissingArguments {
+        context: context.clone(),
+        usage,
+    })?;
+    let contents = fs::read_to_string(path).map_err(|e| ParseError::InvalidValue {
+        message: format!("Failed to read {}: {}", path, e),
+        usage,
     })?;
 
-    let name_idx = rest.iter().position(|&s| s == "--name");
-    let name = name_idx.and_then(|i| rest.get(i + 1).copied());
-    let exact = rest.contains(&"--exact");
+    let mut cmd = json!({ "id": id, "action": action });
+    cmd[field] = json!(contents);
+    if let Some(url) = url {
+        cmd["url"] = json!(url);
+    }
+    Ok(cmd)
+}
 
-    match *locator {
-        "role" | "text" | "label" | "placeholder" | "alt" | "title" | "testid" | "first"
-        | "last" => {
-            let value = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: format!("find {}", locator),
-                usage: match *locator {
-                    "role" => "find role <role> [action] [--name <name>] [--exact]",
-                    "text" => "find text <text> [action] [--exact]",
-                    "label" => "find label <label> [action] [text] [--exact]",
-                    "placeholder" => "find placeholder <text> [action] [text] [--exact]",
-                    "alt" => "find alt <text> [action] [--exact]",
-                    "title" => "find title <text> [action] [--exact]",
-                    "testid" => "find testid <id> [action] [text]",
-                    "first" => "find first <selector> [action] [text]",
-                    "last" => "find last <selector> [action] [text]",
-                    _ => "find <locator> <value> [action] [text]",
-                },
+/// Parses `validate <selector> [--min <n>] [--max <n>]`. The match count is
+/// checked against the bounds (at least one element by default) in main.rs.
+fn parse_validate(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "validate <selector> [--min <n>] [--max <n>]";
+    let bound = |flag: &str| -> Result<Option<u64>, ParseError> {
+        let Some(idx) = rest.iter().position(|&s| s == flag) else {
+            return Ok(None);
+        };
+        let value = rest
+            .get(idx + 1)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: format!("validate {}", flag),
+                usage: USAGE,
             })?;
-            let subaction = rest.get(2).unwrap_or(&"click");
-            let fill_value = if rest.len() > 3 {
-                Some(rest[3..].join(" "))
-            } else {
-                None
-            };
-
-            match *locator {
-                "role" => {
-                    let mut cmd = json!({ "id": id, "action": "getbyrole", "role": value, "subaction": subaction, "name": name, "exact": exact });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "text" => Ok(
-                    json!({ "id": id, "action": "getbytext", "text": value, "subaction": subaction, "exact": exact }),
+        value
+            .parse()
+            .map(Some)
+            .map_err(|_| ParseError::InvalidValue {
+                message: format!(
+                    "Invalid {} value: {}. Expected a non-negative integer",
+                    flag, value
                 ),
-                "label" => {
-                    let mut cmd = json!({ "id": id, "action": "getbylabel", "label": value, "subaction": subaction, "exact": exact });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "placeholder" => {
-                    let mut cmd = json!({ "id": id, "action": "getbyplaceholder", "placeholder": value, "subaction": subaction, "exact": exact });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "alt" => Ok(
-                    json!({ "id": id, "action": "getbyalttext", "text": value, "subaction": subaction, "exact": exact }),
-                ),
-                "title" => Ok(
-                    json!({ "id": id, "action": "getbytitle", "text": value, "subaction": subaction, "exact": exact }),
-                ),
-                "testid" => {
-                    let mut cmd = json!({ "id": id, "action": "getbytestid", "testId": value, "subaction": subaction });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "first" => {
-                    let mut cmd = json!({ "id": id, "action": "nth", "selector": value, "index": 0, "subaction": subaction });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "last" => {
-                    let mut cmd = json!({ "id": id, "action": "nth", "selector": value, "index": -1, "subaction": subaction });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                _ => unreachable!(),
-            }
-        }
-        "nth" => {
-            let idx_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "find nth".to_string(),
-                usage: "find nth <index> <selector> [action] [text]",
-            })?;
-            let idx = idx_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "find nth".to_string(),
-                    usage: "find nth <index> <selector> [action] [text]",
-                })?;
-            let sel = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "find nth".to_string(),
-                usage: "find nth <index> <selector> [action] [text]",
-            })?;
-            let sub = rest.get(3).unwrap_or(&"click");
-            let fv = if rest.len() > 4 {
-                Some(rest[4..].join(" "))
-            } else {
-                None
-            };
-            let mut cmd = json!({ "id": id, "action": "nth", "selector": sel, "index": idx, "subaction": sub });
-            if let Some(v) = fv {
-                cmd["value"] = json!(v);
-            }
-            Ok(cmd)
+                usage: USAGE,
+            })
+    };
+    let min = bound("--min")?;
+    let max = bound("--max")?;
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            return Err(ParseError::InvalidValue {
+                message: format!("--min {} is greater than --max {}", min, max),
+                usage: USAGE,
+            });
         }
-        _ => Err(ParseError::UnknownSubcommand {
-            subcommand: locator.to_string(),
-            valid_options: VALID,
-        }),
     }
-}
+    let sel = positional_args(rest, &["--min", "--max"])
+        .first()
+        .copied()
+        .ok_or_else(|| ParseError::MissingArguments {
+            context: "validate".to_string(),
+            usage: USAGE,
+        })?;
 
-fn parse_mouse(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["move", "down", "up", "wheel"];
+    let mut cmd = json!({ "id": id, "action": "validate", "selector": sel });
+    if let Some(min) = min {
+        cmd["min"] = json!(min);
+    }
+    if let Some(max) = max {
+        cmd["max"] = json!(max);
+    }
+    Ok(cmd)
+}
 
+fn parse_assert(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["visible", "text", "count", "url", "title"];
+    let missing = |context: &str, usage: &'static str| ParseError::MissingArguments {
+        context: context.to_string(),
+        usage,
+    };
     match rest.first().copied() {
-        Some("move") => {
-            let x_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "mouse move".to_string(),
-                usage: "mouse move <x> <y>",
-            })?;
-            let y_str = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "mouse move".to_string(),
-                usage: "mouse move <x> <y>",
-            })?;
-            let x = x_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "mouse move".to_string(),
-                    usage: "mouse move <x> <y>",
-                })?;
-            let y = y_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "mouse move".to_string(),
-                    usage: "mouse move <x> <y>",
-                })?;
-            Ok(json!({ "id": id, "action": "mousemove", "x": x, "y": y }))
+        Some("visible") => {
+            let sel = rest
+                .get(1)
+                .ok_or_else(|| missing("assert visible", "assert visible <selector>"))?;
+            Ok(json!({ "id": id, "action": "assert_visible", "selector": sel }))
         }
-        Some("down") => {
-            Ok(json!({ "id": id, "action": "mousedown", "button": rest.get(1).unwrap_or(&"left") }))
+        Some("text") => {
+            const USAGE: &str = "assert text <selector> <expected>";
+            if rest.len() < 3 {
+                return Err(missing("assert text", USAGE));
+            }
+            Ok(json!({
+                "id": id,
+                "action": "assert_text",
+                "selector": rest[1],
+                "expected": rest[2..].join(" "),
+            }))
         }
-        Some("up") => {
-            Ok(json!({ "id": id, "action": "mouseup", "button": rest.get(1).unwrap_or(&"left") }))
+        Some("count") => {
+            const USAGE: &str = "assert count <selector> <n>";
+            let (Some(sel), Some(n)) = (rest.get(1), rest.get(2)) else {
+                return Err(missing("assert count", USAGE));
+            };
+            let expected = n.parse::<u64>().map_err(|_| ParseError::InvalidValue {
+                message: format!("Invalid count: {}. Expected a non-negative integer", n),
+                usage: USAGE,
+            })?;
+            Ok(json!({
+                "id": id,
+                "action": "assert_count",
+                "selector": sel,
+                "expected": expected,
+            }))
         }
-        Some("wheel") => {
-            let dy = rest
+        Some("url") => {
+            let pattern = rest
                 .get(1)
-                .and_then(|s| s.parse::<i32>().ok())
-                .unwrap_or(100);
-            let dx = rest.get(2).and_then(|s| s.parse::<i32>().ok()).unwrap_or(0);
-            Ok(json!({ "id": id, "action": "wheel", "deltaX": dx, "deltaY": dy }))
+                .ok_or_else(|| missing("assert url", "assert url <pattern>"))?;
+            Ok(json!({ "id": id, "action": "assert_url", "expected": pattern }))
+        }
+        Some("title") => {
+            if rest.len() < 2 {
+                return Err(missing("assert title", "assert title <expected>"));
+            }
+            Ok(json!({ "id": id, "action": "assert_title", "expected": rest[1..].join(" ") }))
         }
         Some(sub) => Err(ParseError::UnknownSubcommand {
             subcommand: sub.to_string(),
             valid_options: VALID,
         }),
-        None => Err(ParseError::MissingArguments {
-            context: "mouse".to_string(),
-            usage: "mouse <move|down|up|wheel> [args...]",
-        }),
+        None => Err(missing(
+            "assert",
+            "assert <visible|text|count|url|title> [args]",
+        )),
     }
 }
 
-fn parse_set(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &[
-        "viewport",
-        "device",
-        "geo",
-        "geolocation",
-        "offline",
-        "headers",
-        "credentials",
-        "auth",
-        "media",
-    ];
-
+fn parse_frame(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "frame <selector|main|list> | frame --url <pattern> | frame --name <name>";
     match rest.first().copied() {
-        Some("viewport") => {
-            let w_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set viewport".to_string(),
-                usage: "set viewport <width> <height>",
-            })?;
-            let h_str = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "set viewport".to_string(),
-                usage: "set viewport <width> <height>",
-            })?;
-            let w = w_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "set viewport".to_string(),
-                    usage: "set viewport <width> <height>",
-                })?;
-            let h = h_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "set viewport".to_string(),
-                    usage: "set viewport <width> <height>",
-                })?;
-            Ok(json!({ "id": id, "action": "viewport", "width": w, "height": h }))
-        }
-        Some("device") => {
-            let dev = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set device".to_string(),
-                usage: "set device <name>",
-            })?;
-            Ok(json!({ "id": id, "action": "device", "device": dev }))
-        }
-        Some("geo") | Some("geolocation") => {
-            let lat_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set geo".to_string(),
-                usage: "set geo <latitude> <longitude>",
-            })?;
-            let lng_str = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "set geo".to_string(),
-                usage: "set geo <latitude> <longitude>",
-            })?;
-            let lat = lat_str
-                .parse::<f64>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "set geo".to_string(),
-                    usage: "set geo <latitude> <longitude>",
-                })?;
-            let lng = lng_str
-                .parse::<f64>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "set geo".to_string(),
-                    usage: "set geo <latitude> <longitude>",
-                })?;
-            Ok(json!({ "id": id, "action": "geolocation", "latitude": lat, "longitude": lng }))
-        }
-        Some("offline") => {
-            let off = rest
-                .get(1)
-                .map(|s| *s != "off" && *s != "false")
-                .unwrap_or(true);
-            Ok(json!({ "id": id, "action": "offline", "offline": off }))
+        Some("main") => return Ok(json!({ "id": id, "action": "mainframe" })),
+        Some("list") => return Ok(json!({ "id": id, "action": "frame_list" })),
+        _ => {}
+    }
+
+    let mut targets = Vec::new();
+    let mut i = 0;
+    while i < rest.len() {
+        match rest[i] {
+            flag @ ("--url" | "--name") => {
+                let value = rest
+                    .get(i + 1)
+                    .ok_or_else(|| ParseError::MissingArguments {
+                        context: format!("frame {}", flag),
+                        usage: USAGE,
+                    })?;
+                let key = if flag == "--url" {
+                    "urlPattern"
+                } else {
+                    "name"
+                };
+                targets.push((key, *value));
+                i += 1;
+            }
+            sel => targets.push(("selector", sel)),
         }
-        Some("headers") => {
-            let headers_json = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set headers".to_string(),
-                usage: "set headers <json>",
+        i += 1;
+    }
+
+    match targets.as_slice() {
+        [] => Err(ParseError::MissingArguments {
+            context: "frame".to_string(),
+            usage: USAGE,
+        }),
+        [(key, value)] => Ok(json!({ "id": id, "action": "frame", *key: value })),
+        _ => Err(ParseError::InvalidValue {
+            message: "Specify exactly one of <selector>, --url or --name".to_string(),
+            usage: USAGE,
+        }),
+    }
+}
+
+fn parse_window(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["new", "list", "switch", "close", "resize"];
+    let parse_index = |value: &str, usage: &'static str| {
+        value.parse::<u32>().map_err(|_| ParseError::InvalidValue {
+            message: format!("Invalid window index: {}", value),
+            usage,
+        })
+    };
+    match rest.first().copied() {
+        Some("new") => Ok(json!({ "id": id, "action": "window_new" })),
+        Some("list") => Ok(json!({ "id": id, "action": "window_list" })),
+        Some("switch") => {
+            const USAGE: &str = "window switch <index>";
+            let value = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "window switch".to_string(),
+                usage: USAGE,
             })?;
-            // Parse the JSON string into an object
-            let headers: serde_json::Value =
-                serde_json::from_str(headers_json).map_err(|_| ParseError::MissingArguments {
-                    context: "set headers".to_string(),
-                    usage: "set headers <json> (must be valid JSON object)",
-                })?;
-            Ok(json!({ "id": id, "action": "headers", "headers": headers }))
+            let index = parse_index(value, USAGE)?;
+            Ok(json!({ "id": id, "action": "window_switch", "index": index }))
         }
-        Some("credentials") | Some("auth") => {
-            let user = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set credentials".to_string(),
-                usage: "set credentials <username> <password>",
-            })?;
-            let pass = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "set credentials".to_string(),
-                usage: "set credentials <username> <password>",
-            })?;
-            Ok(json!({ "id": id, "action": "credentials", "username": user, "password": pass }))
+        Some("close") => {
+            let mut cmd = json!({ "id": id, "action": "window_close" });
+            if let Some(value) = rest.get(1) {
+                cmd["index"] = json!(parse_index(value, "window close [index]")?);
+            }
+            Ok(cmd)
         }
-        Some("media") => {
-            let color = if rest.contains(&"dark") {
-                "dark"
-            } else if rest.contains(&"light") {
-                "light"
-            } else {
-                "no-preference"
+        Some("resize") => {
+            const USAGE: &str = "window resize <width> <height>";
+            let (Some(w), Some(h)) = (rest.get(1), rest.get(2)) else {
+                return Err(ParseError::MissingArguments {
+                    context: "window resize".to_string(),
+                    usage: USAGE,
+                });
             };
-            let reduced = if rest.contains(&"reduced-motion") {
-                "reduce"
-            } else {
-                "no-preference"
+            let parse_dim = |value: &str| {
+                value.parse::<u32>().ok().filter(|n| *n > 0).ok_or_else(|| {
+                    ParseError::InvalidValue {
+                        message: format!(
+                            "Invalid window size: {}. Expected a positive integer",
+                            value
+                        ),
+                        usage: USAGE,
+                    }
+                })
             };
-            Ok(
-                json!({ "id": id, "action": "emulatemedia", "colorScheme": color, "reducedMotion": reduced }),
-            )
+            let width = parse_dim(w)?;
+            let height = parse_dim(h)?;
+            Ok(json!({ "id": id, "action": "window_resize", "width": width, "height": height }))
         }
         Some(sub) => Err(ParseError::UnknownSubcommand {
             subcommand: sub.to_string(),
             valid_options: VALID,
         }),
         None => Err(ParseError::MissingArguments {
-            context: "set".to_string(),
-            usage: "set <viewport|device|geo|offline|headers|credentials|media> [args...]",
+            context: "window".to_string(),
+            usage: "window <new|list|switch|close|resize>",
         }),
     }
 }
 
-fn parse_network(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["route", "unroute", "requests"];
-
+fn parse_tab(rest: &[&str], id: &str) -> Result<Value, ParseError> {
     match rest.first().copied() {
-        Some("route") => {
-            let url = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "network route".to_string(),
-                usage: "network route <url> [--abort|--body <json>]",
-            })?;
-            let abort = rest.contains(&"--abort");
-            let body_idx = rest.iter().position(|&s| s == "--body");
-            let body = body_idx.and_then(|i| rest.get(i + 1).copied());
-            Ok(json!({ "id": id, "action": "route", "url": url, "abort": abort, "body": body }))
-        }
-        Some("unroute") => {
-            let mut cmd = json!({ "id": id, "action": "unroute" });
-            if let Some(url) = rest.get(1) {
+        Some("new") => {
+            let mut cmd = json!({ "id": id, "action": "tab_new" });
+            if let Some(url) = rest[1..].iter().find(|s| **s != "--background") {
                 cmd["url"] = json!(url);
             }
+            if rest.contains(&"--background") {
+                cmd["background"] = json!(true);
+            }
             Ok(cmd)
         }
-        Some("requests") => {
-            let clear = rest.contains(&"--clear");
-            let filter_idx = rest.iter().position(|&s| s == "--filter");
-            let filter = filter_idx.and_then(|i| rest.get(i + 1).copied());
-            let mut cmd = json!({ "id": id, "action": "requests", "clear": clear });
-            if let Some(f) = filter {
-                cmd["filter"] = json!(f);
+        Some("duplicate") => {
+            let mut cmd = json!({ "id": id, "action": "tab_duplicate" });
+            if let Some(n) = rest.get(1) {
+                let index = n.parse::<i32>().map_err(|_| ParseError::InvalidValue {
+                    message: format!("Invalid tab index: {}", n),
+                    usage: "tab duplicate [index]",
+                })?;
+                cmd["index"] = json!(index);
             }
             Ok(cmd)
         }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "network".to_string(),
-            usage: "network <route|unroute|requests> [args...]",
-        }),
-    }
-}
-
-fn parse_storage(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["local", "session"];
-
-    match rest.first().copied() {
-        Some("local") | Some("session") => {
-            let storage_type = rest.first().unwrap();
-            let op = rest.get(1).unwrap_or(&"get");
-            let key = rest.get(2);
-            let value = rest.get(3);
-            match *op {
-                "set" => {
-                    let k = key.ok_or_else(|| ParseError::MissingArguments {
-                        context: format!("storage {} set", storage_type),
-                        usage: "storage <local|session> set <key> <value>",
-                    })?;
-                    let v = value.ok_or_else(|| ParseError::MissingArguments {
-                        context: format!("storage {} set", storage_type),
-                        usage: "storage <local|session> set <key> <value>",
+        Some("list") => Ok(json!({ "id": id, "action": "tab_list" })),
+        Some("info") => Ok(json!({ "id": id, "action": "tab_info" })),
+        Some("close") => {
+            const USAGE: &str = "tab close [index] [--all|--others]";
+            let all = rest.contains(&"--all");
+            let others = rest.contains(&"--others");
+            let index = rest[1..].iter().find_map(|s| s.parse::<i32>().ok());
+            if all && others {
+                return Err(ParseError::InvalidValue {
+                    message: "--all and --others cannot be used together".to_string(),
+                    usage: USAGE,
+                });
+            }
+            if index.is_some() && (all || others) {
+                return Err(ParseError::InvalidValue {
+                    message: "A tab index cannot be combined with --all or --others".to_string(),
+                    usage: USAGE,
+                });
+            }
+            let mut cmd = json!({ "id": id, "action": "tab_close" });
+            if let Some(index) = index {
+                cmd["index"] = json!(index);
+            } else if all {
+                cmd["all"] = json!(true);
+            } else if others {
+                cmd["others"] = json!(true);
+            }
+            Ok(cmd)
+        }
+        Some("switch") => parse_tab_switch(&rest[1..], id),
+        Some(n) if n.parse::<i32>().is_ok() => {
+            let index = n.parse::<i32>().expect("already checked parse succeeds");
+            Ok(json!({ "id": id, "action": "tab_switch", "index": index }))
+        }
+        Some("--url") | Some("--title") => parse_tab_switch(rest, id),
+        _ => Ok(json!({ "id": id, "action": "tab_list" })),
+    }
+}
+
+/// Parses `tab switch <index>` or `tab switch --url <pattern>` / `--title <pattern>`.
+fn parse_tab_switch(args: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "tab switch <index> | --url <pattern> | --title <pattern>";
+
+    let mut cmd = json!({ "id": id, "action": "tab_switch" });
+    let mut index = None;
+    let mut has_pattern = false;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i] {
+            flag @ ("--url" | "--title") => {
+                let pattern = args
+                    .get(i + 1)
+                    .ok_or_else(|| ParseError::MissingArguments {
+                        context: format!("tab switch {}", flag),
+                        usage: USAGE,
                     })?;
-                    Ok(
-                        json!({ "id": id, "action": "storage_set", "type": storage_type, "key": k, "value": v }),
-                    )
-                }
-                "clear" => Ok(json!({ "id": id, "action": "storage_clear", "type": storage_type })),
-                _ => {
-                    let mut cmd =
-                        json!({ "id": id, "action": "storage_get", "type": storage_type });
-                    if let Some(k) = key {
-                        cmd.as_object_mut()
-                            .unwrap()
-                            .insert("key".to_string(), json!(k));
-                    }
-                    Ok(cmd)
-                }
+                let field = if flag == "--url" {
+                    "urlPattern"
+                } else {
+                    "titlePattern"
+                };
+                cmd[field] = json!(pattern);
+                has_pattern = true;
+                i += 1;
+            }
+            arg => {
+                let n = arg.parse::<i32>().map_err(|_| ParseError::InvalidValue {
+                    message: format!("Invalid tab index: {}", arg),
+                    usage: USAGE,
+                })?;
+                index = Some(n);
             }
         }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
+        i += 1;
+    }
+
+    match (index, has_pattern) {
+        (Some(_), true) => Err(ParseError::InvalidValue {
+            message: "A tab index cannot be combined with --url or --title".to_string(),
+            usage: USAGE,
         }),
-        None => Err(ParseError::MissingArguments {
-            context: "storage".to_string(),
-            usage: "storage <local|session> [get|set|clear] [key] [value]",
+        (Some(n), false) => {
+            cmd["index"] = json!(n);
+            Ok(cmd)
+        }
+        (None, true) => Ok(cmd),
+        (None, false) => Err(ParseError::MissingArguments {
+            context: "tab switch".to_string(),
+            usage: USAGE,
         }),
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn parse_diff(rest: &[&str], id: &str, flags: &Flags) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["snapshot", "screenshot", "url"];
 
-    fn default_flags() -> Flags {
-        Flags {
-            session: "test".to_string(),
-            json: false,
-            full: false,
-            headed: false,
-            debug: false,
-            headers: None,
-            executable_path: None,
-            extensions: Vec::new(),
-            cdp: None,
-            profile: None,
-            state: None,
-            proxy: None,
-            proxy_bypass: None,
-            args: None,
-            user_agent: None,
-            provider: None,
-            ignore_https_errors: false,
-            allow_file_access: false,
-            device: None,
-            auto_connect: false,
-            session_name: None,
-            cli_executable_path: false,
-            cli_extensions: false,
-            cli_profile: false,
-            cli_state: false,
-            cli_args: false,
-            cli_user_agent: false,
-            cli_proxy: false,
-            cli_proxy_bypass: false,
-            cli_allow_file_access: false,
-            cli_annotate: false,
-            cli_download_path: false,
-            annotate: false,
-            color_scheme: None,
-            download_path: None,
+    match rest.first().copied() {
+        Some("snapshot") => {
+            let mut cmd = json!({ "id": id, "action": "diff_snapshot" });
+            let obj = cmd.as_object_mut().unwrap();
+            let mut i = 1;
+            while i < rest.len() {
+                match rest[i] {
+                    "-b" | "--baseline" => {
+                        if let Some(path) = rest.get(i + 1) {
+                            obj.insert("baseline".to_string(), json!(path));
+                            i += 1;
+                        } else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff snapshot --baseline".to_string(),
+                                usage: "diff snapshot --baseline <file>",
+                            });
+                        }
+                    }
+                    "-s" | "--selector" => {
+                        if let Some(s) = rest.get(i + 1) {
+                            obj.insert("selector".to_string(), json!(s));
+                            i += 1;
+                        } else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff snapshot --selector".to_string(),
+                                usage: "diff snapshot --selector <sel>",
+                            });
+                        }
+                    }
+                    "-c" | "--compact" => {
+                        obj.insert("compact".to_string(), json!(true));
+                    }
+                    "-d" | "--depth" => {
+                        if let Some(d) = rest.get(i + 1) {
+                            match d.parse::<u32>() {
+                                Ok(n) => {
+                                    obj.insert("maxDepth".to_string(), json!(n));
+                                    i += 1;
+                                }
+                                Err(_) => {
+                                    return Err(ParseError::InvalidValue {
+                                        message: format!("Depth must be a non-negative integer, got: {}", d),
+                                        usage: "diff snapshot --depth <n>",
+                                    });
+                                }
+                            }
+                        } else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff snapshot --depth".to_string(),
+                                usage: "diff snapshot --depth <n>",
+                            });
+                        }
+                    }
+                    "--ignore-refs" => {
+                        obj.insert("ignoreRefs".to_string(), json!(true));
+                    }
+                    "--only-additions" => {
+                        obj.insert("onlyAdditions".to_string(), json!(true));
+                    }
+                    "--only-removals" => {
+                        obj.insert("onlyRemovals".to_string(), json!(true));
+                    }
+                    other if other.starts_with('-') => {
+                        return Err(ParseError::InvalidValue {
+                            message: format!("Unknown flag: {}", other),
+                            usage: "diff snapshot [--baseline <file>] [--selector <sel>] [--compact] [--depth <n>] [--ignore-refs] [--only-additions | --only-removals]",
+                        });
+                    }
+                    other => {
+                        return Err(ParseError::InvalidValue {
+                            message: format!("Unexpected argument: {}", other),
+                            usage: "diff snapshot [--baseline <file>] [--selector <sel>] [--compact] [--depth <n>] [--ignore-refs] [--only-additions | --only-removals]",
+                        });
+                    }
+                }
+                i += 1;
+            }
+            if obj.contains_key("onlyAdditions") && obj.contains_key("onlyRemovals") {
+                return Err(ParseError::InvalidValue {
+                    message: "--only-additions and --only-removals cannot be used together"
+                        .to_string(),
+                    usage: "diff snapshot [--only-additions | --only-removals]",
+                });
+            }
+            Ok(cmd)
         }
+        Some("screenshot") => {
+            let mut cmd = json!({ "id": id, "action": "diff_screenshot" });
+            let obj = cmd.as_object_mut().unwrap();
+            let mut i = 1;
+            while i < rest.len() {
+                match rest[i] {
+                    "-b" | "--baseline" => {
+                        if let Some(path) = rest.get(i + 1) {
+                            obj.insert("baseline".to_string(), json!(path));
+                            i += 1;
+                        } else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff screenshot --baseline".to_string(),
+                                usage: "diff screenshot --baseline <file>",
+                            });
+                        }
+                    }
+                    "-o" | "--output" => {
+                        if let Some(path) = rest.get(i + 1) {
+                            obj.insert("output".to_string(), json!(path));
+                            i += 1;
+                        } else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff screenshot --output".to_string(),
+                                usage: "diff screenshot --output <file>",
+                            });
+                        }
+                    }
+                    "-t" | "--threshold" => {
+                        if let Some(t) = rest.get(i + 1) {
+                            match t.parse::<f64>() {
+                                Ok(n) if (0.0..=1.0).contains(&n) => {
+                                    obj.insert("threshold".to_string(), json!(n));
+                                    i += 1;
+                                }
+                                Ok(n) => {
+                                    return Err(ParseError::InvalidValue {
+                                        message: format!("Threshold must be between 0 and 1, got {}", n),
+                                        usage: "diff screenshot --threshold <0-1>",
+                                    });
+                                }
+                                Err(_) => {
+                                    return Err(ParseError::InvalidValue {
+                                        message: format!("Invalid threshold value: {}", t),
+                                        usage: "diff screenshot --threshold <0-1>",
+                                    });
+                                }
+                            }
+                        } else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff screenshot --threshold".to_string(),
+                                usage: "diff screenshot --threshold <0-1>",
+                            });
+                        }
+                    }
+                    "-s" | "--selector" => {
+                        if let Some(s) = rest.get(i + 1) {
+                            obj.insert("selector".to_string(), json!(s));
+                            i += 1;
+                        } else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff screenshot --selector".to_string(),
+                                usage: "diff screenshot --selector <sel>",
+                            });
+                        }
+                    }
+                    "--full" => {
+                        obj.insert("fullPage".to_string(), json!(true));
+                    }
+                    "--mask" => {
+                        if let Some(s) = rest.get(i + 1) {
+                            let masks = obj.entry("mask").or_insert_with(|| json!([]));
+                            if let Some(masks) = masks.as_array_mut() {
+                                masks.push(json!(s));
+                            }
+                            i += 1;
+                        } else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff screenshot --mask".to_string(),
+                                usage: "diff screenshot --baseline <file> --mask <sel>",
+                            });
+                        }
+                    }
+                    "--ignore-colors" => {
+                        obj.insert("ignoreColors".to_string(), json!(true));
+                    }
+                    "--ignore-antialiasing" => {
+                        obj.insert("ignoreAntialiasing".to_string(), json!(true));
+                    }
+                    "--max-diff-pixels" => {
+                        if let Some(n) = rest.get(i + 1) {
+                            match n.parse::<u64>() {
+                                Ok(n) => {
+                                    obj.insert("maxDiffPixels".to_string(), json!(n));
+                                    i += 1;
+                                }
+                                Err(_) => {
+                                    return Err(ParseError::InvalidValue {
+                                        message: format!("Max diff pixels must be a non-negative integer, got: {}", n),
+                                        usage: "diff screenshot --max-diff-pixels <n>",
+                                    });
+                                }
+                            }
+                        } else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff screenshot --max-diff-pixels".to_string(),
+                                usage: "diff screenshot --max-diff-pixels <n>",
+                            });
+                        }
+                    }
+                    other if other.starts_with('-') => {
+                        return Err(ParseError::InvalidValue {
+                            message: format!("Unknown flag: {}", other),
+                            usage: "diff screenshot --baseline <file> [--output <file>] [--threshold <0-1> | --max-diff-pixels <n>] [--selector <sel>] [--mask <sel>]... [--full] [--ignore-colors] [--ignore-antialiasing]",
+                        });
+                    }
+                    other => {
+                        return Err(ParseError::InvalidValue {
+                            message: format!("Unexpected argument: {}", other),
+                            usage: "diff screenshot --baseline <file> [--output <file>] [--threshold <0-1> | --max-diff-pixels <n>] [--selector <sel>] [--mask <sel>]... [--full] [--ignore-colors] [--ignore-antialiasing]",
+                        });
+                    }
+                }
+                i += 1;
+            }
+            if obj.contains_key("threshold") && obj.contains_key("maxDiffPixels") {
+                return Err(ParseError::InvalidValue {
+                    message: "--threshold and --max-diff-pixels cannot be used together".to_string(),
+                    usage: "diff screenshot --baseline <file> [--threshold <0-1> | --max-diff-pixels <n>]",
+                });
+            }
+            if flags.full {
+                obj.insert("fullPage".to_string(), json!(true));
+            }
+            if !obj.contains_key("baseline") {
+                return Err(ParseError::MissingArguments {
+                    context: "diff screenshot".to_string(),
+                    usage: "diff screenshot --baseline <file>",
+                });
+            }
+            Ok(cmd)
+        }
+        Some("url") => {
+            let url1 = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "diff url".to_string(),
+                usage: "diff url <url1> <url2>",
+            })?;
+            let url2 = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
+                context: "diff url".to_string(),
+                usage: "diff url <url1> <url2>",
+            })?;
+            let mut cmd = json!({
+                "id": id,
+                "action": "diff_url",
+                "url1": url1,
+                "url2": url2,
+            });
+            let obj = cmd.as_object_mut().unwrap();
+            let mut i = 3;
+            while i < rest.len() {
+                match rest[i] {
+                    "--screenshot" => {
+                        obj.insert("screenshot".to_string(), json!(true));
+                    }
+                    "--full" => {
+                        obj.insert("fullPage".to_string(), json!(true));
+                    }
+                    "--output-dir" => {
+                        let Some(dir) = rest.get(i + 1) else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff url --output-dir".to_string(),
+                                usage: "diff url <url1> <url2> --screenshot --output-dir <dir>",
+                            });
+                        };
+                        let path = std::path::Path::new(dir);
+                        if path.exists() && !path.is_dir() {
+                            return Err(ParseError::InvalidValue {
+                                message: format!("--output-dir is not a directory: {}", dir),
+                                usage: "diff url <url1> <url2> --screenshot --output-dir <dir>",
+                            });
+                        }
+                        obj.insert("outputDir".to_string(), json!(dir));
+                        if !path.exists() {
+                            obj.insert("createDir".to_string(), json!(true));
+                        }
+                        i += 1;
+                    }
+                    "--wait-until" => {
+                        if let Some(val) = rest.get(i + 1) {
+                            obj.insert("waitUntil".to_string(), json!(val));
+                            i += 1;
+                        } else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff url --wait-until".to_string(),
+                                usage: "diff url <url1> <url2> --wait-until <load|domcontentloaded|networkidle>",
+                            });
+                        }
+                    }
+                    "-s" | "--selector" => {
+                        if let Some(s) = rest.get(i + 1) {
+                            obj.insert("selector".to_string(), json!(s));
+                            i += 1;
+                        } else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff url --selector".to_string(),
+                                usage: "diff url <url1> <url2> --selector <sel>",
+                            });
+                        }
+                    }
+                    "-c" | "--compact" => {
+                        obj.insert("compact".to_string(), json!(true));
+                    }
+                    "-d" | "--depth" => {
+                        if let Some(d) = rest.get(i + 1) {
+                            match d.parse::<u32>() {
+                                Ok(n) => {
+                                    obj.insert("maxDepth".to_string(), json!(n));
+                                    i += 1;
+                                }
+                                Err(_) => {
+                                    return Err(ParseError::InvalidValue {
+                                        message: format!("Depth must be a non-negative integer, got: {}", d),
+                                        usage: "diff url <url1> <url2> --depth <n>",
+                                    });
+                                }
+                            }
+                        } else {
+                            return Err(ParseError::MissingArguments {
+                                context: "diff url --depth".to_string(),
+                                usage: "diff url <url1> <url2> --depth <n>",
+                            });
+                        }
+                    }
+                    other if other.starts_with('-') => {
+                        return Err(ParseError::InvalidValue {
+                            message: format!("Unknown flag: {}", other),
+                            usage: "diff url <url1> <url2> [--screenshot [--output-dir <dir>]] [--full] [--wait-until <strategy>] [--selector <sel>] [--compact] [--depth <n>]",
+                        });
+                    }
+                    other => {
+                        return Err(ParseError::InvalidValue {
+                            message: format!("Unexpected argument: {}", other),
+                            usage: "diff url <url1> <url2> [--screenshot [--output-dir <dir>]] [--full] [--wait-until <strategy>] [--selector <sel>] [--compact] [--depth <n>]",
+                        });
+                    }
+                }
+                i += 1;
+            }
+            if obj.contains_key("outputDir") && !obj.contains_key("screenshot") {
+                return Err(ParseError::MissingArguments {
+                    context: "diff url --output-dir requires --screenshot".to_string(),
+                    usage: "diff url <url1> <url2> --screenshot --output-dir <dir>",
+                });
+            }
+            if flags.full {
+                obj.insert("fullPage".to_string(), json!(true));
+            }
+            Ok(cmd)
+        }
+        Some(sub) => Err(ParseError::UnknownSubcommand {
+            subcommand: sub.to_string(),
+            valid_options: VALID,
+        }),
+        None => Err(ParseError::MissingArguments {
+            context: "diff".to_string(),
+            usage: "diff <snapshot|screenshot|url>",
+        }),
+    }
+}
+
+fn parse_get(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &[
+        "text", "all-text", "nth", "html", "value", "attr", "aria", "url", "title", "count", "box",
+        "styles",
+    ];
+
+    match rest.first().copied() {
+        Some("text") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "get text".to_string(),
+                usage: "get text <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "gettext", "selector": sel }))
+        }
+        Some("all-text") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "get all-text".to_string(),
+                usage: "get all-text <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "alltext", "selector": sel }))
+        }
+        Some("nth") => {
+            const USAGE: &str = "get nth <index> text <selector>";
+            let missing = || ParseError::MissingArguments {
+                context: "get nth".to_string(),
+                usage: USAGE,
+            };
+            let index_str = rest.get(1).ok_or_else(missing)?;
+            let index = index_str
+                .parse::<i32>()
+                .map_err(|_| ParseError::InvalidValue {
+                    message: format!("Invalid index: {}. Expected an integer", index_str),
+                    usage: USAGE,
+                })?;
+            match rest.get(2).copied() {
+                Some("text") => {}
+                Some(other) => {
+                    return Err(ParseError::UnknownSubcommand {
+                        subcommand: other.to_string(),
+                        valid_options: &["text"],
+                    })
+                }
+                None => return Err(missing()),
+            }
+            let sel = rest.get(3).ok_or_else(missing)?;
+            Ok(json!({ "id": id, "action": "nthtext", "selector": sel, "index": index }))
+        }
+        Some("html") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "get html".to_string(),
+                usage: "get html <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "innerhtml", "selector": sel }))
+        }
+        Some("value") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "get value".to_string(),
+                usage: "get value <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "inputvalue", "selector": sel }))
+        }
+        Some("attr") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "get attr".to_string(),
+                usage: "get attr <selector> <attribute>",
+            })?;
+            let attr = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
+                context: "get attr".to_string(),
+                usage: "get attr <selector> <attribute>",
+            })?;
+            Ok(json!({ "id": id, "action": "getattribute", "selector": sel, "attribute": attr }))
+        }
+        Some("aria") => {
+            const USAGE: &str = "get aria <selector> [aria-attribute]";
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "get aria".to_string(),
+                usage: USAGE,
+            })?;
+            let mut cmd = json!({ "id": id, "action": "getaria", "selector": sel });
+            if let Some(attr) = rest.get(2) {
+                if !attr.starts_with("aria-") && *attr != "role" {
+                    return Err(ParseError::InvalidValue {
+                        message: format!(
+                            "Invalid ARIA attribute: {}. Expected aria-* or role",
+                            attr
+                        ),
+                        usage: USAGE,
+                    });
+                }
+                cmd["attribute"] = json!(attr);
+            }
+            Ok(cmd)
+        }
+        Some("url") => Ok(json!({ "id": id, "action": "url" })),
+        Some("title") => Ok(json!({ "id": id, "action": "title" })),
+        Some("count") => {
+            let (fail_on_zero, rest) = take_bool_flag(&rest[1..], "--fail-on-zero");
+            let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
+                context: "get count".to_string(),
+                usage: "get count <selector> [--fail-on-zero]",
+            })?;
+            let mut cmd = json!({ "id": id, "action": "count", "selector": sel });
+            if fail_on_zero {
+                cmd["failOnZero"] = json!(true);
+            }
+            Ok(cmd)
+        }
+        Some("box") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "get box".to_string(),
+                usage: "get box <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "boundingbox", "selector": sel }))
+        }
+        Some("styles") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "get styles".to_string(),
+                usage: "get styles <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "styles", "selector": sel }))
+        }
+        Some(sub) => Err(ParseError::UnknownSubcommand {
+            subcommand: sub.to_string(),
+            valid_options: VALID,
+        }),
+        None => Err(ParseError::MissingArguments {
+            context: "get".to_string(),
+            usage:
+                "get <text|all-text|nth|html|value|attr|aria|url|title|count|box|styles> [args...]",
+        }),
+    }
+}
+
+/// Parses an `is` check; `--not` (stripped by `clean_args` into `flags.negate`)
+/// asks the daemon to invert the result.
+fn parse_is(rest: &[&str], id: &str, flags: &Flags) -> Result<Value, ParseError> {
+    let mut cmd = parse_is_check(rest, id)?;
+    if flags.negate {
+        cmd["negate"] = json!(true);
+    }
+    Ok(cmd)
+}
+
+fn parse_is_check(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &[
+        "visible", "enabled", "checked", "hidden", "editable", "disabled", "focused", "empty",
+    ];
+
+    match rest.first().copied() {
+        Some("visible") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "is visible".to_string(),
+                usage: "is visible <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "isvisible", "selector": sel }))
+        }
+        Some("enabled") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "is enabled".to_string(),
+                usage: "is enabled <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "isenabled", "selector": sel }))
+        }
+        Some("checked") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "is checked".to_string(),
+                usage: "is checked <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "ischecked", "selector": sel }))
+        }
+        Some("hidden") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "is hidden".to_string(),
+                usage: "is hidden <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "ishidden", "selector": sel }))
+        }
+        Some("editable") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "is editable".to_string(),
+                usage: "is editable <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "iseditable", "selector": sel }))
+        }
+        Some("disabled") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "is disabled".to_string(),
+                usage: "is disabled <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "isdisabled", "selector": sel }))
+        }
+        Some("focused") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "is focused".to_string(),
+                usage: "is focused <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "isfocused", "selector": sel }))
+        }
+        Some("empty") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "is empty".to_string(),
+                usage: "is empty <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "isempty", "selector": sel }))
+        }
+        Some(sub) => Err(ParseError::UnknownSubcommand {
+            subcommand: sub.to_string(),
+            valid_options: VALID,
+        }),
+        None => Err(ParseError::MissingArguments {
+            context: "is".to_string(),
+            usage: "is <visible|enabled|checked|hidden|editable|disabled|focused|empty> <selector> [--not]",
+        }),
+    }
+}
+
+fn parse_find(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "find <locator> <value> [action] [text] [--count <n>] [--timeout <ms>]";
+
+    let expected_count = match rest.iter().position(|&s| s == "--count") {
+        Some(idx) => {
+            let value = rest
+                .get(idx + 1)
+                .ok_or_else(|| ParseError::MissingArguments {
+                    context: "--count".to_string(),
+                    usage: USAGE,
+                })?;
+            Some(value.parse::<u32>().map_err(|_| ParseError::InvalidValue {
+                message: format!(
+                    "Invalid --count value: {}. Expected a non-negative integer",
+                    value
+                ),
+                usage: USAGE,
+            })?)
+        }
+        None => None,
+    };
+    let rest = positional_args(rest, &["--count"]);
+
+    let default_subaction = if expected_count.is_some() {
+        "count"
+    } else {
+        "click"
+    };
+    let mut cmd = parse_find_locator(&rest, id, default_subaction)?;
+    if let Some(n) = expected_count {
+        // Only the count action reports a count to check against
+        match cmd.get("subaction").and_then(|v| v.as_str()) {
+            None | Some("count") => {}
+            Some(other) => {
+                return Err(ParseError::InvalidValue {
+                    message: format!("--count only applies to the count action, not {}", other),
+                    usage: USAGE,
+                })
+            }
+        }
+        cmd["expectedCount"] = json!(n);
+        cmd["subaction"] = json!("count");
+    }
+    Ok(cmd)
+}
+
+fn parse_find_locator(
+    rest: &[&str],
+    id: &str,
+    default_subaction: &str,
+) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &[
+        "role",
+        "text",
+        "label",
+        "placeholder",
+        "alt",
+        "title",
+        "testid",
+        "first",
+        "last",
+        "nth",
+        "filter",
+        "has",
+    ];
+
+    let locator = rest.first().ok_or_else(|| ParseError::MissingArguments {
+        context: "find".to_string(),
+        usage: "find <locator> <value> [action] [text]",
+    })?;
+
+    let name_idx = rest.iter().position(|&s| s == "--name");
+    let name = name_idx.and_then(|i| rest.get(i + 1).copied());
+    let exact = rest.contains(&"--exact");
+
+    match *locator {
+        "role" | "text" | "label" | "placeholder" | "alt" | "title" | "testid" | "first"
+        | "last" => {
+            let value = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: format!("find {}", locator),
+                usage: match *locator {
+                    "role" => "find role <role> [action] [--name <name>] [--exact]",
+                    "text" => "find text <text> [action] [--exact]",
+                    "label" => "find label <label> [action] [text] [--exact]",
+                    "placeholder" => "find placeholder <text> [action] [text] [--exact]",
+                    "alt" => "find alt <text> [action] [--exact]",
+                    "title" => "find title <text> [action] [--exact]",
+                    "testid" => "find testid <id> [action] [text]",
+                    "first" => "find first <selector> [action] [text]",
+                    "last" => "find last <selector> [action] [text]",
+                    _ => "find <locator> <value> [action] [text]",
+                },
+            })?;
+            let subaction = rest.get(2).copied().unwrap_or(default_subaction);
+            let fill_value = if rest.len() > 3 {
+                Some(rest[3..].join(" "))
+            } else {
+                None
+            };
+
+            match *locator {
+                "role" => {
+                    let mut cmd = json!({ "id": id, "action": "getbyrole", "role": value, "subaction": subaction, "name": name, "exact": exact });
+                    if let Some(v) = fill_value {
+                        cmd["value"] = json!(v);
+                    }
+                    Ok(cmd)
+                }
+                "text" => Ok(
+                    json!({ "id": id, "action": "getbytext", "text": value, "subaction": subaction, "exact": exact }),
+                ),
+                "label" => {
+                    let mut cmd = json!({ "id": id, "action": "getbylabel", "label": value, "subaction": subaction, "exact": exact });
+                    if let Some(v) = fill_value {
+                        cmd["value"] = json!(v);
+                    }
+                    Ok(cmd)
+                }
+                "placeholder" => {
+                    let mut cmd = json!({ "id": id, "action": "getbyplaceholder", "placeholder": value, "subaction": subaction, "exact": exact });
+                    if let Some(v) = fill_value {
+                        cmd["value"] = json!(v);
+                    }
+                    Ok(cmd)
+                }
+                "alt" => Ok(
+                    json!({ "id": id, "action": "getbyalttext", "text": value, "subaction": subaction, "exact": exact }),
+                ),
+                "title" => Ok(
+                    json!({ "id": id, "action": "getbytitle", "text": value, "subaction": subaction, "exact": exact }),
+                ),
+                "testid" => {
+                    let mut cmd = json!({ "id": id, "action": "getbytestid", "testId": value, "subaction": subaction });
+                    if let Some(v) = fill_value {
+                        cmd["value"] = json!(v);
+                    }
+                    Ok(cmd)
+                }
+                "first" => {
+                    let mut cmd = json!({ "id": id, "action": "nth", "selector": value, "index": 0, "subaction": subaction });
+                    if let Some(v) = fill_value {
+                        cmd["value"] = json!(v);
+                    }
+                    Ok(cmd)
+                }
+                "last" => {
+                    let mut cmd = json!({ "id": id, "action": "nth", "selector": value, "index": -1, "subaction": subaction });
+                    if let Some(v) = fill_value {
+                        cmd["value"] = json!(v);
+                    }
+                    Ok(cmd)
+                }
+                _ => unreachable!(),
+            }
+        }
+        "nth" => {
+            let idx_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "find nth".to_string(),
+                usage: "find nth <index> <selector> [action] [text]",
+            })?;
+            let idx = idx_str
+                .parse::<i32>()
+                .map_err(|_| ParseError::MissingArguments {
+                    context: "find nth".to_string(),
+                    usage: "find nth <index> <selector> [action] [text]",
+                })?;
+            let sel = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
+                context: "find nth".to_string(),
+                usage: "find nth <index> <selector> [action] [text]",
+            })?;
+            let sub = rest.get(3).copied().unwrap_or(default_subaction);
+            let fv = if rest.len() > 4 {
+                Some(rest[4..].join(" "))
+            } else {
+                None
+            };
+            let mut cmd = json!({ "id": id, "action": "nth", "selector": sel, "index": idx, "subaction": sub });
+            if let Some(v) = fv {
+                cmd["value"] = json!(v);
+            }
+            Ok(cmd)
+        }
+        "filter" => {
+            const USAGE: &str = "find filter <selector> --has-text <text> | --has <selector>";
+            let missing = || ParseError::MissingArguments {
+                context: "find filter".to_string(),
+                usage: USAGE,
+            };
+            let base = rest
+                .get(1)
+                .filter(|s| !s.starts_with("--"))
+                .ok_or_else(missing)?;
+            let flag_value = |flag: &str| -> Result<Option<&str>, ParseError> {
+                match rest.iter().position(|&s| s == flag) {
+                    Some(i) => rest
+                        .get(i + 1)
+                        .copied()
+                        .filter(|v| !v.starts_with("--"))
+                        .map(Some)
+                        .ok_or_else(missing),
+                    None => Ok(None),
+                }
+            };
+            let has_text = flag_value("--has-text")?;
+            let has = flag_value("--has")?;
+            if has_text.is_none() && has.is_none() {
+                return Err(missing());
+            }
+            let mut cmd = json!({ "id": id, "action": "findfilter", "selector": base });
+            if let Some(t) = has_text {
+                cmd["hasText"] = json!(t);
+            }
+            if let Some(h) = has {
+                cmd["has"] = json!(h);
+            }
+            Ok(cmd)
+        }
+        "has" => {
+            let missing = || ParseError::MissingArguments {
+                context: "find has".to_string(),
+                usage: "find has <selector> <sub-selector>",
+            };
+            let base = rest.get(1).ok_or_else(missing)?;
+            let sub = rest.get(2).ok_or_else(missing)?;
+            Ok(json!({ "id": id, "action": "findhas", "selector": base, "has": sub }))
+        }
+        _ => Err(ParseError::UnknownSubcommand {
+            subcommand: locator.to_string(),
+            valid_options: VALID,
+        }),
+    }
+}
+
+/// Parses integer `<x> <y>` coordinates from `rest[1]` and `rest[2]`.
+fn parse_coordinates(
+    rest: &[&str],
+    context: &str,
+    usage: &'static str,
+) -> Result<(i32, i32), ParseError> {
+    let missing = || ParseError::MissingArguments {
+        context: context.to_string(),
+        usage,
+    };
+    let x_str = rest.get(1).ok_or_else(missing)?;
+    let y_str = rest.get(2).ok_or_else(missing)?;
+    let parse = |s: &str| {
+        s.parse::<i32>().map_err(|_| ParseError::InvalidValue {
+            message: format!("Invalid coordinate: {}. Expected an integer", s),
+            usage,
+        })
+    };
+    Ok((parse(x_str)?, parse(y_str)?))
+}
+
+fn parse_mouse(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &[
+        "move",
+        "down",
+        "up",
+        "wheel",
+        "click",
+        "dblclick",
+        "rightclick",
+    ];
+
+    match rest.first().copied() {
+        Some("move") => {
+            let x_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "mouse move".to_string(),
+                usage: "mouse move <x> <y>",
+            })?;
+            let y_str = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
+                context: "mouse move".to_string(),
+                usage: "mouse move <x> <y>",
+            })?;
+            let x = x_str
+                .parse::<i32>()
+                .map_err(|_| ParseError::MissingArguments {
+                    context: "mouse move".to_string(),
+                    usage: "mouse move <x> <y>",
+                })?;
+            let y = y_str
+                .parse::<i32>()
+                .map_err(|_| ParseError::MissingArguments {
+                    context: "mouse move".to_string(),
+                    usage: "mouse move <x> <y>",
+                })?;
+            let mut cmd = json!({ "id": id, "action": "mousemove", "x": x, "y": y });
+            // --steps comes after the coordinates: mouse move <x> <y> --steps <n>
+            if let Some(idx) = rest.iter().skip(3).position(|&s| s == "--steps") {
+                let value = rest
+                    .get(idx + 4)
+                    .ok_or_else(|| ParseError::MissingArguments {
+                        context: "mouse move --steps".to_string(),
+                        usage: "mouse move <x> <y> [--steps <n>]",
+                    })?;
+                let steps = value
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| ParseError::InvalidValue {
+                        message: format!(
+                            "Invalid --steps value: {}. Expected a positive integer",
+                            value
+                        ),
+                        usage: "mouse move <x> <y> [--steps <n>]",
+                    })?;
+                cmd["steps"] = json!(steps);
+            }
+            Ok(cmd)
+        }
+        Some("down") => {
+            Ok(json!({ "id": id, "action": "mousedown", "button": rest.get(1).unwrap_or(&"left") }))
+        }
+        Some("up") => {
+            Ok(json!({ "id": id, "action": "mouseup", "button": rest.get(1).unwrap_or(&"left") }))
+        }
+        Some("wheel") => {
+            let dy = rest
+                .get(1)
+                .and_then(|s| s.parse::<i32>().ok())
+                .unwrap_or(100);
+            let dx = rest.get(2).and_then(|s| s.parse::<i32>().ok()).unwrap_or(0);
+            Ok(json!({ "id": id, "action": "wheel", "deltaX": dx, "deltaY": dy }))
+        }
+        Some(sub @ ("click" | "dblclick" | "rightclick")) => {
+            let usage = match sub {
+                "click" => "mouse click <x> <y>",
+                "dblclick" => "mouse dblclick <x> <y>",
+                _ => "mouse rightclick <x> <y>",
+            };
+            let (x, y) = parse_coordinates(rest, &format!("mouse {}", sub), usage)?;
+            Ok(json!({ "id": id, "action": format!("mouse{}", sub), "x": x, "y": y }))
+        }
+        Some(sub) => Err(ParseError::UnknownSubcommand {
+            subcommand: sub.to_string(),
+            valid_options: VALID,
+        }),
+        None => Err(ParseError::MissingArguments {
+            context: "mouse".to_string(),
+            usage: "mouse <move|down|up|wheel|click|dblclick|rightclick> [args...]",
+        }),
+    }
+}
+
+fn parse_set(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &[
+        "viewport",
+        "device",
+        "geo",
+        "geolocation",
+        "offline",
+        "headers",
+        "credentials",
+        "auth",
+        "media",
+        "timezone",
+        "locale",
+        "permissions",
+        "timeout",
+    ];
+
+    match rest.first().copied() {
+        Some("viewport") => {
+            const USAGE: &str = "set viewport <width> <height> | --preset <name> [--device-scale-factor <n>] [--is-mobile] [--has-touch]";
+            let mut positional = Vec::new();
+            let mut preset = None;
+            let mut scale_factor = None;
+            let mut is_mobile = false;
+            let mut has_touch = false;
+            let mut i = 1;
+            while i < rest.len() {
+                match rest[i] {
+                    "--device-scale-factor" => {
+                        let value = rest.get(i + 1).ok_or_else(|| ParseError::MissingArguments {
+                            context: "set viewport --device-scale-factor".to_string(),
+                            usage: USAGE,
+                        })?;
+                        let factor = value
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|f| *f > 0.0 && f.is_finite())
+                            .ok_or_else(|| ParseError::InvalidValue {
+                                message: format!(
+                                    "Invalid --device-scale-factor value: {}. Expected a positive number",
+                                    value
+                                ),
+                                usage: USAGE,
+                            })?;
+                        scale_factor = Some(factor);
+                        i += 1;
+                    }
+                    "--preset" => {
+                        let name = rest.get(i + 1).ok_or_else(|| ParseError::MissingArguments {
+                            context: "set viewport --preset".to_string(),
+                            usage: USAGE,
+                        })?;
+                        preset = Some(find_viewport_preset(name, USAGE)?);
+                        i += 1;
+                    }
+                    "--is-mobile" => is_mobile = true,
+                    "--has-touch" => has_touch = true,
+                    arg => positional.push(arg),
+                }
+                i += 1;
+            }
+
+            let has_extras = scale_factor.is_some() || is_mobile || has_touch;
+            let mut cmd = if let Some(&(_, width, height, factor, mobile)) = preset {
+                if !positional.is_empty() {
+                    return Err(ParseError::InvalidValue {
+                        message: "--preset cannot be combined with a width and height"
+                            .to_string(),
+                        usage: USAGE,
+                    });
+                }
+                json!({
+                    "id": id,
+                    "action": "viewport",
+                    "width": width,
+                    "height": height,
+                    "deviceScaleFactor": factor,
+                    "isMobile": mobile,
+                })
+            } else if positional.is_empty() && has_extras {
+                json!({ "id": id, "action": "viewport" })
+            } else {
+                let missing = || ParseError::MissingArguments {
+                    context: "set viewport".to_string(),
+                    usage: USAGE,
+                };
+                let w = positional
+                    .first()
+                    .ok_or_else(missing)?
+                    .parse::<i32>()
+                    .map_err(|_| missing())?;
+                let h = positional
+                    .get(1)
+                    .ok_or_else(missing)?
+                    .parse::<i32>()
+                    .map_err(|_| missing())?;
+                json!({ "id": id, "action": "viewport", "width": w, "height": h })
+            };
+            if let Some(factor) = scale_factor {
+                cmd["deviceScaleFactor"] = json!(factor);
+            }
+            if is_mobile {
+                cmd["isMobile"] = json!(true);
+            }
+            if has_touch {
+                cmd["hasTouch"] = json!(true);
+            }
+            Ok(cmd)
+        }
+        Some("device") => {
+            let dev = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "set device".to_string(),
+                usage: "set device <name>",
+            })?;
+            Ok(json!({ "id": id, "action": "device", "device": dev }))
+        }
+        Some("geo") | Some("geolocation") => {
+            let lat_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "set geo".to_string(),
+                usage: "set geo <latitude> <longitude>",
+            })?;
+            let lng_str = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
+                context: "set geo".to_string(),
+                usage: "set geo <latitude> <longitude>",
+            })?;
+            let lat = lat_str
+                .parse::<f64>()
+                .map_err(|_| ParseError::MissingArguments {
+                    context: "set geo".to_string(),
+                    usage: "set geo <latitude> <longitude>",
+                })?;
+            let lng = lng_str
+                .parse::<f64>()
+                .map_err(|_| ParseError::MissingArguments {
+                    context: "set geo".to_string(),
+                    usage: "set geo <latitude> <longitude>",
+                })?;
+            Ok(json!({ "id": id, "action": "geolocation", "latitude": lat, "longitude": lng }))
+        }
+        Some("offline") => {
+            let off = rest
+                .get(1)
+                .map(|s| *s != "off" && *s != "false")
+                .unwrap_or(true);
+            Ok(json!({ "id": id, "action": "offline", "offline": off }))
+        }
+        Some("headers") => {
+            let headers_json = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "set headers".to_string(),
+                usage: "set headers <json>",
+            })?;
+            // Parse the JSON string into an object
+            let headers: serde_json::Value =
+                serde_json::from_str(headers_json).map_err(|_| ParseError::MissingArguments {
+                    context: "set headers".to_string(),
+                    usage: "set headers <json> (must be valid JSON object)",
+                })?;
+            Ok(json!({ "id": id, "action": "headers", "headers": headers }))
+        }
+        Some("credentials") | Some("auth") => {
+            let user = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "set credentials".to_string(),
+                usage: "set credentials <username> <password>",
+            })?;
+            let pass = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
+                context: "set credentials".to_string(),
+                usage: "set credentials <username> <password>",
+            })?;
+            Ok(json!({ "id": id, "action": "credentials", "username": user, "password": pass }))
+        }
+        Some("media") => {
+            const USAGE: &str = "set media [dark|light] [reduced-motion] [--forced-colors active|none] [--contrast more|no-preference]";
+            let flag_value = |flag: &str, valid: &[&str]| -> Result<Option<String>, ParseError> {
+                let Some(idx) = rest.iter().position(|&s| s == flag) else {
+                    return Ok(None);
+                };
+                let value = rest.get(idx + 1).ok_or_else(|| ParseError::MissingArguments {
+                    context: format!("set media {}", flag),
+                    usage: USAGE,
+                })?;
+                if !valid.contains(value) {
+                    return Err(ParseError::InvalidValue {
+                        message: format!(
+                            "Invalid {} value: {}. Expected one of: {}",
+                            flag,
+                            value,
+                            valid.join(", ")
+                        ),
+                        usage: USAGE,
+                    });
+                }
+                Ok(Some(value.to_string()))
+            };
+
+            let mut cmd = json!({ "id": id, "action": "emulatemedia" });
+            if rest.contains(&"dark") {
+                cmd["colorScheme"] = json!("dark");
+            } else if rest.contains(&"light") {
+                cmd["colorScheme"] = json!("light");
+            }
+            if rest.contains(&"reduced-motion") {
+                cmd["reducedMotion"] = json!("reduce");
+            }
+            let forced_colors = flag_value("--forced-colors", &["active", "none"])?
+                .or_else(|| rest.contains(&"forced-colors").then(|| "active".to_string()));
+            if let Some(value) = forced_colors {
+                cmd["forcedColors"] = json!(value);
+            }
+            if let Some(value) = flag_value("--contrast", &["more", "no-preference"])? {
+                cmd["contrast"] = json!(value);
+            }
+            Ok(cmd)
+        }
+        Some("timeout") => {
+            let value = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "set timeout".to_string(),
+                usage: "set timeout <ms>",
+            })?;
+            let timeout = value.parse::<u64>().map_err(|_| ParseError::InvalidValue {
+                message: format!(
+                    "Invalid timeout: {}. Expected a non-negative number of milliseconds",
+                    value
+                ),
+                usage: "set timeout <ms>",
+            })?;
+            Ok(json!({ "id": id, "action": "settimeout", "timeout": timeout }))
+        }
+        Some("timezone") => {
+            let tz = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "set timezone".to_string(),
+                usage: "set timezone <timezone-id>",
+            })?;
+            Ok(json!({ "id": id, "action": "timezone", "timezone": tz }))
+        }
+        Some("locale") => {
+            let locale = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "set locale".to_string(),
+                usage: "set locale <locale>",
+            })?;
+            Ok(json!({ "id": id, "action": "locale", "locale": locale }))
+        }
+        Some("permissions") => {
+            let origin = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "set permissions".to_string(),
+                usage: "set permissions <origin> <permission> [permission...]",
+            })?;
+            let permissions = &rest[2..];
+            if permissions.is_empty() {
+                return Err(ParseError::MissingArguments {
+                    context: "set permissions".to_string(),
+                    usage: "set permissions <origin> <permission> [permission...]",
+                });
+            }
+            Ok(
+                json!({ "id": id, "action": "permissions", "origin": origin, "permissions": permissions }),
+            )
+        }
+        Some(sub) => Err(ParseError::UnknownSubcommand {
+            subcommand: sub.to_string(),
+            valid_options: VALID,
+        }),
+        None => Err(ParseError::MissingArguments {
+            context: "set".to_string(),
+            usage: "set <viewport|device|geo|offline|headers|credentials|media|timezone|locale|permissions|timeout> [args...]",
+        }),
+    }
+}
+
+fn parse_network(rest: &[&str], id: &str, flags: &Flags) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["route", "unroute", "requests", "throttle", "har"];
+
+    match rest.first().copied() {
+        Some("route") => parse_network_route(rest, id, flags),
+        Some("unroute") => {
+            let mut cmd = json!({ "id": id, "action": "unroute" });
+            if let Some(url) = rest.get(1) {
+                cmd["url"] = json!(url);
+            }
+            Ok(cmd)
+        }
+        Some("requests") => parse_network_requests(rest, id),
+        Some("throttle") => parse_network_throttle(rest, id),
+        Some("har") => {
+            const VALID_HAR: &[&str] = &["save", "load"];
+            match rest.get(1).copied() {
+                Some(op @ ("save" | "load")) => {
+                    let path = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
+                        context: format!("network har {}", op),
+                        usage: if op == "save" {
+                            "network har save <path>"
+                        } else {
+                            "network har load <path>"
+                        },
+                    })?;
+                    Ok(json!({ "id": id, "action": format!("har_{}", op), "path": path }))
+                }
+                Some(sub) => Err(ParseError::UnknownSubcommand {
+                    subcommand: sub.to_string(),
+                    valid_options: VALID_HAR,
+                }),
+                None => Err(ParseError::MissingArguments {
+                    context: "network har".to_string(),
+                    usage: "network har <save|load> <path>",
+                }),
+            }
+        }
+        Some(sub) => Err(ParseError::UnknownSubcommand {
+            subcommand: sub.to_string(),
+            valid_options: VALID,
+        }),
+        None => Err(ParseError::MissingArguments {
+            context: "network".to_string(),
+            usage: "network <route|unroute|requests|throttle|har> [args...]",
+        }),
+    }
+}
+
+fn parse_network_route(rest: &[&str], id: &str, flags: &Flags) -> Result<Value, ParseError> {
+    const USAGE: &str = "network route <url> [--abort|--body <json>|--file <path>] [--content-type <mime>] [--status <code>] [--delay <ms>] [--redirect <url>] [--headers <json>]";
+
+    let url = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+        context: "network route".to_string(),
+        usage: USAGE,
+    })?;
+    let flag_value = |flag: &str| -> Result<Option<&str>, ParseError> {
+        match rest.iter().position(|&s| s == flag) {
+            Some(i) => {
+                rest.get(i + 1)
+                    .copied()
+                    .map(Some)
+                    .ok_or_else(|| ParseError::MissingArguments {
+                        context: format!("network route {}", flag),
+                        usage: USAGE,
+                    })
+            }
+            None => Ok(None),
+        }
+    };
+
+    let abort = rest.contains(&"--abort");
+    let body_arg = flag_value("--body")?;
+    let file = flag_value("--file")?;
+    if body_arg.is_some() && file.is_some() {
+        return Err(ParseError::InvalidValue {
+            message: "--body and --file cannot be used together".to_string(),
+            usage: USAGE,
+        });
+    }
+    let body = match file {
+        Some(path) => Some(
+            fs::read_to_string(path).map_err(|e| ParseError::InvalidValue {
+                message: format!("Failed to read --file {}: {}", path, e),
+                usage: USAGE,
+            })?,
+        ),
+        None => body_arg.map(String::from),
+    };
+    let content_type = flag_value("--content-type")?;
+    let redirect = flag_value("--redirect")?;
+    if abort && redirect.is_some() {
+        return Err(ParseError::InvalidValue {
+            message: "--abort and --redirect cannot be used together".to_string(),
+            usage: USAGE,
+        });
+    }
+
+    let status = flag_value("--status")?
+        .map(|v| match v.parse::<u16>() {
+            Ok(code) if (100..=599).contains(&code) => Ok(code),
+            _ => Err(ParseError::InvalidValue {
+                message: format!(
+                    "Invalid --status value: {}. Expected an HTTP status code (100-599)",
+                    v
+                ),
+                usage: USAGE,
+            }),
+        })
+        .transpose()?;
+    let delay = flag_value("--delay")?
+        .map(|v| {
+            v.parse::<u64>().map_err(|_| ParseError::InvalidValue {
+                message: format!(
+                    "Invalid --delay value: {}. Expected a non-negative number of milliseconds",
+                    v
+                ),
+                usage: USAGE,
+            })
+        })
+        .transpose()?;
+
+    let mut cmd = json!({ "id": id, "action": "route", "url": url, "abort": abort, "body": body });
+    if let Some(target) = redirect {
+        cmd["redirect"] = json!(target);
+        cmd["status"] = json!(status.unwrap_or(302));
+    } else if let Some(code) = status {
+        cmd["status"] = json!(code);
+    }
+    if let Some(ms) = delay {
+        cmd["delay"] = json!(ms);
+    }
+    if let Some(mime) = content_type {
+        cmd["contentType"] = json!(mime);
+    }
+    // --headers is consumed as a global flag, so read it back from flags. Only an
+    // explicit CLI value applies here; config/env headers are for navigation.
+    if let Some(headers_json) = flags.headers.as_ref().filter(|_| flags.cli_headers) {
+        let headers = serde_json::from_str::<Value>(headers_json)
+            .ok()
+            .filter(|v| v.is_object())
+            .ok_or_else(|| ParseError::InvalidValue {
+                message: format!("Invalid JSON object for --headers: {}", headers_json),
+                usage: "network route <url> --headers '{\"Key\": \"Value\"}'",
+            })?;
+        cmd["responseHeaders"] = headers;
+    }
+    Ok(cmd)
+}
+
+fn parse_network_requests(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "network requests [--clear] [--follow] [--filter <pattern>] [--url <glob>] [--method <method>] [--status <code>] [--output-format table|json|ndjson]";
+    const FORMATS: &[&str] = &["table", "json", "ndjson"];
+
+    let flag_value = |flag: &str| -> Result<Option<&str>, ParseError> {
+        match rest.iter().position(|&s| s == flag) {
+            Some(i) => rest
+                .get(i + 1)
+                .copied()
+                .filter(|v| !v.starts_with("--"))
+                .map(Some)
+                .ok_or_else(|| ParseError::MissingArguments {
+                    context: format!("network requests {}", flag),
+                    usage: USAGE,
+                }),
+            None => Ok(None),
+        }
+    };
+
+    let clear = rest.contains(&"--clear");
+    let mut cmd = json!({ "id": id, "action": "requests", "clear": clear });
+    if rest.contains(&"--follow") {
+        cmd["follow"] = json!(true);
+    }
+    if let Some(f) = flag_value("--filter")? {
+        cmd["filter"] = json!(f);
+    }
+    if let Some(url) = flag_value("--url")? {
+        cmd["url"] = json!(url);
+    }
+    if let Some(method) = flag_value("--method")? {
+        if !method.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(ParseError::InvalidValue {
+                message: format!("Invalid --method value: {}", method),
+                usage: USAGE,
+            });
+        }
+        cmd["method"] = json!(method.to_ascii_uppercase());
+    }
+    if let Some(status) = flag_value("--status")? {
+        let code = status
+            .parse::<u16>()
+            .ok()
+            .filter(|c| (100..=599).contains(c))
+            .ok_or_else(|| ParseError::InvalidValue {
+                message: format!(
+                    "Invalid --status value: {}. Expected an HTTP status code (100-599)",
+                    status
+                ),
+                usage: USAGE,
+            })?;
+        cmd["status"] = json!(code);
+    }
+    if let Some(format) = flag_value("--output-format")? {
+        if !FORMATS.contains(&format) {
+            return Err(ParseError::InvalidValue {
+                message: format!(
+                    "Invalid --output-format value: {}. Expected one of: {}",
+                    format,
+                    FORMATS.join(", ")
+                ),
+                usage: USAGE,
+            });
+        }
+        cmd["format"] = json!(format);
+    }
+    Ok(cmd)
+}
+
+fn parse_network_throttle(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "network throttle <slow-3g|fast-3g|offline|none> | [--download <kbps>] [--upload <kbps>] [--latency <ms>]";
+    const PRESETS: &[&str] = &["slow-3g", "fast-3g", "offline", "none"];
+
+    match rest.get(1).copied() {
+        Some(preset) if !preset.starts_with("--") => {
+            if !PRESETS.contains(&preset) {
+                return Err(ParseError::InvalidValue {
+                    message: format!(
+                        "Unknown throttle preset: {}. Expected one of: {}",
+                        preset,
+                        PRESETS.join(", ")
+                    ),
+                    usage: USAGE,
+                });
+            }
+            Ok(json!({ "id": id, "action": "throttle", "preset": preset }))
+        }
+        Some(_) => {
+            let mut cmd = json!({ "id": id, "action": "throttle" });
+            let mut has_condition = false;
+            for (flag, field) in [
+                ("--download", "downloadKbps"),
+                ("--upload", "uploadKbps"),
+                ("--latency", "latencyMs"),
+            ] {
+                let Some(idx) = rest.iter().position(|&s| s == flag) else {
+                    continue;
+                };
+                let value = rest
+                    .get(idx + 1)
+                    .ok_or_else(|| ParseError::MissingArguments {
+                        context: format!("network throttle {}", flag),
+                        usage: USAGE,
+                    })?;
+                let n = value.parse::<u64>().map_err(|_| ParseError::InvalidValue {
+                    message: format!(
+                        "Invalid {} value: {}. Expected a non-negative number",
+                        flag, value
+                    ),
+                    usage: USAGE,
+                })?;
+                cmd[field] = json!(n);
+                has_condition = true;
+            }
+            if !has_condition {
+                return Err(ParseError::MissingArguments {
+                    context: "network throttle".to_string(),
+                    usage: USAGE,
+                });
+            }
+            Ok(cmd)
+        }
+        None => Err(ParseError::MissingArguments {
+            context: "network throttle".to_string(),
+            usage: USAGE,
+        }),
+    }
+}
+
+/// Reads a storage file for `storage <type> import`: a JSON object mapping
+/// keys to string values.
+fn read_storage_file(path: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "storage <local|session> import <file>";
+    let invalid = |message: String| ParseError::InvalidValue {
+        message,
+        usage: USAGE,
+    };
+    let content =
+        fs::read_to_string(path).map_err(|e| invalid(format!("Failed to read {}: {}", path, e)))?;
+    let value: Value = serde_json::from_str(&content)
+        .map_err(|e| invalid(format!("Invalid JSON in {}: {}", path, e)))?;
+    let Some(entries) = value.as_object() else {
+        return Err(invalid(format!(
+            "Expected a JSON object of key-value pairs in {}",
+            path
+        )));
+    };
+    if let Some((key, _)) = entries.iter().find(|(_, v)| !v.is_string()) {
+        return Err(invalid(format!(
+            "Value for key \"{}\" in {} must be a string",
+            key, path
+        )));
+    }
+    Ok(value)
+}
+
+fn parse_storage(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["local", "session"];
+
+    match rest.first().copied() {
+        Some("local") | Some("session") => {
+            let storage_type = rest.first().unwrap();
+            let op = rest.get(1).unwrap_or(&"get");
+            let key = rest.get(2);
+            let value = rest.get(3);
+            match *op {
+                "set" => {
+                    let k = key.ok_or_else(|| ParseError::MissingArguments {
+                        context: format!("storage {} set", storage_type),
+                        usage: "storage <local|session> set <key> <value>",
+                    })?;
+                    let v = value.ok_or_else(|| ParseError::MissingArguments {
+                        context: format!("storage {} set", storage_type),
+                        usage: "storage <local|session> set <key> <value>",
+                    })?;
+                    Ok(
+                        json!({ "id": id, "action": "storage_set", "type": storage_type, "key": k, "value": v }),
+                    )
+                }
+                "clear" => Ok(json!({ "id": id, "action": "storage_clear", "type": storage_type })),
+                "delete" => {
+                    let k = key.ok_or_else(|| ParseError::MissingArguments {
+                        context: format!("storage {} delete", storage_type),
+                        usage: "storage <local|session> delete <key>",
+                    })?;
+                    Ok(
+                        json!({ "id": id, "action": "storage_delete", "type": storage_type, "key": k }),
+                    )
+                }
+                "list" => Ok(json!({ "id": id, "action": "storage_keys", "type": storage_type })),
+                "dump" => Ok(json!({ "id": id, "action": "storage_dump", "type": storage_type })),
+                "export" => {
+                    let path = key.ok_or_else(|| ParseError::MissingArguments {
+                        context: format!("storage {} export", storage_type),
+                        usage: "storage <local|session> export <file>",
+                    })?;
+                    Ok(
+                        json!({ "id": id, "action": "storage_export", "type": storage_type, "path": path }),
+                    )
+                }
+                "import" => {
+                    let path = key.ok_or_else(|| ParseError::MissingArguments {
+                        context: format!("storage {} import", storage_type),
+                        usage: "storage <local|session> import <file>",
+                    })?;
+                    let data = read_storage_file(path)?;
+                    Ok(
+                        json!({ "id": id, "action": "storage_import", "type": storage_type, "data": data }),
+                    )
+                }
+                _ => {
+                    let (fail_if_missing, get_args) =
+                        take_bool_flag(rest.get(2..).unwrap_or_default(), "--fail-if-missing");
+                    let mut cmd =
+                        json!({ "id": id, "action": "storage_get", "type": storage_type });
+                    if let Some(k) = get_args.first() {
+                        cmd.as_object_mut()
+                            .unwrap()
+                            .insert("key".to_string(), json!(k));
+                    } else if fail_if_missing {
+                        return Err(ParseError::MissingArguments {
+                            context: format!("storage {} get --fail-if-missing", storage_type),
+                            usage: "storage <local|session> get <key> --fail-if-missing",
+                        });
+                    }
+                    if fail_if_missing {
+                        cmd["failIfMissing"] = json!(true);
+                    }
+                    Ok(cmd)
+                }
+            }
+        }
+        Some(sub) => Err(ParseError::UnknownSubcommand {
+            subcommand: sub.to_string(),
+            valid_options: VALID,
+        }),
+        None => Err(ParseError::MissingArguments {
+            context: "storage".to_string(),
+            usage: "storage <local|session> [get|set|delete|list|dump|export|import|clear] [key] [value]",
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_flags() -> Flags {
+        Flags {
+            session: "test".to_string(),
+            json: false,
+            ndjson: false,
+            output_format: Default::default(),
+            full: false,
+            headed: false,
+            debug: false,
+            headers: None,
+            header_pairs: Vec::new(),
+            executable_path: None,
+            extensions: Vec::new(),
+            cdp: None,
+            profile: None,
+            state: None,
+            proxy: None,
+            proxy_bypass: None,
+            args: None,
+            user_agent: None,
+            provider: None,
+            ignore_https_errors: false,
+            allow_file_access: false,
+            device: None,
+            auto_connect: false,
+            session_name: None,
+            cli_executable_path: false,
+            cli_extensions: false,
+            cli_profile: false,
+            cli_state: false,
+            cli_args: false,
+            cli_user_agent: false,
+            cli_proxy: false,
+            cli_proxy_bypass: false,
+            cli_allow_file_access: false,
+            cli_annotate: false,
+            cli_download_path: false,
+            cli_headers: false,
+            annotate: false,
+            color_scheme: None,
+            download_path: None,
+            timeout: None,
+            state_password: None,
+            retry: 0,
+            retry_delay_ms: 1000,
+            connect_timeout_ms: 30_000,
+            send_timeout_ms: None,
+            output_file: None,
+            color: false,
+            quiet: false,
+            screenshot_on_failure: false,
+            screenshot_on_failure_dir: None,
+            dry_run: false,
+            fail_on_console_error: false,
+            negate: false,
+            default_wait_until: None,
+            default_timeout: None,
+            default_screenshot_format: None,
+            aliases: Default::default(),
+        }
+    }
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    // === Cookies Tests ===
+
+    #[test]
+    fn test_cookies_get() {
+        let cmd = parse_command(&args("cookies"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cookies_get");
+    }
+
+    #[test]
+    fn test_cookies_get_explicit() {
+        let cmd = parse_command(&args("cookies get"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cookies_get");
+    }
+
+    #[test]
+    fn test_cookies_set() {
+        let cmd = parse_command(&args("cookies set mycookie myvalue"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cookies_set");
+        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
+        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
+    }
+
+    #[test]
+    fn test_cookies_set_missing_value() {
+        let result = parse_command(&args("cookies set mycookie"), &default_flags());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_cookies_clear() {
+        let cmd = parse_command(&args("cookies clear"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cookies_clear");
+    }
+
+    #[test]
+    fn test_cookies_set_with_url() {
+        let cmd = parse_command(
+            &args("cookies set mycookie myvalue --url https://example.com"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "cookies_set");
+        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
+        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
+        assert_eq!(cmd["cookies"][0]["url"], "https://example.com");
+    }
+
+    #[test]
+    fn test_cookies_set_with_domain() {
+        let cmd = parse_command(
+            &args("cookies set mycookie myvalue --domain example.com"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "cookies_set");
+        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
+        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
+        assert_eq!(cmd["cookies"][0]["domain"], "example.com");
+    }
+
+    #[test]
+    fn test_cookies_set_with_path() {
+        let cmd = parse_command(
+            &args("cookies set mycookie myvalue --path /api"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "cookies_set");
+        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
+        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
+        assert_eq!(cmd["cookies"][0]["path"], "/api");
+    }
+
+    #[test]
+    fn test_cookies_set_with_httponly() {
+        let cmd = parse_command(
+            &args("cookies set mycookie myvalue --httpOnly"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "cookies_set");
+        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
+        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
+        assert_eq!(cmd["cookies"][0]["httpOnly"], true);
+    }
+
+    #[test]
+    fn test_cookies_set_with_secure() {
+        let cmd = parse_command(
+            &args("cookies set mycookie myvalue --secure"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "cookies_set");
+        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
+        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
+        assert_eq!(cmd["cookies"][0]["secure"], true);
+    }
+
+    #[test]
+    fn test_cookies_set_with_samesite() {
+        let cmd = parse_command(
+            &args("cookies set mycookie myvalue --sameSite Strict"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "cookies_set");
+        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
+        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
+        assert_eq!(cmd["cookies"][0]["sameSite"], "Strict");
+    }
+
+    #[test]
+    fn test_cookies_set_with_expires() {
+        let cmd = parse_command(
+            &args("cookies set mycookie myvalue --expires 1234567890"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "cookies_set");
+        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
+        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
+        assert_eq!(cmd["cookies"][0]["expires"], 1234567890);
+    }
+
+    #[test]
+    fn test_cookies_set_with_multiple_flags() {
+        let cmd = parse_command(&args("cookies set mycookie myvalue --url https://example.com --httpOnly --secure --sameSite Lax"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cookies_set");
+        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
+        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
+        assert_eq!(cmd["cookies"][0]["url"], "https://example.com");
+        assert_eq!(cmd["cookies"][0]["httpOnly"], true);
+        assert_eq!(cmd["cookies"][0]["secure"], true);
+        assert_eq!(cmd["cookies"][0]["sameSite"], "Lax");
+    }
+
+    #[test]
+    fn test_cookies_set_with_all_flags() {
+        let cmd = parse_command(&args("cookies set mycookie myvalue --url https://example.com --domain example.com --path /api --httpOnly --secure --sameSite None --expires 9999999999"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cookies_set");
+        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
+        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
+        assert_eq!(cmd["cookies"][0]["url"], "https://example.com");
+        assert_eq!(cmd["cookies"][0]["domain"], "example.com");
+        assert_eq!(cmd["cookies"][0]["path"], "/api");
+        assert_eq!(cmd["cookies"][0]["httpOnly"], true);
+        assert_eq!(cmd["cookies"][0]["secure"], true);
+        assert_eq!(cmd["cookies"][0]["sameSite"], "None");
+        assert_eq!(cmd["cookies"][0]["expires"], 9999999999i64);
+    }
+
+    #[test]
+    fn test_cookies_set_invalid_samesite() {
+        let result = parse_command(
+            &args("cookies set mycookie myvalue --sameSite Invalid"),
+            &default_flags(),
+        );
+        assert!(result.is_err());
+    }
+
+    // === Storage Tests ===
+
+    #[test]
+    fn test_storage_local_get() {
+        let cmd = parse_command(&args("storage local"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_get");
+        assert_eq!(cmd["type"], "local");
+        assert!(cmd.get("key").is_none());
+    }
+
+    #[test]
+    fn test_storage_local_get_key() {
+        let cmd = parse_command(&args("storage local get mykey"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_get");
+        assert_eq!(cmd["type"], "local");
+        assert_eq!(cmd["key"], "mykey");
+    }
+
+    #[test]
+    fn test_storage_get_fail_if_missing() {
+        let cmd = parse_command(
+            &args("storage local get token --fail-if-missing"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "storage_get");
+        assert_eq!(cmd["key"], "token");
+        assert_eq!(cmd["failIfMissing"], true);
+        assert!(
+            parse_command(&args("storage local get token"), &default_flags())
+                .unwrap()
+                .get("failIfMissing")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_storage_get_fail_if_missing_before_key() {
+        let cmd = parse_command(
+            &args("storage session get --fail-if-missing userId"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["type"], "session");
+        assert_eq!(cmd["key"], "userId");
+        assert_eq!(cmd["failIfMissing"], true);
+    }
+
+    #[test]
+    fn test_storage_get_fail_if_missing_requires_key() {
+        let result = parse_command(
+            &args("storage local get --fail-if-missing"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_storage_session_get() {
+        let cmd = parse_command(&args("storage session"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_get");
+        assert_eq!(cmd["type"], "session");
+    }
+
+    #[test]
+    fn test_storage_local_set() {
+        let cmd =
+            parse_command(&args("storage local set mykey myvalue"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_set");
+        assert_eq!(cmd["type"], "local");
+        assert_eq!(cmd["key"], "mykey");
+        assert_eq!(cmd["value"], "myvalue");
+    }
+
+    #[test]
+    fn test_storage_session_set() {
+        let cmd =
+            parse_command(&args("storage session set skey svalue"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_set");
+        assert_eq!(cmd["type"], "session");
+        assert_eq!(cmd["key"], "skey");
+        assert_eq!(cmd["value"], "svalue");
+    }
+
+    #[test]
+    fn test_storage_set_missing_value() {
+        let result = parse_command(&args("storage local set mykey"), &default_flags());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_storage_local_clear() {
+        let cmd = parse_command(&args("storage local clear"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_clear");
+        assert_eq!(cmd["type"], "local");
+    }
+
+    #[test]
+    fn test_storage_session_clear() {
+        let cmd = parse_command(&args("storage session clear"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_clear");
+        assert_eq!(cmd["type"], "session");
+    }
+
+    #[test]
+    fn test_storage_invalid_type() {
+        let result = parse_command(&args("storage invalid"), &default_flags());
+        assert!(result.is_err());
+    }
+
+    // === Navigation Tests ===
+
+    #[test]
+    fn test_navigate_with_https() {
+        let cmd = parse_command(&args("open https://example.com"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "navigate");
+        assert_eq!(cmd["url"], "https://example.com");
+    }
+
+    #[test]
+    fn test_navigate_without_protocol() {
+        let cmd = parse_command(&args("open example.com"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "navigate");
+        assert_eq!(cmd["url"], "https://example.com");
+    }
+
+    #[test]
+    fn test_navigate_with_headers() {
+        let mut flags = default_flags();
+        flags.headers = Some(r#"{"Authorization": "Bearer token"}"#.to_string());
+        let cmd = parse_command(&args("open api.example.com"), &flags).unwrap();
+        assert_eq!(cmd["action"], "navigate");
+        assert_eq!(cmd["url"], "https://api.example.com");
+        assert_eq!(cmd["headers"]["Authorization"], "Bearer token");
+    }
+
+    #[test]
+    fn test_navigate_with_multiple_headers() {
+        let mut flags = default_flags();
+        flags.headers =
+            Some(r#"{"Authorization": "Bearer token", "X-Custom": "value"}"#.to_string());
+        let cmd = parse_command(&args("open api.example.com"), &flags).unwrap();
+        assert_eq!(cmd["headers"]["Authorization"], "Bearer token");
+        assert_eq!(cmd["headers"]["X-Custom"], "value");
+    }
+
+    #[test]
+    fn test_navigate_without_headers_flag() {
+        let cmd = parse_command(&args("open example.com"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "navigate");
+        // headers should not be present when flag is not set
+        assert!(cmd.get("headers").is_none());
+    }
+
+    #[test]
+    fn test_navigate_with_invalid_headers_json() {
+        let mut flags = default_flags();
+        flags.headers = Some("not valid json".to_string());
+        let result = parse_command(&args("open api.example.com"), &flags);
+        // Invalid JSON should return a ParseError, not silently drop headers
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("Invalid JSON for --headers"));
+    }
+
+    // === Set Headers Tests ===
+
+    #[test]
+    fn test_set_headers_parses_json() {
+        let input: Vec<String> = vec![
+            "set".to_string(),
+            "headers".to_string(),
+            r#"{"Authorization":"Bearer token"}"#.to_string(),
+        ];
+        let cmd = parse_command(&input, &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "headers");
+        // Headers should be an object, not a string
+        assert!(cmd["headers"].is_object());
+        assert_eq!(cmd["headers"]["Authorization"], "Bearer token");
+    }
+
+    #[test]
+    fn test_set_headers_with_multiple_values() {
+        let input: Vec<String> = vec![
+            "set".to_string(),
+            "headers".to_string(),
+            r#"{"Authorization": "Bearer token", "X-Custom": "value"}"#.to_string(),
+        ];
+        let cmd = parse_command(&input, &default_flags()).unwrap();
+        assert_eq!(cmd["headers"]["Authorization"], "Bearer token");
+        assert_eq!(cmd["headers"]["X-Custom"], "value");
+    }
+
+    #[test]
+    fn test_set_headers_invalid_json_error() {
+        let input: Vec<String> = vec![
+            "set".to_string(),
+            "headers".to_string(),
+            "not-valid-json".to_string(),
+        ];
+        let result = parse_command(&input, &default_flags());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_back() {
+        let cmd = parse_command(&args("back"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "back");
+    }
+
+    #[test]
+    fn test_forward() {
+        let cmd = parse_command(&args("forward"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "forward");
+    }
+
+    #[test]
+    fn test_back_without_wait_until() {
+        let cmd = parse_command(&args("back"), &default_flags()).unwrap();
+        assert!(cmd.get("waitUntil").is_none());
+    }
+
+    #[test]
+    fn test_back_wait_until() {
+        let cmd = parse_command(&args("back --wait-until networkidle"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "back");
+        assert_eq!(cmd["waitUntil"], "networkidle");
+    }
+
+    #[test]
+    fn test_forward_wait_until() {
+        let cmd = parse_command(&args("forward --wait-until load"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "forward");
+        assert_eq!(cmd["waitUntil"], "load");
+    }
+
+    #[test]
+    fn test_back_forward_invalid_wait_until() {
+        for input in ["back --wait-until ready", "forward --wait-until ready"] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::InvalidValue { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_back_forward_wait_until_missing_value() {
+        for input in ["back --wait-until", "forward --wait-until"] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::MissingArguments { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_forward_without_wait_until() {
+        let cmd = parse_command(&args("forward"), &default_flags()).unwrap();
+        assert!(cmd.get("waitUntil").is_none());
+    }
+
+    #[test]
+    fn test_reload() {
+        let cmd = parse_command(&args("reload"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "reload");
+        assert!(cmd.get("hard").is_none());
+        assert!(cmd.get("waitUntil").is_none());
+    }
+
+    #[test]
+    fn test_reload_hard() {
+        let cmd = parse_command(&args("reload --hard"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "reload");
+        assert_eq!(cmd["hard"], true);
+        assert!(cmd.get("waitUntil").is_none());
+    }
+
+    #[test]
+    fn test_reload_wait_until() {
+        let cmd =
+            parse_command(&args("reload --wait-until networkidle"), &default_flags()).unwrap();
+        assert_eq!(cmd["waitUntil"], "networkidle");
+        assert!(cmd.get("hard").is_none());
+    }
+
+    #[test]
+    fn test_reload_hard_with_wait_until() {
+        let cmd = parse_command(
+            &args("reload --wait-until domcontentloaded --hard"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["hard"], true);
+        assert_eq!(cmd["waitUntil"], "domcontentloaded");
+    }
+
+    #[test]
+    fn test_reload_invalid_wait_until() {
+        let err = parse_command(&args("reload --wait-until idle"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.to_string().contains("idle"));
+    }
+
+    // === Core Actions ===
+
+    #[test]
+    fn test_click() {
+        let cmd = parse_command(&args("click #button"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "click");
+        assert_eq!(cmd["selector"], "#button");
+    }
+
+    #[test]
+    fn test_fill() {
+        let cmd = parse_command(&args("fill #input hello world"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "fill");
+        assert_eq!(cmd["selector"], "#input");
+        assert_eq!(cmd["value"], "hello world");
+    }
+
+    #[test]
+    fn test_click_no_wait_after() {
+        let cmd = parse_command(&args("click #link --no-wait-after"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], "#link");
+        assert_eq!(cmd["noWaitAfter"], true);
+        assert!(cmd.get("newTab").is_none());
+    }
+
+    #[test]
+    fn test_click_no_wait_after_before_selector() {
+        let cmd = parse_command(&args("click --no-wait-after @e2"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], "@e2");
+        assert_eq!(cmd["noWaitAfter"], true);
+    }
+
+    #[test]
+    fn test_click_no_wait_after_with_new_tab() {
+        let cmd = parse_command(
+            &args("click @e3 --new-tab --no-wait-after"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], "@e3");
+        assert_eq!(cmd["newTab"], true);
+        assert_eq!(cmd["noWaitAfter"], true);
+    }
+
+    #[test]
+    fn test_click_without_no_wait_after() {
+        let cmd = parse_command(&args("click #button"), &default_flags()).unwrap();
+        assert!(cmd.get("noWaitAfter").is_none());
+    }
+
+    #[test]
+    fn test_dblclick_no_wait_after() {
+        let cmd = parse_command(&args("dblclick #row --no-wait-after"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "dblclick");
+        assert_eq!(cmd["selector"], "#row");
+        assert_eq!(cmd["noWaitAfter"], true);
+    }
+
+    #[test]
+    fn test_fill_no_wait_after_excluded_from_value() {
+        let cmd = parse_command(
+            &args("fill #q hello --no-wait-after world"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], "#q");
+        assert_eq!(cmd["value"], "hello world");
+        assert_eq!(cmd["noWaitAfter"], true);
+    }
+
+    #[test]
+    fn test_type_no_wait_after() {
+        let cmd = parse_command(
+            &args("type #search query --no-wait-after"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "type");
+        assert_eq!(cmd["text"], "query");
+        assert_eq!(cmd["noWaitAfter"], true);
+    }
+
+    #[test]
+    fn test_no_wait_after_only_is_missing_selector() {
+        for input in [
+            "click --no-wait-after",
+            "dblclick --no-wait-after",
+            "fill --no-wait-after",
+            "type --no-wait-after",
+        ] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::MissingArguments { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_type_command() {
+        let cmd = parse_command(&args("type #input some text"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "type");
+        assert_eq!(cmd["selector"], "#input");
+        assert_eq!(cmd["text"], "some text");
+    }
+
+    #[test]
+    fn test_select() {
+        let cmd = parse_command(&args("select #menu option1"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "select");
+        assert_eq!(cmd["selector"], "#menu");
+        assert_eq!(cmd["values"], "option1");
+    }
+
+    #[test]
+    fn test_select_multiple_values() {
+        let cmd = parse_command(&args("select #menu opt1 opt2 opt3"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "select");
+        assert_eq!(cmd["selector"], "#menu");
+        assert_eq!(cmd["values"], json!(["opt1", "opt2", "opt3"]));
+    }
+
+    #[test]
+    fn test_select_by_index() {
+        let cmd = parse_command(&args("select #menu --by-index 2"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "select");
+        assert_eq!(cmd["selector"], "#menu");
+        assert_eq!(cmd["byIndex"], 2);
+        assert!(cmd.get("values").is_none());
+    }
+
+    #[test]
+    fn test_select_by_index_zero() {
+        let cmd = parse_command(&args("select @e5 --by-index 0"), &default_flags()).unwrap();
+        assert_eq!(cmd["byIndex"], 0);
+    }
+
+    #[test]
+    fn test_select_by_index_before_selector() {
+        let cmd = parse_command(&args("select --by-index 1 #menu"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], "#menu");
+        assert_eq!(cmd["byIndex"], 1);
+    }
+
+    #[test]
+    fn test_select_by_index_negative() {
+        assert!(matches!(
+            parse_command(&args("select #menu --by-index -1"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_select_by_index_not_a_number() {
+        assert!(matches!(
+            parse_command(&args("select #menu --by-index first"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_select_by_label() {
+        let input: Vec<String> = vec![
+            "select".to_string(),
+            "#country".to_string(),
+            "--by-label".to_string(),
+            "United States".to_string(),
+        ];
+        let cmd = parse_command(&input, &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], "#country");
+        assert_eq!(cmd["byLabel"], "United States");
+        assert!(cmd.get("values").is_none());
+    }
+
+    #[test]
+    fn test_select_value_and_by_index_conflict() {
+        assert!(matches!(
+            parse_command(&args("select #menu opt1 --by-index 1"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_select_by_index_and_by_label_conflict() {
+        assert!(matches!(
+            parse_command(
+                &args("select #menu --by-index 1 --by-label Red"),
+                &default_flags()
+            ),
+            Err(ParseError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_select_by_label_missing_value() {
+        assert!(matches!(
+            parse_command(&args("select #menu --by-label"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_select_missing_value() {
+        assert!(matches!(
+            parse_command(&args("select #menu"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_frame_main() {
+        let cmd = parse_command(&args("frame main"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "mainframe");
+    }
+
+    // === Tabs ===
+
+    #[test]
+    fn test_tab_new() {
+        let cmd = parse_command(&args("tab new"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_new");
+        assert!(
+            cmd.get("url").is_none(),
+            "url should not be present when not provided"
+        );
+    }
+
+    #[test]
+    fn test_tab_new_with_url() {
+        let cmd = parse_command(&args("tab new https://example.com"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_new");
+        assert_eq!(cmd["url"], "https://example.com");
+    }
+
+    #[test]
+    fn test_tab_list() {
+        let cmd = parse_command(&args("tab list"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_list");
+    }
+
+    #[test]
+    fn test_tab_switch() {
+        let cmd = parse_command(&args("tab 2"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_switch");
+        assert_eq!(cmd["index"], 2);
+    }
+
+    #[test]
+    fn test_tab_close() {
+        let cmd = parse_command(&args("tab close"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_close");
+    }
+
+    // === Screenshot ===
+
+    #[test]
+    fn test_screenshot() {
+        let cmd = parse_command(&args("screenshot"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "screenshot");
+        assert_eq!(cmd["path"], serde_json::Value::Null);
+        assert_eq!(cmd["selector"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_screenshot_path() {
+        let cmd = parse_command(&args("screenshot out.png"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "screenshot");
+        assert_eq!(cmd["path"], "out.png");
+    }
+
+    #[test]
+    fn test_screenshot_full_page() {
+        let mut flags = default_flags();
+        flags.full = true;
+        let cmd = parse_command(&args("screenshot"), &flags).unwrap();
+        assert_eq!(cmd["action"], "screenshot");
+        assert_eq!(cmd["fullPage"], true);
+    }
+
+    #[test]
+    fn test_screenshot_with_ref() {
+        let cmd = parse_command(&args("screenshot @e1"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "screenshot");
+        assert_eq!(cmd["selector"], "@e1");
+        assert_eq!(cmd["path"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_screenshot_with_css_class() {
+        let cmd = parse_command(&args("screenshot .my-button"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "screenshot");
+        assert_eq!(cmd["selector"], ".my-button");
+        assert_eq!(cmd["path"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_screenshot_with_css_id() {
+        let cmd = parse_command(&args("screenshot #header"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "screenshot");
+        assert_eq!(cmd["selector"], "#header");
+        assert_eq!(cmd["path"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_screenshot_with_path() {
+        let cmd = parse_command(&args("screenshot ./output.png"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "screenshot");
+        assert_eq!(cmd["selector"], serde_json::Value::Null);
+        assert_eq!(cmd["path"], "./output.png");
+    }
+
+    #[test]
+    fn test_screenshot_with_selector_and_path() {
+        let cmd = parse_command(&args("screenshot .btn ./button.png"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "screenshot");
+        assert_eq!(cmd["selector"], ".btn");
+        assert_eq!(cmd["path"], "./button.png");
+    }
+
+    #[test]
+    fn test_screenshot_mask() {
+        let cmd = parse_command(&args("screenshot --mask #password"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "screenshot");
+        assert_eq!(cmd["mask"], json!(["#password"]));
+        assert!(cmd["selector"].is_null());
+        assert!(cmd["path"].is_null());
+    }
+
+    #[test]
+    fn test_screenshot_multiple_masks() {
+        let cmd = parse_command(
+            &args("screenshot ./page.png --mask #password --mask .card-number"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["path"], "./page.png");
+        assert_eq!(cmd["mask"], json!(["#password", ".card-number"]));
+    }
+
+    #[test]
+    fn test_screenshot_mask_with_selector_and_path() {
+        let cmd = parse_command(
+            &args("screenshot --mask @e3 .form ./form.png"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], ".form");
+        assert_eq!(cmd["path"], "./form.png");
+        assert_eq!(cmd["mask"], json!(["@e3"]));
+    }
+
+    #[test]
+    fn test_screenshot_without_mask() {
+        let cmd = parse_command(&args("screenshot ./page.png"), &default_flags()).unwrap();
+        assert!(cmd.get("mask").is_none());
+    }
+
+    #[test]
+    fn test_screenshot_bare_mask() {
+        let result = parse_command(&args("screenshot ./page.png --mask"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Snapshot ===
+
+    #[test]
+    fn test_snapshot() {
+        let cmd = parse_command(&args("snapshot"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "snapshot");
+    }
+
+    #[test]
+    fn test_snapshot_interactive() {
+        let cmd = parse_command(&args("snapshot -i"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "snapshot");
+        assert_eq!(cmd["interactive"], true);
+    }
+
+    #[test]
+    fn test_snapshot_cursor() {
+        let cmd = parse_command(&args("snapshot -C"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "snapshot");
+        assert_eq!(cmd["cursor"], true);
+    }
+
+    #[test]
+    fn test_snapshot_interactive_cursor() {
+        let cmd = parse_command(&args("snapshot -i -C"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "snapshot");
+        assert_eq!(cmd["interactive"], true);
+        assert_eq!(cmd["cursor"], true);
+    }
+
+    #[test]
+    fn test_snapshot_compact() {
+        let cmd = parse_command(&args("snapshot --compact"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "snapshot");
+        assert_eq!(cmd["compact"], true);
+    }
+
+    #[test]
+    fn test_snapshot_depth() {
+        let cmd = parse_command(&args("snapshot -d 3"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "snapshot");
+        assert_eq!(cmd["maxDepth"], 3);
+    }
+
+    #[test]
+    fn test_snapshot_only_roles() {
+        let cmd = parse_command(
+            &args("snapshot --only-roles button,link,textbox"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["onlyRoles"], json!(["button", "link", "textbox"]));
+    }
+
+    #[test]
+    fn test_snapshot_only_roles_single_and_blank_entries() {
+        let cmd = parse_command(&args("snapshot --only-roles button,,"), &default_flags()).unwrap();
+        assert_eq!(cmd["onlyRoles"], json!(["button"]));
+    }
+
+    #[test]
+    fn test_snapshot_only_roles_with_selector() {
+        let cmd = parse_command(
+            &args("snapshot -s #main --only-roles link -i"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], "#main");
+        assert_eq!(cmd["onlyRoles"], json!(["link"]));
+        assert_eq!(cmd["interactive"], true);
+    }
+
+    #[test]
+    fn test_snapshot_only_roles_empty_list() {
+        let result = parse_command(&args("snapshot --only-roles ,"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_only_roles_missing_value() {
+        let result = parse_command(&args("snapshot --only-roles"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_without_only_roles() {
+        let cmd = parse_command(&args("snapshot -i"), &default_flags()).unwrap();
+        assert!(cmd.get("onlyRoles").is_none());
+    }
+
+    // === Wait ===
+
+    #[test]
+    fn test_wait_selector() {
+        let cmd = parse_command(&args("wait #element"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "wait");
+        assert_eq!(cmd["selector"], "#element");
+    }
+
+    #[test]
+    fn test_wait_timeout() {
+        let cmd = parse_command(&args("wait 5000"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "wait");
+        assert_eq!(cmd["timeout"], 5000);
+    }
+
+    #[test]
+    fn test_wait_url() {
+        let cmd = parse_command(&args("wait --url **/dashboard"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitforurl");
+        assert_eq!(cmd["url"], "**/dashboard");
+    }
+
+    #[test]
+    fn test_wait_load() {
+        let cmd = parse_command(&args("wait --load networkidle"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitforloadstate");
+        assert_eq!(cmd["state"], "networkidle");
+    }
+
+    #[test]
+    fn test_wait_load_missing_state() {
+        let result = parse_command(&args("wait --load"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wait_fn() {
+        let cmd = parse_command(&args("wait --fn window.ready"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitforfunction");
+        assert_eq!(cmd["expression"], "window.ready");
+    }
+
+    #[test]
+    fn test_wait_text() {
+        let cmd = parse_command(&args("wait --text Welcome"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "wait");
+        assert_eq!(cmd["selector"], "text=Welcome");
+    }
+
+    // === Unknown command ===
+
+    // === Record Tests ===
+
+    #[test]
+    fn test_record_start() {
+        let cmd = parse_command(&args("record start output.webm"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "recording_start");
+        assert_eq!(cmd["path"], "output.webm");
+        assert!(cmd.get("url").is_none());
+    }
+
+    #[test]
+    fn test_record_start_with_url() {
+        let cmd = parse_command(
+            &args("record start demo.webm https://example.com"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "recording_start");
+        assert_eq!(cmd["path"], "demo.webm");
+        assert_eq!(cmd["url"], "https://example.com");
+    }
+
+    #[test]
+    fn test_record_start_with_url_no_protocol() {
+        let cmd = parse_command(
+            &args("record start demo.webm example.com"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "recording_start");
+        assert_eq!(cmd["path"], "demo.webm");
+        assert_eq!(cmd["url"], "https://example.com");
+    }
+
+    #[test]
+    fn test_record_start_missing_path() {
+        let result = parse_command(&args("record start"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_record_stop() {
+        let cmd = parse_command(&args("record stop"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "recording_stop");
+    }
+
+    #[test]
+    fn test_record_restart() {
+        let cmd = parse_command(&args("record restart output.webm"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "recording_restart");
+        assert_eq!(cmd["path"], "output.webm");
+        assert!(cmd.get("url").is_none());
+    }
+
+    #[test]
+    fn test_record_restart_with_url() {
+        let cmd = parse_command(
+            &args("record restart demo.webm https://example.com"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "recording_restart");
+        assert_eq!(cmd["path"], "demo.webm");
+        assert_eq!(cmd["url"], "https://example.com");
+    }
+
+    #[test]
+    fn test_record_restart_missing_path() {
+        let result = parse_command(&args("record restart"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_record_invalid_subcommand() {
+        let result = parse_command(&args("record foo"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
+    }
+
+    #[test]
+    fn test_record_missing_subcommand() {
+        let result = parse_command(&args("record"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Profile (CDP Tracing) Tests ===
+
+    #[test]
+    fn test_profiler_start() {
+        let cmd = parse_command(&args("profiler start"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "profiler_start");
+        assert!(cmd.get("categories").is_none());
+    }
+
+    #[test]
+    fn test_profiler_start_with_categories() {
+        let cmd = parse_command(
+            &args("profiler start --categories devtools.timeline,v8.execute"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "profiler_start");
+        let categories = cmd["categories"].as_array().unwrap();
+        assert_eq!(categories.len(), 2);
+        assert_eq!(categories[0], "devtools.timeline");
+        assert_eq!(categories[1], "v8.execute");
+    }
+
+    #[test]
+    fn test_profiler_start_categories_missing_value() {
+        let result = parse_command(&args("profiler start --categories"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_profiler_stop_with_path() {
+        let cmd = parse_command(&args("profiler stop trace.json"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "profiler_stop");
+        assert_eq!(cmd["path"], "trace.json");
+    }
+
+    #[test]
+    fn test_profiler_stop_no_path() {
+        let cmd = parse_command(&args("profiler stop"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "profiler_stop");
+        assert!(cmd.get("path").is_none());
+    }
+
+    #[test]
+    fn test_profiler_invalid_subcommand() {
+        let result = parse_command(&args("profiler foo"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
+    }
+
+    #[test]
+    fn test_profiler_snapshot_with_path() {
+        let cmd = parse_command(
+            &args("profiler snapshot ./heap.heapsnapshot"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "profiler_heapsnapshot");
+        assert_eq!(cmd["path"], "./heap.heapsnapshot");
+    }
+
+    #[test]
+    fn test_profiler_snapshot_missing_path() {
+        let result = parse_command(&args("profiler snapshot"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_profiler_snapshot_ignores_extra_args() {
+        let cmd = parse_command(
+            &args("profiler snapshot heap.heapsnapshot extra"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["path"], "heap.heapsnapshot");
+    }
+
+    #[test]
+    fn test_profiler_status() {
+        let cmd = parse_command(&args("profiler status"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "profiler_status");
+        assert!(cmd.get("path").is_none());
+    }
+
+    #[test]
+    fn test_profiler_status_with_session() {
+        let input = args("--session work profiler status");
+        let mut flags = default_flags();
+        flags.session = "work".to_string();
+        let clean = crate::flags::clean_args(&input);
+        let cmd = parse_command(&clean, &flags).unwrap();
+        assert_eq!(cmd["action"], "profiler_status");
+    }
+
+    #[test]
+    fn test_profiler_invalid_subcommand_lists_snapshot_and_status() {
+        match parse_command(&args("profiler heap"), &default_flags()) {
+            Err(ParseError::UnknownSubcommand { valid_options, .. }) => {
+                assert!(valid_options.contains(&"snapshot"));
+                assert!(valid_options.contains(&"status"));
+            }
+            other => panic!("expected UnknownSubcommand, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_profiler_missing_subcommand() {
+        let result = parse_command(&args("profiler"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Eval Tests ===
+
+    #[test]
+    fn test_eval_basic() {
+        let cmd = parse_command(&args("eval document.title"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "evaluate");
+        assert_eq!(cmd["script"], "document.title");
+    }
+
+    #[test]
+    fn test_eval_base64_short_flag() {
+        // "document.title" in base64
+        let cmd = parse_command(&args("eval -b ZG9jdW1lbnQudGl0bGU="), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "evaluate");
+        assert_eq!(cmd["script"], "document.title");
+    }
+
+    #[test]
+    fn test_eval_base64_long_flag() {
+        // "document.title" in base64
+        let cmd = parse_command(
+            &args("eval --base64 ZG9jdW1lbnQudGl0bGU="),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "evaluate");
+        assert_eq!(cmd["script"], "document.title");
+    }
+
+    #[test]
+    fn test_eval_base64_with_special_chars() {
+        // "document.querySelector('[src*=\"_next\"]')" in base64
+        let cmd = parse_command(
+            &args("eval -b ZG9jdW1lbnQucXVlcnlTZWxlY3RvcignW3NyYyo9Il9uZXh0Il0nKQ=="),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "evaluate");
+        assert_eq!(cmd["script"], "document.querySelector('[src*=\"_next\"]')");
+    }
+
+    #[test]
+    fn test_eval_base64_invalid() {
+        let result = parse_command(&args("eval -b !!!invalid!!!"), &default_flags());
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.to_string().contains("Invalid base64"));
+    }
+
+    #[test]
+    fn test_unknown_command() {
+        let result = parse_command(&args("unknowncommand"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownCommand { .. }
+        ));
+    }
+
+    #[test]
+    fn test_empty_args() {
+        let result = parse_command(&[], &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Error message tests ===
+
+    #[test]
+    fn test_get_missing_subcommand() {
+        let result = parse_command(&args("get"), &default_flags());
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(matches!(err, ParseError::MissingArguments { .. }));
+        assert!(err.to_string().contains("get"));
+    }
+
+    #[test]
+    fn test_get_unknown_subcommand() {
+        let result = parse_command(&args("get foo"), &default_flags());
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(matches!(err, ParseError::UnknownSubcommand { .. }));
+        assert!(err.to_string().contains("foo"));
+        assert!(err.to_string().contains("text"));
+    }
+
+    #[test]
+    fn test_get_text_missing_selector() {
+        let result = parse_command(&args("get text"), &default_flags());
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(matches!(err, ParseError::MissingArguments { .. }));
+        assert!(err.to_string().contains("get text"));
+    }
+
+    // === Protocol alignment tests ===
+
+    #[test]
+    fn test_mouse_wheel() {
+        let cmd = parse_command(&args("mouse wheel 100 50"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "wheel");
+        assert_eq!(cmd["deltaY"], 100);
+        assert_eq!(cmd["deltaX"], 50);
+    }
+
+    #[test]
+    fn test_set_media() {
+        let cmd = parse_command(&args("set media dark"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "emulatemedia");
+        assert_eq!(cmd["colorScheme"], "dark");
+        assert!(cmd.get("reducedMotion").is_none());
+    }
+
+    #[test]
+    fn test_set_media_reduced_motion() {
+        let cmd = parse_command(&args("set media light reduced-motion"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "emulatemedia");
+        assert_eq!(cmd["colorScheme"], "light");
+        assert_eq!(cmd["reducedMotion"], "reduce");
+    }
+
+    #[test]
+    fn test_set_media_reduced_motion_only() {
+        let cmd = parse_command(&args("set media reduced-motion"), &default_flags()).unwrap();
+        assert_eq!(cmd["reducedMotion"], "reduce");
+        assert!(cmd.get("colorScheme").is_none());
+    }
+
+    #[test]
+    fn test_set_media_forced_colors() {
+        let cmd =
+            parse_command(&args("set media --forced-colors active"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "emulatemedia");
+        assert_eq!(cmd["forcedColors"], "active");
+        assert!(cmd.get("colorScheme").is_none());
+        assert!(cmd.get("contrast").is_none());
+    }
+
+    #[test]
+    fn test_set_media_forced_colors_keyword() {
+        let cmd = parse_command(&args("set media dark forced-colors"), &default_flags()).unwrap();
+        assert_eq!(cmd["colorScheme"], "dark");
+        assert_eq!(cmd["forcedColors"], "active");
+    }
+
+    #[test]
+    fn test_set_media_forced_colors_none() {
+        let cmd = parse_command(&args("set media --forced-colors none"), &default_flags()).unwrap();
+        assert_eq!(cmd["forcedColors"], "none");
+    }
+
+    #[test]
+    fn test_set_media_contrast() {
+        let cmd = parse_command(&args("set media --contrast more"), &default_flags()).unwrap();
+        assert_eq!(cmd["contrast"], "more");
+        let cmd = parse_command(
+            &args("set media --contrast no-preference"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["contrast"], "no-preference");
+    }
+
+    #[test]
+    fn test_set_media_all_preferences() {
+        let cmd = parse_command(
+            &args("set media light reduced-motion --forced-colors active --contrast more"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["colorScheme"], "light");
+        assert_eq!(cmd["reducedMotion"], "reduce");
+        assert_eq!(cmd["forcedColors"], "active");
+        assert_eq!(cmd["contrast"], "more");
+    }
+
+    #[test]
+    fn test_set_media_invalid_values() {
+        for input in ["set media --forced-colors on", "set media --contrast less"] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::InvalidValue { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_set_media_missing_flag_values() {
+        for input in ["set media --forced-colors", "set media --contrast"] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::MissingArguments { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_set_media_bare_emits_no_preferences() {
+        let cmd = parse_command(&args("set media"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "emulatemedia");
+        for field in ["colorScheme", "reducedMotion", "forcedColors", "contrast"] {
+            assert!(cmd.get(field).is_none(), "{}", field);
+        }
+    }
+
+    #[test]
+    fn test_find_first_no_value() {
+        let cmd = parse_command(&args("find first a click"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "nth");
+        assert_eq!(cmd["index"], 0);
+        assert!(cmd.get("value").is_none());
+    }
+
+    #[test]
+    fn test_find_first_with_value() {
+        let cmd = parse_command(&args("find first input fill hello"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "nth");
+        assert_eq!(cmd["index"], 0);
+        assert_eq!(cmd["value"], "hello");
+    }
+
+    #[test]
+    fn test_find_nth_no_value() {
+        let cmd = parse_command(&args("find nth 2 a click"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "nth");
+        assert_eq!(cmd["index"], 2);
+        assert!(cmd.get("value").is_none());
+    }
+
+    // === Download Tests ===
+
+    #[test]
+    fn test_download() {
+        let cmd = parse_command(&args("download #btn ./file.pdf"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "download");
+        assert_eq!(cmd["selector"], "#btn");
+        assert_eq!(cmd["path"], "./file.pdf");
+    }
+
+    #[test]
+    fn test_download_with_ref() {
+        let cmd = parse_command(&args("download @e5 ./report.xlsx"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "download");
+        assert_eq!(cmd["selector"], "@e5");
+        assert_eq!(cmd["path"], "./report.xlsx");
+    }
+
+    #[test]
+    fn test_download_missing_path() {
+        let result = parse_command(&args("download #btn"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_download_missing_selector() {
+        let result = parse_command(&args("download"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Wait for Download Tests ===
+
+    #[test]
+    fn test_wait_download() {
+        let cmd = parse_command(&args("wait --download"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitfordownload");
+        assert!(cmd.get("path").is_none());
+    }
+
+    #[test]
+    fn test_wait_download_with_path() {
+        let cmd = parse_command(&args("wait --download ./file.pdf"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitfordownload");
+        assert_eq!(cmd["path"], "./file.pdf");
+    }
+
+    #[test]
+    fn test_wait_download_with_timeout() {
+        let cmd =
+            parse_command(&args("wait --download --timeout 30000"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitfordownload");
+        assert_eq!(cmd["timeout"], 30000);
+    }
+
+    #[test]
+    fn test_wait_download_with_path_and_timeout() {
+        let cmd = parse_command(
+            &args("wait --download ./file.pdf --timeout 30000"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "waitfordownload");
+        assert_eq!(cmd["path"], "./file.pdf");
+        assert_eq!(cmd["timeout"], 30000);
+    }
+
+    #[test]
+    fn test_wait_download_short_flag() {
+        let cmd = parse_command(&args("wait -d ./file.pdf"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitfordownload");
+        assert_eq!(cmd["path"], "./file.pdf");
+    }
+
+    // === Connect (CDP) tests ===
+
+    #[test]
+    fn test_connect_with_port() {
+        let cmd = parse_command(&args("connect 9222"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "launch");
+        assert_eq!(cmd["cdpPort"], 9222);
+        assert!(cmd.get("cdpUrl").is_none());
+    }
+
+    #[test]
+    fn test_connect_with_ws_url() {
+        let input: Vec<String> = vec![
+            "connect".to_string(),
+            "ws://localhost:9222/devtools/browser/abc123".to_string(),
+        ];
+        let cmd = parse_command(&input, &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "launch");
+        assert_eq!(cmd["cdpUrl"], "ws://localhost:9222/devtools/browser/abc123");
+        assert!(cmd.get("cdpPort").is_none());
+    }
+
+    #[test]
+    fn test_connect_with_wss_url() {
+        let input: Vec<String> = vec![
+            "connect".to_string(),
+            "wss://remote-browser.example.com/cdp?token=xyz".to_string(),
+        ];
+        let cmd = parse_command(&input, &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "launch");
+        assert_eq!(
+            cmd["cdpUrl"],
+            "wss://remote-browser.example.com/cdp?token=xyz"
+        );
+        assert!(cmd.get("cdpPort").is_none());
+    }
+
+    #[test]
+    fn test_connect_with_http_url() {
+        let input: Vec<String> = vec!["connect".to_string(), "http://localhost:9222".to_string()];
+        let cmd = parse_command(&input, &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "launch");
+        assert_eq!(cmd["cdpUrl"], "http://localhost:9222");
+        assert!(cmd.get("cdpPort").is_none());
+    }
+
+    #[test]
+    fn test_connect_missing_argument() {
+        let result = parse_command(&args("connect"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_connect_invalid_port() {
+        let result = parse_command(&args("connect notanumber"), &default_flags());
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.to_string().contains("not a valid port number or URL"));
+    }
+
+    #[test]
+    fn test_connect_port_zero() {
+        let result = parse_command(&args("connect 0"), &default_flags());
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.to_string().contains("port must be greater than 0"));
+    }
+
+    #[test]
+    fn test_connect_port_out_of_range() {
+        let result = parse_command(&args("connect 65536"), &default_flags());
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.to_string().contains("out of range"));
+        assert!(err.to_string().contains("1-65535"));
+    }
+
+    #[test]
+    fn test_connect_port_max_valid() {
+        let cmd = parse_command(&args("connect 65535"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "launch");
+        assert_eq!(cmd["cdpPort"], 65535);
+    }
+
+    #[test]
+    fn test_connect_port_min_valid() {
+        let cmd = parse_command(&args("connect 1"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "launch");
+        assert_eq!(cmd["cdpPort"], 1);
+    }
+
+    // === Trace Tests ===
+
+    #[test]
+    fn test_trace_start() {
+        let cmd = parse_command(&args("trace start"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "trace_start");
+    }
+
+    #[test]
+    fn test_trace_stop_with_path() {
+        let cmd = parse_command(&args("trace stop ./trace.zip"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "trace_stop");
+        assert_eq!(cmd["path"], "./trace.zip");
+    }
+
+    #[test]
+    fn test_trace_stop_without_path() {
+        let cmd = parse_command(&args("trace stop"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "trace_stop");
+        assert!(cmd.get("path").is_none() || cmd["path"].is_null());
+    }
+
+    // === Diff Tests ===
+
+    #[test]
+    fn test_diff_snapshot_basic() {
+        let cmd = parse_command(&args("diff snapshot"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "diff_snapshot");
+    }
+
+    #[test]
+    fn test_diff_snapshot_baseline() {
+        let cmd =
+            parse_command(&args("diff snapshot --baseline before.txt"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "diff_snapshot");
+        assert_eq!(cmd["baseline"], "before.txt");
+    }
+
+    #[test]
+    fn test_diff_snapshot_selector_compact_depth() {
+        let cmd = parse_command(
+            &args("diff snapshot --selector #main --compact --depth 3"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "diff_snapshot");
+        assert_eq!(cmd["selector"], "#main");
+        assert_eq!(cmd["compact"], true);
+        assert_eq!(cmd["maxDepth"], 3);
+    }
+
+    #[test]
+    fn test_diff_snapshot_short_flags() {
+        let cmd =
+            parse_command(&args("diff snapshot -b snap.txt -s .content -c -d 2"), &default_flags())
+                .unwrap();
+        assert_eq!(cmd["action"], "diff_snapshot");
+        assert_eq!(cmd["baseline"], "snap.txt");
+        assert_eq!(cmd["selector"], ".content");
+        assert_eq!(cmd["compact"], true);
+        assert_eq!(cmd["maxDepth"], 2);
+    }
+
+    #[test]
+    fn test_diff_screenshot_baseline() {
+        let cmd = parse_command(
+            &args("diff screenshot --baseline before.png"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "diff_screenshot");
+        assert_eq!(cmd["baseline"], "before.png");
+    }
+
+    #[test]
+    fn test_diff_screenshot_all_options() {
+        let cmd = parse_command(
+            &args("diff screenshot --baseline b.png --output d.png --threshold 0.2 --selector #hero --full"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "diff_screenshot");
+        assert_eq!(cmd["baseline"], "b.png");
+        assert_eq!(cmd["output"], "d.png");
+        assert_eq!(cmd["threshold"], 0.2);
+        assert_eq!(cmd["selector"], "#hero");
+        assert_eq!(cmd["fullPage"], true);
+    }
+
+    #[test]
+    fn test_diff_screenshot_missing_baseline() {
+        let result = parse_command(&args("diff screenshot"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_screenshot_global_full_flag() {
+        let mut flags = default_flags();
+        flags.full = true;
+        let cmd =
+            parse_command(&args("diff screenshot --baseline b.png"), &flags).unwrap();
+        assert_eq!(cmd["action"], "diff_screenshot");
+        assert_eq!(cmd["fullPage"], true);
+    }
+
+    #[test]
+    fn test_diff_url_basic() {
+        let cmd = parse_command(
+            &args("diff url https://a.com https://b.com"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "diff_url");
+        assert_eq!(cmd["url1"], "https://a.com");
+        assert_eq!(cmd["url2"], "https://b.com");
+    }
+
+    #[test]
+    fn test_diff_url_with_screenshot_full() {
+        let cmd = parse_command(
+            &args("diff url https://a.com https://b.com --screenshot --full"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "diff_url");
+        assert_eq!(cmd["screenshot"], true);
+        assert_eq!(cmd["fullPage"], true);
+    }
+
+    #[test]
+    fn test_diff_url_with_wait_until() {
+        let cmd = parse_command(
+            &args("diff url https://a.com https://b.com --wait-until networkidle"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "diff_url");
+        assert_eq!(cmd["waitUntil"], "networkidle");
+    }
+
+    #[test]
+    fn test_diff_url_global_full_flag() {
+        let mut flags = default_flags();
+        flags.full = true;
+        let cmd =
+            parse_command(&args("diff url https://a.com https://b.com"), &flags).unwrap();
+        assert_eq!(cmd["fullPage"], true);
+    }
+
+    #[test]
+    fn test_diff_missing_subcommand() {
+        let result = parse_command(&args("diff"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_unknown_subcommand() {
+        let result = parse_command(&args("diff invalid"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_snapshot_baseline_missing_value() {
+        let result = parse_command(&args("diff snapshot --baseline"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_snapshot_selector_missing_value() {
+        let result = parse_command(&args("diff snapshot --selector"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_snapshot_depth_missing_value() {
+        let result = parse_command(&args("diff snapshot --depth"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_screenshot_threshold_missing_value() {
+        let result = parse_command(
+            &args("diff screenshot --baseline b.png --threshold"),
+            &default_flags(),
+        );
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_screenshot_output_missing_value() {
+        let result = parse_command(
+            &args("diff screenshot --baseline b.png --output"),
+            &default_flags(),
+        );
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_url_wait_until_missing_value() {
+        let result = parse_command(
+            &args("diff url https://a.com https://b.com --wait-until"),
+            &default_flags(),
+        );
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_snapshot_unexpected_arg() {
+        let result = parse_command(&args("diff snapshot foo"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_screenshot_unexpected_arg() {
+        let result = parse_command(
+            &args("diff screenshot --baseline b.png unexpected"),
+            &default_flags(),
+        );
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_url_unexpected_arg() {
+        let result = parse_command(
+            &args("diff url https://a.com https://b.com extra"),
+            &default_flags(),
+        );
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_snapshot_unknown_flag() {
+        let result = parse_command(&args("diff snapshot --invalid"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_url_missing_urls() {
+        let result = parse_command(&args("diff url"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_url_missing_second_url() {
+        let result = parse_command(&args("diff url https://a.com"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_snapshot_depth_invalid_value() {
+        let result = parse_command(&args("diff snapshot --depth abc"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_screenshot_threshold_invalid_value() {
+        let result = parse_command(
+            &args("diff screenshot --baseline b.png --threshold abc"),
+            &default_flags(),
+        );
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_screenshot_threshold_out_of_range() {
+        let result = parse_command(
+            &args("diff screenshot --baseline b.png --threshold 1.5"),
+            &default_flags(),
+        );
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_screenshot_threshold_negative() {
+        let result = parse_command(
+            &args("diff screenshot --baseline b.png --threshold -0.5"),
+            &default_flags(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_diff_url_with_selector() {
+        let cmd = parse_command(
+            &args("diff url https://a.com https://b.com --selector #main"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "diff_url");
+        assert_eq!(cmd["selector"], "#main");
+    }
+
+    #[test]
+    fn test_diff_url_with_compact_depth() {
+        let cmd = parse_command(
+            &args("diff url https://a.com https://b.com --compact --depth 3"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "diff_url");
+        assert_eq!(cmd["compact"], true);
+        assert_eq!(cmd["maxDepth"], 3);
+    }
+
+    #[test]
+    fn test_diff_url_with_short_snapshot_flags() {
+        let cmd = parse_command(
+            &args("diff url https://a.com https://b.com -s .content -c -d 2"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "diff_url");
+        assert_eq!(cmd["selector"], ".content");
+        assert_eq!(cmd["compact"], true);
+        assert_eq!(cmd["maxDepth"], 2);
+    }
+
+    #[test]
+    fn test_diff_url_depth_invalid_value() {
+        let result = parse_command(
+            &args("diff url https://a.com https://b.com --depth abc"),
+            &default_flags(),
+        );
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_snapshot_depth_negative_value() {
+        let result = parse_command(&args("diff snapshot --depth -1"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_url_depth_negative_value() {
+        let result = parse_command(
+            &args("diff url https://a.com https://b.com --depth -1"),
+            &default_flags(),
+        );
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_url_selector_missing_value() {
+        let result = parse_command(
+            &args("diff url https://a.com https://b.com --selector"),
+            &default_flags(),
+        );
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_url_output_dir_new() {
+        let dir = std::env::temp_dir().join("ab-test-diff-output-dir-new");
+        let _ = fs::remove_dir_all(&dir);
+        let input = format!(
+            "diff url https://a.com https://b.com --screenshot --output-dir {}",
+            dir.display()
+        );
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["screenshot"], true);
+        assert_eq!(cmd["outputDir"], dir.display().to_string());
+        assert_eq!(cmd["createDir"], true);
+    }
+
+    #[test]
+    fn test_diff_url_output_dir_existing() {
+        let dir = std::env::temp_dir().join("ab-test-diff-output-dir-existing");
+        let _ = fs::create_dir_all(&dir);
+        let input = format!(
+            "diff url https://a.com https://b.com --output-dir {} --screenshot",
+            dir.display()
+        );
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["outputDir"], dir.display().to_string());
+        assert!(cmd.get("createDir").is_none());
+        let _ = fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn test_diff_url_output_dir_requires_screenshot() {
+        let result = parse_command(
+            &args("diff url https://a.com https://b.com --output-dir ./artifacts"),
+            &default_flags(),
+        );
+        match result.unwrap_err() {
+            ParseError::MissingArguments { context, .. } => {
+                assert!(context.contains("--screenshot"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_diff_url_output_dir_missing_value() {
+        let result = parse_command(
+            &args("diff url https://a.com https://b.com --screenshot --output-dir"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_url_output_dir_is_file() {
+        let dir = std::env::temp_dir().join("ab-test-diff-output-dir-file");
+        let _ = fs::create_dir_all(&dir);
+        let file = dir.join("not-a-dir.png");
+        fs::write(&file, "").unwrap();
+        let input = format!(
+            "diff url https://a.com https://b.com --screenshot --output-dir {}",
+            file.display()
+        );
+        let result = parse_command(&args(&input), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_diff_url_without_output_dir() {
+        let cmd = parse_command(
+            &args("diff url https://a.com https://b.com --screenshot"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert!(cmd.get("outputDir").is_none());
+        assert!(cmd.get("createDir").is_none());
+    }
+
+    // === Scroll Tests ===
+
+    #[test]
+    fn test_scroll_defaults() {
+        let cmd = parse_command(&args("scroll"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "scroll");
+        assert_eq!(cmd["direction"], "down");
+        assert_eq!(cmd["amount"], 300);
+        assert!(cmd.get("selector").is_none());
+    }
+
+    #[test]
+    fn test_scroll_direction_and_amount() {
+        let cmd = parse_command(&args("scroll up 200"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "scroll");
+        assert_eq!(cmd["direction"], "up");
+        assert_eq!(cmd["amount"], 200);
+    }
+
+    #[test]
+    fn test_scroll_with_selector() {
+        let cmd = parse_command(
+            &args("scroll down 500 --selector div.scroll-container"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "scroll");
+        assert_eq!(cmd["direction"], "down");
+        assert_eq!(cmd["amount"], 500);
+        assert_eq!(cmd["selector"], "div.scroll-container");
+    }
+
+    #[test]
+    fn test_scroll_with_selector_short_flag() {
+        let cmd = parse_command(
+            &args("scroll left 100 -s .sidebar"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "scroll");
+        assert_eq!(cmd["direction"], "left");
+        assert_eq!(cmd["amount"], 100);
+        assert_eq!(cmd["selector"], ".sidebar");
+    }
+
+    #[test]
+    fn test_scroll_selector_before_positional() {
+        let cmd = parse_command(
+            &args("scroll --selector .panel down 400"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "scroll");
+        assert_eq!(cmd["direction"], "down");
+        assert_eq!(cmd["amount"], 400);
+        assert_eq!(cmd["selector"], ".panel");
+    }
+
+    #[test]
+    fn test_scroll_selector_only() {
+        let cmd = parse_command(
+            &args("scroll --selector .content"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "scroll");
+        assert_eq!(cmd["direction"], "down");
+        assert_eq!(cmd["amount"], 300);
+        assert_eq!(cmd["selector"], ".content");
+    }
+
+    #[test]
+    fn test_scroll_selector_missing_value() {
+        let result = parse_command(&args("scroll down 500 --selector"), &default_flags());
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Is Tests ===
+
+    #[test]
+    fn test_is_hidden() {
+        let cmd = parse_command(&args("is hidden #modal"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "ishidden");
+        assert_eq!(cmd["selector"], "#modal");
+    }
+
+    #[test]
+    fn test_is_hidden_missing_selector() {
+        let result = parse_command(&args("is hidden"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_is_editable() {
+        let cmd = parse_command(&args("is editable @e3"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "iseditable");
+        assert_eq!(cmd["selector"], "@e3");
+    }
+
+    #[test]
+    fn test_is_editable_missing_selector() {
+        let result = parse_command(&args("is editable"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_is_disabled() {
+        let cmd = parse_command(&args("is disabled #submit"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "isdisabled");
+        assert_eq!(cmd["selector"], "#submit");
+    }
+
+    #[test]
+    fn test_is_disabled_missing_selector() {
+        let result = parse_command(&args("is disabled"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_is_focused() {
+        let cmd = parse_command(&args("is focused input[name=q]"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "isfocused");
+        assert_eq!(cmd["selector"], "input[name=q]");
+    }
+
+    #[test]
+    fn test_is_focused_missing_selector() {
+        let result = parse_command(&args("is focused"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let cmd = parse_command(&args("is empty .results"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "isempty");
+        assert_eq!(cmd["selector"], ".results");
+    }
+
+    #[test]
+    fn test_is_empty_missing_selector() {
+        let result = parse_command(&args("is empty"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_is_unknown_subcommand_lists_new_options() {
+        let result = parse_command(&args("is selected #opt"), &default_flags());
+        match result.unwrap_err() {
+            ParseError::UnknownSubcommand { valid_options, .. } => {
+                assert!(valid_options.contains(&"hidden"));
+                assert!(valid_options.contains(&"empty"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    fn negate_flags() -> Flags {
+        let mut flags = default_flags();
+        flags.negate = true;
+        flags
+    }
+
+    #[test]
+    fn test_is_not_visible() {
+        let cmd = parse_command(&args("is visible #modal"), &negate_flags()).unwrap();
+        assert_eq!(cmd["action"], "isvisible");
+        assert_eq!(cmd["selector"], "#modal");
+        assert_eq!(cmd["negate"], true);
+    }
+
+    #[test]
+    fn test_is_not_checked() {
+        let cmd = parse_command(&args("is checked #agree"), &negate_flags()).unwrap();
+        assert_eq!(cmd["action"], "ischecked");
+        assert_eq!(cmd["selector"], "#agree");
+        assert_eq!(cmd["negate"], true);
+    }
+
+    #[test]
+    fn test_is_not_on_new_subcommands() {
+        for sub in ["hidden", "editable", "disabled", "focused", "empty"] {
+            let cmd = parse_command(&args(&format!("is {} #el", sub)), &negate_flags()).unwrap();
+            assert_eq!(cmd["action"], format!("is{}", sub));
+            assert_eq!(cmd["negate"], true);
+        }
+    }
+
+    #[test]
+    fn test_is_without_not_has_no_negate() {
+        let cmd = parse_command(&args("is enabled #submit"), &default_flags()).unwrap();
+        assert!(cmd.get("negate").is_none());
+    }
+
+    #[test]
+    fn test_is_not_missing_selector() {
+        let result = parse_command(&args("is visible"), &negate_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_is_not_stripped_by_clean_args() {
+        let cleaned = crate::flags::clean_args(&args("is --not visible #modal --json"));
+        assert_eq!(cleaned, args("is visible #modal"));
+        let cleaned = crate::flags::clean_args(&args("is checked #agree --not"));
+        assert_eq!(cleaned, args("is checked #agree"));
+    }
+
+    // === Find Tests ===
+
+    #[test]
+    fn test_find_filter_has_text() {
+        let cmd = parse_command(
+            &args("find filter li.item --has-text Apple"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "findfilter");
+        assert_eq!(cmd["selector"], "li.item");
+        assert_eq!(cmd["hasText"], "Apple");
+        assert!(cmd.get("has").is_none());
+    }
+
+    #[test]
+    fn test_find_filter_has() {
+        let cmd = parse_command(
+            &args("find filter .card --has button.buy"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "findfilter");
+        assert_eq!(cmd["selector"], ".card");
+        assert_eq!(cmd["has"], "button.buy");
+        assert!(cmd.get("hasText").is_none());
+    }
+
+    #[test]
+    fn test_find_filter_both() {
+        let cmd = parse_command(
+            &args("find filter .card --has-text Sale --has img"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["hasText"], "Sale");
+        assert_eq!(cmd["has"], "img");
+    }
+
+    #[test]
+    fn test_find_filter_missing_selector() {
+        let result = parse_command(&args("find filter"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_find_filter_missing_filter_flag() {
+        let result = parse_command(&args("find filter li.item"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_find_filter_has_text_missing_value() {
+        let result = parse_command(&args("find filter li.item --has-text"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_find_has() {
+        let cmd = parse_command(&args("find has .card button.buy"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "findhas");
+        assert_eq!(cmd["selector"], ".card");
+        assert_eq!(cmd["has"], "button.buy");
+    }
+
+    #[test]
+    fn test_find_has_missing_sub_selector() {
+        let result = parse_command(&args("find has .card"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_find_has_missing_all() {
+        let result = parse_command(&args("find has"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Wait for Hidden/Detached/Navigation Tests ===
+
+    #[test]
+    fn test_wait_element_hidden() {
+        let cmd = parse_command(&args("wait --element-hidden #spinner"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitforhidden");
+        assert_eq!(cmd["selector"], "#spinner");
+    }
+
+    #[test]
+    fn test_wait_element_hidden_missing_selector() {
+        let result = parse_command(&args("wait --element-hidden"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wait_element_detached() {
+        let cmd = parse_command(&args("wait --element-detached .modal"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitfordetached");
+        assert_eq!(cmd["selector"], ".modal");
+    }
+
+    #[test]
+    fn test_wait_element_detached_missing_selector() {
+        let result = parse_command(&args("wait --element-detached"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wait_navigation() {
+        let cmd = parse_command(&args("wait --navigation"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitfornavigation");
+        assert!(cmd.get("waitUntil").is_none());
+    }
+
+    #[test]
+    fn test_wait_navigation_wait_until() {
+        let cmd = parse_command(
+            &args("wait --navigation --wait-until networkidle"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "waitfornavigation");
+        assert_eq!(cmd["waitUntil"], "networkidle");
+    }
+
+    #[test]
+    fn test_wait_navigation_invalid_wait_until() {
+        let result = parse_command(
+            &args("wait --navigation --wait-until idle"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wait_navigation_rejects_commit_state() {
+        // The daemon's waitUntil only knows load, domcontentloaded and networkidle
+        for input in [
+            "wait --navigation --wait-until commit",
+            "reload --wait-until commit",
+            "back --wait-until commit",
+        ] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::InvalidValue { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_wait_navigation_wait_until_missing_value() {
+        let result = parse_command(&args("wait --navigation --wait-until"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wait_element_hidden_takes_precedence_over_url() {
+        let cmd = parse_command(
+            &args("wait --url **/done --element-hidden #spinner"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "waitforhidden");
+    }
+
+    #[test]
+    fn test_wait_navigation_takes_precedence_over_text() {
+        let cmd = parse_command(&args("wait --text Done --navigation"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitfornavigation");
+    }
+
+    #[test]
+    fn test_wait_new_flags_survive_clean_args() {
+        let input = args("wait --element-hidden #spinner --json");
+        let cleaned = crate::flags::clean_args(&input);
+        assert_eq!(cleaned, args("wait --element-hidden #spinner"));
+    }
+
+    // === Wait for Request/Response Tests ===
+
+    #[test]
+    fn test_wait_request() {
+        let cmd = parse_command(&args("wait --request **/api/login"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitforrequest");
+        assert_eq!(cmd["url"], "**/api/login");
+        assert!(cmd.get("timeout").is_none());
+    }
+
+    #[test]
+    fn test_wait_response() {
+        let cmd = parse_command(&args("wait --response **/api/users"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitforresponse");
+        assert_eq!(cmd["url"], "**/api/users");
+    }
+
+    #[test]
+    fn test_wait_request_with_timeout() {
+        let input = args("wait --request **/api/login --timeout 5000");
+        let mut flags = default_flags();
+        flags.timeout = Some("5000".to_string());
+        let cmd = parse_command(&crate::flags::clean_args(&input), &flags).unwrap();
+        assert_eq!(cmd["action"], "waitforrequest");
+        assert_eq!(cmd["timeout"], 5000);
+    }
+
+    #[test]
+    fn test_wait_response_timeout_before_flag() {
+        let input = args("wait --timeout 2000 --response /\\/api\\/v[0-9]+/");
+        let mut flags = default_flags();
+        flags.timeout = Some("2000".to_string());
+        let cmd = parse_command(&crate::flags::clean_args(&input), &flags).unwrap();
+        assert_eq!(cmd["action"], "waitforresponse");
+        assert_eq!(cmd["url"], "/\\/api\\/v[0-9]+/");
+        assert_eq!(cmd["timeout"], 2000);
+    }
+
+    #[test]
+    fn test_wait_request_missing_url() {
+        let result = parse_command(&args("wait --request"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wait_response_flag_instead_of_url() {
+        let result = parse_command(&args("wait --response --timeout 1000"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wait_request_invalid_timeout() {
+        let input = args("wait --request **/api --timeout soon");
+        let mut flags = default_flags();
+        flags.timeout = Some("soon".to_string());
+        let result = parse_command(&crate::flags::clean_args(&input), &flags);
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wait_existing_branches_unaffected() {
+        let cmd = parse_command(&args("wait --url **/dashboard"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitforurl");
+        let cmd = parse_command(&args("wait --load networkidle"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitforloadstate");
+        let cmd = parse_command(&args("wait #main"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "wait");
+        assert_eq!(cmd["selector"], "#main");
+    }
+
+    // === Wait for Console Tests ===
+
+    #[test]
+    fn test_wait_console() {
+        let cmd = parse_command(&args("wait --console ready"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "waitforconsole");
+        assert_eq!(cmd["pattern"], "ready");
+        assert!(cmd.get("messageType").is_none());
+    }
+
+    #[test]
+    fn test_wait_console_pattern_passed_as_is() {
+        let input = vec![
+            "wait".to_string(),
+            "--console".to_string(),
+            "^App (ready|loaded)$".to_string(),
+        ];
+        let cmd = parse_command(&input, &default_flags()).unwrap();
+        assert_eq!(cmd["pattern"], "^App (ready|loaded)$");
+    }
+
+    #[test]
+    fn test_wait_console_with_type() {
+        let cmd = parse_command(
+            &args("wait --console failed --type error"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "waitforconsole");
+        assert_eq!(cmd["messageType"], "error");
+    }
+
+    #[test]
+    fn test_wait_console_invalid_type() {
+        let result = parse_command(
+            &args("wait --console failed --type debug"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wait_console_type_missing_value() {
+        let result = parse_command(&args("wait --console failed --type"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wait_console_missing_pattern() {
+        let result = parse_command(&args("wait --console"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wait_fn_polling_interval() {
+        let cmd = parse_command(
+            &args("wait --fn window.ready --polling-interval 500"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "waitforfunction");
+        assert_eq!(cmd["expression"], "window.ready");
+        assert_eq!(cmd["pollingInterval"], 500);
+    }
+
+    #[test]
+    fn test_wait_text_polling_interval_first() {
+        let cmd = parse_command(
+            &args("wait --polling-interval 50 --text Welcome"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], "text=Welcome");
+        assert_eq!(cmd["pollingInterval"], 50);
+    }
+
+    #[test]
+    fn test_wait_selector_polling_interval_is_not_the_target() {
+        let cmd = parse_command(
+            &args("wait --polling-interval 250 #ready"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "wait");
+        assert_eq!(cmd["selector"], "#ready");
+        assert!(cmd.get("timeout").is_none());
+        assert_eq!(cmd["pollingInterval"], 250);
+    }
+
+    #[test]
+    fn test_wait_polling_interval_with_timeout() {
+        let mut flags = default_flags();
+        flags.timeout = Some("10000".to_string());
+        let cmd = parse_command(
+            &args("wait --fn window.ready --polling-interval 1000"),
+            &flags,
+        )
+        .unwrap();
+        assert_eq!(cmd["pollingInterval"], 1000);
+        assert_eq!(cmd["timeout"], 10000);
+    }
+
+    #[test]
+    fn test_wait_polling_interval_invalid() {
+        for input in [
+            "wait --fn ok --polling-interval 0",
+            "wait --fn ok --polling-interval -5",
+            "wait --fn ok --polling-interval fast",
+        ] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::InvalidValue { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_wait_polling_interval_missing_value() {
+        let result = parse_command(&args("wait --fn ok --polling-interval"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wait_without_polling_interval() {
+        let cmd = parse_command(&args("wait --fn ok"), &default_flags()).unwrap();
+        assert!(cmd.get("pollingInterval").is_none());
+    }
+
+    // === Network Route Tests ===
+
+    #[test]
+    fn test_network_route_basic() {
+        let cmd = parse_command(&args("network route **/api/*"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "route");
+        assert_eq!(cmd["url"], "**/api/*");
+        assert_eq!(cmd["abort"], false);
+        assert!(cmd.get("status").is_none());
+        assert!(cmd.get("delay").is_none());
+    }
+
+    #[test]
+    fn test_network_route_status() {
+        let cmd =
+            parse_command(&args("network route **/api --status 404"), &default_flags()).unwrap();
+        assert_eq!(cmd["status"], 404);
+    }
+
+    #[test]
+    fn test_network_route_status_lower_bound() {
+        let cmd =
+            parse_command(&args("network route **/api --status 100"), &default_flags()).unwrap();
+        assert_eq!(cmd["status"], 100);
+    }
+
+    #[test]
+    fn test_network_route_status_upper_bound() {
+        let cmd =
+            parse_command(&args("network route **/api --status 599"), &default_flags()).unwrap();
+        assert_eq!(cmd["status"], 599);
+    }
+
+    #[test]
+    fn test_network_route_status_below_range() {
+        let result = parse_command(&args("network route **/api --status 99"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_route_status_above_range() {
+        let result = parse_command(&args("network route **/api --status 600"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_route_delay() {
+        let cmd =
+            parse_command(&args("network route **/api --delay 1500"), &default_flags()).unwrap();
+        assert_eq!(cmd["delay"], 1500);
+    }
+
+    #[test]
+    fn test_network_route_negative_delay() {
+        let result = parse_command(&args("network route **/api --delay -5"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_route_redirect_defaults_to_302() {
+        let cmd = parse_command(
+            &args("network route **/old --redirect https://example.com/new"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["redirect"], "https://example.com/new");
+        assert_eq!(cmd["status"], 302);
+    }
+
+    #[test]
+    fn test_network_route_redirect_with_status() {
+        let cmd = parse_command(
+            &args("network route **/old --redirect /new --status 301"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["redirect"], "/new");
+        assert_eq!(cmd["status"], 301);
+    }
+
+    #[test]
+    fn test_network_route_abort_and_redirect_conflict() {
+        let result = parse_command(
+            &args("network route **/old --abort --redirect /new"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_route_redirect_missing_value() {
+        let result = parse_command(&args("network route **/old --redirect"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_route_body_with_status_and_delay() {
+        let cmd = parse_command(
+            &args("network route **/api --body {} --status 201 --delay 0"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["body"], "{}");
+        assert_eq!(cmd["status"], 201);
+        assert_eq!(cmd["delay"], 0);
+    }
+
+    #[test]
+    fn test_network_route_headers() {
+        let mut flags = default_flags();
+        flags.headers = Some(r#"{"Access-Control-Allow-Origin": "*"}"#.to_string());
+        flags.cli_headers = true;
+        let cmd = parse_command(&args("network route **/api/*"), &flags).unwrap();
+        assert_eq!(cmd["responseHeaders"]["Access-Control-Allow-Origin"], "*");
+    }
+
+    #[test]
+    fn test_network_route_headers_with_body() {
+        let mut flags = default_flags();
+        flags.headers = Some(r#"{"Cache-Control": "no-store"}"#.to_string());
+        flags.cli_headers = true;
+        let cmd = parse_command(&args("network route **/api --body {}"), &flags).unwrap();
+        assert_eq!(cmd["body"], "{}");
+        assert_eq!(cmd["responseHeaders"]["Cache-Control"], "no-store");
+    }
+
+    #[test]
+    fn test_network_route_headers_invalid_json() {
+        let mut flags = default_flags();
+        flags.headers = Some("not json".to_string());
+        flags.cli_headers = true;
+        let result = parse_command(&args("network route **/api"), &flags);
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_route_headers_not_object() {
+        let mut flags = default_flags();
+        flags.headers = Some("[1, 2]".to_string());
+        flags.cli_headers = true;
+        let result = parse_command(&args("network route **/api"), &flags);
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_route_ignores_config_headers() {
+        let mut flags = default_flags();
+        flags.headers = Some(r#"{"Authorization": "Bearer token"}"#.to_string());
+        let cmd = parse_command(&args("network route **/api"), &flags).unwrap();
+        assert!(cmd.get("responseHeaders").is_none());
+    }
+
+    #[test]
+    fn test_network_route_headers_end_to_end() {
+        let input: Vec<String> = vec![
+            "network".to_string(),
+            "route".to_string(),
+            "**/api".to_string(),
+            "--headers".to_string(),
+            r#"{"X-Mock": "1"}"#.to_string(),
+        ];
+        let mut flags = default_flags();
+        flags.headers = Some(r#"{"X-Mock": "1"}"#.to_string());
+        flags.cli_headers = true;
+        let cleaned = crate::flags::clean_args(&input);
+        let cmd = parse_command(&cleaned, &flags).unwrap();
+        assert_eq!(cmd["url"], "**/api");
+        assert_eq!(cmd["responseHeaders"]["X-Mock"], "1");
+    }
+
+    #[test]
+    fn test_network_route_file() {
+        let dir = std::env::temp_dir().join("ab-test-route-file");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("users.json");
+        fs::write(&path, r#"[{"id": 1}]"#).unwrap();
+
+        let input = format!("network route **/api/users --file {}", path.display());
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "route");
+        assert_eq!(cmd["body"], r#"[{"id": 1}]"#);
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn test_network_route_file_not_found() {
+        let result = parse_command(
+            &args("network route **/api --file /nonexistent/fixture.json"),
+            &default_flags(),
+        );
+        match result.unwrap_err() {
+            ParseError::InvalidValue { message, .. } => {
+                assert!(message.contains("/nonexistent/fixture.json"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_network_route_file_missing_path() {
+        let result = parse_command(&args("network route **/api --file"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_route_file_and_body_conflict() {
+        let result = parse_command(
+            &args("network route **/api --body {} --file fixture.json"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_route_file_with_status() {
+        let dir = std::env::temp_dir().join("ab-test-route-file-status");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("error.json");
+        fs::write(&path, r#"{"error": "boom"}"#).unwrap();
+
+        let input = format!(
+            "network route **/api --file {} --status 500",
+            path.display()
+        );
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["body"], r#"{"error": "boom"}"#);
+        assert_eq!(cmd["status"], 500);
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn test_network_route_file_with_content_type() {
+        let dir = std::env::temp_dir().join("ab-test-route-file-ct");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("page.html");
+        fs::write(&path, "<h1>mock</h1>").unwrap();
+
+        let input = format!(
+            "network route **/page --file {} --content-type text/html",
+            path.display()
+        );
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["body"], "<h1>mock</h1>");
+        assert_eq!(cmd["contentType"], "text/html");
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn test_network_route_content_type_with_body() {
+        let cmd = parse_command(
+            &args("network route **/api --body ok --content-type text/plain"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["body"], "ok");
+        assert_eq!(cmd["contentType"], "text/plain");
+    }
+
+    #[test]
+    fn test_network_route_content_type_missing_value() {
+        let result = parse_command(
+            &args("network route **/api --body ok --content-type"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Network Throttle Tests ===
+
+    #[test]
+    fn test_network_throttle_slow_3g() {
+        let cmd = parse_command(&args("network throttle slow-3g"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "throttle");
+        assert_eq!(cmd["preset"], "slow-3g");
+    }
+
+    #[test]
+    fn test_network_throttle_fast_3g() {
+        let cmd = parse_command(&args("network throttle fast-3g"), &default_flags()).unwrap();
+        assert_eq!(cmd["preset"], "fast-3g");
+    }
+
+    #[test]
+    fn test_network_throttle_offline() {
+        let cmd = parse_command(&args("network throttle offline"), &default_flags()).unwrap();
+        assert_eq!(cmd["preset"], "offline");
+    }
+
+    #[test]
+    fn test_network_throttle_none_clears() {
+        let cmd = parse_command(&args("network throttle none"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "throttle");
+        assert_eq!(cmd["preset"], "none");
+    }
+
+    #[test]
+    fn test_network_throttle_unknown_preset() {
+        let result = parse_command(&args("network throttle 5g"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_throttle_custom() {
+        let cmd = parse_command(
+            &args("network throttle --download 500 --upload 250 --latency 300"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "throttle");
+        assert_eq!(cmd["downloadKbps"], 500);
+        assert_eq!(cmd["uploadKbps"], 250);
+        assert_eq!(cmd["latencyMs"], 300);
+        assert!(cmd.get("preset").is_none());
+    }
+
+    #[test]
+    fn test_network_throttle_custom_latency_only() {
+        let cmd = parse_command(&args("network throttle --latency 150"), &default_flags()).unwrap();
+        assert_eq!(cmd["latencyMs"], 150);
+        assert!(cmd.get("downloadKbps").is_none());
+        assert!(cmd.get("uploadKbps").is_none());
+    }
+
+    #[test]
+    fn test_network_throttle_invalid_number() {
+        let result = parse_command(&args("network throttle --download fast"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_throttle_negative_number() {
+        let result = parse_command(&args("network throttle --latency -10"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_throttle_flag_missing_value() {
+        let result = parse_command(&args("network throttle --upload"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_throttle_missing_args() {
+        let result = parse_command(&args("network throttle"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_throttle_unknown_flag_only() {
+        let result = parse_command(&args("network throttle --fast"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Network HAR Tests ===
+
+    #[test]
+    fn test_network_har_save() {
+        let cmd = parse_command(&args("network har save ./traffic.har"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "har_save");
+        assert_eq!(cmd["path"], "./traffic.har");
+    }
+
+    #[test]
+    fn test_network_har_load() {
+        let cmd = parse_command(&args("network har load ./traffic.har"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "har_load");
+        assert_eq!(cmd["path"], "./traffic.har");
+    }
+
+    #[test]
+    fn test_network_har_save_missing_path() {
+        let result = parse_command(&args("network har save"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_har_load_missing_path() {
+        let result = parse_command(&args("network har load"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_har_missing_subcommand() {
+        let result = parse_command(&args("network har"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_har_unknown_subcommand() {
+        let result = parse_command(&args("network har export out.har"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
+    }
+
+    // === Network Requests Tests ===
+
+    #[test]
+    fn test_network_requests_defaults() {
+        let cmd = parse_command(&args("network requests"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "requests");
+        assert_eq!(cmd["clear"], false);
+        assert!(cmd.get("url").is_none());
+        assert!(cmd.get("format").is_none());
+    }
+
+    #[test]
+    fn test_network_requests_url_filter() {
+        let cmd =
+            parse_command(&args("network requests --url **/api/*"), &default_flags()).unwrap();
+        assert_eq!(cmd["url"], "**/api/*");
+    }
+
+    #[test]
+    fn test_network_requests_method_uppercased() {
+        let cmd = parse_command(&args("network requests --method post"), &default_flags()).unwrap();
+        assert_eq!(cmd["method"], "POST");
+    }
+
+    #[test]
+    fn test_network_requests_invalid_method() {
+        let result = parse_command(
+            &args("network requests --method GET/POST"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_requests_status() {
+        let cmd = parse_command(&args("network requests --status 404"), &default_flags()).unwrap();
+        assert_eq!(cmd["status"], 404);
+    }
+
+    #[test]
+    fn test_network_requests_invalid_status() {
+        let result = parse_command(&args("network requests --status abc"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_requests_format() {
+        for format in ["table", "json", "ndjson"] {
+            let cmd = parse_command(
+                &args(&format!("network requests --output-format {}", format)),
+                &default_flags(),
+            )
+            .unwrap();
+            assert_eq!(cmd["format"], format);
+        }
+    }
+
+    #[test]
+    fn test_network_requests_invalid_format() {
+        let result = parse_command(
+            &args("network requests --output-format csv"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_requests_flag_missing_value() {
+        let result = parse_command(&args("network requests --url"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_requests_combined_filters() {
+        let cmd = parse_command(
+            &args("network requests --filter api --url **/v2/* --method DELETE --status 204 --output-format json"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["filter"], "api");
+        assert_eq!(cmd["url"], "**/v2/*");
+        assert_eq!(cmd["method"], "DELETE");
+        assert_eq!(cmd["status"], 204);
+        assert_eq!(cmd["format"], "json");
+    }
+
+    #[test]
+    fn test_network_requests_clear_still_works() {
+        let cmd = parse_command(&args("network requests --clear"), &default_flags()).unwrap();
+        assert_eq!(cmd["clear"], true);
+    }
+
+    // === Mouse Tests ===
+
+    #[test]
+    fn test_mouse_click() {
+        let cmd = parse_command(&args("mouse click 400 300"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "mouseclick");
+        assert_eq!(cmd["x"], 400);
+        assert_eq!(cmd["y"], 300);
+    }
+
+    #[test]
+    fn test_mouse_dblclick() {
+        let cmd = parse_command(&args("mouse dblclick 10 20"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "mousedblclick");
+        assert_eq!(cmd["x"], 10);
+        assert_eq!(cmd["y"], 20);
+    }
+
+    #[test]
+    fn test_mouse_rightclick() {
+        let cmd = parse_command(&args("mouse rightclick 0 0"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "mouserightclick");
+        assert_eq!(cmd["x"], 0);
+        assert_eq!(cmd["y"], 0);
+    }
+
+    #[test]
+    fn test_mouse_click_missing_coordinates() {
+        let result = parse_command(&args("mouse click"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_mouse_click_missing_y() {
+        let result = parse_command(&args("mouse click 400"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_mouse_dblclick_missing_y() {
+        let result = parse_command(&args("mouse dblclick 5"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_mouse_click_non_integer() {
+        let result = parse_command(&args("mouse click 1.5 300"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_mouse_rightclick_non_integer_y() {
+        let result = parse_command(&args("mouse rightclick 100 top"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_mouse_dblclick_non_integer() {
+        let result = parse_command(&args("mouse dblclick x y"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_mouse_move_with_steps() {
+        let cmd = parse_command(&args("mouse move 400 300 --steps 20"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "mousemove");
+        assert_eq!(cmd["x"], 400);
+        assert_eq!(cmd["y"], 300);
+        assert_eq!(cmd["steps"], 20);
+    }
+
+    #[test]
+    fn test_mouse_move_without_steps() {
+        let cmd = parse_command(&args("mouse move 100 200"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "mousemove");
+        assert!(cmd.get("steps").is_none());
+    }
+
+    #[test]
+    fn test_mouse_move_zero_steps() {
+        let result = parse_command(&args("mouse move 100 200 --steps 0"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_mouse_move_non_integer_steps() {
+        let result = parse_command(&args("mouse move 100 200 --steps many"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_mouse_move_steps_missing_value() {
+        let result = parse_command(&args("mouse move 100 200 --steps"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_mouse_move_steps_before_coordinates() {
+        let result = parse_command(&args("mouse move --steps 5 100 200"), &default_flags());
+        assert!(result.is_err());
+    }
+
+    // === Set Timezone/Locale/Permissions Tests ===
+
+    #[test]
+    fn test_set_timezone() {
+        let cmd = parse_command(&args("set timezone America/New_York"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "timezone");
+        assert_eq!(cmd["timezone"], "America/New_York");
+    }
+
+    #[test]
+    fn test_set_timezone_missing_value() {
+        let result = parse_command(&args("set timezone"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_set_locale() {
+        let cmd = parse_command(&args("set locale fr-FR"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "locale");
+        assert_eq!(cmd["locale"], "fr-FR");
+    }
+
+    #[test]
+    fn test_set_locale_missing_value() {
+        let result = parse_command(&args("set locale"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_set_permissions_single() {
+        let cmd = parse_command(
+            &args("set permissions https://example.com geolocation"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "permissions");
+        assert_eq!(cmd["origin"], "https://example.com");
+        assert_eq!(cmd["permissions"], json!(["geolocation"]));
+    }
+
+    #[test]
+    fn test_set_permissions_multiple() {
+        let cmd = parse_command(
+            &args("set permissions https://example.com geolocation notifications camera"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(
+            cmd["permissions"],
+            json!(["geolocation", "notifications", "camera"])
+        );
+    }
+
+    #[test]
+    fn test_set_permissions_missing_permission() {
+        let result = parse_command(
+            &args("set permissions https://example.com"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_set_permissions_missing_origin() {
+        let result = parse_command(&args("set permissions"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_set_timezone_survives_clean_args() {
+        let input = args("set timezone Asia/Tokyo --json");
+        let cleaned = crate::flags::clean_args(&input);
+        let cmd = parse_command(&cleaned, &default_flags()).unwrap();
+        assert_eq!(cmd["timezone"], "Asia/Tokyo");
+    }
+
+    // === Timeout Tests ===
+
+    #[test]
+    fn test_set_timeout() {
+        let cmd = parse_command(&args("set timeout 10000"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "settimeout");
+        assert_eq!(cmd["timeout"], 10000);
+    }
+
+    #[test]
+    fn test_set_timeout_zero_means_none() {
+        let cmd = parse_command(&args("set timeout 0"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "settimeout");
+        assert_eq!(cmd["timeout"], 0);
+    }
+
+    #[test]
+    fn test_set_timeout_negative() {
+        let result = parse_command(&args("set timeout -1"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_set_timeout_missing_value() {
+        let result = parse_command(&args("set timeout"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_global_timeout_added_to_command() {
+        let mut flags = default_flags();
+        flags.timeout = Some("5000".to_string());
+        let cmd = parse_command(&args("click #submit"), &flags).unwrap();
+        assert_eq!(cmd["action"], "click");
+        assert_eq!(cmd["timeout"], 5000);
+    }
+
+    #[test]
+    fn test_no_global_timeout_by_default() {
+        let cmd = parse_command(&args("click #submit"), &default_flags()).unwrap();
+        assert!(cmd.get("timeout").is_none());
+    }
+
+    #[test]
+    fn test_global_timeout_does_not_override_command_timeout() {
+        let mut flags = default_flags();
+        flags.timeout = Some("5000".to_string());
+        let cmd = parse_command(&args("wait 2000"), &flags).unwrap();
+        assert_eq!(cmd["action"], "wait");
+        assert_eq!(cmd["timeout"], 2000);
+    }
+
+    #[test]
+    fn test_global_timeout_invalid() {
+        let mut flags = default_flags();
+        flags.timeout = Some("soon".to_string());
+        let result = parse_command(&args("click #submit"), &flags);
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_global_timeout_zero_rejected() {
+        let mut flags = default_flags();
+        flags.timeout = Some("0".to_string());
+        let result = parse_command(&args("click #submit"), &flags);
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_global_timeout_injected_into_every_command() {
+        let mut flags = default_flags();
+        flags.timeout = Some("7000".to_string());
+        for input in [
+            "open example.com",
+            "fill #q hello",
+            "snapshot",
+            "tab list",
+            "get title",
+        ] {
+            let cmd = parse_command(&args(input), &flags).unwrap();
+            assert_eq!(cmd["timeout"], 7000, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_global_timeout_end_to_end_with_wait_download() {
+        let input = args("wait --download ./file.pdf --timeout 30000");
+        let mut flags = default_flags();
+        flags.timeout = Some("30000".to_string());
+        let cleaned = crate::flags::clean_args(&input);
+        let cmd = parse_command(&cleaned, &flags).unwrap();
+        assert_eq!(cmd["action"], "waitfordownload");
+        assert_eq!(cmd["path"], "./file.pdf");
+        assert_eq!(cmd["timeout"], 30000);
+    }
+
+    // === Set Viewport Tests ===
+
+    #[test]
+    fn test_set_viewport_basic() {
+        let cmd = parse_command(&args("set viewport 1920 1080"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "viewport");
+        assert_eq!(cmd["width"], 1920);
+        assert_eq!(cmd["height"], 1080);
+        assert!(cmd.get("deviceScaleFactor").is_none());
+        assert!(cmd.get("isMobile").is_none());
+        assert!(cmd.get("hasTouch").is_none());
+    }
+
+    #[test]
+    fn test_set_viewport_device_scale_factor() {
+        let cmd = parse_command(
+            &args("set viewport 1280 720 --device-scale-factor 2"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["width"], 1280);
+        assert_eq!(cmd["deviceScaleFactor"], 2.0);
+    }
+
+    #[test]
+    fn test_set_viewport_fractional_scale_factor() {
+        let cmd = parse_command(
+            &args("set viewport 1280 720 --device-scale-factor 1.5"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["deviceScaleFactor"], 1.5);
+    }
+
+    #[test]
+    fn test_set_viewport_is_mobile_and_has_touch() {
+        let cmd = parse_command(
+            &args("set viewport 390 844 --is-mobile --has-touch"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["isMobile"], true);
+        assert_eq!(cmd["hasTouch"], true);
+    }
+
+    #[test]
+    fn test_set_viewport_all_flags() {
+        let cmd = parse_command(
+            &args("set viewport 390 844 --device-scale-factor 3 --is-mobile --has-touch"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["width"], 390);
+        assert_eq!(cmd["height"], 844);
+        assert_eq!(cmd["deviceScaleFactor"], 3.0);
+        assert_eq!(cmd["isMobile"], true);
+        assert_eq!(cmd["hasTouch"], true);
+    }
+
+    #[test]
+    fn test_set_viewport_flags_only() {
+        let cmd = parse_command(
+            &args("set viewport --device-scale-factor 2"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "viewport");
+        assert_eq!(cmd["deviceScaleFactor"], 2.0);
+        assert!(cmd.get("width").is_none());
+        assert!(cmd.get("height").is_none());
+    }
+
+    #[test]
+    fn test_set_viewport_has_touch_only() {
+        let cmd = parse_command(&args("set viewport --has-touch"), &default_flags()).unwrap();
+        assert_eq!(cmd["hasTouch"], true);
+        assert!(cmd.get("width").is_none());
+    }
+
+    #[test]
+    fn test_set_viewport_negative_scale_factor() {
+        let result = parse_command(
+            &args("set viewport 1280 720 --device-scale-factor -2"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_set_viewport_zero_scale_factor() {
+        let result = parse_command(
+            &args("set viewport --device-scale-factor 0"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_set_viewport_scale_factor_missing_value() {
+        let result = parse_command(
+            &args("set viewport 1280 720 --device-scale-factor"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_set_viewport_preset() {
+        let cmd = parse_command(&args("set viewport --preset iPhone15"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "viewport");
+        assert_eq!(cmd["width"], 393);
+        assert_eq!(cmd["height"], 852);
+        assert_eq!(cmd["deviceScaleFactor"], 3.0);
+        assert_eq!(cmd["isMobile"], true);
+    }
+
+    #[test]
+    fn test_set_viewport_preset_case_insensitive() {
+        for name in ["iphone15", "IPHONE15", "iPhone-15", "iphone_15"] {
+            let input = format!("set viewport --preset {}", name);
+            let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+            assert_eq!(cmd["width"], 393, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_set_viewport_desktop_preset() {
+        let cmd =
+            parse_command(&args("set viewport --preset desktop1080"), &default_flags()).unwrap();
+        assert_eq!(cmd["width"], 1920);
+        assert_eq!(cmd["height"], 1080);
+        assert_eq!(cmd["deviceScaleFactor"], 1.0);
+        assert_eq!(cmd["isMobile"], false);
+    }
+
+    #[test]
+    fn test_set_viewport_preset_with_overrides() {
+        let cmd = parse_command(
+            &args("set viewport --preset Pixel7 --device-scale-factor 2 --has-touch"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["width"], 412);
+        assert_eq!(cmd["deviceScaleFactor"], 2.0);
+        assert_eq!(cmd["hasTouch"], true);
+    }
+
+    #[test]
+    fn test_set_viewport_preset_with_size_conflict() {
+        assert!(matches!(
+            parse_command(
+                &args("set viewport 1280 720 --preset iPad"),
+                &default_flags()
+            ),
+            Err(ParseError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_set_viewport_unknown_preset_suggests() {
+        let err =
+            parse_command(&args("set viewport --preset iPhon15"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.to_string().contains("did you mean iPhone15?"));
+    }
+
+    #[test]
+    fn test_set_viewport_unknown_preset_without_suggestion() {
+        let err =
+            parse_command(&args("set viewport --preset toaster"), &default_flags()).unwrap_err();
+        let message = err.to_string();
+        assert!(!message.contains("did you mean"));
+        assert!(message.contains("Desktop1080"));
+    }
+
+    #[test]
+    fn test_set_viewport_preset_missing_name() {
+        assert!(matches!(
+            parse_command(&args("set viewport --preset"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("ipad", "ipad"), 0);
+        assert_eq!(edit_distance("iphon15", "iphone15"), 1);
+        assert_eq!(edit_distance("pixel7", "pixle7"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_set_viewport_missing_height() {
+        let result = parse_command(&args("set viewport 1280 --is-mobile"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_set_viewport_no_args() {
+        let result = parse_command(&args("set viewport"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Tab Switch Tests ===
+
+    #[test]
+    fn test_tab_switch_explicit_index() {
+        let cmd = parse_command(&args("tab switch 3"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_switch");
+        assert_eq!(cmd["index"], 3);
+    }
+
+    #[test]
+    fn test_tab_switch_url_pattern() {
+        let cmd = parse_command(&args("tab switch --url **/dashboard"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_switch");
+        assert_eq!(cmd["urlPattern"], "**/dashboard");
+        assert!(cmd.get("index").is_none());
+    }
+
+    #[test]
+    fn test_tab_switch_title_pattern() {
+        let cmd = parse_command(&args("tab switch --title Inbox"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_switch");
+        assert_eq!(cmd["titlePattern"], "Inbox");
+    }
+
+    #[test]
+    fn test_tab_url_without_switch_keyword() {
+        let cmd = parse_command(&args("tab --url **/settings"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_switch");
+        assert_eq!(cmd["urlPattern"], "**/settings");
+    }
+
+    #[test]
+    fn test_tab_title_without_switch_keyword() {
+        let cmd = parse_command(&args("tab --title Docs"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_switch");
+        assert_eq!(cmd["titlePattern"], "Docs");
+    }
+
+    #[test]
+    fn test_tab_switch_missing_target() {
+        let result = parse_command(&args("tab switch"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_tab_switch_pattern_missing_value() {
+        let result = parse_command(&args("tab switch --url"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_tab_switch_index_and_pattern_conflict() {
+        let result = parse_command(&args("tab switch 2 --url **/x"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_tab_switch_invalid_index() {
+        let result = parse_command(&args("tab switch second"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    // === Tab Close Tests ===
+
+    #[test]
+    fn test_tab_close_all() {
+        let cmd = parse_command(&args("tab close --all"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_close");
+        assert_eq!(cmd["all"], true);
+        assert!(cmd.get("index").is_none());
+    }
+
+    #[test]
+    fn test_tab_close_others() {
+        let cmd = parse_command(&args("tab close --others"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_close");
+        assert_eq!(cmd["others"], true);
+        assert!(cmd.get("all").is_none());
+    }
+
+    #[test]
+    fn test_tab_close_index_unchanged() {
+        let cmd = parse_command(&args("tab close 2"), &default_flags()).unwrap();
+        assert_eq!(cmd["index"], 2);
+        assert!(cmd.get("all").is_none());
+        assert!(cmd.get("others").is_none());
+    }
+
+    #[test]
+    fn test_tab_close_all_and_others_conflict() {
+        let result = parse_command(&args("tab close --all --others"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_tab_close_index_and_all_conflict() {
+        let result = parse_command(&args("tab close 1 --all"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_tab_close_others_and_index_conflict() {
+        let result = parse_command(&args("tab close --others 3"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_tab_close_plain_has_no_flags() {
+        let cmd = parse_command(&args("tab close"), &default_flags()).unwrap();
+        assert!(cmd.get("all").is_none());
+        assert!(cmd.get("others").is_none());
+    }
+
+    #[test]
+    fn test_tab_close_all_survives_clean_args() {
+        let cleaned = crate::flags::clean_args(&args("tab close --all --json"));
+        assert_eq!(cleaned, args("tab close --all"));
+    }
+
+    // === Tab Info Tests ===
+
+    #[test]
+    fn test_tab_info() {
+        let cmd = parse_command(&args("tab info"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_info");
+        assert!(cmd.get("index").is_none());
+    }
+
+    // === Window Tests ===
+
+    #[test]
+    fn test_window_new() {
+        let cmd = parse_command(&args("window new"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "window_new");
+    }
+
+    #[test]
+    fn test_window_list() {
+        let cmd = parse_command(&args("window list"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "window_list");
+    }
+
+    #[test]
+    fn test_window_switch() {
+        let cmd = parse_command(&args("window switch 2"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "window_switch");
+        assert_eq!(cmd["index"], 2);
+    }
+
+    #[test]
+    fn test_window_switch_missing_index() {
+        let result = parse_command(&args("window switch"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_window_switch_invalid_index() {
+        let result = parse_command(&args("window switch two"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_window_close_current() {
+        let cmd = parse_command(&args("window close"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "window_close");
+        assert!(cmd.get("index").is_none());
+    }
+
+    #[test]
+    fn test_window_close_index() {
+        let cmd = parse_command(&args("window close 1"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "window_close");
+        assert_eq!(cmd["index"], 1);
+    }
+
+    #[test]
+    fn test_window_close_invalid_index() {
+        let result = parse_command(&args("window close -1"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_window_resize() {
+        let cmd = parse_command(&args("window resize 1280 800"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "window_resize");
+        assert_eq!(cmd["width"], 1280);
+        assert_eq!(cmd["height"], 800);
+    }
+
+    #[test]
+    fn test_window_resize_missing_height() {
+        let result = parse_command(&args("window resize 1280"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_window_resize_invalid_size() {
+        let result = parse_command(&args("window resize 0 800"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+        let result = parse_command(&args("window resize wide 800"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_window_unknown_subcommand() {
+        let result = parse_command(&args("window maximize"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
+    }
+
+    #[test]
+    fn test_window_missing_subcommand() {
+        let result = parse_command(&args("window"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Frame Targeting Tests ===
+
+    #[test]
+    fn test_frame_list() {
+        let cmd = parse_command(&args("frame list"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "frame_list");
+    }
+
+    #[test]
+    fn test_frame_selector_unchanged() {
+        let cmd = parse_command(&args("frame #embed"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "frame");
+        assert_eq!(cmd["selector"], "#embed");
+    }
+
+    #[test]
+    fn test_frame_by_url() {
+        let cmd = parse_command(&args("frame --url **/checkout"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "frame");
+        assert_eq!(cmd["urlPattern"], "**/checkout");
+        assert!(cmd.get("selector").is_none());
+    }
+
+    #[test]
+    fn test_frame_by_name() {
+        let cmd = parse_command(&args("frame --name payment"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "frame");
+        assert_eq!(cmd["name"], "payment");
+        assert!(cmd.get("selector").is_none());
+    }
+
+    #[test]
+    fn test_frame_url_missing_value() {
+        let result = parse_command(&args("frame --url"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_frame_name_missing_value() {
+        let result = parse_command(&args("frame --name"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_frame_selector_and_url_conflict() {
+        let result = parse_command(&args("frame #embed --url **/checkout"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_frame_url_and_name_conflict() {
+        let result = parse_command(
+            &args("frame --url **/checkout --name payment"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_frame_two_selectors_conflict() {
+        let result = parse_command(&args("frame #a #b"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_frame_missing_target() {
+        let result = parse_command(&args("frame"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Dialog Tests ===
+
+    #[test]
+    fn test_dialog_accept() {
+        let cmd = parse_command(&args("dialog accept"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "dialog");
+        assert_eq!(cmd["response"], "accept");
+        assert!(cmd.get("promptText").is_none());
+    }
+
+    #[test]
+    fn test_dialog_accept_with_text() {
+        let cmd = parse_command(&args("dialog accept hello"), &default_flags()).unwrap();
+        assert_eq!(cmd["response"], "accept");
+        assert_eq!(cmd["promptText"], "hello");
+    }
+
+    #[test]
+    fn test_dialog_dismiss() {
+        let cmd = parse_command(&args("dialog dismiss"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "dialog");
+        assert_eq!(cmd["response"], "dismiss");
+        assert!(cmd.get("promptText").is_none());
+    }
+
+    #[test]
+    fn test_dialog_dismiss_with_text() {
+        let cmd = parse_command(&args("dialog dismiss some-text"), &default_flags()).unwrap();
+        assert_eq!(cmd["response"], "dismiss");
+        assert_eq!(cmd["promptText"], "some-text");
+    }
+
+    #[test]
+    fn test_dialog_unknown_subcommand() {
+        let result = parse_command(&args("dialog ignore"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
+    }
+
+    #[test]
+    fn test_dialog_get() {
+        let cmd = parse_command(&args("dialog get"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "dialog_get");
+        assert!(cmd.get("response").is_none());
+    }
+
+    #[test]
+    fn test_dialog_unknown_subcommand_lists_get() {
+        match parse_command(&args("dialog read"), &default_flags()).unwrap_err() {
+            ParseError::UnknownSubcommand { valid_options, .. } => {
+                assert!(valid_options.contains(&"get"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    // === Dialog Auto Tests ===
+
+    #[test]
+    fn test_dialog_auto_accept() {
+        let cmd = parse_command(&args("dialog auto accept"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "dialog_auto");
+        assert_eq!(cmd["response"], "accept");
+        assert!(cmd.get("promptText").is_none());
+    }
+
+    #[test]
+    fn test_dialog_auto_accept_with_text() {
+        let cmd = parse_command(&args("dialog auto accept --text yes"), &default_flags()).unwrap();
+        assert_eq!(cmd["response"], "accept");
+        assert_eq!(cmd["promptText"], "yes");
+    }
+
+    #[test]
+    fn test_dialog_auto_dismiss() {
+        let cmd = parse_command(&args("dialog auto dismiss"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "dialog_auto");
+        assert_eq!(cmd["response"], "dismiss");
+    }
+
+    #[test]
+    fn test_dialog_auto_off() {
+        let cmd = parse_command(&args("dialog auto off"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "dialog_auto");
+        assert_eq!(cmd["enabled"], false);
+        assert!(cmd.get("response").is_none());
+    }
+
+    #[test]
+    fn test_dialog_auto_missing_mode() {
+        let result = parse_command(&args("dialog auto"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_dialog_auto_unknown_mode() {
+        let result = parse_command(&args("dialog auto ignore"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
+    }
+
+    #[test]
+    fn test_dialog_auto_text_missing_value() {
+        let result = parse_command(&args("dialog auto accept --text"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_dialog_auto_text_requires_accept() {
+        let result = parse_command(&args("dialog auto dismiss --text yes"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    // === Cookies Get Tests ===
+
+    #[test]
+    fn test_cookies_get_by_name() {
+        let cmd = parse_command(&args("cookies get session_token"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cookies_get");
+        assert_eq!(cmd["name"], "session_token");
+    }
+
+    #[test]
+    fn test_cookies_get_without_name_fetches_all() {
+        let cmd = parse_command(&args("cookies get"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cookies_get");
+        assert!(cmd.get("name").is_none());
+    }
+
+    #[test]
+    fn test_cookies_bare_fetches_all() {
+        let cmd = parse_command(&args("cookies"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cookies_get");
+        assert!(cmd.get("name").is_none());
+    }
+
+    #[test]
+    fn test_cookies_get_explicit_matches_bare() {
+        let explicit = parse_command(&args("cookies get"), &default_flags()).unwrap();
+        let bare = parse_command(&args("cookies"), &default_flags()).unwrap();
+        assert_eq!(explicit["action"], bare["action"]);
+        assert_eq!(explicit.get("name"), bare.get("name"));
+    }
+
+    #[test]
+    fn test_cookies_get_name_with_json_flag() {
+        let input: Vec<String> = args("cookies get theme --json");
+        let mut flags = default_flags();
+        flags.json = true;
+        let cleaned = crate::flags::clean_args(&input);
+        let cmd = parse_command(&cleaned, &flags).unwrap();
+        assert_eq!(cmd["name"], "theme");
+    }
+
+    // === Cookies Delete Tests ===
+
+    #[test]
+    fn test_cookies_delete() {
+        let cmd = parse_command(&args("cookies delete session_token"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cookies_delete");
+        assert_eq!(cmd["name"], "session_token");
+        assert!(cmd.get("domain").is_none());
+        assert!(cmd.get("path").is_none());
+    }
+
+    #[test]
+    fn test_cookies_delete_with_domain() {
+        let cmd = parse_command(
+            &args("cookies delete session_token --domain example.com"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["domain"], "example.com");
+    }
+
+    #[test]
+    fn test_cookies_delete_with_path() {
+        let cmd = parse_command(
+            &args("cookies delete session_token --path /api"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["path"], "/api");
+    }
+
+    #[test]
+    fn test_cookies_delete_with_domain_and_path() {
+        let cmd = parse_command(
+            &args("cookies delete sid --path /api --domain .example.com"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["name"], "sid");
+        assert_eq!(cmd["domain"], ".example.com");
+        assert_eq!(cmd["path"], "/api");
+    }
+
+    #[test]
+    fn test_cookies_delete_missing_name() {
+        let result = parse_command(&args("cookies delete"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_cookies_delete_domain_missing_value() {
+        let result = parse_command(&args("cookies delete sid --domain"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Cookies Import/Export Tests ===
+
+    #[test]
+    fn test_cookies_export() {
+        let cmd = parse_command(&args("cookies export cookies.json"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cookies_export");
+        assert_eq!(cmd["path"], "cookies.json");
+    }
+
+    #[test]
+    fn test_cookies_export_missing_path() {
+        let result = parse_command(&args("cookies export"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_cookies_import() {
+        let dir = std::env::temp_dir().join("ab-test-cookies-import");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("cookies.json");
+        fs::write(
+            &path,
+            r#"[{"name": "sid", "value": "abc", "domain": "example.com"}, {"name": "theme", "value": "dark"}]"#,
+        )
+        .unwrap();
+
+        let input = format!("cookies import {}", path.display());
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cookies_set");
+        assert_eq!(cmd["cookies"].as_array().unwrap().len(), 2);
+        assert_eq!(cmd["cookies"][0]["name"], "sid");
+        assert_eq!(cmd["cookies"][0]["domain"], "example.com");
+        assert_eq!(cmd["cookies"][1]["value"], "dark");
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn test_cookies_import_missing_path() {
+        let result = parse_command(&args("cookies import"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_cookies_import_file_not_found() {
+        let result = parse_command(
+            &args("cookies import /nonexistent/cookies.json"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_cookies_import_invalid_json() {
+        let dir = std::env::temp_dir().join("ab-test-cookies-import-invalid");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("cookies.json");
+        fs::write(&path, "not json").unwrap();
+
+        let input = format!("cookies import {}", path.display());
+        let result = parse_command(&args(&input), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn test_cookies_import_not_array() {
+        let dir = std::env::temp_dir().join("ab-test-cookies-import-object");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("cookies.json");
+        fs::write(&path, r#"{"name": "sid", "value": "abc"}"#).unwrap();
+
+        let input = format!("cookies import {}", path.display());
+        let err = parse_command(&args(&input), &default_flags()).unwrap_err();
+        match err {
+            ParseError::InvalidValue { message, .. } => assert!(message.contains("array")),
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn test_cookies_import_entry_missing_value() {
+        let dir = std::env::temp_dir().join("ab-test-cookies-import-entry");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("cookies.json");
+        fs::write(
+            &path,
+            r#"[{"name": "sid", "value": "abc"}, {"name": "broken"}]"#,
+        )
+        .unwrap();
+
+        let input = format!("cookies import {}", path.display());
+        let err = parse_command(&args(&input), &default_flags()).unwrap_err();
+        match err {
+            ParseError::InvalidValue { message, .. } => assert!(message.contains("index 1")),
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
+    }
+
+    // === Storage Delete/List/Dump Tests ===
+
+    #[test]
+    fn test_storage_local_delete() {
+        let cmd = parse_command(&args("storage local delete myKey"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_delete");
+        assert_eq!(cmd["type"], "local");
+        assert_eq!(cmd["key"], "myKey");
+    }
+
+    #[test]
+    fn test_storage_session_delete() {
+        let cmd = parse_command(&args("storage session delete token"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_delete");
+        assert_eq!(cmd["type"], "session");
+        assert_eq!(cmd["key"], "token");
+    }
+
+    #[test]
+    fn test_storage_delete_missing_key() {
+        let result = parse_command(&args("storage local delete"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_storage_session_delete_missing_key() {
+        let result = parse_command(&args("storage session delete"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_storage_local_list() {
+        let cmd = parse_command(&args("storage local list"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_keys");
+        assert_eq!(cmd["type"], "local");
+        assert!(cmd.get("key").is_none());
+    }
+
+    #[test]
+    fn test_storage_session_list() {
+        let cmd = parse_command(&args("storage session list"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_keys");
+        assert_eq!(cmd["type"], "session");
+    }
+
+    #[test]
+    fn test_storage_local_dump() {
+        let cmd = parse_command(&args("storage local dump"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_dump");
+        assert_eq!(cmd["type"], "local");
+    }
+
+    #[test]
+    fn test_storage_session_dump() {
+        let cmd = parse_command(&args("storage session dump"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_dump");
+        assert_eq!(cmd["type"], "session");
+    }
+
+    #[test]
+    fn test_storage_get_still_default() {
+        let cmd = parse_command(&args("storage local get theme"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_get");
+        assert_eq!(cmd["key"], "theme");
+    }
+
+    #[test]
+    fn test_storage_unknown_type_with_delete() {
+        let result = parse_command(&args("storage indexed delete key"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
+    }
+
+    // === Storage Export/Import Tests ===
+
+    #[test]
+    fn test_storage_local_export() {
+        let cmd =
+            parse_command(&args("storage local export state.json"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_export");
+        assert_eq!(cmd["type"], "local");
+        assert_eq!(cmd["path"], "state.json");
+    }
+
+    #[test]
+    fn test_storage_session_export() {
+        let cmd = parse_command(&args("storage session export s.json"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_export");
+        assert_eq!(cmd["type"], "session");
+    }
+
+    #[test]
+    fn test_storage_export_missing_path() {
+        let result = parse_command(&args("storage local export"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_storage_import() {
+        let dir = std::env::temp_dir().join("ab-test-storage-import");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("storage.json");
+        fs::write(&path, r#"{"theme": "dark", "token": "abc"}"#).unwrap();
+
+        let input = format!("storage session import {}", path.display());
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_import");
+        assert_eq!(cmd["type"], "session");
+        assert_eq!(cmd["data"]["theme"], "dark");
+        assert_eq!(cmd["data"]["token"], "abc");
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn test_storage_import_missing_path() {
+        let result = parse_command(&args("storage local import"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_storage_import_file_not_found() {
+        let result = parse_command(
+            &args("storage local import /nonexistent/storage.json"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_storage_import_invalid_json() {
+        let dir = std::env::temp_dir().join("ab-test-storage-import-invalid");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("storage.json");
+        fs::write(&path, "{theme: dark").unwrap();
+
+        let input = format!("storage local import {}", path.display());
+        let result = parse_command(&args(&input), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn test_storage_import_rejects_array() {
+        let dir = std::env::temp_dir().join("ab-test-storage-import-array");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("storage.json");
+        fs::write(&path, r#"["theme", "dark"]"#).unwrap();
+
+        let input = format!("storage local import {}", path.display());
+        let err = parse_command(&args(&input), &default_flags()).unwrap_err();
+        match err {
+            ParseError::InvalidValue { message, .. } => assert!(message.contains("object")),
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn test_storage_import_rejects_non_string_value() {
+        let dir = std::env::temp_dir().join("ab-test-storage-import-number");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("storage.json");
+        fs::write(&path, r#"{"theme": "dark", "count": 3}"#).unwrap();
+
+        let input = format!("storage local import {}", path.display());
+        let err = parse_command(&args(&input), &default_flags()).unwrap_err();
+        match err {
+            ParseError::InvalidValue { message, .. } => assert!(message.contains("count")),
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
+    }
+
+    // === State Diff Tests ===
+
+    #[test]
+    fn test_state_diff_paths() {
+        let cmd = parse_command(
+            &args("state diff ./before.json ./after.json"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "state_diff");
+        assert_eq!(cmd["baseline"], "./before.json");
+        assert_eq!(cmd["current"], "./after.json");
+    }
+
+    #[test]
+    fn test_state_diff_saved_names() {
+        let cmd = parse_command(
+            &args("state diff myapp-default.json myapp-staging"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["baseline"], "myapp-default.json");
+        assert_eq!(cmd["current"], "myapp-staging");
+    }
+
+    #[test]
+    fn test_state_diff_absolute_paths() {
+        let cmd = parse_command(
+            &args("state diff /tmp/a.json /tmp/b.json"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["baseline"], "/tmp/a.json");
+        assert_eq!(cmd["current"], "/tmp/b.json");
+    }
+
+    #[test]
+    fn test_state_diff_missing_both() {
+        let result = parse_command(&args("state diff"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_state_diff_missing_current() {
+        let result = parse_command(&args("state diff before.json"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_state_diff_invalid_saved_name() {
+        let result = parse_command(
+            &args("state diff bad$name.json after.json"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidSessionName { .. }
+        ));
+    }
+
+    #[test]
+    fn test_state_diff_invalid_current_name() {
+        let result = parse_command(&args("state diff ./a.json b@d"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidSessionName { .. }
+        ));
+    }
+
+    #[test]
+    fn test_state_unknown_subcommand_lists_diff() {
+        let result = parse_command(&args("state compare a b"), &default_flags());
+        match result.unwrap_err() {
+            ParseError::UnknownSubcommand { valid_options, .. } => {
+                assert!(valid_options.contains(&"diff"))
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    // === State Encryption Tests ===
+
+    #[test]
+    fn test_state_save_encrypt() {
+        let cmd = parse_command(
+            &args("state save auth.json --encrypt hunter2"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "state_save");
+        assert_eq!(cmd["path"], "auth.json");
+        assert_eq!(cmd["encrypt"], true);
+        assert_eq!(cmd["password"], "hunter2");
+    }
+
+    #[test]
+    fn test_state_save_without_encrypt() {
+        let cmd = parse_command(&args("state save auth.json"), &default_flags()).unwrap();
+        assert!(cmd.get("encrypt").is_none());
+        assert!(cmd.get("password").is_none());
+    }
+
+    #[test]
+    fn test_state_save_encrypt_missing_password() {
+        let result = parse_command(&args("state save auth.json --encrypt"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_state_load_decrypt() {
+        let cmd = parse_command(
+            &args("state load auth.json --decrypt hunter2"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "state_load");
+        assert_eq!(cmd["decrypt"], true);
+        assert_eq!(cmd["password"], "hunter2");
+    }
+
+    #[test]
+    fn test_state_load_decrypt_missing_password() {
+        let result = parse_command(&args("state load auth.json --decrypt"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_state_save_uses_env_password() {
+        let mut flags = default_flags();
+        flags.state_password = Some("from-env".to_string());
+        let cmd = parse_command(&args("state save auth.json --encrypt"), &flags).unwrap();
+        assert_eq!(cmd["encrypt"], true);
+        assert_eq!(cmd["password"], "from-env");
+    }
+
+    #[test]
+    fn test_state_load_uses_env_password() {
+        let mut flags = default_flags();
+        flags.state_password = Some("from-env".to_string());
+        let cmd = parse_command(&args("state load auth.json --decrypt"), &flags).unwrap();
+        assert_eq!(cmd["decrypt"], true);
+        assert_eq!(cmd["password"], "from-env");
+    }
+
+    #[test]
+    fn test_state_env_password_ignored_without_flag() {
+        let mut flags = default_flags();
+        flags.state_password = Some("from-env".to_string());
+        let cmd = parse_command(&args("state save auth.json"), &flags).unwrap();
+        assert!(cmd.get("encrypt").is_none());
+        assert!(cmd.get("password").is_none());
+        let cmd = parse_command(&args("state load auth.json"), &flags).unwrap();
+        assert!(cmd.get("decrypt").is_none());
+        assert!(cmd.get("password").is_none());
+    }
+
+    #[test]
+    fn test_state_encrypt_flag_overrides_env_password() {
+        let mut flags = default_flags();
+        flags.state_password = Some("from-env".to_string());
+        let cmd = parse_command(&args("state save auth.json --encrypt explicit"), &flags).unwrap();
+        assert_eq!(cmd["password"], "explicit");
+    }
+
+    #[test]
+    fn test_state_encrypt_flag_survives_clean_args() {
+        let input = args("state save auth.json --encrypt pw --json");
+        let cleaned = crate::flags::clean_args(&input);
+        assert_eq!(cleaned, args("state save auth.json --encrypt pw"));
+    }
+
+    // === Diff Screenshot Tolerance Tests ===
+
+    #[test]
+    fn test_diff_screenshot_ignore_colors() {
+        let cmd = parse_command(
+            &args("diff screenshot --baseline b.png --ignore-colors"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["ignoreColors"], true);
+        assert!(cmd.get("ignoreAntialiasing").is_none());
+    }
+
+    #[test]
+    fn test_diff_screenshot_ignore_antialiasing() {
+        let cmd = parse_command(
+            &args("diff screenshot --baseline b.png --ignore-antialiasing"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["ignoreAntialiasing"], true);
+        assert!(cmd.get("ignoreColors").is_none());
+    }
+
+    #[test]
+    fn test_diff_screenshot_max_diff_pixels() {
+        let cmd = parse_command(
+            &args("diff screenshot --baseline b.png --max-diff-pixels 50"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["maxDiffPixels"], 50);
+    }
+
+    #[test]
+    fn test_diff_screenshot_max_diff_pixels_zero() {
+        let cmd = parse_command(
+            &args("diff screenshot --baseline b.png --max-diff-pixels 0"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["maxDiffPixels"], 0);
+    }
+
+    #[test]
+    fn test_diff_screenshot_max_diff_pixels_negative() {
+        let result = parse_command(
+            &args("diff screenshot --baseline b.png --max-diff-pixels -5"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_screenshot_max_diff_pixels_not_a_number() {
+        let result = parse_command(
+            &args("diff screenshot --baseline b.png --max-diff-pixels lots"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_screenshot_max_diff_pixels_missing_value() {
+        let result = parse_command(
+            &args("diff screenshot --baseline b.png --max-diff-pixels"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_screenshot_max_diff_pixels_conflicts_with_threshold() {
+        let result = parse_command(
+            &args("diff screenshot --baseline b.png --threshold 0.2 --max-diff-pixels 10"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_screenshot_all_tolerance_flags() {
+        let cmd = parse_command(
+            &args("diff screenshot --baseline b.png --ignore-colors --ignore-antialiasing --max-diff-pixels 3"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["ignoreColors"], true);
+        assert_eq!(cmd["ignoreAntialiasing"], true);
+        assert_eq!(cmd["maxDiffPixels"], 3);
+        assert_eq!(cmd["baseline"], "b.png");
+    }
+
+    #[test]
+    fn test_diff_screenshot_tolerance_flags_survive_clean_args() {
+        let input =
+            args("diff screenshot --baseline b.png --ignore-colors --max-diff-pixels 3 --json");
+        let cleaned = crate::flags::clean_args(&input);
+        assert_eq!(
+            cleaned,
+            args("diff screenshot --baseline b.png --ignore-colors --max-diff-pixels 3")
+        );
+    }
+
+    #[test]
+    fn test_diff_screenshot_mask() {
+        let cmd = parse_command(
+            &args("diff screenshot --baseline b.png --mask #ssn"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["mask"], json!(["#ssn"]));
+    }
+
+    #[test]
+    fn test_diff_screenshot_multiple_masks() {
+        let cmd = parse_command(
+            &args("diff screenshot --mask .avatar --baseline b.png --mask .timestamp"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["baseline"], "b.png");
+        assert_eq!(cmd["mask"], json!([".avatar", ".timestamp"]));
+    }
+
+    #[test]
+    fn test_diff_screenshot_bare_mask() {
+        let result = parse_command(
+            &args("diff screenshot --baseline b.png --mask"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Diff Snapshot Filter Tests ===
+
+    #[test]
+    fn test_diff_snapshot_ignore_refs() {
+        let cmd = parse_command(&args("diff snapshot --ignore-refs"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "diff_snapshot");
+        assert_eq!(cmd["ignoreRefs"], true);
+    }
+
+    #[test]
+    fn test_diff_snapshot_only_additions() {
+        let cmd = parse_command(&args("diff snapshot --only-additions"), &default_flags()).unwrap();
+        assert_eq!(cmd["onlyAdditions"], true);
+        assert!(cmd.get("onlyRemovals").is_none());
+    }
+
+    #[test]
+    fn test_diff_snapshot_only_removals() {
+        let cmd = parse_command(&args("diff snapshot --only-removals"), &default_flags()).unwrap();
+        assert_eq!(cmd["onlyRemovals"], true);
+        assert!(cmd.get("onlyAdditions").is_none());
+    }
+
+    #[test]
+    fn test_diff_snapshot_only_additions_and_removals_conflict() {
+        let result = parse_command(
+            &args("diff snapshot --only-additions --only-removals"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_diff_snapshot_filters_without_flags() {
+        let cmd = parse_command(&args("diff snapshot"), &default_flags()).unwrap();
+        assert!(cmd.get("ignoreRefs").is_none());
+        assert!(cmd.get("onlyAdditions").is_none());
+        assert!(cmd.get("onlyRemovals").is_none());
+    }
+
+    #[test]
+    fn test_diff_snapshot_ignore_refs_with_baseline() {
+        let cmd = parse_command(
+            &args("diff snapshot --baseline before.txt --ignore-refs --only-removals"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["baseline"], "before.txt");
+        assert_eq!(cmd["ignoreRefs"], true);
+        assert_eq!(cmd["onlyRemovals"], true);
     }
 
-    fn args(s: &str) -> Vec<String> {
-        s.split_whitespace().map(String::from).collect()
+    #[test]
+    fn test_diff_snapshot_filters_with_depth_and_compact() {
+        let cmd = parse_command(
+            &args("diff snapshot -c -d 3 --only-additions"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["compact"], true);
+        assert_eq!(cmd["maxDepth"], 3);
+        assert_eq!(cmd["onlyAdditions"], true);
     }
 
-    // === Cookies Tests ===
+    #[test]
+    fn test_diff_snapshot_unknown_flag_still_rejected() {
+        let result = parse_command(&args("diff snapshot --only-changes"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    // === Trace Open/Status Tests ===
 
     #[test]
-    fn test_cookies_get() {
-        let cmd = parse_command(&args("cookies"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "cookies_get");
+    fn test_trace_open_with_path() {
+        let cmd = parse_command(&args("trace open ./trace.zip"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "trace_open");
+        assert_eq!(cmd["path"], "./trace.zip");
     }
 
     #[test]
-    fn test_cookies_get_explicit() {
-        let cmd = parse_command(&args("cookies get"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "cookies_get");
+    fn test_trace_open_without_path() {
+        let cmd = parse_command(&args("trace open"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "trace_open");
+        assert!(cmd.get("path").is_none());
     }
 
     #[test]
-    fn test_cookies_set() {
-        let cmd = parse_command(&args("cookies set mycookie myvalue"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "cookies_set");
-        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
-        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
+    fn test_trace_status() {
+        let cmd = parse_command(&args("trace status"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "trace_status");
     }
 
     #[test]
-    fn test_cookies_set_missing_value() {
-        let result = parse_command(&args("cookies set mycookie"), &default_flags());
-        assert!(result.is_err());
+    fn test_trace_stop_unchanged() {
+        let cmd = parse_command(&args("trace stop out.zip"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "trace_stop");
+        assert_eq!(cmd["path"], "out.zip");
     }
 
     #[test]
-    fn test_cookies_clear() {
-        let cmd = parse_command(&args("cookies clear"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "cookies_clear");
+    fn test_trace_unknown_subcommand_lists_new_options() {
+        let result = parse_command(&args("trace view"), &default_flags());
+        match result.unwrap_err() {
+            ParseError::UnknownSubcommand { valid_options, .. } => {
+                assert!(valid_options.contains(&"open"));
+                assert!(valid_options.contains(&"status"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
     }
 
     #[test]
-    fn test_cookies_set_with_url() {
+    fn test_trace_missing_subcommand() {
+        let result = parse_command(&args("trace"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Highlight Tests ===
+
+    #[test]
+    fn test_highlight_defaults_to_red() {
+        let cmd = parse_command(&args("highlight #main"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "highlight");
+        assert_eq!(cmd["selector"], "#main");
+        assert_eq!(cmd["color"], "#FF0000");
+        assert!(cmd.get("label").is_none());
+        assert!(cmd.get("duration").is_none());
+    }
+
+    #[test]
+    fn test_highlight_with_color() {
         let cmd = parse_command(
-            &args("cookies set mycookie myvalue --url https://example.com"),
+            &args("highlight @e5 --highlight-color #00ff7f"),
             &default_flags(),
         )
         .unwrap();
-        assert_eq!(cmd["action"], "cookies_set");
-        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
-        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
-        assert_eq!(cmd["cookies"][0]["url"], "https://example.com");
+        assert_eq!(cmd["selector"], "@e5");
+        assert_eq!(cmd["color"], "#00ff7f");
     }
 
     #[test]
-    fn test_cookies_set_with_domain() {
+    fn test_highlight_flags_before_selector() {
         let cmd = parse_command(
-            &args("cookies set mycookie myvalue --domain example.com"),
+            &args("highlight --duration 1500 --highlight-color #123ABC .card"),
             &default_flags(),
         )
         .unwrap();
-        assert_eq!(cmd["action"], "cookies_set");
-        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
-        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
-        assert_eq!(cmd["cookies"][0]["domain"], "example.com");
+        assert_eq!(cmd["selector"], ".card");
+        assert_eq!(cmd["color"], "#123ABC");
+        assert_eq!(cmd["duration"], 1500);
     }
 
     #[test]
-    fn test_cookies_set_with_path() {
+    fn test_highlight_with_label_and_duration() {
         let cmd = parse_command(
-            &args("cookies set mycookie myvalue --path /api"),
+            &args("highlight @e1 --label submit --duration 2000"),
             &default_flags(),
         )
         .unwrap();
-        assert_eq!(cmd["action"], "cookies_set");
-        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
-        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
-        assert_eq!(cmd["cookies"][0]["path"], "/api");
+        assert_eq!(cmd["label"], "submit");
+        assert_eq!(cmd["duration"], 2000);
     }
 
     #[test]
-    fn test_cookies_set_with_httponly() {
-        let cmd = parse_command(
-            &args("cookies set mycookie myvalue --httpOnly"),
+    fn test_highlight_color_without_hash_rejected() {
+        let result = parse_command(
+            &args("highlight @e1 --highlight-color FF0000"),
             &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "cookies_set");
-        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
-        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
-        assert_eq!(cmd["cookies"][0]["httpOnly"], true);
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
     }
 
     #[test]
-    fn test_cookies_set_with_secure() {
+    fn test_highlight_color_short_hex_rejected() {
+        let result = parse_command(
+            &args("highlight @e1 --highlight-color #F00"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_highlight_color_non_hex_rejected() {
+        let result = parse_command(
+            &args("highlight @e1 --highlight-color #GG0000"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_highlight_color_missing_value() {
+        let result = parse_command(&args("highlight @e1 --highlight-color"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_highlight_invalid_duration() {
+        for input in [
+            "highlight @e1 --duration soon",
+            "highlight @e1 --duration 0",
+        ] {
+            let result = parse_command(&args(input), &default_flags());
+            assert!(
+                matches!(result, Err(ParseError::InvalidValue { .. })),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_highlight_missing_selector() {
+        let result = parse_command(&args("highlight --label x"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_highlight_clear() {
+        let cmd = parse_command(&args("highlight clear"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "highlight_clear");
+        assert!(cmd.get("selector").is_none());
+    }
+
+    #[test]
+    fn test_highlight_flags_survive_clean_args() {
+        let input = args("highlight @e1 --highlight-color #00FF00 --label hi --duration 500");
+        let clean = crate::flags::clean_args(&input);
+        let cmd = parse_command(&clean, &default_flags()).unwrap();
+        assert_eq!(cmd["color"], "#00FF00");
+        assert_eq!(cmd["label"], "hi");
+        assert_eq!(cmd["duration"], 500);
+    }
+
+    #[test]
+    fn test_highlight_all() {
+        let cmd = parse_command(&args("highlight all .card"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "highlight_all");
+        assert_eq!(cmd["selector"], ".card");
+        assert_eq!(cmd["color"], "#FF0000");
+    }
+
+    #[test]
+    fn test_highlight_all_with_options() {
         let cmd = parse_command(
-            &args("cookies set mycookie myvalue --secure"),
+            &args("highlight all li --highlight-color #0000FF --label item --duration 750"),
             &default_flags(),
         )
         .unwrap();
-        assert_eq!(cmd["action"], "cookies_set");
-        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
-        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
-        assert_eq!(cmd["cookies"][0]["secure"], true);
+        assert_eq!(cmd["action"], "highlight_all");
+        assert_eq!(cmd["selector"], "li");
+        assert_eq!(cmd["color"], "#0000FF");
+        assert_eq!(cmd["label"], "item");
+        assert_eq!(cmd["duration"], 750);
     }
 
     #[test]
-    fn test_cookies_set_with_samesite() {
+    fn test_highlight_all_missing_selector() {
+        let result = parse_command(&args("highlight all"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_highlight_all_only_flags_missing_selector() {
+        let result = parse_command(
+            &args("highlight all --highlight-color #00FF00"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_highlight_all_invalid_color() {
+        let result = parse_command(
+            &args("highlight all .card --highlight-color red"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    // === Console / Errors Tests ===
+
+    #[test]
+    fn test_console_defaults() {
+        let cmd = parse_command(&args("console"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "console");
+        assert_eq!(cmd["clear"], false);
+        assert!(cmd.get("filter").is_none());
+        assert!(cmd.get("filterText").is_none());
+    }
+
+    #[test]
+    fn test_console_filter_level() {
+        for level in ["log", "info", "warn", "error", "debug"] {
+            let cmd = parse_command(
+                &args(&format!("console --filter {}", level)),
+                &default_flags(),
+            )
+            .unwrap();
+            assert_eq!(cmd["filter"], level);
+        }
+    }
+
+    #[test]
+    fn test_console_filter_unknown_level() {
+        let result = parse_command(&args("console --filter verbose"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+    }
+
+    #[test]
+    fn test_console_filter_missing_value() {
+        let result = parse_command(&args("console --filter"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_console_filter_text() {
+        let input = vec![
+            "console".to_string(),
+            "--filter-text".to_string(),
+            "^fetch (failed|aborted)".to_string(),
+        ];
+        let cmd = parse_command(&input, &default_flags()).unwrap();
+        assert_eq!(cmd["filterText"], "^fetch (failed|aborted)");
+        assert!(cmd.get("filter").is_none());
+    }
+
+    #[test]
+    fn test_console_filters_combine_with_clear() {
         let cmd = parse_command(
-            &args("cookies set mycookie myvalue --sameSite Strict"),
+            &args("console --filter error --filter-text timeout --clear"),
             &default_flags(),
         )
         .unwrap();
-        assert_eq!(cmd["action"], "cookies_set");
-        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
-        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
-        assert_eq!(cmd["cookies"][0]["sameSite"], "Strict");
+        assert_eq!(cmd["clear"], true);
+        assert_eq!(cmd["filter"], "error");
+        assert_eq!(cmd["filterText"], "timeout");
     }
 
     #[test]
-    fn test_cookies_set_with_expires() {
+    fn test_errors_type() {
+        let cmd = parse_command(&args("errors --type TypeError"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "errors");
+        assert_eq!(cmd["errorType"], "TypeError");
+        assert_eq!(cmd["clear"], false);
+    }
+
+    #[test]
+    fn test_errors_type_with_clear() {
         let cmd = parse_command(
-            &args("cookies set mycookie myvalue --expires 1234567890"),
+            &args("errors --clear --type ReferenceError"),
             &default_flags(),
         )
         .unwrap();
-        assert_eq!(cmd["action"], "cookies_set");
-        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
-        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
-        assert_eq!(cmd["cookies"][0]["expires"], 1234567890);
+        assert_eq!(cmd["clear"], true);
+        assert_eq!(cmd["errorType"], "ReferenceError");
     }
 
     #[test]
-    fn test_cookies_set_with_multiple_flags() {
-        let cmd = parse_command(&args("cookies set mycookie myvalue --url https://example.com --httpOnly --secure --sameSite Lax"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "cookies_set");
-        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
-        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
-        assert_eq!(cmd["cookies"][0]["url"], "https://example.com");
-        assert_eq!(cmd["cookies"][0]["httpOnly"], true);
-        assert_eq!(cmd["cookies"][0]["secure"], true);
-        assert_eq!(cmd["cookies"][0]["sameSite"], "Lax");
+    fn test_errors_type_missing_value() {
+        let result = parse_command(&args("errors --type --clear"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
+    // === Accessibility Tests ===
+
     #[test]
-    fn test_cookies_set_with_all_flags() {
-        let cmd = parse_command(&args("cookies set mycookie myvalue --url https://example.com --domain example.com --path /api --httpOnly --secure --sameSite None --expires 9999999999"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "cookies_set");
-        assert_eq!(cmd["cookies"][0]["name"], "mycookie");
-        assert_eq!(cmd["cookies"][0]["value"], "myvalue");
-        assert_eq!(cmd["cookies"][0]["url"], "https://example.com");
-        assert_eq!(cmd["cookies"][0]["domain"], "example.com");
-        assert_eq!(cmd["cookies"][0]["path"], "/api");
-        assert_eq!(cmd["cookies"][0]["httpOnly"], true);
-        assert_eq!(cmd["cookies"][0]["secure"], true);
-        assert_eq!(cmd["cookies"][0]["sameSite"], "None");
-        assert_eq!(cmd["cookies"][0]["expires"], 9999999999i64);
+    fn test_accessibility_defaults_to_snapshot() {
+        let cmd = parse_command(&args("accessibility"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "accessibility_snapshot");
+    }
+
+    #[test]
+    fn test_accessibility_snapshot() {
+        let cmd = parse_command(&args("accessibility snapshot"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "accessibility_snapshot");
+    }
+
+    #[test]
+    fn test_accessibility_check() {
+        let cmd = parse_command(&args("accessibility check"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "accessibility_check");
+        assert!(cmd.get("standard").is_none());
+    }
+
+    #[test]
+    fn test_accessibility_check_standards() {
+        for standard in ["wcag2a", "wcag2aa", "wcag21a", "wcag21aa"] {
+            let cmd = parse_command(
+                &args(&format!("accessibility check --standard {}", standard)),
+                &default_flags(),
+            )
+            .unwrap();
+            assert_eq!(cmd["standard"], standard);
+        }
+    }
+
+    #[test]
+    fn test_accessibility_check_invalid_standard() {
+        let result = parse_command(
+            &args("accessibility check --standard section508"),
+            &default_flags(),
+        );
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
     }
 
     #[test]
-    fn test_cookies_set_invalid_samesite() {
-        let result = parse_command(
-            &args("cookies set mycookie myvalue --sameSite Invalid"),
-            &default_flags(),
-        );
-        assert!(result.is_err());
+    fn test_accessibility_check_standard_missing_value() {
+        let result = parse_command(&args("accessibility check --standard"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
-    // === Storage Tests ===
+    #[test]
+    fn test_accessibility_unknown_subcommand() {
+        let result = parse_command(&args("accessibility audit"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
+    }
 
     #[test]
-    fn test_storage_local_get() {
-        let cmd = parse_command(&args("storage local"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "storage_get");
-        assert_eq!(cmd["type"], "local");
-        assert!(cmd.get("key").is_none());
+    fn test_accessibility_check_with_json_flag() {
+        let input = args("--json accessibility check --standard wcag21aa");
+        let mut flags = default_flags();
+        flags.json = true;
+        let cmd = parse_command(&crate::flags::clean_args(&input), &flags).unwrap();
+        assert!(flags.json);
+        assert_eq!(cmd["action"], "accessibility_check");
+        assert_eq!(cmd["standard"], "wcag21aa");
     }
 
+    // === Validate Tests ===
+
     #[test]
-    fn test_storage_local_get_key() {
-        let cmd = parse_command(&args("storage local get mykey"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "storage_get");
-        assert_eq!(cmd["type"], "local");
-        assert_eq!(cmd["key"], "mykey");
+    fn test_validate() {
+        let cmd = parse_command(&args("validate #submit-button"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "validate");
+        assert_eq!(cmd["selector"], "#submit-button");
+        assert!(cmd.get("min").is_none());
+        assert!(cmd.get("max").is_none());
     }
 
     #[test]
-    fn test_storage_session_get() {
-        let cmd = parse_command(&args("storage session"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "storage_get");
-        assert_eq!(cmd["type"], "session");
+    fn test_validate_min_max() {
+        let cmd = parse_command(&args("validate .item --min 2 --max 5"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], ".item");
+        assert_eq!(cmd["min"], 2);
+        assert_eq!(cmd["max"], 5);
     }
 
     #[test]
-    fn test_storage_local_set() {
-        let cmd =
-            parse_command(&args("storage local set mykey myvalue"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "storage_set");
-        assert_eq!(cmd["type"], "local");
-        assert_eq!(cmd["key"], "mykey");
-        assert_eq!(cmd["value"], "myvalue");
+    fn test_validate_flags_before_selector() {
+        let cmd = parse_command(&args("validate --max 0 .error"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], ".error");
+        assert_eq!(cmd["max"], 0);
     }
 
     #[test]
-    fn test_storage_session_set() {
-        let cmd =
-            parse_command(&args("storage session set skey svalue"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "storage_set");
-        assert_eq!(cmd["type"], "session");
-        assert_eq!(cmd["key"], "skey");
-        assert_eq!(cmd["value"], "svalue");
+    fn test_validate_missing_selector() {
+        for input in ["validate", "validate --min 1"] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::MissingArguments { .. })
+            ));
+        }
     }
 
     #[test]
-    fn test_storage_set_missing_value() {
-        let result = parse_command(&args("storage local set mykey"), &default_flags());
-        assert!(result.is_err());
+    fn test_validate_missing_bound_value() {
+        let result = parse_command(&args("validate .item --min"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
     #[test]
-    fn test_storage_local_clear() {
-        let cmd = parse_command(&args("storage local clear"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "storage_clear");
-        assert_eq!(cmd["type"], "local");
+    fn test_validate_invalid_bounds() {
+        for input in [
+            "validate .item --min -1",
+            "validate .item --max many",
+            "validate .item --min 3 --max 2",
+        ] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::InvalidValue { .. })
+            ));
+        }
     }
 
+    // === Assert Tests ===
+
     #[test]
-    fn test_storage_session_clear() {
-        let cmd = parse_command(&args("storage session clear"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "storage_clear");
-        assert_eq!(cmd["type"], "session");
+    fn test_assert_visible() {
+        let cmd = parse_command(&args("assert visible #modal"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "assert_visible");
+        assert_eq!(cmd["selector"], "#modal");
     }
 
     #[test]
-    fn test_storage_invalid_type() {
-        let result = parse_command(&args("storage invalid"), &default_flags());
-        assert!(result.is_err());
+    fn test_assert_visible_missing_selector() {
+        let result = parse_command(&args("assert visible"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
-    // === Navigation Tests ===
+    #[test]
+    fn test_assert_text() {
+        let cmd = parse_command(&args("assert text h1 Welcome back"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "assert_text");
+        assert_eq!(cmd["selector"], "h1");
+        assert_eq!(cmd["expected"], "Welcome back");
+    }
 
     #[test]
-    fn test_navigate_with_https() {
-        let cmd = parse_command(&args("open https://example.com"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "navigate");
-        assert_eq!(cmd["url"], "https://example.com");
+    fn test_assert_text_missing_expected() {
+        let result = parse_command(&args("assert text h1"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
     #[test]
-    fn test_navigate_without_protocol() {
-        let cmd = parse_command(&args("open example.com"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "navigate");
-        assert_eq!(cmd["url"], "https://example.com");
+    fn test_assert_count() {
+        let cmd = parse_command(&args("assert count .item 3"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "assert_count");
+        assert_eq!(cmd["selector"], ".item");
+        assert_eq!(cmd["expected"], 3);
     }
 
     #[test]
-    fn test_navigate_with_headers() {
-        let mut flags = default_flags();
-        flags.headers = Some(r#"{"Authorization": "Bearer token"}"#.to_string());
-        let cmd = parse_command(&args("open api.example.com"), &flags).unwrap();
-        assert_eq!(cmd["action"], "navigate");
-        assert_eq!(cmd["url"], "https://api.example.com");
-        assert_eq!(cmd["headers"]["Authorization"], "Bearer token");
+    fn test_assert_count_zero() {
+        let cmd = parse_command(&args("assert count .error 0"), &default_flags()).unwrap();
+        assert_eq!(cmd["expected"], 0);
     }
 
     #[test]
-    fn test_navigate_with_multiple_headers() {
-        let mut flags = default_flags();
-        flags.headers =
-            Some(r#"{"Authorization": "Bearer token", "X-Custom": "value"}"#.to_string());
-        let cmd = parse_command(&args("open api.example.com"), &flags).unwrap();
-        assert_eq!(cmd["headers"]["Authorization"], "Bearer token");
-        assert_eq!(cmd["headers"]["X-Custom"], "value");
+    fn test_assert_count_invalid_number() {
+        let result = parse_command(&args("assert count .item many"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
     }
 
     #[test]
-    fn test_navigate_without_headers_flag() {
-        let cmd = parse_command(&args("open example.com"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "navigate");
-        // headers should not be present when flag is not set
-        assert!(cmd.get("headers").is_none());
+    fn test_assert_count_missing_number() {
+        let result = parse_command(&args("assert count .item"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
     #[test]
-    fn test_navigate_with_invalid_headers_json() {
-        let mut flags = default_flags();
-        flags.headers = Some("not valid json".to_string());
-        let result = parse_command(&args("open api.example.com"), &flags);
-        // Invalid JSON should return a ParseError, not silently drop headers
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        let msg = err.format();
-        assert!(msg.contains("Invalid JSON for --headers"));
+    fn test_assert_url() {
+        let cmd = parse_command(&args("assert url **/dashboard"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "assert_url");
+        assert_eq!(cmd["expected"], "**/dashboard");
+        assert!(cmd.get("selector").is_none());
     }
 
-    // === Set Headers Tests ===
+    #[test]
+    fn test_assert_url_missing_pattern() {
+        let result = parse_command(&args("assert url"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
 
     #[test]
-    fn test_set_headers_parses_json() {
-        let input: Vec<String> = vec![
-            "set".to_string(),
-            "headers".to_string(),
-            r#"{"Authorization":"Bearer token"}"#.to_string(),
-        ];
-        let cmd = parse_command(&input, &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "headers");
-        // Headers should be an object, not a string
-        assert!(cmd["headers"].is_object());
-        assert_eq!(cmd["headers"]["Authorization"], "Bearer token");
+    fn test_assert_title() {
+        let cmd = parse_command(&args("assert title My Dashboard"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "assert_title");
+        assert_eq!(cmd["expected"], "My Dashboard");
     }
 
     #[test]
-    fn test_set_headers_with_multiple_values() {
-        let input: Vec<String> = vec![
-            "set".to_string(),
-            "headers".to_string(),
-            r#"{"Authorization": "Bearer token", "X-Custom": "value"}"#.to_string(),
-        ];
-        let cmd = parse_command(&input, &default_flags()).unwrap();
-        assert_eq!(cmd["headers"]["Authorization"], "Bearer token");
-        assert_eq!(cmd["headers"]["X-Custom"], "value");
+    fn test_assert_title_missing_expected() {
+        let result = parse_command(&args("assert title"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
     #[test]
-    fn test_set_headers_invalid_json_error() {
-        let input: Vec<String> = vec![
-            "set".to_string(),
-            "headers".to_string(),
-            "not-valid-json".to_string(),
-        ];
-        let result = parse_command(&input, &default_flags());
-        assert!(result.is_err());
+    fn test_assert_unknown_subcommand() {
+        let result = parse_command(&args("assert enabled #btn"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
     }
 
     #[test]
-    fn test_back() {
-        let cmd = parse_command(&args("back"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "back");
+    fn test_assert_missing_subcommand() {
+        let result = parse_command(&args("assert"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
+    // === Performance Tests ===
+
     #[test]
-    fn test_forward() {
-        let cmd = parse_command(&args("forward"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "forward");
+    fn test_performance_metrics() {
+        let cmd = parse_command(&args("performance metrics"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "performance_metrics");
     }
 
     #[test]
-    fn test_reload() {
-        let cmd = parse_command(&args("reload"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "reload");
+    fn test_performance_mark() {
+        let cmd =
+            parse_command(&args("performance mark checkout-start"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "performance_mark");
+        assert_eq!(cmd["name"], "checkout-start");
     }
 
-    // === Core Actions ===
+    #[test]
+    fn test_performance_mark_missing_name() {
+        let result = parse_command(&args("performance mark"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
 
     #[test]
-    fn test_click() {
-        let cmd = parse_command(&args("click #button"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "click");
-        assert_eq!(cmd["selector"], "#button");
+    fn test_performance_measure_name_only() {
+        let cmd = parse_command(&args("performance measure total"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "performance_measure");
+        assert_eq!(cmd["name"], "total");
+        assert!(cmd.get("startMark").is_none());
+        assert!(cmd.get("endMark").is_none());
     }
 
     #[test]
-    fn test_fill() {
-        let cmd = parse_command(&args("fill #input hello world"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "fill");
-        assert_eq!(cmd["selector"], "#input");
-        assert_eq!(cmd["value"], "hello world");
+    fn test_performance_measure_start_mark() {
+        let cmd =
+            parse_command(&args("performance measure total start"), &default_flags()).unwrap();
+        assert_eq!(cmd["startMark"], "start");
+        assert!(cmd.get("endMark").is_none());
     }
 
     #[test]
-    fn test_type_command() {
-        let cmd = parse_command(&args("type #input some text"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "type");
-        assert_eq!(cmd["selector"], "#input");
-        assert_eq!(cmd["text"], "some text");
+    fn test_performance_measure_start_and_end_marks() {
+        let cmd = parse_command(
+            &args("performance measure checkout start end"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["name"], "checkout");
+        assert_eq!(cmd["startMark"], "start");
+        assert_eq!(cmd["endMark"], "end");
     }
 
     #[test]
-    fn test_select() {
-        let cmd = parse_command(&args("select #menu option1"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "select");
-        assert_eq!(cmd["selector"], "#menu");
-        assert_eq!(cmd["values"], "option1");
+    fn test_performance_measure_missing_name() {
+        let result = parse_command(&args("performance measure"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
     #[test]
-    fn test_select_multiple_values() {
-        let cmd = parse_command(&args("select #menu opt1 opt2 opt3"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "select");
-        assert_eq!(cmd["selector"], "#menu");
-        assert_eq!(cmd["values"], json!(["opt1", "opt2", "opt3"]));
+    fn test_performance_missing_subcommand() {
+        let result = parse_command(&args("performance"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
     #[test]
-    fn test_frame_main() {
-        let cmd = parse_command(&args("frame main"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "mainframe");
+    fn test_performance_unknown_subcommand() {
+        let result = parse_command(&args("performance vitals"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
     }
 
-    // === Tabs ===
+    // === Mock Tests ===
 
     #[test]
-    fn test_tab_new() {
-        let cmd = parse_command(&args("tab new"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "tab_new");
-        assert!(
-            cmd.get("url").is_none(),
-            "url should not be present when not provided"
+    fn test_mock_load() {
+        let dir = std::env::temp_dir().join("ab-test-mock-load");
+        let _ = fs::create_dir_all(&dir);
+        fs::write(
+            dir.join("users.json"),
+            r#"{"url": "**/api/users", "body": []}"#,
+        )
+        .unwrap();
+
+        let input = format!("mock load {}", dir.display());
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "mock_load");
+        assert_eq!(
+            cmd["dir"],
+            fs::canonicalize(&dir).unwrap().display().to_string()
         );
+
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
-    fn test_tab_new_with_url() {
-        let cmd = parse_command(&args("tab new https://example.com"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "tab_new");
-        assert_eq!(cmd["url"], "https://example.com");
+    fn test_mock_load_relative_dir_is_absolute() {
+        let cmd = parse_command(&args("mock load ."), &default_flags()).unwrap();
+        let dir = cmd["dir"].as_str().unwrap();
+        assert!(std::path::Path::new(dir).is_absolute());
+    }
+
+    #[test]
+    fn test_mock_load_missing_dir() {
+        let result = parse_command(&args("mock load"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
     #[test]
-    fn test_tab_list() {
-        let cmd = parse_command(&args("tab list"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "tab_list");
+    fn test_mock_load_nonexistent_dir() {
+        let result = parse_command(&args("mock load /nonexistent/fixtures"), &default_flags());
+        match result.unwrap_err() {
+            ParseError::InvalidValue { message, .. } => {
+                assert!(message.contains("/nonexistent/fixtures"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
     }
 
     #[test]
-    fn test_tab_switch() {
-        let cmd = parse_command(&args("tab 2"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "tab_switch");
-        assert_eq!(cmd["index"], 2);
+    fn test_mock_load_file_is_not_a_dir() {
+        let dir = std::env::temp_dir().join("ab-test-mock-load-file");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("route.json");
+        fs::write(&path, "{}").unwrap();
+
+        let input = format!("mock load {}", path.display());
+        let result = parse_command(&args(&input), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
-    fn test_tab_close() {
-        let cmd = parse_command(&args("tab close"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "tab_close");
+    fn test_mock_clear() {
+        let cmd = parse_command(&args("mock clear"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "mock_clear");
     }
 
-    // === Screenshot ===
-
     #[test]
-    fn test_screenshot() {
-        let cmd = parse_command(&args("screenshot"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "screenshot");
-        assert_eq!(cmd["path"], serde_json::Value::Null);
-        assert_eq!(cmd["selector"], serde_json::Value::Null);
+    fn test_mock_status() {
+        let cmd = parse_command(&args("mock status"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "mock_status");
     }
 
     #[test]
-    fn test_screenshot_path() {
-        let cmd = parse_command(&args("screenshot out.png"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "screenshot");
-        assert_eq!(cmd["path"], "out.png");
+    fn test_mock_missing_subcommand() {
+        let result = parse_command(&args("mock"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
     #[test]
-    fn test_screenshot_full_page() {
-        let mut flags = default_flags();
-        flags.full = true;
-        let cmd = parse_command(&args("screenshot"), &flags).unwrap();
-        assert_eq!(cmd["action"], "screenshot");
-        assert_eq!(cmd["fullPage"], true);
+    fn test_mock_unknown_subcommand() {
+        let result = parse_command(&args("mock reset"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
     }
 
+    // === Foreach Tests ===
+
     #[test]
-    fn test_screenshot_with_ref() {
-        let cmd = parse_command(&args("screenshot @e1"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "screenshot");
-        assert_eq!(cmd["selector"], "@e1");
-        assert_eq!(cmd["path"], serde_json::Value::Null);
+    fn test_foreach_check() {
+        let cmd = parse_command(&args("foreach .checkbox check"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "foreach");
+        assert_eq!(cmd["selector"], ".checkbox");
+        assert_eq!(cmd["command"], "check");
+        assert_eq!(cmd["args"]["action"], "check");
+        assert_eq!(cmd["args"]["selector"], "{{ref}}");
+        assert!(cmd["args"].get("id").is_none());
+        assert!(cmd.get("outputTemplate").is_none());
     }
 
     #[test]
-    fn test_screenshot_with_css_class() {
-        let cmd = parse_command(&args("screenshot .my-button"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "screenshot");
-        assert_eq!(cmd["selector"], ".my-button");
-        assert_eq!(cmd["path"], serde_json::Value::Null);
+    fn test_foreach_screenshot_output_template() {
+        let cmd = parse_command(
+            &args("foreach .card screenshot --output ./cards/{{index}}.png"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["command"], "screenshot");
+        assert_eq!(cmd["args"]["selector"], "{{ref}}");
+        assert_eq!(cmd["outputTemplate"], "./cards/{{index}}.png");
     }
 
     #[test]
-    fn test_screenshot_with_css_id() {
-        let cmd = parse_command(&args("screenshot #header"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "screenshot");
-        assert_eq!(cmd["selector"], "#header");
-        assert_eq!(cmd["path"], serde_json::Value::Null);
+    fn test_foreach_fill_with_template_text() {
+        let cmd =
+            parse_command(&args("foreach input fill item-{{index}}"), &default_flags()).unwrap();
+        assert_eq!(cmd["command"], "fill");
+        assert_eq!(cmd["args"]["selector"], "{{ref}}");
+        assert_eq!(cmd["args"]["value"], "item-{{index}}");
     }
 
     #[test]
-    fn test_screenshot_with_path() {
-        let cmd = parse_command(&args("screenshot ./output.png"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "screenshot");
-        assert_eq!(cmd["selector"], serde_json::Value::Null);
-        assert_eq!(cmd["path"], "./output.png");
+    fn test_foreach_get_text_targets_element() {
+        let cmd = parse_command(&args("foreach .row get text"), &default_flags()).unwrap();
+        assert_eq!(cmd["command"], "gettext");
+        assert_eq!(cmd["args"]["selector"], "{{ref}}");
     }
 
     #[test]
-    fn test_screenshot_with_selector_and_path() {
-        let cmd = parse_command(&args("screenshot .btn ./button.png"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "screenshot");
-        assert_eq!(cmd["selector"], ".btn");
-        assert_eq!(cmd["path"], "./button.png");
+    fn test_foreach_missing_subcommand() {
+        for input in ["foreach", "foreach .card"] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::MissingArguments { .. })
+            ));
+        }
     }
 
-    // === Snapshot ===
-
     #[test]
-    fn test_snapshot() {
-        let cmd = parse_command(&args("snapshot"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "snapshot");
+    fn test_foreach_missing_output_value() {
+        let result = parse_command(&args("foreach .card screenshot --output"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
     #[test]
-    fn test_snapshot_interactive() {
-        let cmd = parse_command(&args("snapshot -i"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "snapshot");
-        assert_eq!(cmd["interactive"], true);
+    fn test_foreach_unknown_subcommand() {
+        let result = parse_command(&args("foreach .card frobnicate"), &default_flags());
+        match result.unwrap_err() {
+            ParseError::UnknownCommand { command } => assert_eq!(command, "frobnicate"),
+            other => panic!("unexpected error: {:?}", other),
+        }
     }
 
     #[test]
-    fn test_snapshot_cursor() {
-        let cmd = parse_command(&args("snapshot -C"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "snapshot");
-        assert_eq!(cmd["cursor"], true);
+    fn test_foreach_rejects_non_element_command() {
+        let result = parse_command(&args("foreach .card open"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::InvalidValue { .. }
+        ));
     }
 
     #[test]
-    fn test_snapshot_interactive_cursor() {
-        let cmd = parse_command(&args("snapshot -i -C"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "snapshot");
-        assert_eq!(cmd["interactive"], true);
-        assert_eq!(cmd["cursor"], true);
+    fn test_foreach_propagates_subcommand_errors() {
+        let result = parse_command(&args("foreach .card select"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
+    // === Clipboard Tests ===
+
     #[test]
-    fn test_snapshot_compact() {
-        let cmd = parse_command(&args("snapshot --compact"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "snapshot");
-        assert_eq!(cmd["compact"], true);
+    fn test_clipboard_copy() {
+        let cmd = parse_command(&args("clipboard copy hello"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "clipboard_copy");
+        assert_eq!(cmd["text"], "hello");
     }
 
     #[test]
-    fn test_snapshot_depth() {
-        let cmd = parse_command(&args("snapshot -d 3"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "snapshot");
-        assert_eq!(cmd["maxDepth"], 3);
+    fn test_clipboard_copy_joins_words() {
+        let cmd = parse_command(&args("clipboard copy hello world"), &default_flags()).unwrap();
+        assert_eq!(cmd["text"], "hello world");
     }
 
-    // === Wait ===
+    #[test]
+    fn test_clipboard_copy_missing_text() {
+        let result = parse_command(&args("clipboard copy"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
 
     #[test]
-    fn test_wait_selector() {
-        let cmd = parse_command(&args("wait #element"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "wait");
-        assert_eq!(cmd["selector"], "#element");
+    fn test_clipboard_paste() {
+        let cmd = parse_command(&args("clipboard paste #search"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "clipboard_paste");
+        assert_eq!(cmd["selector"], "#search");
     }
 
     #[test]
-    fn test_wait_timeout() {
-        let cmd = parse_command(&args("wait 5000"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "wait");
-        assert_eq!(cmd["timeout"], 5000);
+    fn test_clipboard_paste_missing_selector() {
+        let result = parse_command(&args("clipboard paste"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
     #[test]
-    fn test_wait_url() {
-        let cmd = parse_command(&args("wait --url **/dashboard"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "waitforurl");
-        assert_eq!(cmd["url"], "**/dashboard");
+    fn test_clipboard_get() {
+        let cmd = parse_command(&args("clipboard get"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "clipboard_get");
+        assert!(cmd.get("text").is_none());
     }
 
     #[test]
-    fn test_wait_load() {
-        let cmd = parse_command(&args("wait --load networkidle"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "waitforloadstate");
-        assert_eq!(cmd["state"], "networkidle");
+    fn test_clipboard_unknown_subcommand() {
+        let result = parse_command(&args("clipboard cut"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
     }
 
     #[test]
-    fn test_wait_load_missing_state() {
-        let result = parse_command(&args("wait --load"), &default_flags());
-        assert!(result.is_err());
+    fn test_clipboard_missing_subcommand() {
+        let result = parse_command(&args("clipboard"), &default_flags());
         assert!(matches!(
             result.unwrap_err(),
             ParseError::MissingArguments { .. }
         ));
     }
 
+    // === Inject Tests ===
+
     #[test]
-    fn test_wait_fn() {
-        let cmd = parse_command(&args("wait --fn window.ready"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "waitforfunction");
-        assert_eq!(cmd["expression"], "window.ready");
+    fn test_inject_script() {
+        let dir = std::env::temp_dir().join("ab-test-inject-script");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("utils.js");
+        fs::write(&path, "window.__ready = true;").unwrap();
+
+        let input = format!("inject script {}", path.display());
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "inject_script");
+        assert_eq!(cmd["script"], "window.__ready = true;");
+        assert!(cmd.get("url").is_none());
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
     }
 
     #[test]
-    fn test_wait_text() {
-        let cmd = parse_command(&args("wait --text Welcome"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "wait");
-        assert_eq!(cmd["selector"], "text=Welcome");
-    }
+    fn test_inject_style() {
+        let dir = std::env::temp_dir().join("ab-test-inject-style");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("overrides.css");
+        fs::write(&path, "* { animation: none !important; }").unwrap();
 
-    // === Unknown command ===
+        let input = format!("inject style {}", path.display());
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "inject_style");
+        assert_eq!(cmd["css"], "* { animation: none !important; }");
+        assert!(cmd.get("script").is_none());
 
-    // === Record Tests ===
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
+    }
 
     #[test]
-    fn test_record_start() {
-        let cmd = parse_command(&args("record start output.webm"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "recording_start");
-        assert_eq!(cmd["path"], "output.webm");
-        assert!(cmd.get("url").is_none());
+    fn test_inject_script_with_url() {
+        let dir = std::env::temp_dir().join("ab-test-inject-url");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("utils.js");
+        fs::write(&path, "1;").unwrap();
+
+        let input = format!("inject script {} --url **/app/*", path.display());
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["url"], "**/app/*");
+
+        let input = format!("inject style --url **/app/* {}", path.display());
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["url"], "**/app/*");
+        assert_eq!(cmd["css"], "1;");
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir(&dir);
     }
 
     #[test]
-    fn test_record_start_with_url() {
-        let cmd = parse_command(
-            &args("record start demo.webm https://example.com"),
+    fn test_inject_file_not_found() {
+        let result = parse_command(
+            &args("inject script /nonexistent/utils.js"),
             &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "recording_start");
-        assert_eq!(cmd["path"], "demo.webm");
-        assert_eq!(cmd["url"], "https://example.com");
+        );
+        match result.unwrap_err() {
+            ParseError::InvalidValue { message, .. } => {
+                assert!(message.contains("/nonexistent/utils.js"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
     }
 
     #[test]
-    fn test_record_start_with_url_no_protocol() {
-        let cmd = parse_command(
-            &args("record start demo.webm example.com"),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "recording_start");
-        assert_eq!(cmd["path"], "demo.webm");
-        assert_eq!(cmd["url"], "https://example.com");
+    fn test_inject_missing_path() {
+        let result = parse_command(&args("inject style"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
     }
 
     #[test]
-    fn test_record_start_missing_path() {
-        let result = parse_command(&args("record start"), &default_flags());
-        assert!(result.is_err());
+    fn test_inject_url_missing_value() {
+        let result = parse_command(&args("inject script utils.js --url"), &default_flags());
         assert!(matches!(
             result.unwrap_err(),
             ParseError::MissingArguments { .. }
@@ -2584,973 +10718,1156 @@ mod tests {
     }
 
     #[test]
-    fn test_record_stop() {
-        let cmd = parse_command(&args("record stop"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "recording_stop");
+    fn test_inject_unknown_subcommand() {
+        let result = parse_command(&args("inject html page.html"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::UnknownSubcommand { .. }
+        ));
     }
 
     #[test]
-    fn test_record_restart() {
-        let cmd = parse_command(&args("record restart output.webm"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "recording_restart");
-        assert_eq!(cmd["path"], "output.webm");
-        assert!(cmd.get("url").is_none());
+    fn test_inject_missing_subcommand() {
+        let result = parse_command(&args("inject"), &default_flags());
+        assert!(matches!(
+            result.unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    // === Run (batch) Tests ===
+
+    fn write_batch_script(name: &str, content: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("ab-test-run-{}", name));
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("script.ab");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn remove_batch_script(path: &std::path::Path) {
+        let _ = fs::remove_file(path);
+        let _ = fs::remove_dir(path.parent().unwrap());
     }
 
     #[test]
-    fn test_record_restart_with_url() {
-        let cmd = parse_command(
-            &args("record restart demo.webm https://example.com"),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "recording_restart");
-        assert_eq!(cmd["path"], "demo.webm");
-        assert_eq!(cmd["url"], "https://example.com");
+    fn test_run_batch_commands() {
+        let path = write_batch_script("basic", "open example.com\nclick #submit\nwait 500\n");
+        let cmd =
+            parse_command(&args(&format!("run {}", path.display())), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "batch");
+        let commands = cmd["commands"].as_array().unwrap();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[0]["action"], "navigate");
+        assert_eq!(commands[1]["action"], "click");
+        assert_eq!(commands[1]["selector"], "#submit");
+        assert_eq!(commands[2]["action"], "wait");
+        remove_batch_script(&path);
+    }
+
+    #[test]
+    fn test_run_skips_blank_lines_and_comments() {
+        let path = write_batch_script(
+            "comments",
+            "# login flow\n\n   \nopen example.com\n  # indented comment\nclick #go\n",
+        );
+        let cmd =
+            parse_command(&args(&format!("run {}", path.display())), &default_flags()).unwrap();
+        assert_eq!(cmd["commands"].as_array().unwrap().len(), 2);
+        remove_batch_script(&path);
     }
 
     #[test]
-    fn test_record_restart_missing_path() {
-        let result = parse_command(&args("record restart"), &default_flags());
-        assert!(result.is_err());
+    fn test_run_quoted_arguments() {
+        let path = write_batch_script(
+            "quotes",
+            "fill \"#search box\" 'hello world'\nfill #q say \\\"hi\\\"\n",
+        );
+        let cmd =
+            parse_command(&args(&format!("run {}", path.display())), &default_flags()).unwrap();
+        let commands = cmd["commands"].as_array().unwrap();
+        assert_eq!(commands[0]["selector"], "#search box");
+        assert_eq!(commands[0]["value"], "hello world");
+        assert_eq!(commands[1]["value"], "say \"hi\"");
+        remove_batch_script(&path);
+    }
+
+    #[test]
+    fn test_run_parse_error_includes_line_number() {
+        let path = write_batch_script("bad-line", "open example.com\n\nclick\n");
+        let result = parse_command(&args(&format!("run {}", path.display())), &default_flags());
+        match result.unwrap_err() {
+            ParseError::InvalidValue { message, .. } => {
+                assert!(message.contains("line 3"), "{}", message);
+                assert!(message.contains("click"), "{}", message);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        remove_batch_script(&path);
+    }
+
+    #[test]
+    fn test_run_unterminated_quote() {
+        let path = write_batch_script("unterminated", "fill #q \"oops\n");
+        let result = parse_command(&args(&format!("run {}", path.display())), &default_flags());
+        match result.unwrap_err() {
+            ParseError::InvalidValue { message, .. } => {
+                assert!(message.contains("line 1"), "{}", message);
+                assert!(message.contains("unterminated quote"), "{}", message);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        remove_batch_script(&path);
+    }
+
+    #[test]
+    fn test_run_nested_run_rejected() {
+        let path = write_batch_script("nested", "run other.ab\n");
+        let result = parse_command(&args(&format!("run {}", path.display())), &default_flags());
         assert!(matches!(
             result.unwrap_err(),
-            ParseError::MissingArguments { .. }
+            ParseError::InvalidValue { .. }
         ));
+        remove_batch_script(&path);
     }
 
-    #[test]
-    fn test_record_invalid_subcommand() {
-        let result = parse_command(&args("record foo"), &default_flags());
-        assert!(result.is_err());
+    #[test]
+    fn test_run_empty_script() {
+        let path = write_batch_script("empty", "# nothing here\n\n");
+        let result = parse_command(&args(&format!("run {}", path.display())), &default_flags());
         assert!(matches!(
             result.unwrap_err(),
-            ParseError::UnknownSubcommand { .. }
+            ParseError::InvalidValue { .. }
         ));
+        remove_batch_script(&path);
     }
 
     #[test]
-    fn test_record_missing_subcommand() {
-        let result = parse_command(&args("record"), &default_flags());
-        assert!(result.is_err());
+    fn test_run_file_not_found() {
+        let result = parse_command(&args("run /nonexistent/script.ab"), &default_flags());
+        match result.unwrap_err() {
+            ParseError::InvalidValue { message, .. } => {
+                assert!(message.contains("/nonexistent/script.ab"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_run_missing_path() {
+        let result = parse_command(&args("run"), &default_flags());
         assert!(matches!(
             result.unwrap_err(),
             ParseError::MissingArguments { .. }
         ));
     }
 
-    // === Profile (CDP Tracing) Tests ===
+    // === Completions Tests ===
 
     #[test]
-    fn test_profiler_start() {
-        let cmd = parse_command(&args("profiler start"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "profiler_start");
-        assert!(cmd.get("categories").is_none());
+    fn test_completions_shells() {
+        for shell in ["bash", "zsh", "fish", "powershell"] {
+            let cmd =
+                parse_command(&args(&format!("completions {}", shell)), &default_flags()).unwrap();
+            assert_eq!(cmd["action"], "completions");
+            assert_eq!(cmd["shell"], shell);
+        }
     }
 
     #[test]
-    fn test_profiler_start_with_categories() {
-        let cmd = parse_command(
-            &args("profiler start --categories devtools.timeline,v8.execute"),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "profiler_start");
-        let categories = cmd["categories"].as_array().unwrap();
-        assert_eq!(categories.len(), 2);
-        assert_eq!(categories[0], "devtools.timeline");
-        assert_eq!(categories[1], "v8.execute");
+    fn test_completions_invalid_shell() {
+        let result = parse_command(&args("completions tcsh"), &default_flags());
+        match result.unwrap_err() {
+            ParseError::UnknownSubcommand {
+                subcommand,
+                valid_options,
+            } => {
+                assert_eq!(subcommand, "tcsh");
+                assert!(valid_options.contains(&"powershell"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
     }
 
     #[test]
-    fn test_profiler_start_categories_missing_value() {
-        let result = parse_command(&args("profiler start --categories"), &default_flags());
-        assert!(result.is_err());
+    fn test_completions_missing_shell() {
+        let result = parse_command(&args("completions"), &default_flags());
         assert!(matches!(
             result.unwrap_err(),
             ParseError::MissingArguments { .. }
         ));
     }
 
+    // === Schema Tests ===
+
     #[test]
-    fn test_profiler_stop_with_path() {
-        let cmd = parse_command(&args("profiler stop trace.json"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "profiler_stop");
-        assert_eq!(cmd["path"], "trace.json");
+    fn test_schema() {
+        let cmd = parse_command(&args("schema"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "schema");
+        assert!(cmd["id"].is_string());
     }
 
     #[test]
-    fn test_profiler_stop_no_path() {
-        let cmd = parse_command(&args("profiler stop"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "profiler_stop");
-        assert!(cmd.get("path").is_none());
+    fn test_schema_with_session_flag() {
+        let input = args("--session ci schema");
+        let mut flags = default_flags();
+        flags.session = "ci".to_string();
+        let cmd = parse_command(&crate::flags::clean_args(&input), &flags).unwrap();
+        assert_eq!(cmd["action"], "schema");
+        assert_eq!(flags.session, "ci");
     }
 
     #[test]
-    fn test_profiler_invalid_subcommand() {
-        let result = parse_command(&args("profiler foo"), &default_flags());
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::UnknownSubcommand { .. }
-        ));
+    fn test_navigate_with_header_pairs() {
+        let mut flags = default_flags();
+        flags.header_pairs = vec![
+            ("Authorization".to_string(), "Bearer t".to_string()),
+            ("X-Env".to_string(), "staging".to_string()),
+        ];
+        let cmd = parse_command(&args("open example.com"), &flags).unwrap();
+        assert_eq!(cmd["headers"]["Authorization"], "Bearer t");
+        assert_eq!(cmd["headers"]["X-Env"], "staging");
     }
 
     #[test]
-    fn test_profiler_missing_subcommand() {
-        let result = parse_command(&args("profiler"), &default_flags());
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
-        ));
+    fn test_navigate_header_pairs_override_headers_json() {
+        let mut flags = default_flags();
+        flags.headers = Some(r#"{"Authorization": "old", "X-Old": "1"}"#.to_string());
+        flags.header_pairs = vec![("Authorization".to_string(), "new".to_string())];
+        let cmd = parse_command(&args("open example.com"), &flags).unwrap();
+        assert_eq!(cmd["headers"]["Authorization"], "new");
+        assert!(cmd["headers"].get("X-Old").is_none());
     }
 
-    // === Eval Tests ===
-
     #[test]
-    fn test_eval_basic() {
-        let cmd = parse_command(&args("eval document.title"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "evaluate");
-        assert_eq!(cmd["script"], "document.title");
+    fn test_navigate_repeated_header_last_wins() {
+        let mut flags = default_flags();
+        flags.header_pairs = vec![
+            ("X-Env".to_string(), "a".to_string()),
+            ("X-Env".to_string(), "b".to_string()),
+        ];
+        let cmd = parse_command(&args("open example.com"), &flags).unwrap();
+        assert_eq!(cmd["headers"], json!({ "X-Env": "b" }));
     }
 
     #[test]
-    fn test_eval_base64_short_flag() {
-        // "document.title" in base64
-        let cmd = parse_command(&args("eval -b ZG9jdW1lbnQudGl0bGU="), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "evaluate");
-        assert_eq!(cmd["script"], "document.title");
+    fn test_gen_id_format() {
+        let id = gen_id();
+        assert_eq!(id.len(), 13);
+        assert!(id.starts_with('r'));
+        assert!(id[1..].chars().all(|c| c.is_ascii_hexdigit()));
     }
 
     #[test]
-    fn test_eval_base64_long_flag() {
-        // "document.title" in base64
-        let cmd = parse_command(
-            &args("eval --base64 ZG9jdW1lbnQudGl0bGU="),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "evaluate");
-        assert_eq!(cmd["script"], "document.title");
+    fn test_gen_id_no_collisions() {
+        let ids: std::collections::HashSet<String> = (0..10_000).map(|_| gen_id()).collect();
+        assert_eq!(ids.len(), 10_000);
     }
 
-    #[test]
-    fn test_eval_base64_with_special_chars() {
-        // "document.querySelector('[src*=\"_next\"]')" in base64
-        let cmd = parse_command(
-            &args("eval -b ZG9jdW1lbnQucXVlcnlTZWxlY3RvcignW3NyYyo9Il9uZXh0Il0nKQ=="),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "evaluate");
-        assert_eq!(cmd["script"], "document.querySelector('[src*=\"_next\"]')");
+    fn all_parse_error_variants() -> Vec<ParseError> {
+        vec![
+            ParseError::UnknownCommand {
+                command: "frobnicate".to_string(),
+            },
+            ParseError::UnknownSubcommand {
+                subcommand: "sideways".to_string(),
+                valid_options: &["up", "down"],
+            },
+            ParseError::MissingArguments {
+                context: "click".to_string(),
+                usage: "click <selector>",
+            },
+            ParseError::InvalidValue {
+                message: "Invalid port".to_string(),
+                usage: "connect <port>",
+            },
+            ParseError::InvalidSessionName {
+                name: "../evil".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_parse_error_display_matches_format() {
+        for err in all_parse_error_variants() {
+            let shown = format!("{}", err);
+            assert!(!shown.is_empty());
+            assert_eq!(shown, err.format());
+        }
     }
 
     #[test]
-    fn test_eval_base64_invalid() {
-        let result = parse_command(&args("eval -b !!!invalid!!!"), &default_flags());
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(matches!(err, ParseError::InvalidValue { .. }));
-        assert!(err.format().contains("Invalid base64"));
+    fn test_parse_error_debug_is_non_empty() {
+        for err in all_parse_error_variants() {
+            assert!(!format!("{:?}", err).is_empty());
+        }
     }
 
     #[test]
-    fn test_unknown_command() {
-        let result = parse_command(&args("unknowncommand"), &default_flags());
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::UnknownCommand { .. }
-        ));
+    fn test_parse_error_as_boxed_error() {
+        fn parse_boxed() -> Result<Value, Box<dyn std::error::Error>> {
+            Ok(parse_command(&args("frobnicate"), &default_flags())?)
+        }
+        let err = parse_boxed().unwrap_err();
+        assert_eq!(err.to_string(), "Unknown command: frobnicate");
+        assert!(err.source().is_none());
     }
 
     #[test]
-    fn test_empty_args() {
-        let result = parse_command(&[], &default_flags());
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
-        ));
+    fn test_parse_error_serializes_variant_fields() {
+        let shapes: Vec<Value> = all_parse_error_variants()
+            .iter()
+            .map(|e| serde_json::to_value(e).unwrap())
+            .collect();
+        assert_eq!(
+            shapes,
+            vec![
+                json!({ "type": "unknown_command", "command": "frobnicate" }),
+                json!({
+                    "type": "unknown_subcommand",
+                    "subcommand": "sideways",
+                    "validOptions": ["up", "down"]
+                }),
+                json!({
+                    "type": "missing_arguments",
+                    "context": "click",
+                    "usage": "click <selector>"
+                }),
+                json!({
+                    "type": "invalid_value",
+                    "message": "Invalid port",
+                    "usage": "connect <port>"
+                }),
+                json!({ "type": "invalid_session_name", "name": "../evil" }),
+            ]
+        );
     }
 
-    // === Error message tests ===
+    #[test]
+    fn test_navigate_uses_config_defaults() {
+        let mut flags = default_flags();
+        flags.default_wait_until = Some("networkidle".to_string());
+        flags.default_timeout = Some(20000);
+        let cmd = parse_command(&args("open example.com"), &flags).unwrap();
+        assert_eq!(cmd["waitUntil"], "networkidle");
+        assert_eq!(cmd["timeout"], 20000);
+    }
 
     #[test]
-    fn test_get_missing_subcommand() {
-        let result = parse_command(&args("get"), &default_flags());
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(matches!(err, ParseError::MissingArguments { .. }));
-        assert!(err.format().contains("get"));
+    fn test_navigate_without_config_defaults() {
+        let cmd = parse_command(&args("open example.com"), &default_flags()).unwrap();
+        assert!(cmd.get("waitUntil").is_none());
+        assert!(cmd.get("timeout").is_none());
     }
 
     #[test]
-    fn test_get_unknown_subcommand() {
-        let result = parse_command(&args("get foo"), &default_flags());
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(matches!(err, ParseError::UnknownSubcommand { .. }));
-        assert!(err.format().contains("foo"));
-        assert!(err.format().contains("text"));
+    fn test_screenshot_uses_default_format() {
+        let mut flags = default_flags();
+        flags.default_screenshot_format = Some("jpeg".to_string());
+        flags.default_timeout = Some(15000);
+        let cmd = parse_command(&args("screenshot"), &flags).unwrap();
+        assert_eq!(cmd["format"], "jpeg");
+        assert_eq!(cmd["timeout"], 15000);
     }
 
     #[test]
-    fn test_get_text_missing_selector() {
-        let result = parse_command(&args("get text"), &default_flags());
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(matches!(err, ParseError::MissingArguments { .. }));
-        assert!(err.format().contains("get text"));
+    fn test_screenshot_path_extension_beats_default_format() {
+        let mut flags = default_flags();
+        flags.default_screenshot_format = Some("jpeg".to_string());
+        let cmd = parse_command(&args("screenshot shot.png"), &flags).unwrap();
+        assert!(cmd.get("format").is_none());
+        let cmd = parse_command(&args("screenshot ./shots/page"), &flags).unwrap();
+        assert_eq!(cmd["format"], "jpeg");
     }
 
-    // === Protocol alignment tests ===
+    #[test]
+    fn test_config_defaults_only_apply_to_navigate_and_screenshot() {
+        let mut flags = default_flags();
+        flags.default_wait_until = Some("load".to_string());
+        flags.default_timeout = Some(20000);
+        flags.default_screenshot_format = Some("jpeg".to_string());
+        let cmd = parse_command(&args("click #btn"), &flags).unwrap();
+        assert!(cmd.get("waitUntil").is_none());
+        assert!(cmd.get("timeout").is_none());
+        assert!(cmd.get("format").is_none());
+    }
 
     #[test]
-    fn test_mouse_wheel() {
-        let cmd = parse_command(&args("mouse wheel 100 50"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "wheel");
-        assert_eq!(cmd["deltaY"], 100);
-        assert_eq!(cmd["deltaX"], 50);
+    fn test_config_validate() {
+        let cmd = parse_command(&args("config validate"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "config_validate");
+        assert_eq!(cmd["strict"], false);
+        let cmd = parse_command(&args("config validate --strict"), &default_flags()).unwrap();
+        assert_eq!(cmd["strict"], true);
     }
 
     #[test]
-    fn test_set_media() {
-        let cmd = parse_command(&args("set media dark"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "emulatemedia");
-        assert_eq!(cmd["colorScheme"], "dark");
-        assert_eq!(cmd["reducedMotion"], "no-preference");
+    fn test_config_requires_known_subcommand() {
+        assert!(matches!(
+            parse_command(&args("config"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("config check"), &default_flags()),
+            Err(ParseError::UnknownSubcommand { .. })
+        ));
     }
 
     #[test]
-    fn test_set_media_reduced_motion() {
-        let cmd = parse_command(&args("set media light reduced-motion"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "emulatemedia");
-        assert_eq!(cmd["colorScheme"], "light");
-        assert_eq!(cmd["reducedMotion"], "reduce");
+    fn test_daemon_subcommands() {
+        for sub in ["start", "stop", "status", "restart", "logs"] {
+            let cmd = parse_command(&args(&format!("daemon {}", sub)), &default_flags()).unwrap();
+            assert_eq!(cmd["action"], "daemon");
+            assert_eq!(cmd["subcommand"], sub);
+        }
     }
 
     #[test]
-    fn test_find_first_no_value() {
-        let cmd = parse_command(&args("find first a click"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "nth");
-        assert_eq!(cmd["index"], 0);
-        assert!(cmd.get("value").is_none());
+    fn test_daemon_missing_subcommand() {
+        assert!(matches!(
+            parse_command(&args("daemon"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
     }
 
     #[test]
-    fn test_find_first_with_value() {
-        let cmd = parse_command(&args("find first input fill hello"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "nth");
-        assert_eq!(cmd["index"], 0);
-        assert_eq!(cmd["value"], "hello");
+    fn test_daemon_unknown_subcommand() {
+        let err = parse_command(&args("daemon kill"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::UnknownSubcommand { .. }));
+        assert!(err.to_string().contains("restart"));
     }
 
+    // === Gestures ===
+
     #[test]
-    fn test_find_nth_no_value() {
-        let cmd = parse_command(&args("find nth 2 a click"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "nth");
-        assert_eq!(cmd["index"], 2);
-        assert!(cmd.get("value").is_none());
+    fn test_swipe_with_selector() {
+        let cmd =
+            parse_command(&args("swipe left --selector #carousel"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "swipe");
+        assert_eq!(cmd["direction"], "left");
+        assert_eq!(cmd["selector"], "#carousel");
+        assert!(cmd.get("distance").is_none());
     }
 
-    // === Download Tests ===
+    #[test]
+    fn test_swipe_selector_with_distance() {
+        let cmd = parse_command(&args("swipe up --selector .list 500"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], ".list");
+        assert_eq!(cmd["distance"], 500);
+    }
 
     #[test]
-    fn test_download() {
-        let cmd = parse_command(&args("download #btn ./file.pdf"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "download");
-        assert_eq!(cmd["selector"], "#btn");
-        assert_eq!(cmd["path"], "./file.pdf");
+    fn test_swipe_distance_before_selector() {
+        let cmd = parse_command(&args("swipe down 200 --selector @e3"), &default_flags()).unwrap();
+        assert_eq!(cmd["distance"], 200);
+        assert_eq!(cmd["selector"], "@e3");
     }
 
     #[test]
-    fn test_download_with_ref() {
-        let cmd = parse_command(&args("download @e5 ./report.xlsx"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "download");
-        assert_eq!(cmd["selector"], "@e5");
-        assert_eq!(cmd["path"], "./report.xlsx");
+    fn test_swipe_without_selector_unchanged() {
+        let cmd = parse_command(&args("swipe right 300"), &default_flags()).unwrap();
+        assert_eq!(cmd["distance"], 300);
+        assert!(cmd.get("selector").is_none());
     }
 
     #[test]
-    fn test_download_missing_path() {
-        let result = parse_command(&args("download #btn"), &default_flags());
-        assert!(result.is_err());
+    fn test_swipe_selector_missing_value() {
         assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
+            parse_command(&args("swipe up --selector"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
         ));
     }
 
     #[test]
-    fn test_download_missing_selector() {
-        let result = parse_command(&args("download"), &default_flags());
-        assert!(result.is_err());
+    fn test_pinch_in_with_selector_and_scale() {
+        let cmd = parse_command(
+            &args("pinch in --selector #map --scale 0.5"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "pinch");
+        assert_eq!(cmd["direction"], "in");
+        assert_eq!(cmd["selector"], "#map");
+        assert_eq!(cmd["scale"], 0.5);
+    }
+
+    #[test]
+    fn test_pinch_out_defaults() {
+        let cmd = parse_command(&args("pinch out"), &default_flags()).unwrap();
+        assert_eq!(cmd["direction"], "out");
+        assert!(cmd.get("selector").is_none());
+        assert!(cmd.get("scale").is_none());
+    }
+
+    #[test]
+    fn test_pinch_missing_direction() {
         assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
+            parse_command(&args("pinch"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
         ));
     }
 
-    // === Wait for Download Tests ===
-
     #[test]
-    fn test_wait_download() {
-        let cmd = parse_command(&args("wait --download"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "waitfordownload");
-        assert!(cmd.get("path").is_none());
+    fn test_pinch_invalid_direction() {
+        let err = parse_command(&args("pinch sideways"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.to_string().contains("sideways"));
     }
 
     #[test]
-    fn test_wait_download_with_path() {
-        let cmd = parse_command(&args("wait --download ./file.pdf"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "waitfordownload");
-        assert_eq!(cmd["path"], "./file.pdf");
+    fn test_pinch_rejects_non_positive_scale() {
+        for scale in ["0", "-1.5"] {
+            let input = format!("pinch out --scale {}", scale);
+            assert!(matches!(
+                parse_command(&args(&input), &default_flags()),
+                Err(ParseError::InvalidValue { .. })
+            ));
+        }
     }
 
     #[test]
-    fn test_wait_download_with_timeout() {
-        let cmd =
-            parse_command(&args("wait --download --timeout 30000"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "waitfordownload");
-        assert_eq!(cmd["timeout"], 30000);
+    fn test_pinch_rejects_non_numeric_scale() {
+        assert!(matches!(
+            parse_command(&args("pinch in --scale big"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
     }
 
     #[test]
-    fn test_wait_download_with_path_and_timeout() {
-        let cmd = parse_command(
-            &args("wait --download ./file.pdf --timeout 30000"),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "waitfordownload");
-        assert_eq!(cmd["path"], "./file.pdf");
-        assert_eq!(cmd["timeout"], 30000);
+    fn test_pinch_scale_missing_value() {
+        assert!(matches!(
+            parse_command(&args("pinch in --scale"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
     }
 
     #[test]
-    fn test_wait_download_short_flag() {
-        let cmd = parse_command(&args("wait -d ./file.pdf"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "waitfordownload");
-        assert_eq!(cmd["path"], "./file.pdf");
+    fn test_tab_new_background() {
+        let cmd = parse_command(&args("tab new --background"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_new");
+        assert_eq!(cmd["background"], true);
+        assert!(cmd.get("url").is_none());
     }
 
-    // === Connect (CDP) tests ===
-
     #[test]
-    fn test_connect_with_port() {
-        let cmd = parse_command(&args("connect 9222"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "launch");
-        assert_eq!(cmd["cdpPort"], 9222);
-        assert!(cmd.get("cdpUrl").is_none());
+    fn test_tab_new_background_with_url() {
+        for input in [
+            "tab new https://example.com --background",
+            "tab new --background https://example.com",
+        ] {
+            let cmd = parse_command(&args(input), &default_flags()).unwrap();
+            assert_eq!(cmd["url"], "https://example.com");
+            assert_eq!(cmd["background"], true);
+        }
     }
 
     #[test]
-    fn test_connect_with_ws_url() {
-        let input: Vec<String> = vec![
-            "connect".to_string(),
-            "ws://localhost:9222/devtools/browser/abc123".to_string(),
-        ];
-        let cmd = parse_command(&input, &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "launch");
-        assert_eq!(cmd["cdpUrl"], "ws://localhost:9222/devtools/browser/abc123");
-        assert!(cmd.get("cdpPort").is_none());
+    fn test_tab_new_without_background() {
+        let cmd = parse_command(&args("tab new https://example.com"), &default_flags()).unwrap();
+        assert!(cmd.get("background").is_none());
     }
 
     #[test]
-    fn test_connect_with_wss_url() {
-        let input: Vec<String> = vec![
-            "connect".to_string(),
-            "wss://remote-browser.example.com/cdp?token=xyz".to_string(),
-        ];
-        let cmd = parse_command(&input, &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "launch");
-        assert_eq!(
-            cmd["cdpUrl"],
-            "wss://remote-browser.example.com/cdp?token=xyz"
-        );
-        assert!(cmd.get("cdpPort").is_none());
+    fn test_tab_duplicate_current() {
+        let cmd = parse_command(&args("tab duplicate"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_duplicate");
+        assert!(cmd.get("index").is_none());
     }
 
     #[test]
-    fn test_connect_with_http_url() {
-        let input: Vec<String> = vec!["connect".to_string(), "http://localhost:9222".to_string()];
-        let cmd = parse_command(&input, &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "launch");
-        assert_eq!(cmd["cdpUrl"], "http://localhost:9222");
-        assert!(cmd.get("cdpPort").is_none());
+    fn test_tab_duplicate_index() {
+        let cmd = parse_command(&args("tab duplicate 2"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tab_duplicate");
+        assert_eq!(cmd["index"], 2);
     }
 
     #[test]
-    fn test_connect_missing_argument() {
-        let result = parse_command(&args("connect"), &default_flags());
-        assert!(result.is_err());
+    fn test_tab_duplicate_invalid_index() {
         assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
+            parse_command(&args("tab duplicate two"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
         ));
     }
 
+    // === Upload ===
+
     #[test]
-    fn test_connect_invalid_port() {
-        let result = parse_command(&args("connect notanumber"), &default_flags());
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(matches!(err, ParseError::InvalidValue { .. }));
-        assert!(err.format().contains("not a valid port number or URL"));
+    fn test_upload_plain_paths() {
+        let cmd = parse_command(&args("upload #file ./a.pdf ./b.pdf"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "upload");
+        assert_eq!(cmd["selector"], "#file");
+        assert_eq!(cmd["files"], json!(["./a.pdf", "./b.pdf"]));
+        assert!(cmd.get("inlineFiles").is_none());
     }
 
     #[test]
-    fn test_connect_port_zero() {
-        let result = parse_command(&args("connect 0"), &default_flags());
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(matches!(err, ParseError::InvalidValue { .. }));
-        assert!(err.format().contains("port must be greater than 0"));
+    fn test_upload_missing_selector() {
+        assert!(matches!(
+            parse_command(&args("upload"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
     }
 
     #[test]
-    fn test_connect_port_out_of_range() {
-        let result = parse_command(&args("connect 65536"), &default_flags());
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(matches!(err, ParseError::InvalidValue { .. }));
-        assert!(err.format().contains("out of range"));
-        assert!(err.format().contains("1-65535"));
+    fn test_upload_glob_expands_matches() {
+        let dir = std::env::temp_dir().join("ab-test-upload-glob");
+        let _ = fs::create_dir_all(&dir);
+        for name in ["b.csv", "a.csv", "notes.txt"] {
+            fs::write(dir.join(name), "x").unwrap();
+        }
+
+        let input = format!("upload #file {}/*.csv", dir.display());
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        let expected = vec![
+            dir.join("a.csv").display().to_string(),
+            dir.join("b.csv").display().to_string(),
+        ];
+        assert_eq!(cmd["files"], json!(expected));
+
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
-    fn test_connect_port_max_valid() {
-        let cmd = parse_command(&args("connect 65535"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "launch");
-        assert_eq!(cmd["cdpPort"], 65535);
+    fn test_upload_glob_mixed_with_plain_path() {
+        let dir = std::env::temp_dir().join("ab-test-upload-glob-mixed");
+        let _ = fs::create_dir_all(&dir);
+        fs::write(dir.join("one.png"), "x").unwrap();
+
+        let input = format!("upload #file ./doc.pdf {}/*.png", dir.display());
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["files"][0], "./doc.pdf");
+        assert_eq!(cmd["files"][1], dir.join("one.png").display().to_string());
+
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
-    fn test_connect_port_min_valid() {
-        let cmd = parse_command(&args("connect 1"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "launch");
-        assert_eq!(cmd["cdpPort"], 1);
+    fn test_upload_existing_path_with_glob_chars_is_literal() {
+        let dir = std::env::temp_dir().join("ab-test-upload-literal");
+        let _ = fs::create_dir_all(&dir);
+        fs::write(dir.join("report[1].pdf"), "x").unwrap();
+
+        let path = dir.join("report[1].pdf").display().to_string();
+        let input = format!("upload #file {}", path);
+        let cmd = parse_command(&args(&input), &default_flags()).unwrap();
+        assert_eq!(cmd["files"], json!([path]));
+
+        let _ = fs::remove_dir_all(&dir);
     }
 
-    // === Trace Tests ===
+    #[test]
+    fn test_upload_glob_no_matches() {
+        let dir = std::env::temp_dir().join("ab-test-upload-glob-empty");
+        let _ = fs::create_dir_all(&dir);
+
+        let input = format!("upload #file {}/*.nothing", dir.display());
+        let err = parse_command(&args(&input), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.to_string().contains("No files match"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 
     #[test]
-    fn test_trace_start() {
-        let cmd = parse_command(&args("trace start"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "trace_start");
+    fn test_upload_base64_inline() {
+        let cmd = parse_command(
+            &args("upload #input --base64 test.txt aGVsbG8="),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["files"], json!([]));
+        assert_eq!(
+            cmd["inlineFiles"],
+            json!([{ "name": "test.txt", "content": "aGVsbG8=" }])
+        );
     }
 
     #[test]
-    fn test_trace_stop_with_path() {
-        let cmd = parse_command(&args("trace stop ./trace.zip"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "trace_stop");
-        assert_eq!(cmd["path"], "./trace.zip");
+    fn test_upload_base64_with_files() {
+        let cmd = parse_command(
+            &args("upload #input ./a.pdf --base64 a.txt YQ== --base64 b.txt Yg=="),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["files"], json!(["./a.pdf"]));
+        assert_eq!(cmd["inlineFiles"].as_array().unwrap().len(), 2);
+        assert_eq!(cmd["inlineFiles"][1]["name"], "b.txt");
     }
 
     #[test]
-    fn test_trace_stop_without_path() {
-        let cmd = parse_command(&args("trace stop"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "trace_stop");
-        assert!(cmd.get("path").is_none() || cmd["path"].is_null());
+    fn test_upload_base64_missing_content() {
+        assert!(matches!(
+            parse_command(&args("upload #input --base64 test.txt"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
     }
 
-    // === Diff Tests ===
+    #[test]
+    fn test_upload_base64_invalid_content() {
+        let err = parse_command(
+            &args("upload #input --base64 test.txt not*base64"),
+            &default_flags(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.to_string().contains("test.txt"));
+    }
+
+    // === Download ===
 
     #[test]
-    fn test_diff_snapshot_basic() {
-        let cmd = parse_command(&args("diff snapshot"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "diff_snapshot");
+    fn test_download_positional() {
+        let cmd = parse_command(&args("download #btn ./file.pdf"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "download");
+        assert_eq!(cmd["selector"], "#btn");
+        assert_eq!(cmd["path"], "./file.pdf");
+        assert!(cmd.get("waitForDownload").is_none());
     }
 
     #[test]
-    fn test_diff_snapshot_baseline() {
-        let cmd =
-            parse_command(&args("diff snapshot --baseline before.txt"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "diff_snapshot");
-        assert_eq!(cmd["baseline"], "before.txt");
+    fn test_download_wait() {
+        for input in [
+            "download #btn ./file.pdf --wait",
+            "download --wait #btn ./file.pdf",
+        ] {
+            let cmd = parse_command(&args(input), &default_flags()).unwrap();
+            assert_eq!(cmd["selector"], "#btn");
+            assert_eq!(cmd["path"], "./file.pdf");
+            assert_eq!(cmd["waitForDownload"], true);
+        }
     }
 
     #[test]
-    fn test_diff_snapshot_selector_compact_depth() {
+    fn test_download_wait_for_download_alias() {
         let cmd = parse_command(
-            &args("diff snapshot --selector #main --compact --depth 3"),
+            &args("download #btn --wait-for-download ./file.pdf"),
             &default_flags(),
         )
         .unwrap();
-        assert_eq!(cmd["action"], "diff_snapshot");
-        assert_eq!(cmd["selector"], "#main");
-        assert_eq!(cmd["compact"], true);
-        assert_eq!(cmd["maxDepth"], 3);
+        assert_eq!(cmd["path"], "./file.pdf");
+        assert_eq!(cmd["waitForDownload"], true);
     }
 
     #[test]
-    fn test_diff_snapshot_short_flags() {
-        let cmd =
-            parse_command(&args("diff snapshot -b snap.txt -s .content -c -d 2"), &default_flags())
-                .unwrap();
-        assert_eq!(cmd["action"], "diff_snapshot");
-        assert_eq!(cmd["baseline"], "snap.txt");
-        assert_eq!(cmd["selector"], ".content");
-        assert_eq!(cmd["compact"], true);
-        assert_eq!(cmd["maxDepth"], 2);
+    fn test_download_wait_with_timeout() {
+        let input = args("download #btn ./file.pdf --wait --timeout 60000");
+        let mut flags = default_flags();
+        flags.timeout = Some("60000".to_string());
+        let cmd = parse_command(&crate::flags::clean_args(&input), &flags).unwrap();
+        assert_eq!(cmd["waitForDownload"], true);
+        assert_eq!(cmd["timeout"], 60000);
     }
 
     #[test]
-    fn test_diff_screenshot_baseline() {
-        let cmd = parse_command(
-            &args("diff screenshot --baseline before.png"),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "diff_screenshot");
-        assert_eq!(cmd["baseline"], "before.png");
+    fn test_download_wait_with_global_timeout() {
+        let mut flags = default_flags();
+        flags.timeout = Some("45000".to_string());
+        let cmd = parse_command(&args("download #btn ./file.pdf --wait"), &flags).unwrap();
+        assert_eq!(cmd["timeout"], 45000);
     }
 
     #[test]
-    fn test_diff_screenshot_all_options() {
+    fn test_download_path_flag() {
         let cmd = parse_command(
-            &args("diff screenshot --baseline b.png --output d.png --threshold 0.2 --selector #hero --full"),
+            &args("download @e5 --wait --path ./report.xlsx"),
             &default_flags(),
         )
         .unwrap();
-        assert_eq!(cmd["action"], "diff_screenshot");
-        assert_eq!(cmd["baseline"], "b.png");
-        assert_eq!(cmd["output"], "d.png");
-        assert_eq!(cmd["threshold"], 0.2);
-        assert_eq!(cmd["selector"], "#hero");
-        assert_eq!(cmd["fullPage"], true);
+        assert_eq!(cmd["selector"], "@e5");
+        assert_eq!(cmd["path"], "./report.xlsx");
     }
 
     #[test]
-    fn test_diff_screenshot_missing_baseline() {
-        let result = parse_command(&args("diff screenshot"), &default_flags());
-        assert!(result.is_err());
+    fn test_download_wait_missing_path() {
         assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
+            parse_command(&args("download #btn --wait"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("download #btn --path"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
         ));
     }
 
     #[test]
-    fn test_diff_screenshot_global_full_flag() {
+    fn test_download_invalid_timeout() {
+        let input = args("download #btn ./f.pdf --wait --timeout soon");
         let mut flags = default_flags();
-        flags.full = true;
+        flags.timeout = Some("soon".to_string());
+        let result = parse_command(&crate::flags::clean_args(&input), &flags);
+        assert!(matches!(result, Err(ParseError::InvalidValue { .. })));
+    }
+
+    // === Press ===
+
+    fn press_args(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_press_single_key() {
+        let cmd = parse_command(&args("press Enter"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "press");
+        assert_eq!(cmd["key"], "Enter");
+        assert!(cmd.get("keys").is_none());
+        assert!(cmd.get("selector").is_none());
+    }
+
+    #[test]
+    fn test_press_key_alias() {
+        let cmd = parse_command(&args("key Control+a"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "press");
+        assert_eq!(cmd["key"], "Control+a");
+    }
+
+    #[test]
+    fn test_press_sequence() {
         let cmd =
-            parse_command(&args("diff screenshot --baseline b.png"), &flags).unwrap();
-        assert_eq!(cmd["action"], "diff_screenshot");
-        assert_eq!(cmd["fullPage"], true);
+            parse_command(&press_args(&["press", "Tab Tab Enter"]), &default_flags()).unwrap();
+        assert_eq!(cmd["keys"], json!(["Tab", "Tab", "Enter"]));
+        assert!(cmd.get("key").is_none());
     }
 
     #[test]
-    fn test_diff_url_basic() {
-        let cmd = parse_command(
-            &args("diff url https://a.com https://b.com"),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "diff_url");
-        assert_eq!(cmd["url1"], "https://a.com");
-        assert_eq!(cmd["url2"], "https://b.com");
+    fn test_press_sequence_trims_whitespace() {
+        let cmd = parse_command(&press_args(&["press", "  Enter  "]), &default_flags()).unwrap();
+        assert_eq!(cmd["key"], "Enter");
     }
 
     #[test]
-    fn test_diff_url_with_screenshot_full() {
+    fn test_press_sequence_with_modifiers() {
         let cmd = parse_command(
-            &args("diff url https://a.com https://b.com --screenshot --full"),
+            &press_args(&["press", "Control+a Backspace"]),
             &default_flags(),
         )
         .unwrap();
-        assert_eq!(cmd["action"], "diff_url");
-        assert_eq!(cmd["screenshot"], true);
-        assert_eq!(cmd["fullPage"], true);
+        assert_eq!(cmd["keys"], json!(["Control+a", "Backspace"]));
     }
 
     #[test]
-    fn test_diff_url_with_wait_until() {
+    fn test_press_sequence_invalid_item() {
+        let err =
+            parse_command(&press_args(&["press", "Tab $ Enter"]), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.to_string().contains('$'));
+    }
+
+    #[test]
+    fn test_press_with_selector() {
+        let cmd = parse_command(&args("press --selector #input Enter"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], "#input");
+        assert_eq!(cmd["key"], "Enter");
+    }
+
+    #[test]
+    fn test_press_sequence_with_selector() {
         let cmd = parse_command(
-            &args("diff url https://a.com https://b.com --wait-until networkidle"),
+            &press_args(&["press", "Tab Enter", "--selector", "@e2"]),
             &default_flags(),
         )
         .unwrap();
-        assert_eq!(cmd["action"], "diff_url");
-        assert_eq!(cmd["waitUntil"], "networkidle");
+        assert_eq!(cmd["selector"], "@e2");
+        assert_eq!(cmd["keys"], json!(["Tab", "Enter"]));
     }
 
     #[test]
-    fn test_diff_url_global_full_flag() {
-        let mut flags = default_flags();
-        flags.full = true;
-        let cmd =
-            parse_command(&args("diff url https://a.com https://b.com"), &flags).unwrap();
-        assert_eq!(cmd["fullPage"], true);
+    fn test_press_selector_missing_value() {
+        assert!(matches!(
+            parse_command(&args("press Enter --selector"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
     }
 
     #[test]
-    fn test_diff_missing_subcommand() {
-        let result = parse_command(&args("diff"), &default_flags());
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
-        ));
+    fn test_press_missing_key() {
+        for input in ["press", "press --selector #input"] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::MissingArguments { .. })
+            ));
+        }
     }
 
+    // === Find --count / --timeout ===
+
     #[test]
-    fn test_diff_unknown_subcommand() {
-        let result = parse_command(&args("diff invalid"), &default_flags());
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::UnknownSubcommand { .. }
-        ));
+    fn test_find_count_defaults_subaction() {
+        let cmd = parse_command(&args("find role row --count 5"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "getbyrole");
+        assert_eq!(cmd["role"], "row");
+        assert_eq!(cmd["subaction"], "count");
+        assert_eq!(cmd["expectedCount"], 5);
     }
 
     #[test]
-    fn test_diff_snapshot_baseline_missing_value() {
-        let result = parse_command(&args("diff snapshot --baseline"), &default_flags());
-        assert!(result.is_err());
+    fn test_find_count_rejects_other_subaction() {
         assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
+            parse_command(&args("find text Item hover --count 1"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
         ));
     }
 
     #[test]
-    fn test_diff_snapshot_selector_missing_value() {
-        let result = parse_command(&args("diff snapshot --selector"), &default_flags());
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
-        ));
+    fn test_find_count_explicit_count_subaction() {
+        let cmd = parse_command(&args("find text Item count --count 1"), &default_flags()).unwrap();
+        assert_eq!(cmd["subaction"], "count");
+        assert_eq!(cmd["expectedCount"], 1);
     }
 
     #[test]
-    fn test_diff_snapshot_depth_missing_value() {
-        let result = parse_command(&args("diff snapshot --depth"), &default_flags());
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
-        ));
+    fn test_find_count_before_value() {
+        let cmd = parse_command(&args("find --count 0 testid banner"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "getbytestid");
+        assert_eq!(cmd["testId"], "banner");
+        assert_eq!(cmd["expectedCount"], 0);
     }
 
     #[test]
-    fn test_diff_screenshot_threshold_missing_value() {
-        let result = parse_command(
-            &args("diff screenshot --baseline b.png --threshold"),
-            &default_flags(),
-        );
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
-        ));
+    fn test_find_nth_count() {
+        let cmd = parse_command(&args("find nth 0 li --count 1"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "nth");
+        assert_eq!(cmd["subaction"], "count");
+        assert_eq!(cmd["expectedCount"], 1);
     }
 
     #[test]
-    fn test_diff_screenshot_output_missing_value() {
-        let result = parse_command(
-            &args("diff screenshot --baseline b.png --output"),
+    fn test_find_filter_count_adds_subaction() {
+        let cmd = parse_command(
+            &args("find filter li --has-text Apple --count 2"),
             &default_flags(),
-        );
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
-        ));
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "findfilter");
+        assert_eq!(cmd["subaction"], "count");
+        assert_eq!(cmd["expectedCount"], 2);
     }
 
     #[test]
-    fn test_diff_url_wait_until_missing_value() {
-        let result = parse_command(
-            &args("diff url https://a.com https://b.com --wait-until"),
-            &default_flags(),
-        );
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
-        ));
+    fn test_find_count_invalid() {
+        for input in ["find role row --count -1", "find role row --count many"] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::InvalidValue { .. })
+            ));
+        }
     }
 
     #[test]
-    fn test_diff_snapshot_unexpected_arg() {
-        let result = parse_command(&args("diff snapshot foo"), &default_flags());
-        assert!(result.is_err());
+    fn test_find_count_missing_value() {
         assert!(matches!(
-            result.unwrap_err(),
-            ParseError::InvalidValue { .. }
+            parse_command(&args("find role row --count"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
         ));
     }
 
     #[test]
-    fn test_diff_screenshot_unexpected_arg() {
-        let result = parse_command(
-            &args("diff screenshot --baseline b.png unexpected"),
-            &default_flags(),
-        );
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::InvalidValue { .. }
-        ));
+    fn test_find_timeout() {
+        let input = args("find text Submit click --timeout 2500");
+        let mut flags = default_flags();
+        flags.timeout = Some("2500".to_string());
+        let cmd = parse_command(&crate::flags::clean_args(&input), &flags).unwrap();
+        assert_eq!(cmd["subaction"], "click");
+        assert_eq!(cmd["timeout"], 2500);
+        assert!(cmd.get("expectedCount").is_none());
     }
 
     #[test]
-    fn test_diff_url_unexpected_arg() {
-        let result = parse_command(
-            &args("diff url https://a.com https://b.com extra"),
-            &default_flags(),
-        );
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::InvalidValue { .. }
-        ));
+    fn test_find_without_count_defaults_to_click() {
+        let cmd = parse_command(&args("find role button"), &default_flags()).unwrap();
+        assert_eq!(cmd["subaction"], "click");
+        assert!(cmd.get("expectedCount").is_none());
     }
 
+    // === Get all-text / nth text ===
+
     #[test]
-    fn test_diff_snapshot_unknown_flag() {
-        let result = parse_command(&args("diff snapshot --invalid"), &default_flags());
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::InvalidValue { .. }
-        ));
+    fn test_get_all_text() {
+        let cmd = parse_command(&args("get all-text .item"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "alltext");
+        assert_eq!(cmd["selector"], ".item");
     }
 
     #[test]
-    fn test_diff_url_missing_urls() {
-        let result = parse_command(&args("diff url"), &default_flags());
-        assert!(result.is_err());
+    fn test_get_all_text_missing_selector() {
         assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
+            parse_command(&args("get all-text"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
         ));
     }
 
     #[test]
-    fn test_diff_url_missing_second_url() {
-        let result = parse_command(&args("diff url https://a.com"), &default_flags());
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
-        ));
+    fn test_get_nth_text() {
+        let cmd = parse_command(&args("get nth 2 text .item"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "nthtext");
+        assert_eq!(cmd["selector"], ".item");
+        assert_eq!(cmd["index"], 2);
     }
 
     #[test]
-    fn test_diff_snapshot_depth_invalid_value() {
-        let result = parse_command(&args("diff snapshot --depth abc"), &default_flags());
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::InvalidValue { .. }
-        ));
+    fn test_get_nth_text_negative_index() {
+        let cmd = parse_command(&args("get nth -1 text li"), &default_flags()).unwrap();
+        assert_eq!(cmd["index"], -1);
+        let cmd = parse_command(&args("get nth -2 text li"), &default_flags()).unwrap();
+        assert_eq!(cmd["index"], -2);
     }
 
     #[test]
-    fn test_diff_screenshot_threshold_invalid_value() {
-        let result = parse_command(
-            &args("diff screenshot --baseline b.png --threshold abc"),
-            &default_flags(),
-        );
-        assert!(result.is_err());
+    fn test_get_nth_invalid_index() {
         assert!(matches!(
-            result.unwrap_err(),
-            ParseError::InvalidValue { .. }
+            parse_command(&args("get nth second text li"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
         ));
     }
 
     #[test]
-    fn test_diff_screenshot_threshold_out_of_range() {
-        let result = parse_command(
-            &args("diff screenshot --baseline b.png --threshold 1.5"),
-            &default_flags(),
-        );
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::InvalidValue { .. }
-        ));
+    fn test_get_nth_unknown_property() {
+        let err = parse_command(&args("get nth 0 html li"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::UnknownSubcommand { .. }));
     }
 
     #[test]
-    fn test_diff_screenshot_threshold_negative() {
-        let result = parse_command(
-            &args("diff screenshot --baseline b.png --threshold -0.5"),
-            &default_flags(),
-        );
-        assert!(result.is_err());
+    fn test_get_nth_missing_arguments() {
+        for input in ["get nth", "get nth 1", "get nth 1 text"] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::MissingArguments { .. })
+            ));
+        }
     }
 
     #[test]
-    fn test_diff_url_with_selector() {
-        let cmd = parse_command(
-            &args("diff url https://a.com https://b.com --selector #main"),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "diff_url");
-        assert_eq!(cmd["selector"], "#main");
+    fn test_get_unknown_lists_new_subcommands() {
+        let err = parse_command(&args("get everything"), &default_flags()).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("all-text"));
+        assert!(message.contains("nth"));
     }
 
     #[test]
-    fn test_diff_url_with_compact_depth() {
-        let cmd = parse_command(
-            &args("diff url https://a.com https://b.com --compact --depth 3"),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "diff_url");
-        assert_eq!(cmd["compact"], true);
-        assert_eq!(cmd["maxDepth"], 3);
+    fn test_network_requests_follow() {
+        let cmd = parse_command(&args("network requests --follow"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "requests");
+        assert_eq!(cmd["follow"], true);
     }
 
     #[test]
-    fn test_diff_url_with_short_snapshot_flags() {
+    fn test_network_requests_follow_with_filters() {
         let cmd = parse_command(
-            &args("diff url https://a.com https://b.com -s .content -c -d 2"),
+            &args("network requests --url **/api/* --follow --method post"),
             &default_flags(),
         )
         .unwrap();
-        assert_eq!(cmd["action"], "diff_url");
-        assert_eq!(cmd["selector"], ".content");
-        assert_eq!(cmd["compact"], true);
-        assert_eq!(cmd["maxDepth"], 2);
+        assert_eq!(cmd["follow"], true);
+        assert_eq!(cmd["url"], "**/api/*");
+        assert_eq!(cmd["method"], "POST");
     }
 
     #[test]
-    fn test_diff_url_depth_invalid_value() {
-        let result = parse_command(
-            &args("diff url https://a.com https://b.com --depth abc"),
-            &default_flags(),
-        );
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::InvalidValue { .. }
-        ));
+    fn test_network_requests_without_follow() {
+        let cmd = parse_command(&args("network requests"), &default_flags()).unwrap();
+        assert!(cmd.get("follow").is_none());
     }
 
     #[test]
-    fn test_diff_snapshot_depth_negative_value() {
-        let result = parse_command(&args("diff snapshot --depth -1"), &default_flags());
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::InvalidValue { .. }
-        ));
+    fn test_console_follow() {
+        let cmd = parse_command(&args("console --follow"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "console");
+        assert_eq!(cmd["follow"], true);
     }
 
     #[test]
-    fn test_diff_url_depth_negative_value() {
-        let result = parse_command(
-            &args("diff url https://a.com https://b.com --depth -1"),
-            &default_flags(),
-        );
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::InvalidValue { .. }
-        ));
+    fn test_console_follow_with_filter() {
+        let cmd =
+            parse_command(&args("console --follow --filter error"), &default_flags()).unwrap();
+        assert_eq!(cmd["follow"], true);
+        assert_eq!(cmd["filter"], "error");
     }
 
     #[test]
-    fn test_diff_url_selector_missing_value() {
-        let result = parse_command(
-            &args("diff url https://a.com https://b.com --selector"),
-            &default_flags(),
-        );
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
-        ));
+    fn test_errors_follow_with_type() {
+        let cmd =
+            parse_command(&args("errors --type TypeError --follow"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "errors");
+        assert_eq!(cmd["follow"], true);
+        assert_eq!(cmd["errorType"], "TypeError");
     }
 
-    // === Scroll Tests ===
+    #[test]
+    fn test_console_errors_without_follow() {
+        for input in ["console", "errors"] {
+            let cmd = parse_command(&args(input), &default_flags()).unwrap();
+            assert!(cmd.get("follow").is_none(), "{}", input);
+        }
+    }
+
+    // === Get aria ===
 
     #[test]
-    fn test_scroll_defaults() {
-        let cmd = parse_command(&args("scroll"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "scroll");
-        assert_eq!(cmd["direction"], "down");
-        assert_eq!(cmd["amount"], 300);
-        assert!(cmd.get("selector").is_none());
+    fn test_get_aria_attribute() {
+        let cmd = parse_command(&args("get aria #btn aria-expanded"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "getaria");
+        assert_eq!(cmd["selector"], "#btn");
+        assert_eq!(cmd["attribute"], "aria-expanded");
     }
 
     #[test]
-    fn test_scroll_direction_and_amount() {
-        let cmd = parse_command(&args("scroll up 200"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "scroll");
-        assert_eq!(cmd["direction"], "up");
-        assert_eq!(cmd["amount"], 200);
+    fn test_get_aria_all() {
+        let cmd = parse_command(&args("get aria @e2"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "getaria");
+        assert_eq!(cmd["selector"], "@e2");
+        assert!(cmd.get("attribute").is_none());
     }
 
     #[test]
-    fn test_scroll_with_selector() {
-        let cmd = parse_command(
-            &args("scroll down 500 --selector div.scroll-container"),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "scroll");
-        assert_eq!(cmd["direction"], "down");
-        assert_eq!(cmd["amount"], 500);
-        assert_eq!(cmd["selector"], "div.scroll-container");
+    fn test_get_aria_role() {
+        let cmd = parse_command(&args("get aria nav role"), &default_flags()).unwrap();
+        assert_eq!(cmd["attribute"], "role");
     }
 
     #[test]
-    fn test_scroll_with_selector_short_flag() {
-        let cmd = parse_command(
-            &args("scroll left 100 -s .sidebar"),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "scroll");
-        assert_eq!(cmd["direction"], "left");
-        assert_eq!(cmd["amount"], 100);
-        assert_eq!(cmd["selector"], ".sidebar");
+    fn test_get_aria_rejects_non_aria_attribute() {
+        let err = parse_command(&args("get aria #btn href"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.to_string().contains("href"));
     }
 
     #[test]
-    fn test_scroll_selector_before_positional() {
-        let cmd = parse_command(
-            &args("scroll --selector .panel down 400"),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "scroll");
-        assert_eq!(cmd["direction"], "down");
-        assert_eq!(cmd["amount"], 400);
-        assert_eq!(cmd["selector"], ".panel");
+    fn test_get_aria_missing_selector() {
+        assert!(matches!(
+            parse_command(&args("get aria"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
     }
 
     #[test]
-    fn test_scroll_selector_only() {
-        let cmd = parse_command(
-            &args("scroll --selector .content"),
-            &default_flags(),
-        )
-        .unwrap();
-        assert_eq!(cmd["action"], "scroll");
-        assert_eq!(cmd["direction"], "down");
-        assert_eq!(cmd["amount"], 300);
-        assert_eq!(cmd["selector"], ".content");
+    fn test_get_count() {
+        let cmd = parse_command(&args("get count li.item"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "count");
+        assert_eq!(cmd["selector"], "li.item");
+        assert!(cmd.get("failOnZero").is_none());
     }
 
     #[test]
-    fn test_scroll_selector_missing_value() {
-        let result = parse_command(&args("scroll down 500 --selector"), &default_flags());
-        assert!(result.is_err());
+    fn test_get_count_fail_on_zero() {
+        let cmd =
+            parse_command(&args("get count .missing --fail-on-zero"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], ".missing");
+        assert_eq!(cmd["failOnZero"], true);
+    }
+
+    #[test]
+    fn test_get_count_fail_on_zero_before_selector() {
+        let cmd =
+            parse_command(&args("get count --fail-on-zero .missing"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], ".missing");
+        assert_eq!(cmd["failOnZero"], true);
+    }
+
+    #[test]
+    fn test_get_count_fail_on_zero_missing_selector() {
         assert!(matches!(
-            result.unwrap_err(),
-            ParseError::MissingArguments { .. }
+            parse_command(&args("get count --fail-on-zero"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
         ));
     }
 }
@@ -0,0 +1,489 @@
+//! Shell completion scripts for `agent-browser completions <shell>`.
+//!
+//! The scripts are generated from the tables below so every shell completes
+//! the same commands, subcommands and global flags.
+
+use crate::flags::{GLOBAL_BOOL_FLAGS, GLOBAL_FLAGS_WITH_VALUE};
+
+/// Top-level commands and their subcommands (empty when a command takes
+/// free-form arguments such as a selector or URL).
+const COMMANDS: &[(&str, &[&str])] = &[
+    ("open", &[]),
+    ("back", &[]),
+    ("forward", &[]),
+    ("reload", &[]),
+    ("click", &[]),
+    ("dblclick", &[]),
+    ("fill", &[]),
+    ("type", &[]),
+    ("hover", &[]),
+    ("focus", &[]),
+    ("check", &[]),
+    ("uncheck", &[]),
+    ("select", &[]),
+    ("drag", &[]),
+    ("upload", &[]),
+    ("download", &[]),
+    ("press", &[]),
+    ("keydown", &[]),
+    ("keyup", &[]),
+    ("keyboard", &["type", "inserttext"]),
+    ("scroll", &["up", "down", "left", "right"]),
+    ("scrollintoview", &[]),
+    ("wait", &[]),
+    ("screenshot", &[]),
+    ("pdf", &[]),
+    ("snapshot", &[]),
+    ("eval", &[]),
+    ("close", &[]),
+    ("connect", &[]),
+    (
+        "get",
+        &[
+            "text", "html", "value", "attr", "url", "title", "count", "box", "styles",
+        ],
+    ),
+    (
+        "is",
+        &[
+            "visible", "enabled", "checked", "hidden", "editable", "disabled", "focused", "empty",
+        ],
+    ),
+    ("assert", &["visible", "text", "count", "url", "title"]),
+    (
+        "find",
+        &[
+            "role",
+            "text",
+            "label",
+            "placeholder",
+            "alt",
+            "title",
+            "testid",
+            "first",
+            "last",
+            "nth",
+        ],
+    ),
+    (
+        "mouse",
+        &[
+            "move",
+            "down",
+            "up",
+            "wheel",
+            "click",
+            "dblclick",
+            "rightclick",
+        ],
+    ),
+    (
+        "set",
+        &[
+            "viewport",
+            "device",
+            "geo",
+            "offline",
+            "headers",
+            "credentials",
+            "media",
+            "timezone",
+            "locale",
+            "permissions",
+            "timeout",
+        ],
+    ),
+    (
+        "network",
+        &["route", "unroute", "requests", "throttle", "har"],
+    ),
+    ("storage", &["local", "session"]),
+    (
+        "cookies",
+        &["get", "set", "clear", "delete", "import", "export"],
+    ),
+    (
+        "tab",
+        &["new", "list", "close", "switch", "info", "duplicate"],
+    ),
+    ("window", &["new", "list", "switch", "close", "resize"]),
+    ("frame", &["main", "list"]),
+    ("dialog", &["accept", "dismiss", "auto", "get"]),
+    ("accessibility", &["snapshot", "check"]),
+    ("clipboard", &["copy", "paste", "get"]),
+    ("inject", &["script", "style"]),
+    ("performance", &["metrics", "mark", "measure"]),
+    ("mock", &["load", "clear", "status"]),
+    ("run", &[]),
+    ("foreach", &[]),
+    ("validate", &[]),
+    ("trace", &["start", "stop", "open", "status"]),
+    ("profiler", &["start", "stop", "snapshot", "status"]),
+    ("record", &["start", "stop", "restart"]),
+    ("console", &[]),
+    ("errors", &[]),
+    ("highlight", &["all", "clear"]),
+    (
+        "state",
+        &[
+            "save", "load", "list", "clear", "show", "clean", "rename", "diff",
+        ],
+    ),
+    ("diff", &["snapshot", "screenshot", "url"]),
+    ("tap", &[]),
+    ("swipe", &["up", "down", "left", "right"]),
+    ("pinch", &["in", "out"]),
+    ("device", &["list"]),
+    (
+        "session",
+        &["list", "info", "create", "switch", "kill", "delete"],
+    ),
+    ("install", &[]),
+    ("version", &[]),
+    ("schema", &[]),
+    ("completions", &["bash", "zsh", "fish", "powershell"]),
+    ("config", &["validate"]),
+    ("daemon", &["start", "stop", "status", "restart", "logs"]),
+];
+
+/// Global flags whose value is a file or directory path.
+const PATH_FLAGS: &[&str] = &[
+    "--executable-path",
+    "--extension",
+    "--profile",
+    "--state",
+    "--config",
+    "--download-path",
+    "--output-file",
+    "--screenshot-dir",
+];
+
+/// Flags accepted everywhere besides the global flag tables.
+const EXTRA_FLAGS: &[&str] = &["--help", "--version"];
+
+fn all_flags() -> Vec<&'static str> {
+    GLOBAL_BOOL_FLAGS
+        .iter()
+        .chain(GLOBAL_FLAGS_WITH_VALUE)
+        .chain(EXTRA_FLAGS)
+        .copied()
+        .collect()
+}
+
+fn command_names() -> Vec<&'static str> {
+    COMMANDS.iter().map(|(name, _)| *name).collect()
+}
+
+/// Returns the completion script for `shell`, or None for an unsupported shell.
+pub fn completion_script(shell: &str) -> Option<String> {
+    match shell {
+        "bash" => Some(bash_script()),
+        "zsh" => Some(zsh_script()),
+        "fish" => Some(fish_script()),
+        "powershell" => Some(powershell_script()),
+        _ => None,
+    }
+}
+
+fn bash_script() -> String {
+    let mut subcommands = String::new();
+    for (name, subs) in COMMANDS.iter().filter(|(_, subs)| !subs.is_empty()) {
+        subcommands.push_str(&format!(
+            "        {}) [[ -z \"$sub\" ]] && COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;\n",
+            name,
+            subs.join(" ")
+        ));
+    }
+
+    format!(
+        r#"# bash completion for agent-browser
+# Load with: source <(agent-browser completions bash)
+
+_agent_browser() {{
+    local cur prev cmd="" sub="" i word
+    cur="${{COMP_WORDS[COMP_CWORD]}}"
+    prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+
+    case "$prev" in
+        {path_flags})
+            COMPREPLY=($(compgen -f -- "$cur"))
+            return ;;
+        --color-scheme)
+            COMPREPLY=($(compgen -W "dark light no-preference" -- "$cur"))
+            return ;;
+        {value_flags})
+            return ;;
+    esac
+
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        word="${{COMP_WORDS[i]}}"
+        case "$word" in
+            {value_flags_all}) ((i++)) ;;
+            -*) ;;
+            *)
+                if [[ -z "$cmd" ]]; then
+                    cmd="$word"
+                elif [[ -z "$sub" ]]; then
+                    sub="$word"
+                fi ;;
+        esac
+    done
+
+    if [[ "$cur" == -* ]]; then
+        COMPREPLY=($(compgen -W "{flags}" -- "$cur"))
+        return
+    fi
+
+    if [[ -z "$cmd" ]]; then
+        COMPREPLY=($(compgen -W "{commands}" -- "$cur"))
+        return
+    fi
+
+    case "$cmd" in
+{subcommands}    esac
+}}
+
+complete -o default -F _agent_browser agent-browser
+"#,
+        path_flags = PATH_FLAGS.join("|"),
+        value_flags = non_path_value_flags().join("|"),
+        value_flags_all = GLOBAL_FLAGS_WITH_VALUE.join("|"),
+        flags = all_flags().join(" "),
+        commands = command_names().join(" "),
+        subcommands = subcommands,
+    )
+}
+
+fn zsh_script() -> String {
+    let mut subcommands = String::new();
+    for (name, subs) in COMMANDS.iter().filter(|(_, subs)| !subs.is_empty()) {
+        subcommands.push_str(&format!(
+            "        {}) [[ -z $sub ]] && compadd -- {} && return ;;\n",
+            name,
+            subs.join(" ")
+        ));
+    }
+
+    format!(
+        r#"#compdef agent-browser
+# zsh completion for agent-browser
+# Load with: source <(agent-browser completions zsh)
+
+_agent_browser() {{
+    local cmd="" sub="" i word
+    local prev=${{words[CURRENT-1]}}
+
+    case $prev in
+        {path_flags})
+            _files
+            return ;;
+        --color-scheme)
+            compadd -- dark light no-preference
+            return ;;
+        {value_flags})
+            return ;;
+    esac
+
+    for ((i = 2; i < CURRENT; i++)); do
+        word=${{words[i]}}
+        case $word in
+            {value_flags_all}) ((i++)) ;;
+            -*) ;;
+            *)
+                if [[ -z $cmd ]]; then
+                    cmd=$word
+                elif [[ -z $sub ]]; then
+                    sub=$word
+                fi ;;
+        esac
+    done
+
+    if [[ ${{words[CURRENT]}} == -* ]]; then
+        compadd -- {flags}
+        return
+    fi
+
+    if [[ -z $cmd ]]; then
+        compadd -- {commands}
+        return
+    fi
+
+    case $cmd in
+{subcommands}    esac
+
+    _files
+}}
+
+compdef _agent_browser agent-browser
+"#,
+        path_flags = PATH_FLAGS.join("|"),
+        value_flags = non_path_value_flags().join("|"),
+        value_flags_all = GLOBAL_FLAGS_WITH_VALUE.join("|"),
+        flags = all_flags().join(" "),
+        commands = command_names().join(" "),
+        subcommands = subcommands,
+    )
+}
+
+fn fish_script() -> String {
+    let mut out = String::from(
+        "# fish completion for agent-browser\n\
+         # Load with: agent-browser completions fish | source\n\n",
+    );
+
+    out.push_str(&format!(
+        "complete -c agent-browser -n __fish_use_subcommand -f -a \"{}\"\n",
+        command_names().join(" ")
+    ));
+    for (name, subs) in COMMANDS.iter().filter(|(_, subs)| !subs.is_empty()) {
+        out.push_str(&format!(
+            "complete -c agent-browser -n \"__fish_seen_subcommand_from {name}; and not __fish_seen_subcommand_from {subs}\" -f -a \"{subs}\"\n",
+            name = name,
+            subs = subs.join(" ")
+        ));
+    }
+
+    out.push('\n');
+    for flag in all_flags() {
+        let option = match flag.strip_prefix("--") {
+            Some(long) => format!("-l {}", long),
+            None => format!("-s {}", flag.trim_start_matches('-')),
+        };
+        let value = if PATH_FLAGS.contains(&flag) {
+            " -r -F"
+        } else if flag == "--color-scheme" {
+            " -x -a \"dark light no-preference\""
+        } else if GLOBAL_FLAGS_WITH_VALUE.contains(&flag) {
+            " -x"
+        } else {
+            ""
+        };
+        out.push_str(&format!("complete -c agent-browser {}{}\n", option, value));
+    }
+    out
+}
+
+fn powershell_script() -> String {
+    let mut table = String::new();
+    for (name, subs) in COMMANDS {
+        let subs: Vec<String> = subs.iter().map(|s| format!("'{}'", s)).collect();
+        table.push_str(&format!("        '{}' = @({})\n", name, subs.join(", ")));
+    }
+    let flags: Vec<String> = all_flags().iter().map(|f| format!("'{}'", f)).collect();
+    let value_flags: Vec<String> = GLOBAL_FLAGS_WITH_VALUE
+        .iter()
+        .map(|f| format!("'{}'", f))
+        .collect();
+
+    format!(
+        r#"# PowerShell completion for agent-browser
+# Load with: agent-browser completions powershell | Out-String | Invoke-Expression
+
+Register-ArgumentCompleter -Native -CommandName agent-browser -ScriptBlock {{
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $commands = [ordered]@{{
+{table}    }}
+    $flags = @({flags})
+    $valueFlags = @({value_flags})
+
+    $elements = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object {{ $_.ToString() }})
+    if ($wordToComplete -and $elements.Count -gt 0) {{
+        $elements = @($elements | Select-Object -First ($elements.Count - 1))
+    }}
+
+    $positional = @()
+    for ($i = 0; $i -lt $elements.Count; $i++) {{
+        if ($valueFlags -contains $elements[$i]) {{ $i++; continue }}
+        if ($elements[$i].StartsWith('-')) {{ continue }}
+        $positional += $elements[$i]
+    }}
+
+    if ($wordToComplete.StartsWith('-')) {{
+        $candidates = $flags
+    }} elseif ($positional.Count -eq 0) {{
+        $candidates = $commands.Keys
+    }} elseif ($positional.Count -eq 1 -and $commands.Contains($positional[0])) {{
+        $candidates = $commands[$positional[0]]
+    }} else {{
+        return
+    }}
+
+    $candidates | Where-Object {{ $_ -like "$wordToComplete*" }} | ForEach-Object {{
+        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+    }}
+}}
+"#,
+        table = table,
+        flags = flags.join(", "),
+        value_flags = value_flags.join(", "),
+    )
+}
+
+fn non_path_value_flags() -> Vec<&'static str> {
+    GLOBAL_FLAGS_WITH_VALUE
+        .iter()
+        .copied()
+        .filter(|f| !PATH_FLAGS.contains(f) && *f != "--color-scheme")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bash_script_registers_completion() {
+        let script = completion_script("bash").unwrap();
+        assert!(script.contains("complete -o default -F _agent_browser agent-browser"));
+        assert!(script.contains(
+            "tab) [[ -z \"$sub\" ]] && COMPREPLY=($(compgen -W \"new list close switch info duplicate\""
+        ));
+        assert!(script.contains("--session"));
+    }
+
+    #[test]
+    fn test_zsh_script_registers_completion() {
+        let script = completion_script("zsh").unwrap();
+        assert!(script.starts_with("#compdef agent-browser"));
+        assert!(script.contains("compdef _agent_browser agent-browser"));
+        assert!(script.contains("trace) [[ -z $sub ]] && compadd -- start stop open status"));
+    }
+
+    #[test]
+    fn test_fish_script_completes_commands_and_flags() {
+        let script = completion_script("fish").unwrap();
+        assert!(script.contains("complete -c agent-browser -n __fish_use_subcommand -f -a"));
+        assert!(script.contains("__fish_seen_subcommand_from cookies;"));
+        assert!(script.contains("complete -c agent-browser -l json\n"));
+        assert!(script.contains("complete -c agent-browser -l config -r -F\n"));
+        assert!(script.contains("complete -c agent-browser -s p -x\n"));
+    }
+
+    #[test]
+    fn test_powershell_script_registers_completer() {
+        let script = completion_script("powershell").unwrap();
+        assert!(script.contains("Register-ArgumentCompleter -Native -CommandName agent-browser"));
+        assert!(script.contains("'profiler' = @('start', 'stop', 'snapshot', 'status')"));
+        assert!(script.contains("'open' = @()"));
+    }
+
+    #[test]
+    fn test_every_script_lists_all_commands_and_global_flags() {
+        for shell in ["bash", "zsh", "fish", "powershell"] {
+            let script = completion_script(shell).unwrap();
+            for (name, _) in COMMANDS {
+                assert!(script.contains(name), "{} missing command {}", shell, name);
+            }
+            for flag in GLOBAL_BOOL_FLAGS.iter().chain(GLOBAL_FLAGS_WITH_VALUE) {
+                let needle = flag.trim_start_matches('-');
+                assert!(script.contains(needle), "{} missing flag {}", shell, flag);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported_shell() {
+        assert!(completion_script("tcsh").is_none());
+    }
+}
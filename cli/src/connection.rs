@@ -6,8 +6,9 @@ use std::io::{BufRead, BufReader, Read, Write};
 use std::net::TcpStream;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[cfg(unix)]
 use std::os::unix::net::UnixStream;
@@ -21,6 +22,39 @@ pub struct Request {
     pub extra: Value,
 }
 
+/// Version of the JSON command protocol spoken between the CLI and the daemon.
+/// Bump when an action or response shape changes incompatibly.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// How long to wait on the daemon socket, in milliseconds. 0 waits forever.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timeouts {
+    /// Connecting to the daemon and writing the command.
+    pub connect_ms: u64,
+    /// Waiting for the daemon's response.
+    pub send_ms: u64,
+}
+
+/// Errors from an expired `Timeouts` deadline start with this.
+const TIMEOUT_ERROR_PREFIX: &str = "Timed out";
+
+/// True if `send_command` failed because a `Timeouts` deadline expired.
+pub fn is_timeout_error(error: &str) -> bool {
+    error.starts_with(TIMEOUT_ERROR_PREFIX)
+}
+
+/// True if `send_command` failed before the command reached the daemon
+/// (refused, socket missing, or the daemon is still starting), so sending it
+/// again cannot run it twice.
+pub fn is_connect_error(error: &str) -> bool {
+    error.starts_with("Failed to connect")
+        || (is_timeout_error(error) && error.contains("connecting to the daemon"))
+}
+
+fn timeout_duration(ms: u64) -> Option<Duration> {
+    (ms > 0).then(|| Duration::from_millis(ms))
+}
+
 #[derive(Deserialize, Serialize, Default)]
 pub struct Response {
     pub success: bool,
@@ -116,6 +150,11 @@ fn get_pid_path(session: &str) -> PathBuf {
     get_socket_dir().join(format!("{}.pid", session))
 }
 
+/// Path of the file that captures a session daemon's stderr.
+pub fn get_log_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.log", session))
+}
+
 /// Clean up stale socket and PID files for a session
 fn cleanup_stale_files(session: &str) {
     let pid_path = get_pid_path(session);
@@ -140,7 +179,7 @@ fn get_port_path(session: &str) -> PathBuf {
 }
 
 #[cfg(windows)]
-fn get_port_for_session(session: &str) -> u16 {
+pub fn get_port_for_session(session: &str) -> u16 {
     let mut hash: i32 = 0;
     for c in session.chars() {
         hash = ((hash << 5).wrapping_sub(hash)).wrapping_add(c as i32);
@@ -299,6 +338,13 @@ pub fn ensure_daemon(
         .find(|p| p.exists())
         .ok_or("Daemon not found. Set AGENT_BROWSER_HOME environment variable or run from project directory.")?;
 
+    // Capture the daemon's stderr so `agent-browser daemon logs` can show it
+    let daemon_stderr = || {
+        fs::File::create(get_log_path(session))
+            .map(Stdio::from)
+            .unwrap_or_else(|_| Stdio::null())
+    };
+
     // Spawn daemon as a fully detached background process
     #[cfg(unix)]
     {
@@ -380,7 +426,7 @@ pub fn ensure_daemon(
 
         cmd.stdin(Stdio::null())
             .stdout(Stdio::null())
-            .stderr(Stdio::null())
+            .stderr(daemon_stderr())
             .spawn()
             .map_err(|e| format!("Failed to start daemon: {}", e))?;
     }
@@ -463,7 +509,7 @@ pub fn ensure_daemon(
         cmd.creation_flags(CREATE_NEW_PROCESS_GROUP | DETACHED_PROCESS)
             .stdin(Stdio::null())
             .stdout(Stdio::null())
-            .stderr(Stdio::null())
+            .stderr(daemon_stderr())
             .spawn()
             .map_err(|e| format!("Failed to start daemon: {}", e))?;
     }
@@ -483,9 +529,12 @@ pub fn ensure_daemon(
     ))
 }
 
-fn connect(session: &str) -> Result<Connection, String> {
+fn connect(session: &str, timeouts: Timeouts) -> Result<Connection, String> {
     #[cfg(unix)]
     {
+        // Connecting to a local Unix socket never blocks, so the connect
+        // timeout only applies to writing the command (see send_command_once)
+        let _ = timeouts;
         let socket_path = get_socket_path(session);
         UnixStream::connect(&socket_path)
             .map(Connection::Unix)
@@ -494,13 +543,22 @@ fn connect(session: &str) -> Result<Connection, String> {
     #[cfg(windows)]
     {
         let port = get_port_for_session(session);
-        TcpStream::connect(format!("127.0.0.1:{}", port))
-            .map(Connection::Tcp)
-            .map_err(|e| format!("Failed to connect: {}", e))
+        let addr: std::net::SocketAddr = format!("127.0.0.1:{}", port).parse().unwrap();
+        let stream = match timeout_duration(timeouts.connect_ms) {
+            Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+            None => TcpStream::connect(addr),
+        };
+        stream.map(Connection::Tcp).map_err(|e| match e.kind() {
+            std::io::ErrorKind::TimedOut => format!(
+                "{} after {}ms connecting to the daemon",
+                TIMEOUT_ERROR_PREFIX, timeouts.connect_ms
+            ),
+            _ => format!("Failed to connect: {}", e),
+        })
     }
 }
 
-pub fn send_command(cmd: Value, session: &str) -> Result<Response, String> {
+pub fn send_command(cmd: Value, session: &str, timeouts: Timeouts) -> Result<Response, String> {
     // Retry logic for transient errors (EAGAIN/EWOULDBLOCK/connection issues)
     const MAX_RETRIES: u32 = 5;
     const RETRY_DELAY_MS: u64 = 200;
@@ -512,7 +570,7 @@ pub fn send_command(cmd: Value, session: &str) -> Result<Response, String> {
             thread::sleep(Duration::from_millis(RETRY_DELAY_MS * (attempt as u64)));
         }
 
-        match send_command_once(&cmd, session) {
+        match send_command_once(&cmd, session, timeouts) {
             Ok(response) => return Ok(response),
             Err(e) => {
                 if is_transient_error(&e) {
@@ -553,11 +611,15 @@ fn is_transient_error(error: &str) -> bool {
         || error.contains("os error 111") // Connection refused (Linux)
 }
 
-fn send_command_once(cmd: &Value, session: &str) -> Result<Response, String> {
-    let mut stream = connect(session)?;
+fn send_command_once(cmd: &Value, session: &str, timeouts: Timeouts) -> Result<Response, String> {
+    let mut stream = connect(session, timeouts)?;
 
-    stream.set_read_timeout(Some(Duration::from_secs(30))).ok();
-    stream.set_write_timeout(Some(Duration::from_secs(5))).ok();
+    stream
+        .set_read_timeout(timeout_duration(timeouts.send_ms))
+        .ok();
+    stream
+        .set_write_timeout(timeout_duration(timeouts.connect_ms))
+        .ok();
 
     let mut json_str = serde_json::to_string(cmd).map_err(|e| e.to_string())?;
     json_str.push('\n');
@@ -568,13 +630,103 @@ fn send_command_once(cmd: &Value, session: &str) -> Result<Response, String> {
 
     let mut reader = BufReader::new(stream);
     let mut response_line = String::new();
-    reader
-        .read_line(&mut response_line)
-        .map_err(|e| format!("Failed to read: {}", e))?;
+    let sent_at = Instant::now();
+    reader.read_line(&mut response_line).map_err(|e| {
+        // An expired read timeout surfaces as EAGAIN/WouldBlock on Unix, which
+        // is otherwise retried as transient; resending could repeat the command
+        if is_read_timeout(&e, sent_at.elapsed(), timeouts.send_ms) {
+            format!(
+                "{} after {}ms waiting for the daemon to respond",
+                TIMEOUT_ERROR_PREFIX, timeouts.send_ms
+            )
+        } else {
+            format!("Failed to read: {}", e)
+        }
+    })?;
 
     serde_json::from_str(&response_line).map_err(|e| format!("Invalid response: {}", e))
 }
 
+/// How often a streaming read wakes up to check whether it should stop.
+const STREAM_POLL_MS: u64 = 200;
+
+/// Sends a command the daemon answers with newline-delimited JSON events
+/// (`--follow`) and hands each event to `on_event` until the daemon closes
+/// the connection, `on_event` fails, or `stop` is set.
+pub fn stream_command(
+    cmd: &Value,
+    session: &str,
+    timeouts: Timeouts,
+    stop: &AtomicBool,
+    on_event: impl FnMut(Value) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut stream = connect(session, timeouts)?;
+
+    // Events may be minutes apart, so the send timeout does not apply; the
+    // short read timeout only lets the loop notice `stop`
+    stream
+        .set_read_timeout(Some(Duration::from_millis(STREAM_POLL_MS)))
+        .ok();
+    stream
+        .set_write_timeout(timeout_duration(timeouts.connect_ms))
+        .ok();
+
+    let mut json_str = serde_json::to_string(cmd).map_err(|e| e.to_string())?;
+    json_str.push('\n');
+    stream
+        .write_all(json_str.as_bytes())
+        .map_err(|e| format!("Failed to send: {}", e))?;
+
+    read_events(BufReader::new(stream), stop, on_event)
+}
+
+/// Reads newline-delimited JSON events from `reader`. Read timeouts and
+/// interrupted reads are retried, so a partial line is kept until it ends.
+pub fn read_events<R: BufRead>(
+    mut reader: R,
+    stop: &AtomicBool,
+    mut on_event: impl FnMut(Value) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut line = Vec::new();
+    while !stop.load(Ordering::SeqCst) {
+        let eof = match reader.read_until(b'\n', &mut line) {
+            Ok(n) => n == 0,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock
+                        | std::io::ErrorKind::TimedOut
+                        | std::io::ErrorKind::Interrupted
+                ) =>
+            {
+                continue
+            }
+            Err(e) => return Err(format!("Failed to read: {}", e)),
+        };
+        if eof || line.ends_with(b"\n") {
+            let text = String::from_utf8_lossy(&line);
+            if !text.trim().is_empty() {
+                let event = serde_json::from_str(text.trim())
+                    .map_err(|e| format!("Invalid event: {}", e))?;
+                on_event(event)?;
+            }
+            line.clear();
+        }
+        if eof {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// True if a read error is the send timeout expiring rather than a spurious EAGAIN.
+fn is_read_timeout(error: &std::io::Error, elapsed: Duration, send_ms: u64) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+    ) && timeout_duration(send_ms).is_some_and(|timeout| elapsed >= timeout)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -765,4 +917,123 @@ mod tests {
         assert!(!is_transient_error("Permission denied"));
         assert!(!is_transient_error("Daemon not found"));
     }
+
+    #[test]
+    fn test_get_log_path_in_socket_dir() {
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+
+        env::set_var("AGENT_BROWSER_SOCKET_DIR", "/custom/socket/path");
+
+        assert_eq!(
+            get_log_path("work"),
+            PathBuf::from("/custom/socket/path/work.log")
+        );
+    }
+
+    #[test]
+    fn test_is_timeout_error() {
+        assert!(is_timeout_error(
+            "Timed out after 60000ms waiting for the daemon to respond"
+        ));
+        assert!(!is_timeout_error("Failed to read: Connection reset"));
+        assert!(!is_transient_error(
+            "Timed out after 60000ms waiting for the daemon to respond"
+        ));
+    }
+
+    #[test]
+    fn test_is_connect_error() {
+        assert!(is_connect_error(
+            "Failed to connect: Connection refused (os error 111)"
+        ));
+        assert!(is_connect_error(
+            "Failed to connect: No such file or directory (os error 2) (after 5 retries - daemon may be busy or unresponsive)"
+        ));
+        assert!(is_connect_error(
+            "Timed out after 30000ms connecting to the daemon"
+        ));
+        assert!(!is_connect_error(
+            "Timed out after 60000ms waiting for the daemon to respond"
+        ));
+        assert!(!is_connect_error("Failed to read: Connection reset"));
+        assert!(!is_connect_error("Failed to send: Broken pipe"));
+    }
+
+    #[test]
+    fn test_timeout_duration_zero_disables() {
+        assert_eq!(timeout_duration(0), None);
+        assert_eq!(timeout_duration(1500), Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn test_is_read_timeout() {
+        let would_block = std::io::Error::from(std::io::ErrorKind::WouldBlock);
+        let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        assert!(is_read_timeout(
+            &would_block,
+            Duration::from_millis(1000),
+            1000
+        ));
+        // EAGAIN before the deadline is still treated as transient
+        assert!(!is_read_timeout(
+            &would_block,
+            Duration::from_millis(10),
+            1000
+        ));
+        assert!(!is_read_timeout(&would_block, Duration::from_secs(5), 0));
+        assert!(!is_read_timeout(&reset, Duration::from_secs(5), 1000));
+    }
+
+    #[test]
+    fn test_read_events_parses_each_line() {
+        let input = "{\"url\":\"https://a.test/\"}\n\n{\"url\":\"https://b.test/\"}";
+        let stop = AtomicBool::new(false);
+        let mut urls = Vec::new();
+        read_events(std::io::Cursor::new(input), &stop, |event| {
+            urls.push(event["url"].as_str().unwrap().to_string());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(urls, vec!["https://a.test/", "https://b.test/"]);
+    }
+
+    #[test]
+    fn test_read_events_stops_when_interrupted() {
+        let input = "{\"n\":1}\n{\"n\":2}\n{\"n\":3}\n";
+        let stop = AtomicBool::new(false);
+        let mut seen = Vec::new();
+        read_events(std::io::Cursor::new(input), &stop, |event| {
+            seen.push(event["n"].as_u64().unwrap());
+            // Simulates Ctrl-C arriving while the first event is printed
+            stop.store(true, Ordering::SeqCst);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(seen, vec![1]);
+    }
+
+    #[test]
+    fn test_read_events_already_interrupted_reads_nothing() {
+        let stop = AtomicBool::new(true);
+        let mut called = false;
+        read_events(std::io::Cursor::new("{\"n\":1}\n"), &stop, |_| {
+            called = true;
+            Ok(())
+        })
+        .unwrap();
+        assert!(!called);
+    }
+
+    #[test]
+    fn test_read_events_propagates_errors() {
+        let stop = AtomicBool::new(false);
+        let err = read_events(std::io::Cursor::new("not json\n"), &stop, |_| Ok(())).unwrap_err();
+        assert!(err.starts_with("Invalid event"));
+
+        let err = read_events(std::io::Cursor::new("{}\n{}\n"), &stop, |_| {
+            Err("boom".to_string())
+        })
+        .unwrap_err();
+        assert_eq!(err, "boom");
+    }
 }
@@ -1,5 +1,7 @@
 use crate::color;
+use crate::output::OutputFormat;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -8,6 +10,13 @@ const CONFIG_DIR: &str = ".agent-browser";
 const CONFIG_FILENAME: &str = "config.json";
 const PROJECT_CONFIG_FILENAME: &str = "agent-browser.json";
 
+/// Delay between `--retry` attempts when `--retry-delay` is not given.
+const DEFAULT_RETRY_DELAY_MS: u64 = 1000;
+const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 30_000;
+/// Daemon response timeout when neither --send-timeout nor the command's own
+/// `timeout` asks for longer.
+pub const DEFAULT_SEND_TIMEOUT_MS: u64 = 60_000;
+
 #[derive(Debug, Default, Deserialize)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Config {
@@ -35,6 +44,18 @@ pub struct Config {
     pub annotate: Option<bool>,
     pub color_scheme: Option<String>,
     pub download_path: Option<String>,
+    pub timeout: Option<u64>,
+    pub retry: Option<u32>,
+    pub retry_delay: Option<u64>,
+    pub dry_run: Option<bool>,
+    pub default_wait_until: Option<String>,
+    pub default_timeout: Option<u64>,
+    pub default_screenshot_format: Option<String>,
+    /// Command shortcuts: alias name to the CLI args it expands to
+    pub aliases: Option<HashMap<String, String>>,
+    /// Path of a base config whose values this file overrides, relative to
+    /// this file's directory.
+    pub extends: Option<String>,
 }
 
 impl Config {
@@ -70,14 +91,41 @@ impl Config {
             annotate: other.annotate.or(self.annotate),
             color_scheme: other.color_scheme.or(self.color_scheme),
             download_path: other.download_path.or(self.download_path),
+            timeout: other.timeout.or(self.timeout),
+            retry: other.retry.or(self.retry),
+            retry_delay: other.retry_delay.or(self.retry_delay),
+            dry_run: other.dry_run.or(self.dry_run),
+            default_wait_until: other.default_wait_until.or(self.default_wait_until),
+            default_timeout: other.default_timeout.or(self.default_timeout),
+            default_screenshot_format: other
+                .default_screenshot_format
+                .or(self.default_screenshot_format),
+            aliases: match (self.aliases, other.aliases) {
+                (Some(mut a), Some(b)) => {
+                    a.extend(b);
+                    Some(a)
+                }
+                (a, b) => b.or(a),
+            },
+            extends: other.extends.or(self.extends),
         }
     }
 }
 
+/// How many `extends` links are followed from one config file. Stops cycles
+/// such as two files extending each other.
+const MAX_EXTENDS_DEPTH: usize = 5;
+
 fn read_config_file(path: &Path) -> Option<Config> {
+    read_config_chain(path, 0)
+}
+
+/// Reads a config file and, if it has `extends`, the base config it names,
+/// with this file's values taking priority over the base.
+fn read_config_chain(path: &Path, depth: usize) -> Option<Config> {
     let content = fs::read_to_string(path).ok()?;
-    match serde_json::from_str::<Config>(&content) {
-        Ok(config) => Some(config),
+    let config = match serde_json::from_str::<Config>(&content) {
+        Ok(config) => config,
         Err(e) => {
             eprintln!(
                 "{} invalid config file {}: {}",
@@ -85,20 +133,153 @@ fn read_config_file(path: &Path) -> Option<Config> {
                 path.display(),
                 e
             );
-            None
+            return None;
         }
+    };
+    let Some(base) = config.extends.as_deref() else {
+        return Some(config);
+    };
+    if depth >= MAX_EXTENDS_DEPTH {
+        eprintln!(
+            "{} config extends chain deeper than {} at {}, ignoring further bases",
+            color::warning_indicator(),
+            MAX_EXTENDS_DEPTH,
+            path.display()
+        );
+        return Some(config);
+    }
+    let base_path = path.parent().unwrap_or(Path::new("")).join(base);
+    if !base_path.exists() {
+        eprintln!(
+            "{} config {} extends missing file {}",
+            color::warning_indicator(),
+            path.display(),
+            base_path.display()
+        );
+        return Some(config);
+    }
+    match read_config_chain(&base_path, depth + 1) {
+        Some(base_config) => Some(base_config.merge(config)),
+        None => Some(config),
     }
 }
 
 /// Check if a boolean environment variable is set to a truthy value.
 /// Returns false when unset, empty, or set to "0", "false", or "no" (case-insensitive).
 fn env_var_is_truthy(name: &str) -> bool {
+    value_is_truthy(env::var(name).ok().as_deref())
+}
+
+/// Whether an environment variable value enables a boolean setting.
+fn value_is_truthy(value: Option<&str>) -> bool {
+    match value {
+        Some(val) => !matches!(val.to_lowercase().as_str(), "0" | "false" | "no" | ""),
+        None => false,
+    }
+}
+
+/// Parses a numeric flag or environment variable value, naming its source in the error.
+fn parse_number<T: std::str::FromStr>(source: &str, value: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| {
+        format!(
+            "Invalid {} value: {}. Expected a non-negative integer",
+            source, value
+        )
+    })
+}
+
+/// Reads a numeric environment variable; unset or empty means None.
+fn env_number<T: std::str::FromStr>(name: &str) -> Result<Option<T>, String> {
     match env::var(name) {
-        Ok(val) => !matches!(val.to_lowercase().as_str(), "0" | "false" | "no" | ""),
-        Err(_) => false,
+        Ok(v) if !v.is_empty() => parse_number(name, &v).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Resolves a `--headers` value. `@path` (as in curl) reads the JSON from a
+/// file; any other value is returned unchanged.
+fn read_headers_arg(value: &str) -> Result<String, String> {
+    let Some(path) = value.strip_prefix('@') else {
+        return Ok(value.to_string());
+    };
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read headers file {}: {}", path, e))?;
+    serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&contents)
+        .map_err(|e| format!("Invalid JSON object in headers file {}: {}", path, e))?;
+    Ok(contents)
+}
+
+/// Load states the daemon accepts for navigation (`--wait-until`, `defaultWaitUntil`).
+pub const WAIT_UNTIL_STATES: &[&str] = &["load", "domcontentloaded", "networkidle"];
+
+/// Image formats the daemon can write screenshots in (`defaultScreenshotFormat`).
+const SCREENSHOT_FORMATS: &[&str] = &["png", "jpeg"];
+
+/// Checks a config string against its allowed values, naming the key in the error.
+fn validate_choice(key: &str, value: String, allowed: &[&str]) -> Result<String, String> {
+    if allowed.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(format!(
+            "Invalid {} in config: {}. Expected one of: {}",
+            key,
+            value,
+            allowed.join(", ")
+        ))
     }
 }
 
+/// Checks that a config timeout is a positive number of milliseconds.
+fn validate_timeout(key: &str, ms: u64) -> Result<u64, String> {
+    if ms > 0 {
+        Ok(ms)
+    } else {
+        Err(format!(
+            "Invalid {} in config: {}. Expected a positive number of milliseconds",
+            key, ms
+        ))
+    }
+}
+
+/// Parses a `--header` value in `Name: Value` or `Name:Value` form. The split
+/// is at the first colon, so values such as URLs may contain colons.
+fn parse_header_pair(value: &str) -> Result<(String, String), String> {
+    let (name, header_value) = value.split_once(':').ok_or_else(|| {
+        format!(
+            "Invalid --header '{}': expected \"Name: Value\" (e.g. --header \"Authorization: Bearer token\")",
+            value
+        )
+    })?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!(
+            "Invalid --header '{}': header name is empty",
+            value
+        ));
+    }
+    Ok((name.to_string(), header_value.trim().to_string()))
+}
+
+fn exit_invalid_value(message: String) -> ! {
+    eprintln!("{} {}", color::error_indicator(), message);
+    std::process::exit(1);
+}
+
+/// Default per-command timeout: AGENT_BROWSER_TIMEOUT wins over the config
+/// file's `timeout`. Kept as a string so `parse_command` validates it the same
+/// way as `--timeout`.
+fn default_timeout(env_value: Option<String>, config_value: Option<u64>) -> Option<String> {
+    env_value
+        .filter(|v| !v.is_empty())
+        .or_else(|| config_value.map(|ms| ms.to_string()))
+}
+
+/// Resolves the state file password from AGENT_BROWSER_STATE_PASSWORD, treating
+/// an empty value as unset.
+fn state_password(env_value: Option<String>) -> Option<String> {
+    env_value.filter(|p| !p.is_empty())
+}
+
 /// Parse an optional boolean value after a flag. Returns (value, consumed_next_arg).
 /// Recognizes "true" as true, "false" as false. Bare flag defaults to true.
 fn parse_bool_arg(args: &[String], i: usize) -> (bool, bool) {
@@ -120,6 +301,7 @@ fn extract_config_path(args: &[String]) -> Option<Option<String>> {
     const FLAGS_WITH_VALUE: &[&str] = &[
         "--session",
         "--headers",
+        "--header",
         "--executable-path",
         "--cdp",
         "--extension",
@@ -135,7 +317,16 @@ fn extract_config_path(args: &[String]) -> Option<Option<String>> {
         "--session-name",
         "--color-scheme",
         "--download-path",
+        "--timeout",
+        "--retry",
+        "--retry-delay",
+        "--connect-timeout",
+        "--send-timeout",
+        "--output-file",
+        "--screenshot-dir",
+        "--format",
     ];
+    let args = &split_flag_values(args);
     let mut i = 0;
     while i < args.len() {
         if args[i] == "--config" {
@@ -182,13 +373,93 @@ pub fn load_config(args: &[String]) -> Result<Config, String> {
     })
 }
 
+/// Config files `load_config` would read for these args: the explicit
+/// `--config` / AGENT_BROWSER_CONFIG file, or whichever of the user and
+/// project files exist.
+pub fn config_file_paths(args: &[String]) -> Vec<PathBuf> {
+    let explicit = extract_config_path(args)
+        .flatten()
+        .or_else(|| env::var("AGENT_BROWSER_CONFIG").ok());
+    if let Some(path) = explicit {
+        return vec![PathBuf::from(path)];
+    }
+    dirs::home_dir()
+        .map(|d| d.join(CONFIG_DIR).join(CONFIG_FILENAME))
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from(PROJECT_CONFIG_FILENAME)))
+        .filter(|p| p.exists())
+        .collect()
+}
+
+/// Every key `Config` understands, for `config validate`.
+const CONFIG_KEYS: &[&str] = &[
+    "headed",
+    "json",
+    "full",
+    "debug",
+    "session",
+    "sessionName",
+    "executablePath",
+    "extensions",
+    "profile",
+    "state",
+    "proxy",
+    "proxyBypass",
+    "args",
+    "userAgent",
+    "provider",
+    "device",
+    "ignoreHttpsErrors",
+    "allowFileAccess",
+    "cdp",
+    "autoConnect",
+    "headers",
+    "annotate",
+    "colorScheme",
+    "downloadPath",
+    "timeout",
+    "retry",
+    "retryDelay",
+    "dryRun",
+    "defaultWaitUntil",
+    "defaultTimeout",
+    "defaultScreenshotFormat",
+    "aliases",
+    "extends",
+];
+
+/// Lists the top-level keys in raw config JSON that `Config` does not know,
+/// each paired with the known key it matches when only the casing is wrong.
+pub fn unknown_config_keys(raw: &str) -> Result<Vec<(String, Option<&'static str>)>, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(raw).map_err(|e| format!("invalid JSON: {}", e))?;
+    let obj = value
+        .as_object()
+        .ok_or_else(|| "config must be a JSON object".to_string())?;
+    Ok(obj
+        .keys()
+        .filter(|key| !CONFIG_KEYS.contains(&key.as_str()))
+        .map(|key| {
+            let hint = CONFIG_KEYS
+                .iter()
+                .find(|known| known.eq_ignore_ascii_case(key))
+                .copied();
+            (key.clone(), hint)
+        })
+        .collect())
+}
+
 pub struct Flags {
     pub json: bool,
+    pub ndjson: bool,
+    /// Resolved from --format, --json and --ndjson; `json` and `ndjson` mirror it.
+    pub output_format: OutputFormat,
     pub full: bool,
     pub headed: bool,
     pub debug: bool,
     pub session: String,
     pub headers: Option<String>,
+    pub header_pairs: Vec<(String, String)>,
     pub executable_path: Option<String>,
     pub cdp: Option<String>,
     pub extensions: Vec<String>,
@@ -207,6 +478,27 @@ pub struct Flags {
     pub annotate: bool,
     pub color_scheme: Option<String>,
     pub download_path: Option<String>,
+    pub timeout: Option<String>,
+    pub state_password: Option<String>,
+    pub retry: u32,
+    pub retry_delay_ms: u64,
+    pub connect_timeout_ms: u64,
+    /// Explicit --send-timeout / AGENT_BROWSER_SEND_TIMEOUT; `None` derives it
+    /// from the command's timeout.
+    pub send_timeout_ms: Option<u64>,
+    pub output_file: Option<String>,
+    pub color: bool,
+    pub quiet: bool,
+    pub screenshot_on_failure: bool,
+    pub screenshot_on_failure_dir: Option<String>,
+    pub dry_run: bool,
+    pub fail_on_console_error: bool,
+    /// `--not`: invert the result of an `is` check.
+    pub negate: bool,
+    pub default_wait_until: Option<String>,
+    pub default_timeout: Option<u64>,
+    pub default_screenshot_format: Option<String>,
+    pub aliases: HashMap<String, String>,
 
     // Track which launch-time options were explicitly passed via CLI
     // (as opposed to being set only via environment variables)
@@ -221,9 +513,11 @@ pub struct Flags {
     pub cli_allow_file_access: bool,
     pub cli_annotate: bool,
     pub cli_download_path: bool,
+    pub cli_headers: bool,
 }
 
 pub fn parse_flags(args: &[String]) -> Flags {
+    let args = &split_flag_values(args);
     let config = load_config(args).unwrap_or_else(|e| {
         eprintln!("{} {}", color::warning_indicator(), e);
         std::process::exit(1);
@@ -248,6 +542,8 @@ pub fn parse_flags(args: &[String]) -> Flags {
     let mut flags = Flags {
         json: env_var_is_truthy("AGENT_BROWSER_JSON")
             || config.json.unwrap_or(false),
+        ndjson: false,
+        output_format: OutputFormat::Human,
         full: env_var_is_truthy("AGENT_BROWSER_FULL")
             || config.full.unwrap_or(false),
         headed: env_var_is_truthy("AGENT_BROWSER_HEADED")
@@ -258,6 +554,7 @@ pub fn parse_flags(args: &[String]) -> Flags {
             .or(config.session)
             .unwrap_or_else(|| "default".to_string()),
         headers: config.headers,
+        header_pairs: Vec::new(),
         executable_path: env::var("AGENT_BROWSER_EXECUTABLE_PATH").ok()
             .or(config.executable_path),
         cdp: config.cdp,
@@ -292,6 +589,45 @@ pub fn parse_flags(args: &[String]) -> Flags {
             .or(config.color_scheme),
         download_path: env::var("AGENT_BROWSER_DOWNLOAD_PATH").ok()
             .or(config.download_path),
+        timeout: default_timeout(env::var("AGENT_BROWSER_TIMEOUT").ok(), config.timeout),
+        state_password: state_password(env::var("AGENT_BROWSER_STATE_PASSWORD").ok()),
+        retry: env_number("AGENT_BROWSER_RETRY")
+            .unwrap_or_else(|e| exit_invalid_value(e))
+            .or(config.retry)
+            .unwrap_or(0),
+        retry_delay_ms: env_number("AGENT_BROWSER_RETRY_DELAY")
+            .unwrap_or_else(|e| exit_invalid_value(e))
+            .or(config.retry_delay)
+            .unwrap_or(DEFAULT_RETRY_DELAY_MS),
+        connect_timeout_ms: env_number("AGENT_BROWSER_CONNECT_TIMEOUT")
+            .unwrap_or_else(|e| exit_invalid_value(e))
+            .unwrap_or(DEFAULT_CONNECT_TIMEOUT_MS),
+        send_timeout_ms: env_number("AGENT_BROWSER_SEND_TIMEOUT")
+            .unwrap_or_else(|e| exit_invalid_value(e)),
+        output_file: None,
+        color: color::detect(),
+        quiet: env_var_is_truthy("AGENT_BROWSER_QUIET"),
+        screenshot_on_failure: false,
+        screenshot_on_failure_dir: None,
+        dry_run: config.dry_run.unwrap_or(false),
+        fail_on_console_error: env_var_is_truthy("AGENT_BROWSER_FAIL_ON_CONSOLE_ERROR"),
+        negate: false,
+        default_wait_until: config
+            .default_wait_until
+            .map(|v| validate_choice("defaultWaitUntil", v, WAIT_UNTIL_STATES))
+            .transpose()
+            .unwrap_or_else(|e| exit_invalid_value(e)),
+        default_timeout: config
+            .default_timeout
+            .map(|ms| validate_timeout("defaultTimeout", ms))
+            .transpose()
+            .unwrap_or_else(|e| exit_invalid_value(e)),
+        aliases: config.aliases.unwrap_or_default(),
+        default_screenshot_format: config
+            .default_screenshot_format
+            .map(|v| validate_choice("defaultScreenshotFormat", v, SCREENSHOT_FORMATS))
+            .transpose()
+            .unwrap_or_else(|e| exit_invalid_value(e)),
         cli_executable_path: false,
         cli_extensions: false,
         cli_profile: false,
@@ -303,16 +639,40 @@ pub fn parse_flags(args: &[String]) -> Flags {
         cli_allow_file_access: false,
         cli_annotate: false,
         cli_download_path: false,
+        cli_headers: false,
     };
 
+    let mut cli_json = false;
+    let mut cli_format = None;
     let mut i = 0;
     while i < args.len() {
         match args[i].as_str() {
             "--json" => {
                 let (val, consumed) = parse_bool_arg(args, i);
                 flags.json = val;
+                cli_json = val;
                 if consumed { i += 1; }
             }
+            "--ndjson" => {
+                let (val, consumed) = parse_bool_arg(args, i);
+                flags.ndjson = val;
+                if consumed {
+                    i += 1;
+                }
+            }
+            "--format" => {
+                if let Some(s) = args.get(i + 1) {
+                    let format = OutputFormat::from_name(s).unwrap_or_else(|| {
+                        exit_invalid_value(format!(
+                            "Invalid --format value: {}. Expected one of: {}",
+                            s,
+                            OutputFormat::NAMES.join(", ")
+                        ))
+                    });
+                    cli_format = Some(format);
+                    i += 1;
+                }
+            }
             "--full" | "-f" => {
                 let (val, consumed) = parse_bool_arg(args, i);
                 flags.full = val;
@@ -328,6 +688,61 @@ pub fn parse_flags(args: &[String]) -> Flags {
                 flags.debug = val;
                 if consumed { i += 1; }
             }
+            "--color" => {
+                let (val, consumed) = parse_bool_arg(args, i);
+                flags.color = val;
+                if consumed {
+                    i += 1;
+                }
+            }
+            "--quiet" | "-q" => {
+                let (val, consumed) = parse_bool_arg(args, i);
+                flags.quiet = val;
+                if consumed {
+                    i += 1;
+                }
+            }
+            "--screenshot-on-failure" => {
+                let (val, consumed) = parse_bool_arg(args, i);
+                flags.screenshot_on_failure = val;
+                if consumed {
+                    i += 1;
+                }
+            }
+            "--screenshot-dir" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.screenshot_on_failure_dir = Some(s.clone());
+                    i += 1;
+                }
+            }
+            "--dry-run" => {
+                let (val, consumed) = parse_bool_arg(args, i);
+                flags.dry_run = val;
+                if consumed {
+                    i += 1;
+                }
+            }
+            "--not" => {
+                let (val, consumed) = parse_bool_arg(args, i);
+                flags.negate = val;
+                if consumed {
+                    i += 1;
+                }
+            }
+            "--fail-on-console-error" => {
+                let (val, consumed) = parse_bool_arg(args, i);
+                flags.fail_on_console_error = val;
+                if consumed {
+                    i += 1;
+                }
+            }
+            "--no-color" => {
+                let (val, consumed) = parse_bool_arg(args, i);
+                flags.color = !val;
+                if consumed {
+                    i += 1;
+                }
+            }
             "--session" => {
                 if let Some(s) = args.get(i + 1) {
                     flags.session = s.clone();
@@ -336,7 +751,16 @@ pub fn parse_flags(args: &[String]) -> Flags {
             }
             "--headers" => {
                 if let Some(h) = args.get(i + 1) {
-                    flags.headers = Some(h.clone());
+                    let headers = read_headers_arg(h).unwrap_or_else(|e| exit_invalid_value(e));
+                    flags.headers = Some(headers);
+                    flags.cli_headers = true;
+                    i += 1;
+                }
+            }
+            "--header" => {
+                if let Some(h) = args.get(i + 1) {
+                    let pair = parse_header_pair(h).unwrap_or_else(|e| exit_invalid_value(e));
+                    flags.header_pairs.push(pair);
                     i += 1;
                 }
             }
@@ -455,6 +879,47 @@ pub fn parse_flags(args: &[String]) -> Flags {
                     i += 1;
                 }
             }
+            "--timeout" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.timeout = Some(s.clone());
+                    i += 1;
+                }
+            }
+            "--retry" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.retry =
+                        parse_number("--retry", s).unwrap_or_else(|e| exit_invalid_value(e));
+                    i += 1;
+                }
+            }
+            "--retry-delay" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.retry_delay_ms =
+                        parse_number("--retry-delay", s).unwrap_or_else(|e| exit_invalid_value(e));
+                    i += 1;
+                }
+            }
+            "--connect-timeout" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.connect_timeout_ms = parse_number("--connect-timeout", s)
+                        .unwrap_or_else(|e| exit_invalid_value(e));
+                    i += 1;
+                }
+            }
+            "--send-timeout" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.send_timeout_ms = Some(
+                        parse_number("--send-timeout", s).unwrap_or_else(|e| exit_invalid_value(e)),
+                    );
+                    i += 1;
+                }
+            }
+            "--output-file" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.output_file = Some(s.clone());
+                    i += 1;
+                }
+            }
             "--config" => {
                 // Already handled by load_config(); skip the value
                 i += 1;
@@ -463,46 +928,122 @@ pub fn parse_flags(args: &[String]) -> Flags {
         }
         i += 1;
     }
+    flags.output_format = resolve_output_format(cli_format, cli_json, flags.json, flags.ndjson)
+        .unwrap_or_else(|e| exit_invalid_value(e));
+    flags.json = flags.output_format == OutputFormat::Json;
+    flags.ndjson = flags.output_format == OutputFormat::Ndjson;
+    // An explicit timeout (--timeout, AGENT_BROWSER_TIMEOUT or `timeout`)
+    // takes over from the config's defaultTimeout
+    if flags.timeout.is_some() {
+        flags.default_timeout = None;
+    }
     flags
 }
 
+/// Boolean flags that optionally take true/false
+pub(crate) const GLOBAL_BOOL_FLAGS: &[&str] = &[
+    "--json",
+    "--ndjson",
+    "--full",
+    "--headed",
+    "--debug",
+    "--ignore-https-errors",
+    "--allow-file-access",
+    "--auto-connect",
+    "--annotate",
+    "--color",
+    "--no-color",
+    "--quiet",
+    "--screenshot-on-failure",
+    "--dry-run",
+    "--fail-on-console-error",
+    "--not",
+];
+
+/// Global flags that always take a value (need to skip the next arg too)
+pub(crate) const GLOBAL_FLAGS_WITH_VALUE: &[&str] = &[
+    "--session",
+    "--headers",
+    "--header",
+    "--executable-path",
+    "--cdp",
+    "--extension",
+    "--profile",
+    "--state",
+    "--proxy",
+    "--proxy-bypass",
+    "--args",
+    "--user-agent",
+    "-p",
+    "--provider",
+    "--device",
+    "--session-name",
+    "--color-scheme",
+    "--download-path",
+    "--timeout",
+    "--config",
+    "--retry",
+    "--retry-delay",
+    "--connect-timeout",
+    "--send-timeout",
+    "--output-file",
+    "--screenshot-dir",
+    "--format",
+];
+
+/// Combines `--format` with `--json` / `--ndjson`. `cli_json` is `--json`
+/// given on the command line: it conflicts with `--ndjson` or another
+/// `--format`, while JSON mode from the environment or config gives way to them.
+fn resolve_output_format(
+    format: Option<OutputFormat>,
+    cli_json: bool,
+    json: bool,
+    ndjson: bool,
+) -> Result<OutputFormat, String> {
+    if cli_json && ndjson {
+        return Err("--json and --ndjson cannot be used together".to_string());
+    }
+    match format {
+        Some(f) if cli_json && f != OutputFormat::Json => {
+            Err(format!("--json conflicts with --format {}", f.name()))
+        }
+        Some(f) if ndjson && f != OutputFormat::Ndjson => {
+            Err(format!("--ndjson conflicts with --format {}", f.name()))
+        }
+        Some(f) => Ok(f),
+        None if ndjson => Ok(OutputFormat::Ndjson),
+        None if json => Ok(OutputFormat::Json),
+        None => Ok(OutputFormat::Human),
+    }
+}
+
+/// Expands `--flag=value` into `--flag value` for global flags, so parsing
+/// only has to handle the space-separated form. The value is everything after
+/// the first `=`: `--session=` gives an empty value and `--session==x` gives
+/// `=x`. Boolean flags are only split for `=true` / `=false`. Anything else,
+/// including unknown flags, is left untouched for the command parser.
+pub(crate) fn split_flag_values(args: &[String]) -> Vec<String> {
+    let mut result = Vec::with_capacity(args.len());
+    for arg in args {
+        if let Some((flag, value)) = arg.split_once('=').filter(|_| arg.starts_with("--")) {
+            let takes_value = GLOBAL_FLAGS_WITH_VALUE.contains(&flag);
+            let is_bool = GLOBAL_BOOL_FLAGS.contains(&flag) && matches!(value, "true" | "false");
+            if takes_value || is_bool {
+                result.push(flag.to_string());
+                result.push(value.to_string());
+                continue;
+            }
+        }
+        result.push(arg.clone());
+    }
+    result
+}
+
 pub fn clean_args(args: &[String]) -> Vec<String> {
+    let args = &split_flag_values(args);
     let mut result = Vec::new();
     let mut skip_next = false;
 
-    // Boolean flags that optionally take true/false
-    const GLOBAL_BOOL_FLAGS: &[&str] = &[
-        "--json",
-        "--full",
-        "--headed",
-        "--debug",
-        "--ignore-https-errors",
-        "--allow-file-access",
-        "--auto-connect",
-        "--annotate",
-    ];
-    // Global flags that always take a value (need to skip the next arg too)
-    const GLOBAL_FLAGS_WITH_VALUE: &[&str] = &[
-        "--session",
-        "--headers",
-        "--executable-path",
-        "--cdp",
-        "--extension",
-        "--profile",
-        "--state",
-        "--proxy",
-        "--proxy-bypass",
-        "--args",
-        "--user-agent",
-        "-p",
-        "--provider",
-        "--device",
-        "--session-name",
-        "--color-scheme",
-        "--download-path",
-        "--config",
-    ];
-
     let mut i = 0;
     while i < args.len() {
         let arg = &args[i];
@@ -516,7 +1057,7 @@ pub fn clean_args(args: &[String]) -> Vec<String> {
             i += 1;
             continue;
         }
-        if GLOBAL_BOOL_FLAGS.contains(&arg.as_str()) || arg == "-f" {
+        if GLOBAL_BOOL_FLAGS.contains(&arg.as_str()) || arg == "-f" || arg == "-q" {
             if let Some(v) = args.get(i + 1) {
                 if matches!(v.as_str(), "true" | "false") {
                     i += 1;
@@ -703,6 +1244,159 @@ mod tests {
         assert!(!flags.cli_download_path);
     }
 
+    #[test]
+    fn test_cli_headers_tracking() {
+        let flags = parse_flags(&args(r#"network route **/api --headers {"X-Mock":"1"}"#));
+        assert!(flags.cli_headers);
+        assert_eq!(flags.headers, Some(r#"{"X-Mock":"1"}"#.to_string()));
+    }
+
+    #[test]
+    fn test_parse_timeout_flag() {
+        let flags = parse_flags(&args("click #btn --timeout 5000"));
+        assert_eq!(flags.timeout, Some("5000".to_string()));
+    }
+
+    #[test]
+    fn test_clean_args_removes_timeout() {
+        let clean = clean_args(&args("click #btn --timeout 5000 --json"));
+        assert_eq!(clean, vec!["click", "#btn"]);
+    }
+
+    #[test]
+    fn test_default_timeout_env_overrides_config() {
+        assert_eq!(
+            default_timeout(Some("8000".to_string()), Some(3000)),
+            Some("8000".to_string())
+        );
+    }
+
+    #[test]
+    fn test_default_timeout_from_config() {
+        assert_eq!(default_timeout(None, Some(3000)), Some("3000".to_string()));
+        assert_eq!(
+            default_timeout(Some(String::new()), Some(3000)),
+            Some("3000".to_string())
+        );
+        assert_eq!(default_timeout(None, None), None);
+    }
+
+    #[test]
+    fn test_config_timeout_deserializes() {
+        let config: Config = serde_json::from_str(r#"{"timeout": 15000}"#).unwrap();
+        assert_eq!(config.timeout, Some(15000));
+    }
+
+    #[test]
+    fn test_config_merge_timeout() {
+        let user = Config {
+            timeout: Some(1000),
+            ..Config::default()
+        };
+        let project = Config {
+            timeout: Some(2000),
+            ..Config::default()
+        };
+        assert_eq!(user.merge(project).timeout, Some(2000));
+    }
+
+    #[test]
+    fn test_retry_defaults() {
+        let flags = parse_flags(&args("click #btn"));
+        assert_eq!(flags.retry, 0);
+        assert_eq!(flags.retry_delay_ms, DEFAULT_RETRY_DELAY_MS);
+    }
+
+    #[test]
+    fn test_parse_retry_flags() {
+        let flags = parse_flags(&args("click #btn --retry 3 --retry-delay 250"));
+        assert_eq!(flags.retry, 3);
+        assert_eq!(flags.retry_delay_ms, 250);
+    }
+
+    #[test]
+    fn test_clean_args_removes_retry_flags() {
+        let clean = clean_args(&args("--retry 3 click #btn --retry-delay 250"));
+        assert_eq!(clean, vec!["click", "#btn"]);
+    }
+
+    #[test]
+    fn test_connection_timeout_defaults() {
+        let flags = parse_flags(&args("click #btn"));
+        assert_eq!(flags.connect_timeout_ms, DEFAULT_CONNECT_TIMEOUT_MS);
+        assert_eq!(flags.send_timeout_ms, None);
+    }
+
+    #[test]
+    fn test_parse_connect_timeout() {
+        let flags = parse_flags(&args("--connect-timeout 5000 click #btn"));
+        assert_eq!(flags.connect_timeout_ms, 5000);
+        assert_eq!(flags.send_timeout_ms, None);
+    }
+
+    #[test]
+    fn test_parse_send_timeout() {
+        let flags = parse_flags(&args("open example.com --send-timeout 120000"));
+        assert_eq!(flags.send_timeout_ms, Some(120_000));
+        assert_eq!(flags.connect_timeout_ms, DEFAULT_CONNECT_TIMEOUT_MS);
+    }
+
+    #[test]
+    fn test_parse_connection_timeouts_equals_syntax() {
+        let flags = parse_flags(&args("click #btn --connect-timeout=0 --send-timeout=90000"));
+        assert_eq!(flags.connect_timeout_ms, 0);
+        assert_eq!(flags.send_timeout_ms, Some(90_000));
+    }
+
+    #[test]
+    fn test_clean_args_removes_connection_timeouts() {
+        let clean = clean_args(&args(
+            "--connect-timeout 5000 click #btn --send-timeout 90000 --json",
+        ));
+        assert_eq!(clean, vec!["click", "#btn"]);
+    }
+
+    #[test]
+    fn test_clean_args_removes_connection_timeouts_equals_syntax() {
+        let clean = clean_args(&args(
+            "--connect-timeout=5000 open example.com --send-timeout=1",
+        ));
+        assert_eq!(clean, vec!["open", "example.com"]);
+    }
+
+    #[test]
+    fn test_connection_timeout_does_not_swallow_config_path() {
+        assert_eq!(
+            extract_config_path(&args("--send-timeout 100 --config ./ab.json open x")),
+            Some(Some("./ab.json".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_number_rejects_invalid() {
+        assert_eq!(parse_number::<u32>("--retry", " 4 "), Ok(4));
+        let err = parse_number::<u32>("--retry", "-1").unwrap_err();
+        assert!(err.contains("--retry"));
+        assert!(parse_number::<u64>("AGENT_BROWSER_RETRY_DELAY", "soon").is_err());
+    }
+
+    #[test]
+    fn test_config_retry_deserializes() {
+        let config: Config = serde_json::from_str(r#"{"retry": 2, "retryDelay": 500}"#).unwrap();
+        assert_eq!(config.retry, Some(2));
+        assert_eq!(config.retry_delay, Some(500));
+    }
+
+    #[test]
+    fn test_state_password_from_env() {
+        assert_eq!(
+            state_password(Some("s3cret".to_string())),
+            Some("s3cret".to_string())
+        );
+        assert_eq!(state_password(Some(String::new())), None);
+        assert_eq!(state_password(None), None);
+    }
+
     #[test]
     fn test_cli_multiple_flags_tracking() {
         let flags = parse_flags(&args(
@@ -1084,4 +1778,771 @@ mod tests {
         let merged = user.merge(project);
         assert_eq!(merged.extensions, Some(vec!["/ext2".to_string()]));
     }
+
+    #[test]
+    fn test_parse_output_file_flag() {
+        let flags = parse_flags(&args("snapshot --output-file out/snap.txt"));
+        assert_eq!(flags.output_file.as_deref(), Some("out/snap.txt"));
+        assert_eq!(parse_flags(&args("snapshot")).output_file, None);
+    }
+
+    #[test]
+    fn test_clean_args_removes_output_file() {
+        let clean = clean_args(&args("--output-file out.json get url --json"));
+        assert_eq!(clean, vec!["get", "url"]);
+    }
+
+    #[test]
+    fn test_parse_color_flags() {
+        assert!(parse_flags(&args("--color snapshot")).color);
+        assert!(!parse_flags(&args("--no-color snapshot")).color);
+        assert!(!parse_flags(&args("--color false snapshot")).color);
+    }
+
+    #[test]
+    fn test_clean_args_removes_color_flags() {
+        let clean = clean_args(&args("--no-color get url --color"));
+        assert_eq!(clean, vec!["get", "url"]);
+    }
+
+    #[test]
+    fn test_highlight_color_is_not_global() {
+        let input = args("--no-color highlight @e1 --highlight-color #00FF00");
+        assert!(!parse_flags(&input).color);
+        assert_eq!(
+            clean_args(&input),
+            vec!["highlight", "@e1", "--highlight-color", "#00FF00"]
+        );
+    }
+
+    #[test]
+    fn test_parse_not_flag() {
+        let input = args("is --not visible #modal");
+        assert!(parse_flags(&input).negate);
+        assert_eq!(clean_args(&input), vec!["is", "visible", "#modal"]);
+        assert!(!parse_flags(&args("is visible #modal")).negate);
+    }
+
+    #[test]
+    fn test_parse_quiet_flag() {
+        assert!(parse_flags(&args("-q click #btn")).quiet);
+        assert!(parse_flags(&args("click #btn --quiet")).quiet);
+        assert!(!parse_flags(&args("click #btn --quiet false")).quiet);
+    }
+
+    #[test]
+    fn test_clean_args_removes_quiet() {
+        let clean = clean_args(&args("-q click #btn --quiet"));
+        assert_eq!(clean, vec!["click", "#btn"]);
+    }
+
+    #[test]
+    fn test_quiet_from_env() {
+        // AGENT_BROWSER_QUIET goes through env_var_is_truthy
+        assert!(value_is_truthy(Some("1")));
+        assert!(value_is_truthy(Some("true")));
+        assert!(!value_is_truthy(Some("0")));
+        assert!(!value_is_truthy(Some("False")));
+        assert!(!value_is_truthy(Some("")));
+        assert!(!value_is_truthy(None));
+    }
+
+    #[test]
+    fn test_parse_screenshot_on_failure_flags() {
+        let flags = parse_flags(&args(
+            "--screenshot-on-failure --screenshot-dir ci/shots click #btn",
+        ));
+        assert!(flags.screenshot_on_failure);
+        assert_eq!(flags.screenshot_on_failure_dir.as_deref(), Some("ci/shots"));
+    }
+
+    #[test]
+    fn test_screenshot_on_failure_defaults_off() {
+        let flags = parse_flags(&args("click #btn"));
+        assert!(!flags.screenshot_on_failure);
+        assert_eq!(flags.screenshot_on_failure_dir, None);
+    }
+
+    #[test]
+    fn test_clean_args_removes_screenshot_on_failure_flags() {
+        let clean = clean_args(&args(
+            "--screenshot-on-failure click #btn --screenshot-dir out",
+        ));
+        assert_eq!(clean, vec!["click", "#btn"]);
+    }
+
+    #[test]
+    fn test_dry_run_flag_and_config() {
+        assert!(parse_flags(&args("--dry-run click #btn")).dry_run);
+        assert_eq!(
+            clean_args(&args("--dry-run click #btn")),
+            vec!["click", "#btn"]
+        );
+        let config: Config = serde_json::from_str(r#"{"dryRun": true}"#).unwrap();
+        assert_eq!(config.dry_run, Some(true));
+    }
+
+    #[test]
+    fn test_equals_syntax_session() {
+        let flags = parse_flags(&args("--session=work open example.com"));
+        assert_eq!(flags.session, "work");
+        assert_eq!(
+            clean_args(&args("--session=work open example.com")),
+            vec!["open", "example.com"]
+        );
+    }
+
+    #[test]
+    fn test_equals_syntax_timeout() {
+        let flags = parse_flags(&args("click #btn --timeout=5000"));
+        assert_eq!(flags.timeout.as_deref(), Some("5000"));
+        assert_eq!(
+            clean_args(&args("click #btn --timeout=5000")),
+            vec!["click", "#btn"]
+        );
+    }
+
+    #[test]
+    fn test_equals_syntax_retry_flags() {
+        let flags = parse_flags(&args("--retry=3 --retry-delay=250 click #btn"));
+        assert_eq!(flags.retry, 3);
+        assert_eq!(flags.retry_delay_ms, 250);
+    }
+
+    #[test]
+    fn test_equals_syntax_output_and_screenshot_dir() {
+        let input = args("--output-file=out/a.txt --screenshot-dir=shots snapshot");
+        let flags = parse_flags(&input);
+        assert_eq!(flags.output_file.as_deref(), Some("out/a.txt"));
+        assert_eq!(flags.screenshot_on_failure_dir.as_deref(), Some("shots"));
+        assert_eq!(clean_args(&input), vec!["snapshot"]);
+    }
+
+    #[test]
+    fn test_equals_syntax_empty_value() {
+        let flags = parse_flags(&args("--session-name= open example.com"));
+        assert_eq!(flags.session_name.as_deref(), Some(""));
+        assert_eq!(
+            clean_args(&args("--session-name= open example.com")),
+            vec!["open", "example.com"]
+        );
+    }
+
+    #[test]
+    fn test_equals_syntax_value_starting_with_equals() {
+        let flags = parse_flags(&args("--user-agent==bot open example.com"));
+        assert_eq!(flags.user_agent.as_deref(), Some("=bot"));
+    }
+
+    #[test]
+    fn test_equals_syntax_value_containing_equals() {
+        let split = split_flag_values(&args("--proxy=http://h:1/?a=b get url"));
+        assert_eq!(split, vec!["--proxy", "http://h:1/?a=b", "get", "url"]);
+    }
+
+    #[test]
+    fn test_equals_syntax_unknown_flag_passes_through() {
+        let clean = clean_args(&args("network route **/api --body={} --json"));
+        assert_eq!(clean, vec!["network", "route", "**/api", "--body={}"]);
+    }
+
+    #[test]
+    fn test_equals_syntax_bool_flags() {
+        let flags = parse_flags(&args("--headed=false --json=true snapshot"));
+        assert!(!flags.headed);
+        assert!(flags.json);
+        assert_eq!(
+            clean_args(&args("--headed=false --json=true snapshot")),
+            vec!["snapshot"]
+        );
+    }
+
+    #[test]
+    fn test_equals_syntax_bool_flag_with_other_value_is_untouched() {
+        let clean = clean_args(&args("snapshot --json=yes"));
+        assert_eq!(clean, vec!["snapshot", "--json=yes"]);
+    }
+
+    #[test]
+    fn test_equals_syntax_short_flags_not_split() {
+        let split = split_flag_values(&args("-p=ios open example.com"));
+        assert_eq!(split, vec!["-p=ios", "open", "example.com"]);
+    }
+
+    #[test]
+    fn test_extract_config_path_equals_syntax() {
+        assert_eq!(
+            extract_config_path(&args("--config=./my-config.json open example.com")),
+            Some(Some("./my-config.json".to_string()))
+        );
+        assert_eq!(
+            extract_config_path(&args("--config= open")),
+            Some(Some(String::new()))
+        );
+    }
+
+    #[test]
+    fn test_equals_syntax_highlight_color_left_for_command() {
+        let clean = clean_args(&args("highlight @e1 --highlight-color=#00FF00"));
+        assert_eq!(clean, vec!["highlight", "@e1", "--highlight-color=#00FF00"]);
+    }
+
+    #[test]
+    fn test_parse_header_pair_formats() {
+        assert_eq!(
+            parse_header_pair("Authorization: Bearer token"),
+            Ok(("Authorization".to_string(), "Bearer token".to_string()))
+        );
+        assert_eq!(
+            parse_header_pair("X-Trace:abc"),
+            Ok(("X-Trace".to_string(), "abc".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_header_pair_value_with_colon() {
+        assert_eq!(
+            parse_header_pair("Referer: https://example.com:8443/"),
+            Ok((
+                "Referer".to_string(),
+                "https://example.com:8443/".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_header_pair_requires_colon() {
+        let err = parse_header_pair("Authorization Bearer").unwrap_err();
+        assert!(err.contains("Name: Value"));
+        assert!(parse_header_pair(": value")
+            .unwrap_err()
+            .contains("name is empty"));
+    }
+
+    #[test]
+    fn test_parse_repeated_header_flags() {
+        let input: Vec<String> = vec![
+            "open".into(),
+            "example.com".into(),
+            "--header".into(),
+            "Authorization: Bearer t".into(),
+            "--header=X-Env:staging".into(),
+        ];
+        let flags = parse_flags(&input);
+        assert_eq!(
+            flags.header_pairs,
+            vec![
+                ("Authorization".to_string(), "Bearer t".to_string()),
+                ("X-Env".to_string(), "staging".to_string()),
+            ]
+        );
+        assert_eq!(clean_args(&input), vec!["open", "example.com"]);
+    }
+
+    #[test]
+    fn test_read_headers_arg_inline_unchanged() {
+        let inline = r#"{"Authorization": "Bearer t"}"#;
+        assert_eq!(read_headers_arg(inline), Ok(inline.to_string()));
+    }
+
+    #[test]
+    fn test_read_headers_arg_from_file() {
+        let path = std::env::temp_dir().join("ab-test-headers.json");
+        fs::write(&path, r#"{"Authorization": "Bearer file"}"#).unwrap();
+        let value = read_headers_arg(&format!("@{}", path.display())).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(value, r#"{"Authorization": "Bearer file"}"#);
+    }
+
+    #[test]
+    fn test_read_headers_arg_missing_file() {
+        let err = read_headers_arg("@/nonexistent/ab-headers.json").unwrap_err();
+        assert!(err.contains("Failed to read headers file /nonexistent/ab-headers.json"));
+    }
+
+    #[test]
+    fn test_read_headers_arg_invalid_json() {
+        let path = std::env::temp_dir().join("ab-test-headers-invalid.json");
+        fs::write(&path, "Authorization: Bearer").unwrap();
+        let err = read_headers_arg(&format!("@{}", path.display())).unwrap_err();
+        let _ = fs::remove_file(&path);
+        assert!(err.contains("Invalid JSON object in headers file"));
+    }
+
+    #[test]
+    fn test_read_headers_arg_rejects_non_object() {
+        let path = std::env::temp_dir().join("ab-test-headers-array.json");
+        fs::write(&path, r#"["Authorization"]"#).unwrap();
+        let err = read_headers_arg(&format!("@{}", path.display())).unwrap_err();
+        let _ = fs::remove_file(&path);
+        assert!(err.contains("Invalid JSON object"));
+    }
+
+    #[test]
+    fn test_parse_headers_flag_from_file() {
+        let path = std::env::temp_dir().join("ab-test-headers-flag.json");
+        fs::write(&path, r#"{"X-Env": "staging"}"#).unwrap();
+        let input = vec![
+            "open".to_string(),
+            "example.com".to_string(),
+            "--headers".to_string(),
+            format!("@{}", path.display()),
+        ];
+        let flags = parse_flags(&input);
+        let _ = fs::remove_file(&path);
+        assert_eq!(flags.headers.as_deref(), Some(r#"{"X-Env": "staging"}"#));
+        assert!(flags.cli_headers);
+    }
+
+    fn extends_test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ab-test-extends-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_config_extends_deserializes() {
+        let config: Config = serde_json::from_str(r#"{"extends": "../base.json"}"#).unwrap();
+        assert_eq!(config.extends.as_deref(), Some("../base.json"));
+    }
+
+    #[test]
+    fn test_config_extends_overrides_base() {
+        let dir = extends_test_dir("override");
+        fs::write(
+            dir.join("base.json"),
+            r#"{"headed": true, "proxy": "http://base:1"}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("child.json"),
+            r#"{"extends": "base.json", "proxy": "http://child:2"}"#,
+        )
+        .unwrap();
+        let config = read_config_file(&dir.join("child.json")).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(config.headed, Some(true));
+        assert_eq!(config.proxy.as_deref(), Some("http://child:2"));
+    }
+
+    #[test]
+    fn test_config_extends_relative_to_config_dir() {
+        let dir = extends_test_dir("relative");
+        fs::create_dir_all(dir.join("project")).unwrap();
+        fs::write(dir.join("shared.json"), r#"{"session": "shared"}"#).unwrap();
+        fs::write(
+            dir.join("project/agent-browser.json"),
+            r#"{"extends": "../shared.json"}"#,
+        )
+        .unwrap();
+        let config = read_config_file(&dir.join("project/agent-browser.json")).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(config.session.as_deref(), Some("shared"));
+    }
+
+    #[test]
+    fn test_config_extends_absolute_path() {
+        let dir = extends_test_dir("absolute");
+        let base = dir.join("base.json");
+        fs::write(&base, r#"{"userAgent": "base-agent"}"#).unwrap();
+        fs::write(
+            dir.join("child.json"),
+            serde_json::json!({ "extends": base.to_string_lossy() }).to_string(),
+        )
+        .unwrap();
+        let config = read_config_file(&dir.join("child.json")).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(config.user_agent.as_deref(), Some("base-agent"));
+    }
+
+    #[test]
+    fn test_config_extends_chain() {
+        let dir = extends_test_dir("chain");
+        fs::write(dir.join("a.json"), r#"{"headed": true, "debug": true}"#).unwrap();
+        fs::write(
+            dir.join("b.json"),
+            r#"{"extends": "a.json", "debug": false}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("c.json"), r#"{"extends": "b.json", "json": true}"#).unwrap();
+        let config = read_config_file(&dir.join("c.json")).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(config.headed, Some(true));
+        assert_eq!(config.debug, Some(false));
+        assert_eq!(config.json, Some(true));
+    }
+
+    #[test]
+    fn test_config_extends_missing_base_keeps_config() {
+        let dir = extends_test_dir("missing");
+        fs::write(
+            dir.join("child.json"),
+            r#"{"extends": "nope.json", "headed": true}"#,
+        )
+        .unwrap();
+        let config = read_config_file(&dir.join("child.json")).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(config.headed, Some(true));
+    }
+
+    #[test]
+    fn test_config_extends_cycle_terminates() {
+        let dir = extends_test_dir("cycle");
+        fs::write(
+            dir.join("a.json"),
+            r#"{"extends": "b.json", "headed": true}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("b.json"),
+            r#"{"extends": "a.json", "debug": true}"#,
+        )
+        .unwrap();
+        let config = read_config_file(&dir.join("a.json")).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(config.headed, Some(true));
+        assert_eq!(config.debug, Some(true));
+    }
+
+    #[test]
+    fn test_config_extends_depth_limit() {
+        let dir = extends_test_dir("depth");
+        // f0 -> f1 -> ... -> f6; only MAX_EXTENDS_DEPTH bases are followed
+        for i in 0..5 {
+            let body = format!(r#"{{"extends": "f{}.json"}}"#, i + 1);
+            fs::write(dir.join(format!("f{}.json", i)), body).unwrap();
+        }
+        fs::write(
+            dir.join("f5.json"),
+            r#"{"extends": "f6.json", "session": "f5"}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("f6.json"), r#"{"headed": true}"#).unwrap();
+        let config = read_config_file(&dir.join("f0.json")).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(config.session.as_deref(), Some("f5"));
+        assert_eq!(config.headed, None);
+    }
+
+    #[test]
+    fn test_config_extends_merge_order_user_base_project() {
+        let dir = extends_test_dir("order");
+        fs::write(dir.join("base.json"), r#"{"headed": false, "debug": true}"#).unwrap();
+        fs::write(
+            dir.join("project.json"),
+            r#"{"extends": "base.json", "debug": false}"#,
+        )
+        .unwrap();
+        let user = Config {
+            headed: Some(true),
+            session: Some("user".to_string()),
+            ..Config::default()
+        };
+        let project = read_config_file(&dir.join("project.json")).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        let merged = user.merge(project);
+        assert_eq!(merged.headed, Some(false));
+        assert_eq!(merged.debug, Some(false));
+        assert_eq!(merged.session.as_deref(), Some("user"));
+    }
+
+    #[test]
+    fn test_config_default_fields_deserialize() {
+        let config: Config = serde_json::from_str(
+            r#"{"defaultWaitUntil": "networkidle", "defaultTimeout": 20000, "defaultScreenshotFormat": "jpeg"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.default_wait_until.as_deref(), Some("networkidle"));
+        assert_eq!(config.default_timeout, Some(20000));
+        assert_eq!(config.default_screenshot_format.as_deref(), Some("jpeg"));
+    }
+
+    #[test]
+    fn test_config_merge_default_fields() {
+        let user = Config {
+            default_wait_until: Some("load".to_string()),
+            default_screenshot_format: Some("png".to_string()),
+            ..Config::default()
+        };
+        let project = Config {
+            default_wait_until: Some("networkidle".to_string()),
+            ..Config::default()
+        };
+        let merged = user.merge(project);
+        assert_eq!(merged.default_wait_until.as_deref(), Some("networkidle"));
+        assert_eq!(merged.default_screenshot_format.as_deref(), Some("png"));
+    }
+
+    #[test]
+    fn test_validate_choice() {
+        assert_eq!(
+            validate_choice(
+                "defaultWaitUntil",
+                "networkidle".to_string(),
+                WAIT_UNTIL_STATES
+            ),
+            Ok("networkidle".to_string())
+        );
+        let err =
+            validate_choice("defaultWaitUntil", "idle".to_string(), WAIT_UNTIL_STATES).unwrap_err();
+        assert!(err.contains("defaultWaitUntil"));
+        assert!(err.contains("load, domcontentloaded, networkidle"));
+    }
+
+    #[test]
+    fn test_validate_timeout_rejects_zero() {
+        assert_eq!(validate_timeout("defaultTimeout", 9000), Ok(9000));
+        let err = validate_timeout("defaultTimeout", 0).unwrap_err();
+        assert!(err.contains("defaultTimeout"));
+    }
+
+    #[test]
+    fn test_validate_choice_rejects_unsupported_screenshot_format() {
+        let err = validate_choice(
+            "defaultScreenshotFormat",
+            "webp".to_string(),
+            SCREENSHOT_FORMATS,
+        )
+        .unwrap_err();
+        assert!(err.contains("png, jpeg"));
+    }
+
+    #[test]
+    fn test_parse_flags_applies_config_defaults() {
+        let dir = std::env::temp_dir().join("ab-test-config-defaults");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+        fs::write(
+            &path,
+            r#"{"defaultWaitUntil": "domcontentloaded", "defaultTimeout": 9000, "defaultScreenshotFormat": "jpeg"}"#,
+        )
+        .unwrap();
+        let config_arg = path.to_string_lossy().to_string();
+        let flags = parse_flags(&[
+            "--config".to_string(),
+            config_arg.clone(),
+            "snapshot".to_string(),
+        ]);
+        let with_timeout = parse_flags(&[
+            "--config".to_string(),
+            config_arg,
+            "--timeout".to_string(),
+            "500".to_string(),
+        ]);
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(
+            flags.default_wait_until.as_deref(),
+            Some("domcontentloaded")
+        );
+        assert_eq!(flags.default_timeout, Some(9000));
+        assert_eq!(flags.default_screenshot_format.as_deref(), Some("jpeg"));
+        assert_eq!(with_timeout.default_timeout, None);
+    }
+
+    #[test]
+    fn test_config_aliases_deserialize_and_merge() {
+        let user: Config = serde_json::from_str(
+            r#"{"aliases": {"login": "open https://a.test/login", "shot": "screenshot"}}"#,
+        )
+        .unwrap();
+        let project: Config =
+            serde_json::from_str(r#"{"aliases": {"login": "open https://b.test/login"}}"#).unwrap();
+        let aliases = user.merge(project).aliases.unwrap();
+        assert_eq!(aliases["login"], "open https://b.test/login");
+        assert_eq!(aliases["shot"], "screenshot");
+    }
+
+    #[test]
+    fn test_unknown_config_keys() {
+        let keys =
+            unknown_config_keys(r#"{"headed": true, "executablepath": "/bin/x", "colour": "red"}"#)
+                .unwrap();
+        assert_eq!(keys.len(), 2);
+        assert!(keys.contains(&("executablepath".to_string(), Some("executablePath"))));
+        assert!(keys.contains(&("colour".to_string(), None)));
+        assert!(
+            unknown_config_keys(r#"{"retryDelay": 5, "extends": "a.json"}"#)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_unknown_config_keys_rejects_invalid_json() {
+        assert!(unknown_config_keys("{not json")
+            .unwrap_err()
+            .contains("invalid JSON"));
+        assert!(unknown_config_keys("[1]")
+            .unwrap_err()
+            .contains("JSON object"));
+    }
+
+    #[test]
+    fn test_config_file_paths_uses_explicit_config() {
+        let paths = config_file_paths(&args("--config ./ci.json config validate"));
+        assert_eq!(paths, vec![PathBuf::from("./ci.json")]);
+    }
+
+    #[test]
+    fn test_parse_fail_on_console_error() {
+        assert!(!parse_flags(&args("open example.com")).fail_on_console_error);
+        let flags = parse_flags(&args("--fail-on-console-error open example.com"));
+        assert!(flags.fail_on_console_error);
+        let flags = parse_flags(&args("open example.com --fail-on-console-error=false"));
+        assert!(!flags.fail_on_console_error);
+    }
+
+    #[test]
+    fn test_clean_args_removes_fail_on_console_error() {
+        let clean = clean_args(&args("--fail-on-console-error open example.com"));
+        assert_eq!(clean, vec!["open", "example.com"]);
+        let clean = clean_args(&args("open example.com --fail-on-console-error true"));
+        assert_eq!(clean, vec!["open", "example.com"]);
+    }
+
+    #[test]
+    fn test_parse_ndjson_flag() {
+        let flags = parse_flags(&args("--ndjson console"));
+        assert!(flags.ndjson);
+        assert!(!flags.json);
+        assert!(!parse_flags(&args("console")).ndjson);
+    }
+
+    #[test]
+    fn test_clean_args_removes_ndjson() {
+        assert_eq!(clean_args(&args("--ndjson console")), vec!["console"]);
+        assert_eq!(
+            clean_args(&args("network requests --ndjson=true")),
+            vec!["network", "requests"]
+        );
+    }
+
+    #[test]
+    fn test_parse_format_flag() {
+        let flags = parse_flags(&args("--format table cookies"));
+        assert_eq!(flags.output_format, OutputFormat::Table);
+        assert!(!flags.json);
+        let flags = parse_flags(&args("tab list --format=json"));
+        assert_eq!(flags.output_format, OutputFormat::Json);
+        assert!(flags.json);
+        let flags = parse_flags(&args("console --format ndjson"));
+        assert!(flags.ndjson);
+        assert_eq!(
+            parse_flags(&args("cookies")).output_format,
+            OutputFormat::Human
+        );
+    }
+
+    #[test]
+    fn test_json_and_ndjson_set_output_format() {
+        assert_eq!(
+            parse_flags(&args("--json cookies")).output_format,
+            OutputFormat::Json
+        );
+        assert_eq!(
+            parse_flags(&args("--ndjson cookies")).output_format,
+            OutputFormat::Ndjson
+        );
+    }
+
+    #[test]
+    fn test_resolve_output_format_conflicts() {
+        assert!(resolve_output_format(None, true, true, true).is_err());
+        let err = resolve_output_format(Some(OutputFormat::Table), true, true, false).unwrap_err();
+        assert_eq!(err, "--json conflicts with --format table");
+        assert!(resolve_output_format(Some(OutputFormat::Json), false, false, true).is_err());
+        assert_eq!(
+            resolve_output_format(Some(OutputFormat::Json), true, true, false),
+            Ok(OutputFormat::Json)
+        );
+    }
+
+    #[test]
+    fn test_resolve_output_format_overrides_env_json() {
+        // JSON mode from AGENT_BROWSER_JSON or config gives way to --format / --ndjson
+        assert_eq!(
+            resolve_output_format(Some(OutputFormat::Table), false, true, false),
+            Ok(OutputFormat::Table)
+        );
+        assert_eq!(
+            resolve_output_format(None, false, true, true),
+            Ok(OutputFormat::Ndjson)
+        );
+        assert_eq!(
+            resolve_output_format(None, false, true, false),
+            Ok(OutputFormat::Json)
+        );
+    }
+
+    #[test]
+    fn test_network_requests_keeps_its_own_format() {
+        let flags = parse_flags(&args("network requests --output-format json"));
+        assert_eq!(flags.output_format, OutputFormat::Human);
+        assert_eq!(
+            clean_args(&args("network requests --output-format json")),
+            vec!["network", "requests", "--output-format", "json"]
+        );
+        assert_eq!(
+            clean_args(&args(
+                "--format table network requests --output-format ndjson"
+            )),
+            vec!["network", "requests", "--output-format", "ndjson"]
+        );
+        assert_eq!(clean_args(&args("cookies --format table")), vec!["cookies"]);
+    }
+
+    #[test]
+    fn test_format_and_color_always_global() {
+        let input = args("network requests --format table");
+        assert_eq!(parse_flags(&input).output_format, OutputFormat::Table);
+        assert_eq!(clean_args(&input), vec!["network", "requests"]);
+        let input = args("highlight @e1 --color=false");
+        assert!(!parse_flags(&input).color);
+        assert_eq!(clean_args(&input), vec!["highlight", "@e1"]);
+    }
+
+    #[test]
+    fn test_clean_args_keeps_tab_background() {
+        let cleaned = clean_args(&args(
+            "--session a tab new --background https://example.com",
+        ));
+        assert_eq!(cleaned, args("tab new --background https://example.com"));
+    }
+
+    #[test]
+    fn test_clean_args_keeps_reload_flags() {
+        let cleaned = clean_args(&args("reload --hard --wait-until load --session a"));
+        assert_eq!(cleaned, args("reload --hard --wait-until load"));
+    }
+
+    #[test]
+    fn test_clean_args_keeps_back_wait_until() {
+        let cleaned = clean_args(&args("--json back --wait-until networkidle"));
+        assert_eq!(cleaned, args("back --wait-until networkidle"));
+    }
+
+    #[test]
+    fn test_clean_args_keeps_no_wait_after() {
+        let cleaned = clean_args(&args("--headed click #go --no-wait-after"));
+        assert_eq!(cleaned, args("click #go --no-wait-after"));
+    }
+
+    #[test]
+    fn test_clean_args_keeps_screenshot_masks() {
+        let cleaned = clean_args(&args("screenshot --mask #pw --json --mask .card out.png"));
+        assert_eq!(cleaned, args("screenshot --mask #pw --mask .card out.png"));
+    }
+
+    #[test]
+    fn test_clean_args_keeps_wait_polling_interval() {
+        let cleaned = clean_args(&args("wait --fn ok --polling-interval 500 --timeout 9000"));
+        assert_eq!(cleaned, args("wait --fn ok --polling-interval 500"));
+    }
+
+    #[test]
+    fn test_clean_args_keeps_press_selector() {
+        let cleaned = clean_args(&args("--session s press Enter --selector #input"));
+        assert_eq!(cleaned, args("press Enter --selector #input"));
+    }
 }
@@ -1,26 +1,40 @@
 mod color;
 mod commands;
+mod completions;
 mod connection;
 mod flags;
 mod install;
+#[macro_use]
 mod output;
 mod validation;
 
 use serde_json::json;
+use std::collections::HashMap;
 use std::env;
 use std::fs;
-use std::process::exit;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
 #[cfg(windows)]
 use windows_sys::Win32::Foundation::CloseHandle;
 #[cfg(windows)]
-use windows_sys::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
-
-use commands::{gen_id, parse_command, ParseError};
-use connection::{ensure_daemon, get_socket_dir, send_command};
-use flags::{clean_args, parse_flags};
+use windows_sys::Win32::System::Threading::{
+    OpenProcess, TerminateProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE,
+};
+
+use commands::{gen_id, parse_command, split_script_line, ParseError};
+use connection::{
+    ensure_daemon, get_log_path, get_socket_dir, is_connect_error, is_timeout_error, send_command,
+    stream_command, DaemonResult, Response, Timeouts,
+};
+use flags::{clean_args, parse_flags, Flags, DEFAULT_SEND_TIMEOUT_MS};
 use install::run_install;
-use output::{print_command_help, print_help, print_response, print_version};
+use output::{
+    print_command_help, print_help, print_requests, print_response, print_version, OutputFormat,
+};
 
 fn parse_proxy(proxy_str: &str) -> serde_json::Value {
     let Some(protocol_end) = proxy_str.find("://") else {
@@ -52,64 +66,711 @@ fn parse_proxy(proxy_str: &str) -> serde_json::Value {
     })
 }
 
-fn run_session(args: &[String], session: &str, json_mode: bool) {
+/// Returns true when an `is ... --not` check came back false, so the CLI can
+/// exit non-zero the same way it does for a failed command.
+fn negated_check_failed(cmd: &serde_json::Value, resp: &Response) -> bool {
+    const STATE_FIELDS: &[&str] = &[
+        "visible", "enabled", "checked", "hidden", "editable", "disabled", "focused", "empty",
+    ];
+
+    if cmd.get("negate").and_then(|v| v.as_bool()) != Some(true) {
+        return false;
+    }
+    resp.data.as_ref().is_some_and(|data| {
+        STATE_FIELDS
+            .iter()
+            .any(|field| data.get(*field).and_then(|v| v.as_bool()) == Some(false))
+    })
+}
+
+/// `--version` / `-V` in place of a command (after global flags are removed)
+/// prints the version. After a command word it is left for the command, so
+/// `fill #v -V` still fills.
+fn has_version_flag(clean: &[String]) -> bool {
+    clean.first().is_some_and(|a| a == "--version" || a == "-V")
+}
+
+/// Sends a command up to `retry + 1` times, sleeping `delay_ms` between
+/// attempts, while it fails to reach the daemon. Daemon failures and read
+/// timeouts are returned as-is: the command may already have run, and sending
+/// it again could repeat a click or form submission. Returns the last result
+/// and its 1-based attempt.
+fn send_with_retry<F>(retry: u32, delay_ms: u64, mut send: F) -> (Result<Response, String>, u32)
+where
+    F: FnMut() -> Result<Response, String>,
+{
+    let mut attempt = 1;
+    loop {
+        let result = send();
+        let retryable = matches!(&result, Err(e) if is_connect_error(e));
+        if !retryable || attempt > retry {
+            return (result, attempt);
+        }
+        thread::sleep(Duration::from_millis(delay_ms));
+        attempt += 1;
+    }
+}
+
+/// Serializes a response for `--json` output with the attempt that produced it.
+fn response_with_attempt(resp: &Response, attempt: u32) -> serde_json::Value {
+    let mut value = serde_json::to_value(resp).unwrap_or_default();
+    value["attempt"] = json!(attempt);
+    value
+}
+
+/// Turns a failed `assert_*` response into a readable assertion failure.
+///
+/// The response data always carries `expected` and `actual` so JSON consumers
+/// can report the mismatch without re-parsing the error message.
+fn assertion_failure(cmd: &serde_json::Value, mut resp: Response) -> Response {
+    let action = cmd.get("action").and_then(|v| v.as_str()).unwrap_or("");
+    let Some(kind) = action.strip_prefix("assert_") else {
+        return resp;
+    };
+    if resp.success {
+        return resp;
+    }
+
+    let expected = cmd.get("expected").cloned().unwrap_or(json!(true));
+    let actual = resp
+        .data
+        .as_ref()
+        .and_then(|d| d.get("actual"))
+        .cloned()
+        .unwrap_or(serde_json::Value::Null);
+    let subject = match cmd.get("selector").and_then(|v| v.as_str()) {
+        Some(sel) => format!("{} {}", kind, sel),
+        None => kind.to_string(),
+    };
+    let detail = match (&actual, resp.error.as_deref()) {
+        (serde_json::Value::Null, Some(err)) => err.to_string(),
+        _ => format!("expected {}, got {}", expected, actual),
+    };
+    resp.error = Some(format!("Assertion failed: {}: {}", subject, detail));
+
+    let mut data = match resp.data.take() {
+        Some(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    data.insert("expected".to_string(), expected);
+    data.insert("actual".to_string(), actual);
+    resp.data = Some(serde_json::Value::Object(data));
+    resp
+}
+
+/// Fails a `find ... --count <n>` response whose element count is not `n`.
+fn count_mismatch(cmd: &serde_json::Value, mut resp: Response) -> Response {
+    let Some(expected) = cmd.get("expectedCount").and_then(|v| v.as_u64()) else {
+        return resp;
+    };
+    if !resp.success {
+        return resp;
+    }
+    let actual = resp
+        .data
+        .as_ref()
+        .and_then(|d| d.get("count"))
+        .and_then(|v| v.as_u64());
+    if actual == Some(expected) {
+        return resp;
+    }
+    resp.success = false;
+    resp.error = Some(match actual {
+        Some(n) => format!("Count mismatch: expected {}, got {}", expected, n),
+        None => format!("Count mismatch: expected {}, got no count", expected),
+    });
+    resp
+}
+
+/// Fails a `storage <type> get <key> --fail-if-missing` response whose key
+/// is not set.
+fn missing_storage_key(cmd: &serde_json::Value, mut resp: Response) -> Response {
+    if cmd.get("failIfMissing").and_then(|v| v.as_bool()) != Some(true) || !resp.success {
+        return resp;
+    }
+    let value = resp.data.as_ref().and_then(|d| d.get("value"));
+    if value.is_none_or(|v| v.is_null()) {
+        let key = cmd.get("key").and_then(|v| v.as_str()).unwrap_or("");
+        let storage = match cmd.get("type").and_then(|v| v.as_str()) {
+            Some("session") => "sessionStorage",
+            _ => "localStorage",
+        };
+        resp.success = false;
+        resp.error = Some(format!("Key not found in {}: {}", storage, key));
+    }
+    resp
+}
+
+/// Fails a `get count --fail-on-zero` response when nothing matched.
+fn zero_count_failure(cmd: &serde_json::Value, mut resp: Response) -> Response {
+    if cmd.get("failOnZero").and_then(|v| v.as_bool()) != Some(true) || !resp.success {
+        return resp;
+    }
+    let count = resp
+        .data
+        .as_ref()
+        .and_then(|d| d.get("count"))
+        .and_then(|v| v.as_u64());
+    if count == Some(0) {
+        let selector = cmd.get("selector").and_then(|v| v.as_str()).unwrap_or("");
+        resp.success = false;
+        resp.error = Some(format!("No elements match {}", selector));
+    }
+    resp
+}
+
+/// Fails a `validate` response whose match count is outside its bounds
+/// (at least one element unless `--min` or `--max 0` says otherwise).
+fn validate_failure(cmd: &serde_json::Value, mut resp: Response) -> Response {
+    if cmd.get("action").and_then(|v| v.as_str()) != Some("validate") || !resp.success {
+        return resp;
+    }
+    let selector = cmd.get("selector").and_then(|v| v.as_str()).unwrap_or("");
+    let max = cmd.get("max").and_then(|v| v.as_u64());
+    // `--max 0` asserts absence, so it lowers the default minimum
+    let min = cmd
+        .get("min")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(max.map_or(1, |max| max.min(1)));
+    let count = resp
+        .data
+        .as_ref()
+        .and_then(|d| d.get("count"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let error = if count == 0 && min > 0 {
+        format!("No elements match {}", selector)
+    } else if count < min {
+        format!(
+            "Expected at least {} elements matching {}, found {}",
+            min, selector, count
+        )
+    } else if max.is_some_and(|max| count > max) {
+        format!(
+            "Expected at most {} elements matching {}, found {}",
+            max.unwrap_or_default(),
+            selector,
+            count
+        )
+    } else {
+        return resp;
+    };
+    resp.success = false;
+    resp.error = Some(error);
+    resp
+}
+
+/// Reads the daemon PID recorded for a session, if it has a PID file.
+fn read_session_pid(socket_dir: &Path, name: &str) -> Option<u32> {
+    fs::read_to_string(socket_dir.join(format!("{}.pid", name)))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn is_process_running(pid: u32) -> bool {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(pid as i32, 0) == 0
+    }
+    #[cfg(windows)]
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle != 0 {
+            CloseHandle(handle);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Asks a daemon process to shut down: SIGTERM on Unix, TerminateProcess on Windows.
+fn terminate_process(pid: u32) -> bool {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(pid as i32, libc::SIGTERM) == 0
+    }
+    #[cfg(windows)]
+    unsafe {
+        let handle = OpenProcess(PROCESS_TERMINATE, 0, pid);
+        if handle == 0 {
+            return false;
+        }
+        let ok = TerminateProcess(handle, 1) != 0;
+        CloseHandle(handle);
+        ok
+    }
+}
+
+/// Handles `session create|delete|switch|kill|info <name>` against the session files
+/// in `socket_dir`, returning the data to report on success.
+/// `info` falls back to the `current` session when no name is given.
+fn session_subcommand(
+    sub: &str,
+    name: Option<&str>,
+    current: &str,
+    socket_dir: &Path,
+) -> Result<serde_json::Value, String> {
+    let name = name.or((sub == "info").then_some(current));
+    let name = name.ok_or_else(|| {
+        format!(
+            "Missing session name. Usage: agent-browser session {} <name>",
+            sub
+        )
+    })?;
+    if !validation::is_valid_session_name(name) {
+        return Err(validation::session_name_error(name));
+    }
+    let pid = read_session_pid(socket_dir, name);
+    let running = pid.is_some_and(is_process_running);
+
+    match sub {
+        "create" => Ok(json!({ "session": name, "running": running })),
+        "switch" => Ok(json!({
+            "session": name,
+            "env": format!("AGENT_BROWSER_SESSION={}", name)
+        })),
+        "delete" => {
+            if pid.is_none() {
+                return Err(format!("No session '{}' found", name));
+            }
+            if running {
+                return Err(format!(
+                    "Session '{}' is still running. Use 'agent-browser session kill {}' first",
+                    name, name
+                ));
+            }
+            for ext in ["pid", "sock", "port", "log"] {
+                let _ = fs::remove_file(socket_dir.join(format!("{}.{}", name, ext)));
+            }
+            Ok(json!({ "session": name, "deleted": true }))
+        }
+        "info" => {
+            let Some(pid) = pid.filter(|_| running) else {
+                return Err(format!("Session '{}' is not running", name));
+            };
+            let uptime = fs::metadata(socket_dir.join(format!("{}.pid", name)))
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|started| SystemTime::now().duration_since(started).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            #[cfg(unix)]
+            let socket_path = socket_dir
+                .join(format!("{}.sock", name))
+                .display()
+                .to_string();
+            #[cfg(windows)]
+            let socket_path = format!("127.0.0.1:{}", connection::get_port_for_session(name));
+            Ok(json!({
+                "session": name,
+                "pid": pid,
+                "uptimeSeconds": uptime,
+                "socketPath": socket_path
+            }))
+        }
+        "kill" => {
+            let Some(pid) = pid.filter(|_| running) else {
+                return Err(format!("Session '{}' is not running", name));
+            };
+            if !terminate_process(pid) {
+                return Err(format!("Failed to stop session '{}' (PID {})", name, pid));
+            }
+            Ok(json!({ "session": name, "pid": pid, "killed": true }))
+        }
+        _ => unreachable!("unhandled session subcommand: {}", sub),
+    }
+}
+
+/// A session with a live daemon, as found by `scan_sessions`.
+struct SessionEntry {
+    name: String,
+    /// When the daemon started, taken from its PID file's mtime.
+    started: Option<SystemTime>,
+}
+
+/// Scans `socket_dir` for sessions whose daemon is still running, sorted by name.
+/// With `clean`, files left behind by dead daemons are removed and the names of
+/// those sessions are returned as the second element.
+fn scan_sessions(socket_dir: &Path, clean: bool) -> (Vec<SessionEntry>, Vec<String>) {
+    let mut sessions = Vec::new();
+    let mut removed = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(socket_dir) {
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            // Look for pid files in socket directory
+            let Some(name) = file_name.strip_suffix(".pid").filter(|n| !n.is_empty()) else {
+                continue;
+            };
+            // Check if session is actually running
+            if read_session_pid(socket_dir, name).is_some_and(is_process_running) {
+                let started = entry.metadata().and_then(|m| m.modified()).ok();
+                sessions.push(SessionEntry {
+                    name: name.to_string(),
+                    started,
+                });
+            } else if clean {
+                for ext in ["pid", "sock", "port", "log"] {
+                    let _ = fs::remove_file(socket_dir.join(format!("{}.{}", name, ext)));
+                }
+                removed.push(name.to_string());
+            }
+        }
+    }
+
+    sessions.sort_by(|a, b| a.name.cmp(&b.name));
+    removed.sort();
+    (sessions, removed)
+}
+
+/// Formats the `session list` table, marking the current session and showing
+/// how long ago each one started relative to `now`.
+fn format_session_list(sessions: &[SessionEntry], current: &str, now: SystemTime) -> String {
+    if sessions.is_empty() {
+        return "No active sessions\n".to_string();
+    }
+    let width = sessions.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    let mut out = String::from("Active sessions:\n");
+    for s in sessions {
+        let marker = if s.name == current {
+            color::cyan("→")
+        } else {
+            " ".to_string()
+        };
+        match s.started.and_then(|t| now.duration_since(t).ok()) {
+            Some(elapsed) => out.push_str(&format!(
+                "{} {:<width$}  {}\n",
+                marker,
+                s.name,
+                color::dim(&format!(
+                    "started {}",
+                    format_relative_time(elapsed.as_secs())
+                )),
+                width = width
+            )),
+            None => out.push_str(&format!("{} {}\n", marker, s.name)),
+        }
+    }
+    out
+}
+
+/// Formats an elapsed number of seconds as a short relative time, e.g. "3h ago".
+fn format_relative_time(secs: u64) -> String {
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+/// Formats a timestamp as an ISO 8601 UTC string, e.g. "2024-01-15T09:30:00Z".
+fn format_iso8601(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = (secs / 86400, secs % 86400);
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Wraps a session subcommand result in the CLI's JSON response envelope.
+fn session_json_response(result: &Result<serde_json::Value, String>) -> serde_json::Value {
+    match result {
+        Ok(data) => json!({ "success": true, "data": data }),
+        Err(e) => json!({ "success": false, "error": e }),
+    }
+}
+
+/// Formats an uptime in seconds as e.g. "1h 2m 3s", dropping leading zero units.
+fn format_uptime(secs: u64) -> String {
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, s) => format!("{}h {}m {}s", h, m, s),
+    }
+}
+
+/// Formats `session info` data as one labelled field per line.
+fn format_session_info(data: &serde_json::Value) -> String {
+    format!(
+        "Session: {}\nPID:     {}\nUptime:  {}\nSocket:  {}\n",
+        data["session"].as_str().unwrap_or_default(),
+        data["pid"],
+        format_uptime(data["uptimeSeconds"].as_u64().unwrap_or(0)),
+        data["socketPath"].as_str().unwrap_or_default()
+    )
+}
+
+/// Starts the session's daemon with the launch options from `flags`,
+/// or reuses it if it is already running.
+fn start_daemon(flags: &Flags) -> Result<DaemonResult, String> {
+    ensure_daemon(
+        &flags.session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+        flags.args.as_deref(),
+        flags.user_agent.as_deref(),
+        flags.proxy.as_deref(),
+        flags.proxy_bypass.as_deref(),
+        flags.ignore_https_errors,
+        flags.allow_file_access,
+        flags.profile.as_deref(),
+        flags.state.as_deref(),
+        flags.provider.as_deref(),
+        flags.device.as_deref(),
+        flags.session_name.as_deref(),
+        flags.download_path.as_deref(),
+    )
+}
+
+/// Reports whether `session`'s daemon is running, with its PID and uptime when it is.
+fn daemon_status(session: &str, socket_dir: &Path) -> serde_json::Value {
+    let Some(pid) = read_session_pid(socket_dir, session).filter(|&pid| is_process_running(pid))
+    else {
+        return json!({ "session": session, "running": false });
+    };
+    let uptime = fs::metadata(socket_dir.join(format!("{}.pid", session)))
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|started| SystemTime::now().duration_since(started).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    json!({ "session": session, "running": true, "pid": pid, "uptimeSeconds": uptime })
+}
+
+/// Stops `session`'s daemon and waits up to `wait` for it to exit, then removes
+/// its PID and socket files. Stopping a daemon that is not running is not an error.
+fn daemon_stop(
+    session: &str,
+    socket_dir: &Path,
+    wait: Duration,
+) -> Result<serde_json::Value, String> {
+    let Some(pid) = read_session_pid(socket_dir, session).filter(|&pid| is_process_running(pid))
+    else {
+        return Ok(json!({ "session": session, "stopped": false }));
+    };
+    if !terminate_process(pid) {
+        return Err(format!(
+            "Failed to stop daemon for session '{}' (PID {})",
+            session, pid
+        ));
+    }
+    let deadline = SystemTime::now() + wait;
+    while is_process_running(pid) {
+        if SystemTime::now() >= deadline {
+            return Err(format!(
+                "Daemon for session '{}' (PID {}) did not exit",
+                session, pid
+            ));
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    for ext in ["pid", "sock", "port"] {
+        let _ = fs::remove_file(socket_dir.join(format!("{}.{}", session, ext)));
+    }
+    Ok(json!({ "session": session, "stopped": true, "pid": pid }))
+}
+
+/// Reads the stderr captured from `session`'s daemon at `path`.
+fn daemon_logs(session: &str, path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("No daemon log for session '{}'", session),
+        _ => format!("Failed to read {}: {}", path.display(), e),
+    })
+}
+
+/// Formats `daemon status` data for human output.
+fn format_daemon_status(data: &serde_json::Value) -> String {
+    let session = data["session"].as_str().unwrap_or_default();
+    if data["running"] != true {
+        return format!("Daemon not running (session: {})\n", session);
+    }
+    format!(
+        "Daemon running (session: {})\nPID:     {}\nUptime:  {}\n",
+        session,
+        data["pid"],
+        format_uptime(data["uptimeSeconds"].as_u64().unwrap_or(0))
+    )
+}
+
+fn run_daemon(sub: &str, flags: &Flags) {
+    let socket_dir = get_socket_dir();
+    let session = flags.session.as_str();
+    let stop_wait = Duration::from_secs(5);
+
+    let result = match sub {
+        "status" => Ok(daemon_status(session, &socket_dir)),
+        "stop" => daemon_stop(session, &socket_dir, stop_wait),
+        "logs" => daemon_logs(session, &get_log_path(session))
+            .map(|log| json!({ "session": session, "log": log })),
+        "start" | "restart" => {
+            let stopped = if sub == "restart" {
+                daemon_stop(session, &socket_dir, stop_wait)
+            } else {
+                Ok(json!({}))
+            };
+            stopped.and_then(|_| start_daemon(flags)).map(|started| {
+                let mut data = daemon_status(session, &socket_dir);
+                data["alreadyRunning"] = json!(started.already_running);
+                data
+            })
+        }
+        _ => unreachable!("unhandled daemon subcommand: {}", sub),
+    };
+
+    if flags.json {
+        println!("{}", session_json_response(&result));
+        if result.is_err() {
+            exit(1);
+        }
+        return;
+    }
+    let data = match result {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("{} {}", color::error_indicator(), e);
+            exit(1);
+        }
+    };
+    if flags.quiet {
+        return;
+    }
+    match sub {
+        "status" => print!("{}", format_daemon_status(&data)),
+        "logs" => print!("{}", data["log"].as_str().unwrap_or_default()),
+        "stop" if data["stopped"] != true => {
+            println!("Daemon not running (session: {})", session)
+        }
+        "stop" => println!(
+            "{} Daemon stopped (session: {}, PID {})",
+            color::success_indicator(),
+            session,
+            data["pid"]
+        ),
+        _ if data["alreadyRunning"] == true => println!(
+            "{} Daemon already running (session: {}, PID {})",
+            color::success_indicator(),
+            session,
+            data["pid"]
+        ),
+        _ => println!(
+            "{} Daemon started (session: {}, PID {})",
+            color::success_indicator(),
+            session,
+            data["pid"]
+        ),
+    }
+}
+
+fn run_session(args: &[String], session: &str, json_mode: bool, quiet: bool) {
     let subcommand = args.get(1).map(|s| s.as_str());
 
     match subcommand {
         Some("list") => {
-            let socket_dir = get_socket_dir();
-            let mut sessions: Vec<String> = Vec::new();
-
-            if let Ok(entries) = fs::read_dir(&socket_dir) {
-                for entry in entries.flatten() {
-                    let name = entry.file_name().to_string_lossy().to_string();
-                    // Look for pid files in socket directory
-                    if name.ends_with(".pid") {
-                        let session_name = name.strip_suffix(".pid").unwrap_or("");
-                        if !session_name.is_empty() {
-                            // Check if session is actually running
-                            let pid_path = socket_dir.join(&name);
-                            if let Ok(pid_str) = fs::read_to_string(&pid_path) {
-                                if let Ok(pid) = pid_str.trim().parse::<u32>() {
-                                    #[cfg(unix)]
-                                    let running = unsafe { libc::kill(pid as i32, 0) == 0 };
-                                    #[cfg(windows)]
-                                    let running = unsafe {
-                                        let handle =
-                                            OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
-                                        if handle != 0 {
-                                            CloseHandle(handle);
-                                            true
-                                        } else {
-                                            false
-                                        }
-                                    };
-                                    if running {
-                                        sessions.push(session_name.to_string());
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+            let clean = args.iter().any(|a| a == "--clean");
+            let (sessions, removed) = scan_sessions(&get_socket_dir(), clean);
 
             if json_mode {
-                println!(
-                    r#"{{"success":true,"data":{{"sessions":{}}}}}"#,
-                    serde_json::to_string(&sessions).unwrap_or_default()
+                let entries: Vec<serde_json::Value> = sessions
+                    .iter()
+                    .map(|s| {
+                        json!({
+                            "name": s.name,
+                            "startedAt": s.started.map(format_iso8601),
+                            "current": s.name == session
+                        })
+                    })
+                    .collect();
+                let mut data = json!({ "sessions": entries });
+                if clean {
+                    data["removed"] = json!(removed);
+                }
+                println!("{}", json!({ "success": true, "data": data }));
+            } else if !quiet {
+                if !removed.is_empty() {
+                    println!(
+                        "{} Removed {} stale session file(s): {}",
+                        color::success_indicator(),
+                        removed.len(),
+                        removed.join(", ")
+                    );
+                }
+                print!(
+                    "{}",
+                    format_session_list(&sessions, session, SystemTime::now())
                 );
-            } else if sessions.is_empty() {
-                println!("No active sessions");
-            } else {
-                println!("Active sessions:");
-                for s in &sessions {
-                    let marker = if s == session {
-                        color::cyan("→")
-                    } else {
-                        " ".to_string()
-                    };
-                    println!("{} {}", marker, s);
+            }
+        }
+        Some(sub @ ("create" | "delete" | "switch" | "kill" | "info")) => {
+            let name = args.get(2).map(|s| s.as_str());
+            let result = session_subcommand(sub, name, session, &get_socket_dir());
+            if json_mode {
+                println!("{}", session_json_response(&result));
+                if result.is_err() {
+                    exit(1);
+                }
+                return;
+            }
+            match result {
+                Ok(data) => {
+                    let name = data["session"].as_str().unwrap_or_default();
+                    match sub {
+                        "info" => print!("{}", format_session_info(&data)),
+                        "create" => println!(
+                            "{} Session {} ready. Use --session {} to run commands in it",
+                            color::success_indicator(),
+                            color::bold(name),
+                            name
+                        ),
+                        "switch" => {
+                            println!("{}", name);
+                            println!(
+                                "  {}",
+                                color::dim(&format!(
+                                    "export {}",
+                                    data["env"].as_str().unwrap_or("")
+                                ))
+                            );
+                        }
+                        "delete" => println!(
+                            "{} Session {} deleted",
+                            color::success_indicator(),
+                            color::bold(name)
+                        ),
+                        _ => println!(
+                            "{} Session {} stopped (PID {})",
+                            color::success_indicator(),
+                            color::bold(name),
+                            data["pid"]
+                        ),
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{} {}", color::error_indicator(), e);
+                    exit(1);
                 }
             }
         }
@@ -124,6 +785,16 @@ fn run_session(args: &[String], session: &str, json_mode: bool) {
     }
 }
 
+/// `--output-file` path and whether to keep output exactly as printed (JSON)
+/// instead of stripping colors.
+static OUTPUT_FILE: OnceLock<(String, bool)> = OnceLock::new();
+
+/// Exits the process, first writing captured output to `--output-file`.
+fn exit(code: i32) -> ! {
+    save_output_file();
+    std::process::exit(code)
+}
+
 fn main() {
     // Ignore SIGPIPE to prevent panic when piping to head/tail
     #[cfg(unix)]
@@ -133,10 +804,24 @@ fn main() {
 
     let args: Vec<String> = env::args().skip(1).collect();
     let flags = parse_flags(&args);
+    if let Some(path) = flags.output_file.clone() {
+        let _ = OUTPUT_FILE.set((path, flags.json));
+        output::start_capture();
+    }
+    run(args, flags);
+    save_output_file();
+}
+
+fn run(args: Vec<String>, flags: Flags) {
+    color::set_enabled(flags.color);
     let clean = clean_args(&args);
 
+    if has_version_flag(&clean) {
+        print_version(flags.json);
+        return;
+    }
+
     let has_help = args.iter().any(|a| a == "--help" || a == "-h");
-    let has_version = args.iter().any(|a| a == "--version" || a == "-V");
 
     if has_help {
         if let Some(cmd) = clean.first() {
@@ -148,13 +833,14 @@ fn main() {
         return;
     }
 
-    if has_version {
-        print_version();
+    if clean.is_empty() {
+        print_help();
         return;
     }
 
-    if clean.is_empty() {
-        print_help();
+    // Handle version separately (doesn't need daemon)
+    if clean.first().map(|s| s.as_str()) == Some("version") {
+        print_version(flags.json);
         return;
     }
 
@@ -167,33 +853,53 @@ fn main() {
 
     // Handle session separately (doesn't need daemon)
     if clean.first().map(|s| s.as_str()) == Some("session") {
-        run_session(&clean, &flags.session, flags.json);
+        run_session(&clean, &flags.session, flags.json, flags.quiet);
         return;
     }
 
+    let clean = match expand_alias(&clean, &flags.aliases) {
+        Ok(Some(expanded)) => expanded,
+        Ok(None) => clean,
+        Err(msg) => {
+            if flags.json {
+                println!("{}", json!({ "success": false, "error": msg }));
+            } else {
+                eprintln!("{} {}", color::error_indicator(), msg);
+            }
+            exit(1);
+        }
+    };
+
     let cmd = match parse_command(&clean, &flags) {
         Ok(c) => c,
         Err(e) => {
             if flags.json {
-                let error_type = match &e {
-                    ParseError::UnknownCommand { .. } => "unknown_command",
-                    ParseError::UnknownSubcommand { .. } => "unknown_subcommand",
-                    ParseError::MissingArguments { .. } => "missing_arguments",
-                    ParseError::InvalidValue { .. } => "invalid_value",
-                    ParseError::InvalidSessionName { .. } => "invalid_session_name",
-                };
-                println!(
-                    r#"{{"success":false,"error":"{}","type":"{}"}}"#,
-                    e.format().replace('\n', " "),
-                    error_type
-                );
+                println!("{}", parse_error_json(&e));
             } else {
-                eprintln!("{}", color::red(&e.format()));
+                eprintln!("{}", color::red(&e.to_string()));
             }
             exit(1);
         }
     };
 
+    // config validate only reads local files; no daemon needed
+    if cmd.get("action").and_then(|v| v.as_str()) == Some("config_validate") {
+        let strict = cmd.get("strict").and_then(|v| v.as_bool()).unwrap_or(false);
+        if !run_config_validate(&args, flags.json, strict) {
+            exit(1);
+        }
+        return;
+    }
+
+    // Completion scripts are generated locally; no daemon needed
+    if cmd.get("action").and_then(|v| v.as_str()) == Some("completions") {
+        let shell = cmd.get("shell").and_then(|v| v.as_str()).unwrap_or("");
+        if let Some(script) = completions::completion_script(shell) {
+            print!("{}", script);
+        }
+        return;
+    }
+
     // Validate session name before starting daemon
     if let Some(ref name) = flags.session_name {
         if !validation::is_valid_session_name(name) {
@@ -210,24 +916,20 @@ fn main() {
         }
     }
 
-    let daemon_result = match ensure_daemon(
-        &flags.session,
-        flags.headed,
-        flags.executable_path.as_deref(),
-        &flags.extensions,
-        flags.args.as_deref(),
-        flags.user_agent.as_deref(),
-        flags.proxy.as_deref(),
-        flags.proxy_bypass.as_deref(),
-        flags.ignore_https_errors,
-        flags.allow_file_access,
-        flags.profile.as_deref(),
-        flags.state.as_deref(),
-        flags.provider.as_deref(),
-        flags.device.as_deref(),
-        flags.session_name.as_deref(),
-        flags.download_path.as_deref(),
-    ) {
+    // --dry-run stops here: no daemon is started or contacted
+    if let Some(output) = dry_run(&flags, &cmd) {
+        print!("{}", output);
+        return;
+    }
+
+    // daemon start|stop|status|restart|logs manages the daemon directly
+    if cmd.get("action").and_then(|v| v.as_str()) == Some("daemon") {
+        let sub = cmd.get("subcommand").and_then(|v| v.as_str()).unwrap_or("");
+        run_daemon(sub, &flags);
+        return;
+    }
+
+    let daemon_result = match start_daemon(&flags) {
         Ok(result) => result,
         Err(e) => {
             if flags.json {
@@ -368,7 +1070,7 @@ fn main() {
             launch_cmd["downloadPath"] = json!(dp);
         }
 
-        let err = match send_command(launch_cmd, &flags.session) {
+        let err = match send_command(launch_cmd, &flags.session, timeouts(&flags)) {
             Ok(resp) if resp.success => None,
             Ok(resp) => Some(
                 resp.error
@@ -458,7 +1160,7 @@ fn main() {
             launch_cmd["downloadPath"] = json!(dp);
         }
 
-        let err = match send_command(launch_cmd, &flags.session) {
+        let err = match send_command(launch_cmd, &flags.session, timeouts(&flags)) {
             Ok(resp) if resp.success => None,
             Ok(resp) => Some(
                 resp.error
@@ -489,7 +1191,7 @@ fn main() {
             launch_cmd["colorScheme"] = json!(cs);
         }
 
-        let err = match send_command(launch_cmd, &flags.session) {
+        let err = match send_command(launch_cmd, &flags.session, timeouts(&flags)) {
             Ok(resp) if resp.success => None,
             Ok(resp) => Some(
                 resp.error
@@ -588,7 +1290,7 @@ fn main() {
             launch_cmd["downloadPath"] = json!(dp);
         }
 
-        match send_command(launch_cmd, &flags.session) {
+        match send_command(launch_cmd, &flags.session, timeouts(&flags)) {
             Ok(resp) if !resp.success => {
                 // Launch command failed (e.g., invalid state file, profile error)
                 let error_msg = resp
@@ -619,37 +1321,461 @@ fn main() {
         }
     }
 
-    match send_command(cmd.clone(), &flags.session) {
+    if cmd.get("follow").and_then(|v| v.as_bool()) == Some(true) {
+        run_follow(&cmd, &flags);
+    }
+
+    let send_timeouts = command_timeouts(&flags, &cmd);
+    let (result, attempt) = send_with_retry(flags.retry, flags.retry_delay_ms, || {
+        send_command(cmd.clone(), &flags.session, send_timeouts)
+    });
+    match result {
         Ok(resp) => {
+            let resp = count_mismatch(&cmd, assertion_failure(&cmd, resp));
+            let resp = zero_count_failure(&cmd, validate_failure(&cmd, resp));
+            let resp = missing_storage_key(&cmd, resp);
             let success = resp.success;
             // Extract action for context-specific output handling
             let action = cmd.get("action").and_then(|v| v.as_str());
-            print_response(&resp, flags.json, action);
-            if !success {
+            match cmd.get("format").and_then(|v| v.as_str()) {
+                Some(format)
+                    if action == Some("requests")
+                        && !flags.json
+                        && !flags.ndjson
+                        && !flags.quiet =>
+                {
+                    print_requests(&resp, format, action)
+                }
+                _ if flags.json && flags.retry > 0 => {
+                    println!("{}", response_with_attempt(&resp, attempt))
+                }
+                _ => print_response(&resp, flags.output_format, flags.quiet, action),
+            }
+            if !success && flags.screenshot_on_failure {
+                capture_failure_screenshot(&flags, &cmd);
+            }
+            let console_errors = if flags.fail_on_console_error {
+                console_errors(&resp)
+            } else {
+                Vec::new()
+            };
+            if !console_errors.is_empty() && !flags.json && !flags.ndjson {
+                eprint!("{}", format_console_errors(&console_errors));
+            }
+            if !success
+                || negated_check_failed(&cmd, &resp)
+                || output::is_missing_attribute(&resp)
+                || !console_errors.is_empty()
+            {
                 exit(1);
             }
         }
         Err(e) => {
-            if flags.json {
-                println!(r#"{{"success":false,"error":"{}"}}"#, e);
-            } else {
-                eprintln!("{} {}", color::error_indicator(), e);
-            }
+            print_send_error(&e, &flags, (flags.retry > 0).then_some(attempt));
             exit(1);
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_parse_proxy_simple() {
-        let result = parse_proxy("http://proxy.com:8080");
-        assert_eq!(result["server"], "http://proxy.com:8080");
-        assert!(result.get("username").is_none());
-        assert!(result.get("password").is_none());
+/// Prints an error from talking to the daemon in the active output mode.
+fn print_send_error(e: &str, flags: &Flags, attempt: Option<u32>) {
+    if flags.json {
+        println!("{}", send_error_json(e, attempt));
+    } else if flags.ndjson {
+        println!("{}", json!({ "type": "error", "error": e }));
+    } else {
+        eprintln!("{} {}", color::error_indicator(), e);
+    }
+}
+
+/// Set by Ctrl-C while a `--follow` stream is open so it can stop cleanly.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Streams the events of a `--follow` command until the daemon closes the
+/// connection or the user presses Ctrl-C, then exits.
+fn run_follow(cmd: &serde_json::Value, flags: &Flags) -> ! {
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+
+    let action = cmd.get("action").and_then(|v| v.as_str()).unwrap_or("");
+    let kind = follow_event_kind(action);
+    // `network requests --output-format json|ndjson` prints the raw objects
+    let format = match cmd.get("format").and_then(|v| v.as_str()) {
+        Some("json" | "ndjson") => OutputFormat::Json,
+        Some(_) => OutputFormat::Human,
+        None => flags.output_format,
+    };
+    let result = stream_command(
+        cmd,
+        &flags.session,
+        timeouts(flags),
+        &INTERRUPTED,
+        |event| follow_event(&event, kind, format),
+    );
+    if let Err(e) = result {
+        print_send_error(&e, flags, None);
+        exit(1);
+    }
+    exit(0);
+}
+
+/// NDJSON event type of the entries streamed for a `--follow` action.
+fn follow_event_kind(action: &str) -> &'static str {
+    match action {
+        "requests" => "request",
+        "console" => "log",
+        "errors" => "page_error",
+        _ => "event",
+    }
+}
+
+/// Handles one line of a `--follow` stream. Lines carrying `success` are
+/// daemon responses rather than events: a failure ends the stream with its
+/// error and an acknowledgement is skipped.
+fn follow_event(event: &serde_json::Value, kind: &str, format: OutputFormat) -> Result<(), String> {
+    match event.get("success").and_then(|v| v.as_bool()) {
+        Some(false) => Err(event
+            .get("error")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Unknown error")
+            .to_string()),
+        Some(true) => Ok(()),
+        None => {
+            output::print_stream_event(event, kind, format);
+            Ok(())
+        }
+    }
+}
+
+/// JavaScript errors the daemon reported in `data.consoleErrors`. Entries are
+/// either strings or objects with a `text` field.
+fn console_errors(resp: &Response) -> Vec<String> {
+    let Some(errors) = resp
+        .data
+        .as_ref()
+        .and_then(|d| d.get("consoleErrors"))
+        .and_then(|e| e.as_array())
+    else {
+        return Vec::new();
+    };
+    errors
+        .iter()
+        .map(|e| match e {
+            serde_json::Value::String(s) => s.clone(),
+            other => other
+                .get("text")
+                .and_then(|t| t.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| other.to_string()),
+        })
+        .collect()
+}
+
+/// Formats the errors that fail a command under --fail-on-console-error.
+fn format_console_errors(errors: &[String]) -> String {
+    let mut out = format!(
+        "{} Page logged {} console error(s):\n",
+        color::error_indicator(),
+        errors.len()
+    );
+    for e in errors {
+        out.push_str(&format!("  {}\n", e));
+    }
+    out
+}
+
+/// Extra time the daemon gets beyond a command's own `timeout`, so its timeout
+/// error arrives before the socket read gives up.
+const SEND_TIMEOUT_MARGIN_MS: u64 = 5_000;
+
+fn timeouts(flags: &Flags) -> Timeouts {
+    Timeouts {
+        connect_ms: flags.connect_timeout_ms,
+        send_ms: flags.send_timeout_ms.unwrap_or(DEFAULT_SEND_TIMEOUT_MS),
+    }
+}
+
+/// Like `timeouts`, but unless --send-timeout was given the response timeout
+/// grows to cover the command's own `timeout` plus a margin.
+fn command_timeouts(flags: &Flags, cmd: &serde_json::Value) -> Timeouts {
+    let mut timeouts = timeouts(flags);
+    if flags.send_timeout_ms.is_none() {
+        if let Some(ms) = cmd.get("timeout").and_then(|v| v.as_u64()) {
+            timeouts.send_ms = timeouts
+                .send_ms
+                .max(ms.saturating_add(SEND_TIMEOUT_MARGIN_MS));
+        }
+    }
+    timeouts
+}
+
+/// JSON error for a command the daemon never answered. Expired
+/// --connect-timeout/--send-timeout deadlines are tagged `"type": "timeout"`.
+fn send_error_json(e: &str, attempt: Option<u32>) -> serde_json::Value {
+    let mut err = json!({ "success": false, "error": e });
+    if is_timeout_error(e) {
+        err["type"] = json!("timeout");
+    }
+    if let Some(attempt) = attempt {
+        err["attempt"] = json!(attempt);
+    }
+    err
+}
+
+/// Problems found by `config validate` across the config files it read.
+#[derive(Debug, Default)]
+struct ConfigReport {
+    /// (file, key, known key it probably meant)
+    unknown_keys: Vec<(String, String, Option<&'static str>)>,
+    errors: Vec<String>,
+}
+
+fn check_config_files(paths: &[PathBuf]) -> ConfigReport {
+    let mut report = ConfigReport::default();
+    for path in paths {
+        let file = path.display().to_string();
+        let checked = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|raw| flags::unknown_config_keys(&raw));
+        match checked {
+            Ok(keys) => report.unknown_keys.extend(
+                keys.into_iter()
+                    .map(|(key, hint)| (file.clone(), key, hint)),
+            ),
+            Err(e) => report.errors.push(format!("{}: {}", file, e)),
+        }
+    }
+    report
+}
+
+/// Runs `config validate`, returning whether the config is valid. Unknown keys
+/// are warnings unless `strict` is set.
+fn run_config_validate(args: &[String], json_mode: bool, strict: bool) -> bool {
+    let mut report = check_config_files(&flags::config_file_paths(args));
+    if let Err(e) = flags::load_config(args) {
+        report.errors.push(e);
+    }
+    let valid = report.errors.is_empty() && (!strict || report.unknown_keys.is_empty());
+
+    if json_mode {
+        let keys: Vec<&str> = report
+            .unknown_keys
+            .iter()
+            .map(|(_, k, _)| k.as_str())
+            .collect();
+        let mut out = json!({ "success": valid, "unknownKeys": keys });
+        if !report.errors.is_empty() {
+            out["error"] = json!(report.errors.join("; "));
+        }
+        println!("{}", out);
+        return valid;
+    }
+
+    for (file, key, hint) in &report.unknown_keys {
+        let hint = hint
+            .map(|k| format!(" (did you mean {}?)", k))
+            .unwrap_or_default();
+        eprintln!(
+            "{} Unknown config key \"{}\" in {}{}",
+            color::warning_indicator(),
+            key,
+            file,
+            hint
+        );
+    }
+    for e in &report.errors {
+        eprintln!("{} {}", color::error_indicator(), e);
+    }
+    if valid {
+        println!("{} Valid", color::success_indicator());
+    } else {
+        println!("{} Invalid", color::error_indicator());
+    }
+    valid
+}
+
+/// Expands a config alias named by the first argument, returning None when it
+/// is not an alias. `$1`, `$2`, ... in the alias are replaced by the arguments
+/// after it; an alias without placeholders gets those arguments appended.
+fn expand_alias(
+    args: &[String],
+    aliases: &HashMap<String, String>,
+) -> Result<Option<Vec<String>>, String> {
+    let Some((name, params)) = args.split_first() else {
+        return Ok(None);
+    };
+    let Some(template) = aliases.get(name) else {
+        return Ok(None);
+    };
+    let words = split_script_line(template).map_err(|e| format!("alias {}: {}", name, e))?;
+    if words.is_empty() {
+        return Err(format!("alias {} is empty", name));
+    }
+
+    let mut used_placeholder = false;
+    let mut expanded = Vec::with_capacity(words.len() + params.len());
+    for word in words {
+        let mut out = String::new();
+        let mut chars = word.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '$' || !chars.peek().is_some_and(|d| d.is_ascii_digit()) {
+                out.push(c);
+                continue;
+            }
+            let mut digits = String::new();
+            while let Some(d) = chars.next_if(|d| d.is_ascii_digit()) {
+                digits.push(d);
+            }
+            let n: usize = digits.parse().unwrap_or(0);
+            let value = n
+                .checked_sub(1)
+                .and_then(|i| params.get(i))
+                .ok_or_else(|| {
+                    format!(
+                        "alias {} uses ${} but {} argument(s) were given",
+                        name,
+                        n,
+                        params.len()
+                    )
+                })?;
+            out.push_str(value);
+            used_placeholder = true;
+        }
+        expanded.push(out);
+    }
+    if !used_placeholder {
+        expanded.extend(params.iter().cloned());
+    }
+    Ok(Some(expanded))
+}
+
+/// `--json` output for a command that failed to parse. `type` is kept at the
+/// top level for scripts that matched on it before `details` existed.
+fn parse_error_json(e: &ParseError) -> serde_json::Value {
+    let details = serde_json::to_value(e).unwrap_or_default();
+    json!({
+        "success": false,
+        "error": e.to_string(),
+        "type": details["type"],
+        "details": details,
+    })
+}
+
+/// `--dry-run` output for `cmd`, or None when the command should really be
+/// sent. Human mode pretty-prints the command; JSON mode wraps it as
+/// `{"success":true,"dryRun":true,"command":{...}}`.
+fn dry_run(flags: &Flags, cmd: &serde_json::Value) -> Option<String> {
+    if !flags.dry_run {
+        return None;
+    }
+    let output = if flags.json {
+        json!({ "success": true, "dryRun": true, "command": cmd }).to_string()
+    } else {
+        serde_json::to_string_pretty(cmd).unwrap_or_default()
+    };
+    Some(output + "\n")
+}
+
+/// Where `--screenshot-on-failure` saves the screenshot for a failed `action`,
+/// e.g. `failure-click-2024-01-15T09-30-00Z.png`.
+fn failure_screenshot_path(dir: &Path, action: &str, time: SystemTime) -> PathBuf {
+    let action: String = action
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let stamp = format_iso8601(time).replace(':', "-");
+    dir.join(format!("failure-{}-{}.png", action, stamp))
+}
+
+fn failure_screenshot_cmd(path: &Path) -> serde_json::Value {
+    json!({
+        "id": gen_id(),
+        "action": "screenshot",
+        "path": path.to_string_lossy(),
+        "fullPage": false,
+    })
+}
+
+/// Saves a screenshot of the page after a failed command. The path is always
+/// printed to stderr, even with `--quiet`, so it shows up in CI logs.
+fn capture_failure_screenshot(flags: &Flags, cmd: &serde_json::Value) {
+    let dir = flags
+        .screenshot_on_failure_dir
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir);
+    let action = cmd
+        .get("action")
+        .and_then(|v| v.as_str())
+        .unwrap_or("command");
+    let path = failure_screenshot_path(&dir, action, SystemTime::now());
+    let result = fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))
+        .and_then(|_| {
+            send_command(
+                failure_screenshot_cmd(&path),
+                &flags.session,
+                timeouts(flags),
+            )
+        });
+    match result {
+        Ok(resp) if resp.success => {
+            eprintln!("Failure screenshot saved to {}", path.display())
+        }
+        Ok(resp) => eprintln!(
+            "{} Could not save failure screenshot: {}",
+            color::warning_indicator(),
+            resp.error.as_deref().unwrap_or("unknown error")
+        ),
+        Err(e) => eprintln!(
+            "{} Could not save failure screenshot: {}",
+            color::warning_indicator(),
+            e
+        ),
+    }
+}
+
+/// Copies the output captured for this command to `--output-file`. JSON mode
+/// writes the JSON as printed; human mode writes plain text without colors.
+fn save_output_file() {
+    let (Some((path, json)), Some(captured)) = (OUTPUT_FILE.get(), output::take_capture()) else {
+        return;
+    };
+    let contents = if *json {
+        captured
+    } else {
+        output::strip_ansi(&captured)
+    };
+    if let Err(e) = output::write_output_file(path, &contents) {
+        if *json {
+            println!("{}", json!({ "success": false, "error": e }));
+        } else {
+            eprintln!("{} {}", color::error_indicator(), e);
+        }
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proxy_simple() {
+        let result = parse_proxy("http://proxy.com:8080");
+        assert_eq!(result["server"], "http://proxy.com:8080");
+        assert!(result.get("username").is_none());
+        assert!(result.get("password").is_none());
     }
 
     #[test]
@@ -697,4 +1823,1077 @@ mod tests {
         assert_eq!(result["username"], "user");
         assert_eq!(result["password"], "p@ss:w0rd");
     }
+
+    #[test]
+    fn test_negated_check_failed_when_false() {
+        let cmd = json!({ "action": "isvisible", "selector": "#modal", "negate": true });
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "visible": false })),
+            error: None,
+        };
+        assert!(negated_check_failed(&cmd, &resp));
+    }
+
+    #[test]
+    fn test_negated_check_passes_when_true() {
+        let cmd = json!({ "action": "ishidden", "selector": "#modal", "negate": true });
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "hidden": true })),
+            error: None,
+        };
+        assert!(!negated_check_failed(&cmd, &resp));
+    }
+
+    #[test]
+    fn test_negated_check_ignored_without_negate() {
+        let cmd = json!({ "action": "isvisible", "selector": "#modal" });
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "visible": false })),
+            error: None,
+        };
+        assert!(!negated_check_failed(&cmd, &resp));
+    }
+
+    fn session_test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("ab-test-session-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // A PID above the kernel's pid_max, so it never belongs to a live process
+    const DEAD_PID: u32 = 99_999_999;
+
+    #[test]
+    fn test_session_create_valid_name() {
+        let dir = session_test_dir("create");
+        let data = session_subcommand("create", Some("work"), "default", &dir).unwrap();
+        assert_eq!(data["session"], "work");
+        assert_eq!(data["running"], false);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_create_invalid_name() {
+        let dir = session_test_dir("create-invalid");
+        let err = session_subcommand("create", Some("bad name!"), "default", &dir).unwrap_err();
+        assert!(err.contains("Invalid session name"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_create_missing_name() {
+        let dir = session_test_dir("create-missing");
+        let err = session_subcommand("create", None, "default", &dir).unwrap_err();
+        assert!(err.contains("Missing session name"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_switch_suggests_env_var() {
+        let dir = session_test_dir("switch");
+        let data = session_subcommand("switch", Some("staging"), "default", &dir).unwrap();
+        assert_eq!(data["session"], "staging");
+        assert_eq!(data["env"], "AGENT_BROWSER_SESSION=staging");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_switch_invalid_name() {
+        let dir = session_test_dir("switch-invalid");
+        let err = session_subcommand("switch", Some("../etc"), "default", &dir).unwrap_err();
+        assert!(err.contains("Invalid session name"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_delete_stale_session() {
+        let dir = session_test_dir("delete-stale");
+        fs::write(dir.join("old.pid"), DEAD_PID.to_string()).unwrap();
+        fs::write(dir.join("old.sock"), "").unwrap();
+        fs::write(dir.join("old.log"), "").unwrap();
+        let data = session_subcommand("delete", Some("old"), "default", &dir).unwrap();
+        assert_eq!(data["deleted"], true);
+        assert!(!dir.join("old.pid").exists());
+        assert!(!dir.join("old.sock").exists());
+        assert!(!dir.join("old.log").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_delete_missing_session() {
+        let dir = session_test_dir("delete-missing");
+        let err = session_subcommand("delete", Some("ghost"), "default", &dir).unwrap_err();
+        assert!(err.contains("No session 'ghost' found"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_delete_running_session_refused() {
+        let dir = session_test_dir("delete-running");
+        fs::write(dir.join("live.pid"), std::process::id().to_string()).unwrap();
+        let err = session_subcommand("delete", Some("live"), "default", &dir).unwrap_err();
+        assert!(err.contains("still running"));
+        assert!(dir.join("live.pid").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_delete_invalid_name() {
+        let dir = session_test_dir("delete-invalid");
+        let err = session_subcommand("delete", Some("a/b"), "default", &dir).unwrap_err();
+        assert!(err.contains("Invalid session name"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_kill_not_running() {
+        let dir = session_test_dir("kill-stale");
+        fs::write(dir.join("old.pid"), DEAD_PID.to_string()).unwrap();
+        let err = session_subcommand("kill", Some("old"), "default", &dir).unwrap_err();
+        assert!(err.contains("is not running"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_kill_missing_name() {
+        let dir = session_test_dir("kill-missing");
+        let err = session_subcommand("kill", None, "default", &dir).unwrap_err();
+        assert!(err.contains("Usage: agent-browser session kill <name>"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_session_kill_running_process() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let dir = session_test_dir("kill-running");
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        fs::write(dir.join("victim.pid"), child.id().to_string()).unwrap();
+
+        let data = session_subcommand("kill", Some("victim"), "default", &dir).unwrap();
+        assert_eq!(data["killed"], true);
+        assert_eq!(data["pid"], child.id());
+        let status = child.wait().unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGTERM));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_read_session_pid_ignores_garbage() {
+        let dir = session_test_dir("pid-garbage");
+        fs::write(dir.join("junk.pid"), "not-a-pid").unwrap();
+        assert_eq!(read_session_pid(&dir, "junk"), None);
+        fs::write(dir.join("ok.pid"), "1234\n").unwrap();
+        assert_eq!(read_session_pid(&dir, "ok"), Some(1234));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_info_running_session() {
+        let dir = session_test_dir("info");
+        fs::write(dir.join("live.pid"), std::process::id().to_string()).unwrap();
+        let data = session_subcommand("info", Some("live"), "default", &dir).unwrap();
+        assert_eq!(data["session"], "live");
+        assert_eq!(data["pid"], std::process::id());
+        assert!(data["uptimeSeconds"].as_u64().unwrap() < 60);
+        #[cfg(unix)]
+        assert_eq!(
+            data["socketPath"],
+            dir.join("live.sock").display().to_string()
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_info_defaults_to_current_session() {
+        let dir = session_test_dir("info-current");
+        fs::write(dir.join("mine.pid"), std::process::id().to_string()).unwrap();
+        let data = session_subcommand("info", None, "mine", &dir).unwrap();
+        assert_eq!(data["session"], "mine");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_info_not_running() {
+        let dir = session_test_dir("info-stale");
+        fs::write(dir.join("old.pid"), DEAD_PID.to_string()).unwrap();
+        let err = session_subcommand("info", Some("old"), "default", &dir).unwrap_err();
+        assert!(err.contains("is not running"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_info_invalid_name() {
+        let dir = session_test_dir("info-invalid");
+        let err = session_subcommand("info", Some("no spaces"), "default", &dir).unwrap_err();
+        assert!(err.contains("Invalid session name"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_info_json_response() {
+        let dir = session_test_dir("info-json");
+        fs::write(dir.join("live.pid"), std::process::id().to_string()).unwrap();
+        let result = session_subcommand("info", Some("live"), "default", &dir);
+        let resp = session_json_response(&result);
+        assert_eq!(resp["success"], true);
+        assert_eq!(resp["data"]["session"], "live");
+        assert!(resp["data"]["pid"].is_u64());
+        assert!(resp["data"]["uptimeSeconds"].is_u64());
+        assert!(resp["data"]["socketPath"].is_string());
+
+        let err = session_json_response(&Err("Session 'x' is not running".to_string()));
+        assert_eq!(err["success"], false);
+        assert_eq!(err["error"], "Session 'x' is not running");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_format_session_info() {
+        let data = json!({
+            "session": "work",
+            "pid": 4242,
+            "uptimeSeconds": 3725,
+            "socketPath": "/tmp/agent-browser/work.sock"
+        });
+        assert_eq!(
+            format_session_info(&data),
+            "Session: work\nPID:     4242\nUptime:  1h 2m 5s\nSocket:  /tmp/agent-browser/work.sock\n"
+        );
+    }
+
+    #[test]
+    fn test_scan_sessions_keeps_stale_files_without_clean() {
+        let dir = session_test_dir("scan");
+        fs::write(dir.join("live.pid"), std::process::id().to_string()).unwrap();
+        fs::write(dir.join("dead.pid"), DEAD_PID.to_string()).unwrap();
+        let (sessions, removed) = scan_sessions(&dir, false);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].name, "live");
+        assert!(sessions[0].started.is_some());
+        assert!(removed.is_empty());
+        assert!(dir.join("dead.pid").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_scan_sessions_clean_removes_stale_files() {
+        let dir = session_test_dir("scan-clean");
+        fs::write(dir.join("live.pid"), std::process::id().to_string()).unwrap();
+        fs::write(dir.join("dead.pid"), DEAD_PID.to_string()).unwrap();
+        fs::write(dir.join("dead.sock"), "").unwrap();
+        fs::write(dir.join("dead.log"), "").unwrap();
+        let (sessions, removed) = scan_sessions(&dir, true);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(removed, vec!["dead".to_string()]);
+        assert!(!dir.join("dead.pid").exists());
+        assert!(!dir.join("dead.sock").exists());
+        assert!(!dir.join("dead.log").exists());
+        assert!(dir.join("live.pid").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_format_session_list_marks_current() {
+        let now = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(100_000);
+        let sessions = vec![
+            SessionEntry {
+                name: "agent1".to_string(),
+                started: Some(now - std::time::Duration::from_secs(300)),
+            },
+            SessionEntry {
+                name: "default".to_string(),
+                started: Some(now - std::time::Duration::from_secs(3 * 3600)),
+            },
+        ];
+        let out = format_session_list(&sessions, "default", now);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "Active sessions:");
+        assert!(lines[1].starts_with("  agent1"));
+        assert!(lines[1].contains("started 5m ago"));
+        assert!(lines[2].contains("→"));
+        assert!(lines[2].contains("default"));
+        assert!(lines[2].contains("started 3h ago"));
+    }
+
+    #[test]
+    fn test_format_session_list_empty() {
+        assert_eq!(
+            format_session_list(&[], "default", SystemTime::now()),
+            "No active sessions\n"
+        );
+    }
+
+    #[test]
+    fn test_format_relative_time() {
+        assert_eq!(format_relative_time(5), "just now");
+        assert_eq!(format_relative_time(125), "2m ago");
+        assert_eq!(format_relative_time(3 * 3600 + 59), "3h ago");
+        assert_eq!(format_relative_time(2 * 86400), "2d ago");
+    }
+
+    #[test]
+    fn test_format_iso8601() {
+        let epoch = SystemTime::UNIX_EPOCH;
+        assert_eq!(format_iso8601(epoch), "1970-01-01T00:00:00Z");
+        let t = epoch + std::time::Duration::from_secs(1_705_311_000);
+        assert_eq!(format_iso8601(t), "2024-01-15T09:30:00Z");
+        let leap = epoch + std::time::Duration::from_secs(951_782_400);
+        assert_eq!(format_iso8601(leap), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn test_assertion_failure_reports_expected_and_actual() {
+        let cmd = json!({ "action": "assert_title", "expected": "Dashboard" });
+        let resp = Response {
+            success: false,
+            data: Some(json!({ "actual": "Login" })),
+            error: None,
+        };
+        let resp = assertion_failure(&cmd, resp);
+        assert!(!resp.success);
+        assert_eq!(
+            resp.error.as_deref(),
+            Some(r#"Assertion failed: title: expected "Dashboard", got "Login""#)
+        );
+        let data = resp.data.unwrap();
+        assert_eq!(data["expected"], "Dashboard");
+        assert_eq!(data["actual"], "Login");
+    }
+
+    #[test]
+    fn test_missing_storage_key() {
+        let cmd = json!({ "action": "storage_get", "type": "local", "key": "token", "failIfMissing": true });
+        let resp = |value: serde_json::Value| Response {
+            success: true,
+            data: Some(json!({ "key": "token", "value": value })),
+            error: None,
+        };
+        let failed = missing_storage_key(&cmd, resp(serde_json::Value::Null));
+        assert!(!failed.success);
+        assert_eq!(
+            failed.error.as_deref(),
+            Some("Key not found in localStorage: token")
+        );
+        assert!(missing_storage_key(&cmd, resp(json!("abc"))).success);
+        assert!(missing_storage_key(&cmd, resp(json!(""))).success);
+
+        let cmd = json!({ "action": "storage_get", "type": "local", "key": "token" });
+        assert!(missing_storage_key(&cmd, resp(serde_json::Value::Null)).success);
+    }
+
+    #[test]
+    fn test_zero_count_failure() {
+        let resp = |count: u64| Response {
+            success: true,
+            data: Some(json!({ "count": count })),
+            error: None,
+        };
+        let cmd = json!({ "action": "count", "selector": ".missing", "failOnZero": true });
+        let failed = zero_count_failure(&cmd, resp(0));
+        assert!(!failed.success);
+        assert_eq!(failed.error.as_deref(), Some("No elements match .missing"));
+        assert_eq!(failed.data.unwrap()["count"], 0);
+        assert!(zero_count_failure(&cmd, resp(2)).success);
+    }
+
+    #[test]
+    fn test_zero_count_failure_requires_flag() {
+        let cmd = json!({ "action": "count", "selector": ".missing" });
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "count": 0 })),
+            error: None,
+        };
+        assert!(zero_count_failure(&cmd, resp).success);
+    }
+
+    #[test]
+    fn test_validate_failure_bounds() {
+        let resp = |count: u64| Response {
+            success: true,
+            data: Some(json!({ "count": count })),
+            error: None,
+        };
+        let cmd = json!({ "action": "validate", "selector": "#submit" });
+        assert!(validate_failure(&cmd, resp(1)).success);
+        let failed = validate_failure(&cmd, resp(0));
+        assert!(!failed.success);
+        assert_eq!(failed.error.as_deref(), Some("No elements match #submit"));
+        assert_eq!(failed.data.unwrap()["count"], 0);
+
+        let cmd = json!({ "action": "validate", "selector": "li", "min": 2, "max": 3 });
+        assert!(validate_failure(&cmd, resp(2)).success);
+        assert_eq!(
+            validate_failure(&cmd, resp(1)).error.as_deref(),
+            Some("Expected at least 2 elements matching li, found 1")
+        );
+        assert_eq!(
+            validate_failure(&cmd, resp(4)).error.as_deref(),
+            Some("Expected at most 3 elements matching li, found 4")
+        );
+
+        let cmd = json!({ "action": "validate", "selector": ".error", "max": 0 });
+        assert!(validate_failure(&cmd, resp(0)).success);
+    }
+
+    #[test]
+    fn test_validate_failure_ignores_other_commands() {
+        let cmd = json!({ "action": "count", "selector": "li" });
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "count": 0 })),
+            error: None,
+        };
+        assert!(validate_failure(&cmd, resp).success);
+    }
+
+    #[test]
+    fn test_count_mismatch_fails_response() {
+        let cmd = json!({ "action": "getbyrole", "subaction": "count", "expectedCount": 3 });
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "count": 5 })),
+            error: None,
+        };
+        let resp = count_mismatch(&cmd, resp);
+        assert!(!resp.success);
+        assert_eq!(
+            resp.error.as_deref(),
+            Some("Count mismatch: expected 3, got 5")
+        );
+    }
+
+    #[test]
+    fn test_count_mismatch_passes_matching_count() {
+        let cmd = json!({ "action": "getbyrole", "expectedCount": 2 });
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "count": 2 })),
+            error: None,
+        };
+        let resp = count_mismatch(&cmd, resp);
+        assert!(resp.success);
+        assert!(resp.error.is_none());
+    }
+
+    #[test]
+    fn test_count_mismatch_ignores_commands_without_expected_count() {
+        let cmd = json!({ "action": "count", "selector": "li" });
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "count": 7 })),
+            error: None,
+        };
+        assert!(count_mismatch(&cmd, resp).success);
+    }
+
+    #[test]
+    fn test_assertion_failure_includes_selector() {
+        let cmd = json!({ "action": "assert_count", "selector": "li", "expected": 3 });
+        let resp = Response {
+            success: false,
+            data: Some(json!({ "actual": 2 })),
+            error: None,
+        };
+        let resp = assertion_failure(&cmd, resp);
+        assert_eq!(
+            resp.error.as_deref(),
+            Some("Assertion failed: count li: expected 3, got 2")
+        );
+    }
+
+    #[test]
+    fn test_assertion_failure_visible_defaults_expected_true() {
+        let cmd = json!({ "action": "assert_visible", "selector": "#modal" });
+        let resp = Response {
+            success: false,
+            data: None,
+            error: Some("Element not found: #modal".to_string()),
+        };
+        let resp = assertion_failure(&cmd, resp);
+        assert_eq!(
+            resp.error.as_deref(),
+            Some("Assertion failed: visible #modal: Element not found: #modal")
+        );
+        let data = resp.data.unwrap();
+        assert_eq!(data["expected"], true);
+        assert!(data["actual"].is_null());
+    }
+
+    #[test]
+    fn test_assertion_failure_leaves_passing_and_other_responses() {
+        let cmd = json!({ "action": "assert_url", "expected": "/home" });
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "actual": "https://example.com/home" })),
+            error: None,
+        };
+        assert!(assertion_failure(&cmd, resp).error.is_none());
+
+        let cmd = json!({ "action": "click", "selector": "#go" });
+        let resp = Response {
+            success: false,
+            data: None,
+            error: Some("boom".to_string()),
+        };
+        let resp = assertion_failure(&cmd, resp);
+        assert_eq!(resp.error.as_deref(), Some("boom"));
+        assert!(resp.data.is_none());
+    }
+
+    #[test]
+    fn test_has_version_flag() {
+        let version = |s: &str| {
+            let args: Vec<String> = s.split_whitespace().map(String::from).collect();
+            has_version_flag(&clean_args(&args))
+        };
+        assert!(version("--version"));
+        assert!(version("-V"));
+        assert!(version("--json --session work --version"));
+    }
+
+    #[test]
+    fn test_has_version_flag_left_for_commands() {
+        let version = |s: &str| {
+            let args: Vec<String> = s.split_whitespace().map(String::from).collect();
+            has_version_flag(&clean_args(&args))
+        };
+        assert!(!version("fill #v -V"));
+        assert!(!version("type #x --version"));
+        assert!(!version("open -V"));
+    }
+
+    #[test]
+    fn test_has_version_flag_ignores_version_command() {
+        let args: Vec<String> = vec!["version".to_string(), "--json".to_string()];
+        assert!(!has_version_flag(&args));
+    }
+
+    fn ok_response(success: bool) -> Response {
+        Response {
+            success,
+            data: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_send_with_retry_no_retry_by_default() {
+        let mut calls = 0;
+        let (result, attempt) = send_with_retry(0, 0, || {
+            calls += 1;
+            Err("Failed to connect: Connection refused".to_string())
+        });
+        assert_eq!(calls, 1);
+        assert_eq!(attempt, 1);
+        assert_eq!(
+            result.err().as_deref(),
+            Some("Failed to connect: Connection refused")
+        );
+    }
+
+    #[test]
+    fn test_send_with_retry_stops_on_success() {
+        let mut calls = 0;
+        let (result, attempt) = send_with_retry(5, 0, || {
+            calls += 1;
+            if calls < 3 {
+                Err("Failed to connect: Connection refused".to_string())
+            } else {
+                Ok(ok_response(true))
+            }
+        });
+        assert_eq!(calls, 3);
+        assert_eq!(attempt, 3);
+        assert!(result.unwrap().success);
+    }
+
+    #[test]
+    fn test_send_with_retry_does_not_resend_failed_responses() {
+        let mut calls = 0;
+        let (result, attempt) = send_with_retry(2, 0, || {
+            calls += 1;
+            Ok(ok_response(false))
+        });
+        assert_eq!(calls, 1);
+        assert_eq!(attempt, 1);
+        assert!(!result.unwrap().success);
+    }
+
+    #[test]
+    fn test_send_with_retry_does_not_resend_after_read_timeout() {
+        let mut calls = 0;
+        let (result, attempt) = send_with_retry(2, 0, || {
+            calls += 1;
+            Err("Timed out after 60000ms waiting for the daemon to respond".to_string())
+        });
+        assert_eq!(calls, 1);
+        assert_eq!(attempt, 1);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_send_with_retry_returns_final_error() {
+        let mut calls = 0;
+        let (result, attempt) = send_with_retry(1, 0, || {
+            calls += 1;
+            Err(format!("Failed to connect: error {}", calls))
+        });
+        assert_eq!(attempt, 2);
+        assert_eq!(result.err().as_deref(), Some("Failed to connect: error 2"));
+    }
+
+    #[test]
+    fn test_response_with_attempt() {
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "title": "Example" })),
+            error: None,
+        };
+        let value = response_with_attempt(&resp, 2);
+        assert_eq!(value["attempt"], 2);
+        assert_eq!(value["success"], true);
+        assert_eq!(value["data"]["title"], "Example");
+    }
+
+    #[test]
+    fn test_failure_screenshot_path_is_timestamped() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_705_311_000);
+        let path = failure_screenshot_path(Path::new("shots"), "click", time);
+        assert_eq!(
+            path,
+            Path::new("shots").join("failure-click-2024-01-15T09-30-00Z.png")
+        );
+    }
+
+    #[test]
+    fn test_failure_screenshot_path_sanitizes_action() {
+        let path = failure_screenshot_path(Path::new("/tmp"), "assert/url", SystemTime::UNIX_EPOCH);
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        assert_eq!(name, "failure-assert-url-1970-01-01T00-00-00Z.png");
+    }
+
+    #[test]
+    fn test_failure_screenshot_cmd() {
+        let cmd = failure_screenshot_cmd(Path::new("shots/fail.png"));
+        assert_eq!(cmd["action"], "screenshot");
+        assert_eq!(cmd["path"], "shots/fail.png");
+        assert_eq!(cmd["fullPage"], false);
+        assert!(cmd["id"].as_str().is_some());
+    }
+
+    fn dry_run_flags(input: &str) -> (Flags, serde_json::Value) {
+        let input: Vec<String> = input.split_whitespace().map(String::from).collect();
+        let flags = parse_flags(&input);
+        let cmd = parse_command(&clean_args(&input), &flags).unwrap();
+        (flags, cmd)
+    }
+
+    #[test]
+    fn test_dry_run_human_mode_pretty_prints_command() {
+        let (flags, cmd) = dry_run_flags("--dry-run click #submit");
+        let output = dry_run(&flags, &cmd).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(parsed["action"], "click");
+        assert_eq!(parsed["selector"], "#submit");
+        assert!(output.contains("\n  \"action\": \"click\""));
+    }
+
+    #[test]
+    fn test_dry_run_json_mode_wraps_command() {
+        let (flags, cmd) = dry_run_flags("--dry-run --json open example.com");
+        let output = dry_run(&flags, &cmd).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(parsed["success"], true);
+        assert_eq!(parsed["dryRun"], true);
+        assert_eq!(parsed["command"]["action"], "navigate");
+    }
+
+    #[test]
+    fn test_dry_run_does_not_contact_daemon() {
+        let (flags, cmd) = dry_run_flags("--dry-run --session ab-test-dry-run snapshot");
+        assert!(dry_run(&flags, &cmd).is_some());
+        assert!(!get_socket_dir().join("ab-test-dry-run.pid").exists());
+
+        let (flags, cmd) = dry_run_flags("--session ab-test-dry-run snapshot");
+        assert_eq!(dry_run(&flags, &cmd), None);
+    }
+
+    #[test]
+    fn test_dry_run_still_validates_command() {
+        let input: Vec<String> = vec!["--dry-run".into(), "click".into()];
+        let flags = parse_flags(&input);
+        assert!(flags.dry_run);
+        assert!(matches!(
+            parse_command(&clean_args(&input), &flags),
+            Err(ParseError::MissingArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_error_json_shape() {
+        let err = ParseError::MissingArguments {
+            context: "click".to_string(),
+            usage: "click <selector>",
+        };
+        assert_eq!(
+            parse_error_json(&err),
+            json!({
+                "success": false,
+                "error": "Missing arguments for: click\nUsage: agent-browser click <selector>",
+                "type": "missing_arguments",
+                "details": {
+                    "type": "missing_arguments",
+                    "context": "click",
+                    "usage": "click <selector>"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_error_json_unknown_command() {
+        let err = parse_command(&["frobnicate".to_string()], &parse_flags(&[])).unwrap_err();
+        let out = parse_error_json(&err);
+        assert_eq!(out["type"], "unknown_command");
+        assert_eq!(out["details"]["command"], "frobnicate");
+        assert_eq!(out["error"], "Unknown command: frobnicate");
+    }
+
+    fn alias_map(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn words(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_expand_alias_not_an_alias() {
+        let aliases = alias_map(&[("login", "open https://app.test/login")]);
+        assert_eq!(expand_alias(&words("open example.com"), &aliases), Ok(None));
+        assert_eq!(expand_alias(&[], &aliases), Ok(None));
+    }
+
+    #[test]
+    fn test_expand_alias_simple() {
+        let aliases = alias_map(&[("login", "open https://app.test/login")]);
+        assert_eq!(
+            expand_alias(&words("login"), &aliases),
+            Ok(Some(words("open https://app.test/login")))
+        );
+    }
+
+    #[test]
+    fn test_expand_alias_positional_substitution() {
+        let aliases = alias_map(&[("type-in", "fill $1 $2")]);
+        assert_eq!(
+            expand_alias(&words("type-in #email me@co.com"), &aliases),
+            Ok(Some(words("fill #email me@co.com")))
+        );
+    }
+
+    #[test]
+    fn test_expand_alias_placeholder_inside_word() {
+        let aliases = alias_map(&[("issue", "open https://github.com/$1/issues/$2")]);
+        assert_eq!(
+            expand_alias(&words("issue acme/web 42"), &aliases),
+            Ok(Some(words("open https://github.com/acme/web/issues/42")))
+        );
+    }
+
+    #[test]
+    fn test_expand_alias_appends_args_without_placeholders() {
+        let aliases = alias_map(&[("shot", "screenshot --full")]);
+        assert_eq!(
+            expand_alias(&words("shot page.png"), &aliases),
+            Ok(Some(words("screenshot --full page.png")))
+        );
+    }
+
+    #[test]
+    fn test_expand_alias_quoted_words() {
+        let aliases = alias_map(&[("hello", r#"fill #msg "hello world""#)]);
+        assert_eq!(
+            expand_alias(&words("hello"), &aliases),
+            Ok(Some(vec![
+                "fill".to_string(),
+                "#msg".to_string(),
+                "hello world".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_expand_alias_missing_argument() {
+        let aliases = alias_map(&[("type-in", "fill $1 $2")]);
+        let err = expand_alias(&words("type-in #email"), &aliases).unwrap_err();
+        assert_eq!(err, "alias type-in uses $2 but 1 argument(s) were given");
+    }
+
+    #[test]
+    fn test_expand_alias_dollar_without_digit_is_literal() {
+        let aliases = alias_map(&[("price", "find text $ click")]);
+        assert_eq!(
+            expand_alias(&words("price"), &aliases),
+            Ok(Some(words("find text $ click")))
+        );
+    }
+
+    #[test]
+    fn test_expand_alias_errors() {
+        let aliases = alias_map(&[("empty", "  "), ("broken", "fill \"unterminated")]);
+        assert_eq!(
+            expand_alias(&words("empty"), &aliases),
+            Err("alias empty is empty".to_string())
+        );
+        assert!(expand_alias(&words("broken"), &aliases)
+            .unwrap_err()
+            .starts_with("alias broken:"));
+    }
+
+    #[test]
+    fn test_check_config_files_reports_unknown_keys_and_errors() {
+        let dir = std::env::temp_dir().join("ab-test-config-validate");
+        fs::create_dir_all(&dir).unwrap();
+        let good = dir.join("good.json");
+        let typo = dir.join("typo.json");
+        let broken = dir.join("broken.json");
+        fs::write(&good, r#"{"headed": true}"#).unwrap();
+        fs::write(&typo, r#"{"userAgnet": "x", "sessionname": "y"}"#).unwrap();
+        fs::write(&broken, "{headed: true").unwrap();
+
+        let report = check_config_files(&[good]);
+        assert!(report.unknown_keys.is_empty() && report.errors.is_empty());
+
+        let report = check_config_files(&[typo, broken]);
+        let _ = fs::remove_dir_all(&dir);
+        let keys: Vec<(&str, Option<&str>)> = report
+            .unknown_keys
+            .iter()
+            .map(|(_, k, hint)| (k.as_str(), *hint))
+            .collect();
+        assert!(keys.contains(&("userAgnet", None)));
+        assert!(keys.contains(&("sessionname", Some("sessionName"))));
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("broken.json: invalid JSON"));
+    }
+
+    #[test]
+    fn test_daemon_status_not_running() {
+        let dir = session_test_dir("daemon-status-stale");
+        fs::write(dir.join("old.pid"), DEAD_PID.to_string()).unwrap();
+        let data = daemon_status("old", &dir);
+        assert_eq!(data, json!({ "session": "old", "running": false }));
+        let data = daemon_status("missing", &dir);
+        assert_eq!(data["running"], false);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_daemon_status_running() {
+        let dir = session_test_dir("daemon-status");
+        fs::write(dir.join("live.pid"), std::process::id().to_string()).unwrap();
+        let data = daemon_status("live", &dir);
+        assert_eq!(data["running"], true);
+        assert_eq!(data["pid"], std::process::id());
+        assert!(data["uptimeSeconds"].as_u64().unwrap() < 60);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_daemon_stop_not_running() {
+        let dir = session_test_dir("daemon-stop-stale");
+        fs::write(dir.join("old.pid"), DEAD_PID.to_string()).unwrap();
+        let data = daemon_stop("old", &dir, Duration::from_secs(1)).unwrap();
+        assert_eq!(data["stopped"], false);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_daemon_stop_running_process() {
+        let dir = session_test_dir("daemon-stop");
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        fs::write(dir.join("victim.pid"), pid.to_string()).unwrap();
+        fs::write(dir.join("victim.log"), "boot\n").unwrap();
+        // Reap the child so it does not linger as a zombie that still answers kill(pid, 0)
+        let reaper = thread::spawn(move || child.wait());
+
+        let data = daemon_stop("victim", &dir, Duration::from_secs(5)).unwrap();
+        assert_eq!(data["stopped"], true);
+        assert_eq!(data["pid"], pid);
+        assert!(!dir.join("victim.pid").exists());
+        assert!(dir.join("victim.log").exists());
+        reaper.join().unwrap().unwrap();
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_daemon_logs() {
+        let dir = session_test_dir("daemon-logs");
+        fs::write(dir.join("work.log"), "Error: launch failed\n").unwrap();
+        assert_eq!(
+            daemon_logs("work", &dir.join("work.log")).unwrap(),
+            "Error: launch failed\n"
+        );
+        let err = daemon_logs("other", &dir.join("other.log")).unwrap_err();
+        assert_eq!(err, "No daemon log for session 'other'");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_format_daemon_status() {
+        let running =
+            json!({ "session": "work", "running": true, "pid": 42, "uptimeSeconds": 125 });
+        assert_eq!(
+            format_daemon_status(&running),
+            "Daemon running (session: work)\nPID:     42\nUptime:  2m 5s\n"
+        );
+        let stopped = json!({ "session": "work", "running": false });
+        assert_eq!(
+            format_daemon_status(&stopped),
+            "Daemon not running (session: work)\n"
+        );
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(0), "0s");
+        assert_eq!(format_uptime(59), "59s");
+        assert_eq!(format_uptime(61), "1m 1s");
+        assert_eq!(format_uptime(3723), "1h 2m 3s");
+    }
+
+    #[test]
+    fn test_command_timeouts_cover_command_timeout() {
+        let flags = parse_flags(&[]);
+        let short = command_timeouts(&flags, &json!({ "action": "click" }));
+        assert_eq!(short.send_ms, DEFAULT_SEND_TIMEOUT_MS);
+        let long = command_timeouts(&flags, &json!({ "action": "wait", "timeout": 120_000 }));
+        assert_eq!(long.send_ms, 125_000);
+        let within = command_timeouts(&flags, &json!({ "action": "wait", "timeout": 2000 }));
+        assert_eq!(within.send_ms, DEFAULT_SEND_TIMEOUT_MS);
+    }
+
+    #[test]
+    fn test_command_timeouts_explicit_send_timeout_wins() {
+        let flags = parse_flags(&["--send-timeout".to_string(), "1000".to_string()]);
+        let t = command_timeouts(&flags, &json!({ "action": "wait", "timeout": 120_000 }));
+        assert_eq!(t.send_ms, 1000);
+    }
+
+    #[test]
+    fn test_send_error_json_tags_timeouts() {
+        let err = send_error_json(
+            "Timed out after 5000ms waiting for the daemon to respond",
+            None,
+        );
+        assert_eq!(err["success"], false);
+        assert_eq!(err["type"], "timeout");
+        assert!(err.get("attempt").is_none());
+
+        let err = send_error_json("Failed to connect: No such file", Some(3));
+        assert!(err.get("type").is_none());
+        assert_eq!(err["attempt"], 3);
+    }
+
+    #[test]
+    fn test_console_errors_from_strings_and_objects() {
+        let resp = Response {
+            success: true,
+            data: Some(json!({
+                "consoleErrors": [
+                    "Uncaught TypeError: x is undefined",
+                    { "text": "ReferenceError: y is not defined", "url": "app.js" },
+                    { "line": 3 }
+                ]
+            })),
+            error: None,
+        };
+        assert_eq!(
+            console_errors(&resp),
+            vec![
+                "Uncaught TypeError: x is undefined",
+                "ReferenceError: y is not defined",
+                r#"{"line":3}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn test_console_errors_empty_or_missing() {
+        let empty = Response {
+            success: true,
+            data: Some(json!({ "consoleErrors": [] })),
+            error: None,
+        };
+        assert!(console_errors(&empty).is_empty());
+        let missing = Response {
+            success: true,
+            data: Some(json!({ "url": "https://example.com" })),
+            error: None,
+        };
+        assert!(console_errors(&missing).is_empty());
+        assert!(console_errors(&Response::default()).is_empty());
+    }
+
+    #[test]
+    fn test_format_console_errors() {
+        let out = format_console_errors(&["boom".to_string(), "bang".to_string()]);
+        assert!(out.contains("Page logged 2 console error(s):"));
+        assert!(out.ends_with("  boom\n  bang\n"));
+    }
+
+    #[test]
+    fn test_follow_event_kind() {
+        assert_eq!(follow_event_kind("requests"), "request");
+        assert_eq!(follow_event_kind("console"), "log");
+        assert_eq!(follow_event_kind("errors"), "page_error");
+        assert_eq!(follow_event_kind("other"), "event");
+    }
+
+    #[test]
+    fn test_follow_event_prints_events() {
+        output::start_capture();
+        let event = json!({ "method": "GET", "status": 200, "url": "https://a.test/" });
+        follow_event(&event, "request", OutputFormat::Ndjson).unwrap();
+        follow_event(&event, "request", OutputFormat::Human).unwrap();
+        let captured = output::take_capture().unwrap();
+        let lines: Vec<&str> = captured.lines().collect();
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["type"], "request");
+        assert_eq!(first["url"], "https://a.test/");
+        assert_eq!(lines[1], "GET 200 https://a.test/ -");
+    }
+
+    #[test]
+    fn test_follow_event_daemon_responses() {
+        output::start_capture();
+        assert!(follow_event(&json!({ "success": true }), "request", OutputFormat::Human).is_ok());
+        let err = follow_event(
+            &json!({ "success": false, "error": "Unknown action" }),
+            "request",
+            OutputFormat::Human,
+        )
+        .unwrap_err();
+        assert_eq!(err, "Unknown action");
+        assert_eq!(output::take_capture().unwrap(), "");
+    }
 }
@@ -1,12 +1,160 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+
 use crate::color;
-use crate::connection::Response;
+use crate::connection::{Response, PROTOCOL_VERSION};
+
+/// How responses are printed, chosen with `--format` (or `--json` / `--ndjson`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+    Ndjson,
+    /// Like `Human`, but cookies, tabs and network requests as aligned columns.
+    Table,
+}
 
-pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
-    if json_mode {
+impl OutputFormat {
+    /// Names accepted by `--format`.
+    pub const NAMES: &'static [&'static str] = &["human", "json", "ndjson", "table"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "human" => Some(Self::Human),
+            "json" => Some(Self::Json),
+            "ndjson" => Some(Self::Ndjson),
+            "table" => Some(Self::Table),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Human => "human",
+            Self::Json => "json",
+            Self::Ndjson => "ndjson",
+            Self::Table => "table",
+        }
+    }
+}
+
+thread_local! {
+    /// Copy of command output kept while `--output-file` is in effect.
+    static CAPTURE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// Printing in this module, and in main.rs through `#[macro_use]`, goes through
+// `emit` / `emit_err`, so `--output-file` gets exactly what the terminal gets.
+macro_rules! print {
+    ($($arg:tt)*) => {
+        $crate::output::emit(&format!($($arg)*))
+    };
+}
+
+macro_rules! println {
+    () => {
+        $crate::output::emit("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::output::emit(&format!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+macro_rules! eprint {
+    ($($arg:tt)*) => {
+        $crate::output::emit_err(&format!($($arg)*))
+    };
+}
+
+macro_rules! eprintln {
+    () => {
+        $crate::output::emit_err("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::output::emit_err(&format!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+/// Writes to stdout, and to the capture buffer when one is active.
+pub fn emit(text: &str) {
+    std::print!("{}", text);
+    CAPTURE.with(|c| {
+        if let Some(buf) = c.borrow_mut().as_mut() {
+            buf.push_str(text);
+        }
+    });
+}
+
+/// Like `emit`, but for stderr.
+pub fn emit_err(text: &str) {
+    std::eprint!("{}", text);
+    CAPTURE.with(|c| {
+        if let Some(buf) = c.borrow_mut().as_mut() {
+            buf.push_str(text);
+        }
+    });
+}
+
+/// Starts collecting everything printed through `emit` / `emit_err`.
+pub fn start_capture() {
+    CAPTURE.with(|c| *c.borrow_mut() = Some(String::new()));
+}
+
+/// Stops collecting and returns what was printed since `start_capture`.
+pub fn take_capture() -> Option<String> {
+    CAPTURE.with(|c| c.borrow_mut().take())
+}
+
+/// Removes ANSI escape sequences (colors, bold, dim) from `text`.
+pub fn strip_ansi(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            result.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'[') {
+            chars.next();
+            // CSI sequence: parameters, then a final byte in @..~
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Writes `--output-file` contents, creating missing parent directories.
+pub fn write_output_file(path: &str, contents: &str) -> Result<(), String> {
+    let path = Path::new(path);
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+    }
+    fs::write(path, contents)
+        .map_err(|e| format!("Failed to write output file {}: {}", path.display(), e))
+}
+
+/// Prints a daemon response. `quiet` (`--quiet`) drops output for successful
+/// commands; errors, `--json` and `--ndjson` output are always printed.
+pub fn print_response(resp: &Response, format: OutputFormat, quiet: bool, action: Option<&str>) {
+    if format == OutputFormat::Json {
         println!("{}", serde_json::to_string(resp).unwrap_or_default());
         return;
     }
 
+    if format == OutputFormat::Ndjson {
+        for event in ndjson_events(resp, action) {
+            println!("{}", event);
+        }
+        return;
+    }
+
     if !resp.success {
         eprintln!(
             "{} {}",
@@ -16,7 +164,17 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
         return;
     }
 
+    if quiet {
+        return;
+    }
+
     if let Some(data) = &resp.data {
+        if format == OutputFormat::Table {
+            if let Some(table) = format_table_response(data) {
+                print!("{}", table);
+                return;
+            }
+        }
         // Navigation response
         if let Some(url) = data.get("url").and_then(|v| v.as_str()) {
             if let Some(title) = data.get("title").and_then(|v| v.as_str()) {
@@ -38,16 +196,47 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
                     print_screenshot_diff(obj);
                     return;
                 }
+                Some(a) if a.starts_with("assert_") => {
+                    println!("{} Assertion passed", color::success_indicator());
+                    return;
+                }
+                Some("schema") => {
+                    println!("{}", serde_json::to_string_pretty(data).unwrap_or_default());
+                    return;
+                }
+                Some("accessibility_snapshot") => {
+                    if let Some(tree) = obj.get("tree") {
+                        println!("{}", serde_json::to_string_pretty(tree).unwrap_or_default());
+                        return;
+                    }
+                }
+                Some("state_diff") => {
+                    if let Some(diff) = obj.get("diff") {
+                        print!("{}", format_state_diff(diff));
+                        return;
+                    }
+                }
                 Some("diff_url") => {
-                    if let Some(snap_data) =
-                        obj.get("snapshot").and_then(|v| v.as_object())
-                    {
+                    // Nested {snapshot, screenshot} from the daemon, or both
+                    // results flattened into one object
+                    let snap_data = obj
+                        .get("snapshot")
+                        .and_then(|v| v.as_object())
+                        .or_else(|| obj.contains_key("diff").then_some(obj));
+                    let ss_data = obj
+                        .get("screenshot")
+                        .and_then(|v| v.as_object())
+                        .or_else(|| {
+                            ["mismatchPercentage", "diffPercent", "diffPixels"]
+                                .iter()
+                                .any(|k| obj.contains_key(*k))
+                                .then_some(obj)
+                        });
+                    if let Some(snap_data) = snap_data {
                         println!("{}", color::bold("Snapshot diff:"));
                         print_snapshot_diff(snap_data);
                     }
-                    if let Some(ss_data) =
-                        obj.get("screenshot").and_then(|v| v.as_object())
-                    {
+                    if let Some(ss_data) = ss_data {
                         println!("\n{}", color::bold("Screenshot diff:"));
                         print_screenshot_diff(ss_data);
                     }
@@ -71,11 +260,78 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
             println!("{}", text);
             return;
         }
+        // All text: one line per matching element
+        if let Some(texts) = data.get("texts").and_then(|v| v.as_array()) {
+            for text in texts {
+                match text {
+                    serde_json::Value::String(s) => println!("{}", s),
+                    serde_json::Value::Null => println!(),
+                    other => println!("{}", other),
+                }
+            }
+            return;
+        }
         // HTML
         if let Some(html) = data.get("html").and_then(|v| v.as_str()) {
             println!("{}", html);
             return;
         }
+        // ARIA: one attribute's value, or every ARIA attribute as `name: value`
+        if let Some(aria) = data.get("aria") {
+            match aria {
+                serde_json::Value::String(s) => println!("{}", s),
+                serde_json::Value::Object(map) if map.is_empty() => {
+                    println!("No ARIA attributes")
+                }
+                serde_json::Value::Object(_) => print!("{}", format_style_lines(aria)),
+                serde_json::Value::Null => {
+                    eprintln!(
+                        "{} Element has no such ARIA attribute",
+                        color::error_indicator()
+                    )
+                }
+                other => println!("{}", other),
+            }
+            return;
+        }
+        // Attribute: the daemon sends `{attribute: <name>, value}`; a bare
+        // `{attribute: <value>}` is also accepted
+        if let Some(attribute) = data.get("attribute") {
+            match attribute_value(data) {
+                serde_json::Value::Null => {
+                    // With the daemon's shape, `attribute` is the name asked for
+                    match attribute.as_str().filter(|_| data.get("value").is_some()) {
+                        Some(name) => eprintln!(
+                            "{} Element has no '{}' attribute",
+                            color::error_indicator(),
+                            name
+                        ),
+                        None => {
+                            eprintln!("{} Element has no such attribute", color::error_indicator())
+                        }
+                    }
+                }
+                serde_json::Value::String(s) => println!("{}", s),
+                other => println!("{}", other),
+            }
+            return;
+        }
+        // Property: any JSON value
+        if let Some(property) = data.get("property") {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(property).unwrap_or_default()
+            );
+            return;
+        }
+        // Styles / computed style: `property: value` lines
+        if let Some(styles) = data.get("styles").or_else(|| data.get("computedStyle")) {
+            match styles {
+                serde_json::Value::String(s) => println!("{}", s),
+                _ => print!("{}", format_style_lines(styles)),
+            }
+            return;
+        }
         // Value
         if let Some(value) = data.get("value").and_then(|v| v.as_str()) {
             println!("{}", value);
@@ -99,6 +355,26 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
             println!("{}", checked);
             return;
         }
+        if let Some(hidden) = data.get("hidden").and_then(|v| v.as_bool()) {
+            println!("{}", hidden);
+            return;
+        }
+        if let Some(editable) = data.get("editable").and_then(|v| v.as_bool()) {
+            println!("{}", editable);
+            return;
+        }
+        if let Some(disabled) = data.get("disabled").and_then(|v| v.as_bool()) {
+            println!("{}", disabled);
+            return;
+        }
+        if let Some(focused) = data.get("focused").and_then(|v| v.as_bool()) {
+            println!("{}", focused);
+            return;
+        }
+        if let Some(empty) = data.get("empty").and_then(|v| v.as_bool()) {
+            println!("{}", empty);
+            return;
+        }
         // Eval result
         if let Some(result) = data.get("result") {
             println!(
@@ -109,66 +385,17 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
         }
         // iOS Devices
         if let Some(devices) = data.get("devices").and_then(|v| v.as_array()) {
-            if devices.is_empty() {
-                println!("No iOS devices available. Open Xcode to download simulator runtimes.");
-                return;
-            }
-
-            // Separate real devices from simulators
-            let real_devices: Vec<_> = devices
-                .iter()
-                .filter(|d| {
-                    d.get("isRealDevice")
-                        .and_then(|v| v.as_bool())
-                        .unwrap_or(false)
-                })
-                .collect();
-            let simulators: Vec<_> = devices
-                .iter()
-                .filter(|d| {
-                    !d.get("isRealDevice")
-                        .and_then(|v| v.as_bool())
-                        .unwrap_or(false)
-                })
-                .collect();
-
-            if !real_devices.is_empty() {
-                println!("Connected Devices:\n");
-                for device in real_devices.iter() {
-                    let name = device
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown");
-                    let runtime = device.get("runtime").and_then(|v| v.as_str()).unwrap_or("");
-                    let udid = device.get("udid").and_then(|v| v.as_str()).unwrap_or("");
-                    println!("  {} {} ({})", color::green("●"), name, runtime);
-                    println!("    {}", color::dim(udid));
-                }
-                println!();
-            }
-
-            if !simulators.is_empty() {
-                println!("Simulators:\n");
-                for device in simulators.iter() {
-                    let name = device
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown");
-                    let runtime = device.get("runtime").and_then(|v| v.as_str()).unwrap_or("");
-                    let state = device
-                        .get("state")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown");
-                    let udid = device.get("udid").and_then(|v| v.as_str()).unwrap_or("");
-                    let state_indicator = if state == "Booted" {
-                        color::green("●")
-                    } else {
-                        color::dim("○")
-                    };
-                    println!("  {} {} ({})", state_indicator, name, runtime);
-                    println!("    {}", color::dim(udid));
-                }
-            }
+            print!("{}", format_device_list(devices));
+            return;
+        }
+        // Single device (device info)
+        if let Some(info) = data.get("deviceInfo").filter(|v| v.is_object()) {
+            print!("{}", format_device_info(info));
+            return;
+        }
+        // Current tab info
+        if let Some(tab) = data.get("tab").filter(|v| v.is_object()) {
+            print!("{}", format_tab_info(tab));
             return;
         }
         // Tabs
@@ -189,23 +416,54 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
             }
             return;
         }
+        // Windows
+        if let Some(windows) = data.get("windows").and_then(|v| v.as_array()) {
+            for (i, window) in windows.iter().enumerate() {
+                let index = window
+                    .get("index")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(i as u64);
+                let active = window
+                    .get("active")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let marker = if active {
+                    color::cyan("→")
+                } else {
+                    " ".to_string()
+                };
+                let tabs = window.get("tabs").and_then(|v| v.as_u64()).unwrap_or(0);
+                let size = match (
+                    window.get("width").and_then(|v| v.as_u64()),
+                    window.get("height").and_then(|v| v.as_u64()),
+                ) {
+                    (Some(w), Some(h)) => format!(" {}x{}", w, h),
+                    _ => String::new(),
+                };
+                let plural = if tabs == 1 { "" } else { "s" };
+                println!("{} [{}] {} tab{}{}", marker, index, tabs, plural, size);
+            }
+            return;
+        }
         // Console logs
         if let Some(logs) = data.get("messages").and_then(|v| v.as_array()) {
             for log in logs {
-                let level = log.get("type").and_then(|v| v.as_str()).unwrap_or("log");
-                let text = log.get("text").and_then(|v| v.as_str()).unwrap_or("");
-                println!("{} {}", color::console_level_prefix(level), text);
+                println!("{}", format_log_line(log));
             }
             return;
         }
         // Errors
         if let Some(errors) = data.get("errors").and_then(|v| v.as_array()) {
             for err in errors {
-                let msg = err.get("message").and_then(|v| v.as_str()).unwrap_or("");
-                println!("{} {}", color::error_indicator(), msg);
+                println!("{}", format_page_error_line(err));
             }
             return;
         }
+        // Single cookie
+        if let Some(cookie) = data.get("cookie").filter(|v| v.is_object()) {
+            println!("{}", format_cookie(cookie));
+            return;
+        }
         // Cookies
         if let Some(cookies) = data.get("cookies").and_then(|v| v.as_array()) {
             for cookie in cookies {
@@ -215,20 +473,26 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
             }
             return;
         }
+        if action == Some("cookies_delete") {
+            println!("{} Cookie deleted", color::success_indicator());
+            return;
+        }
+        // Storage keys
+        if let Some(keys) = data.get("keys").and_then(|v| v.as_array()) {
+            if keys.is_empty() {
+                println!("No keys");
+            }
+            for key in keys.iter().filter_map(|k| k.as_str()) {
+                println!("{}", key);
+            }
+            return;
+        }
         // Network requests
         if let Some(requests) = data.get("requests").and_then(|v| v.as_array()) {
             if requests.is_empty() {
                 println!("No requests captured");
             } else {
-                for req in requests {
-                    let method = req.get("method").and_then(|v| v.as_str()).unwrap_or("GET");
-                    let url = req.get("url").and_then(|v| v.as_str()).unwrap_or("");
-                    let resource_type = req
-                        .get("resourceType")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("");
-                    println!("{} {} ({})", method, url, resource_type);
-                }
+                print!("{}", format_requests_table(requests));
             }
             return;
         }
@@ -310,11 +574,7 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
                     }
                     _ => {
                         if let Some(path) = data.get("path").and_then(|v| v.as_str()) {
-                            println!(
-                                "{} Recording started: {}",
-                                color::success_indicator(),
-                                path
-                            );
+                            println!("{} Recording started: {}", color::success_indicator(), path);
                         } else {
                             println!("{} Recording started", color::success_indicator());
                         }
@@ -341,6 +601,31 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
             }
             return;
         }
+        // Frame list
+        if let Some(frames) = data.get("frames").and_then(|v| v.as_array()) {
+            if frames.is_empty() {
+                println!("No frames");
+            }
+            for (i, frame) in frames.iter().enumerate() {
+                let name = frame
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .filter(|s| !s.is_empty())
+                    .unwrap_or("(unnamed)");
+                let url = frame.get("url").and_then(|v| v.as_str()).unwrap_or("");
+                let active = frame
+                    .get("active")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let marker = if active {
+                    color::cyan("→")
+                } else {
+                    " ".to_string()
+                };
+                println!("{} [{}] {} - {}", marker, i, name, url);
+            }
+            return;
+        }
         // Recording stop (has "frames" field - from recording_stop action)
         if data.get("frames").is_some() {
             if let Some(path) = data.get("path").and_then(|v| v.as_str()) {
@@ -384,6 +669,97 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
                 return;
             }
         }
+        // HAR save/load (from network har save|load)
+        for (key, label, preposition) in [
+            ("harSaved", "HAR saved", "to"),
+            ("harLoaded", "HAR loaded", "from"),
+        ] {
+            if let Some(value) = data.get(key) {
+                let path = value
+                    .as_str()
+                    .or_else(|| data.get("path").and_then(|v| v.as_str()));
+                match path {
+                    Some(p) => println!(
+                        "{} {} {} {}",
+                        color::success_indicator(),
+                        label,
+                        preposition,
+                        color::green(p)
+                    ),
+                    None => println!("{} {}", color::success_indicator(), label),
+                }
+                return;
+            }
+        }
+        // Trace viewer
+        if let Some(url) = data.get("traceViewerUrl").and_then(|v| v.as_str()) {
+            println!("{} Trace viewer: {}", color::success_indicator(), url);
+            return;
+        }
+        // Clipboard contents
+        if let Some(text) = data.get("clipboard").and_then(|v| v.as_str()) {
+            println!("{}", text);
+            return;
+        }
+        // Open dialog
+        if let Some(dialog) = data.get("dialog") {
+            match dialog {
+                serde_json::Value::Object(_) => print!("{}", format_dialog(dialog)),
+                _ => println!("No dialog is open"),
+            }
+            return;
+        }
+        // Mock rules
+        if let Some(rules) = data.get("mockRules") {
+            let count = rules
+                .as_u64()
+                .or_else(|| rules.as_array().map(|a| a.len() as u64))
+                .unwrap_or(0);
+            let plural = if count == 1 { "" } else { "s" };
+            println!("{} active mock rule{}", count, plural);
+            return;
+        }
+        // Performance metrics
+        if let Some(metrics) = data.get("metrics").filter(|v| v.is_object()) {
+            if metrics.as_object().is_some_and(|m| m.is_empty()) {
+                println!("No performance metrics");
+            } else {
+                print!("{}", format_metrics(metrics));
+            }
+            return;
+        }
+        // Accessibility check
+        if let Some(violations) = data.get("violations").and_then(|v| v.as_array()) {
+            print!("{}", format_violations(violations));
+            return;
+        }
+        // Heap snapshot
+        if let Some(path) = data.get("heapSnapshot").and_then(|v| v.as_str()) {
+            println!(
+                "{} Heap snapshot saved to {}",
+                color::success_indicator(),
+                color::green(path)
+            );
+            return;
+        }
+        // Profiler status
+        if let Some(profiling) = data.get("profiling").and_then(|v| v.as_bool()) {
+            if profiling {
+                println!("Profiling: {}", color::green("active"));
+            } else {
+                println!("Profiling: {}", color::dim("inactive"));
+            }
+            return;
+        }
+        // Trace status
+        if let Some(recording) = data.get("traceRecording").and_then(|v| v.as_bool()) {
+            if recording {
+                println!("Trace recording: {}", color::green("active"));
+            } else {
+                println!("Trace recording: {}", color::dim("inactive"));
+            }
+            return;
+        }
         // Trace stop without path
         if data.get("traceStopped").is_some() {
             println!("{} Trace stopped", color::success_indicator());
@@ -454,6 +830,16 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
                     color::success_indicator(),
                     color::green(path)
                 ),
+                "cookies_export" => println!(
+                    "{} Cookies exported to {}",
+                    color::success_indicator(),
+                    color::green(path)
+                ),
+                "storage_export" => println!(
+                    "{} Storage exported to {}",
+                    color::success_indicator(),
+                    color::green(path)
+                ),
                 "state_save" => println!(
                     "{} State saved to {}",
                     color::success_indicator(),
@@ -505,7 +891,11 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
                     };
                     let date_str = modified.split('T').next().unwrap_or(modified);
                     let enc_str = if encrypted { " [encrypted]" } else { "" };
-                    println!("  {} {}", filename, color::dim(&format!("({}, {}){}", size_str, date_str, enc_str)));
+                    println!(
+                        "  {} {}",
+                        filename,
+                        color::dim(&format!("({}, {}){}", size_str, date_str, enc_str))
+                    );
                 }
             }
             return;
@@ -515,13 +905,22 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
         if let Some(true) = data.get("renamed").and_then(|v| v.as_bool()) {
             let old_name = data.get("oldName").and_then(|v| v.as_str()).unwrap_or("");
             let new_name = data.get("newName").and_then(|v| v.as_str()).unwrap_or("");
-            println!("{} Renamed {} -> {}", color::success_indicator(), old_name, new_name);
+            println!(
+                "{} Renamed {} -> {}",
+                color::success_indicator(),
+                old_name,
+                new_name
+            );
             return;
         }
 
         // State clear
         if let Some(cleared) = data.get("cleared").and_then(|v| v.as_i64()) {
-            println!("{} Cleared {} state file(s)", color::success_indicator(), cleared);
+            println!(
+                "{} Cleared {} state file(s)",
+                color::success_indicator(),
+                cleared
+            );
             return;
         }
 
@@ -539,7 +938,11 @@ pub fn print_response(resp: &Response, json_mode: bool, action: Option<&str>) {
 
         // State clean
         if let Some(cleaned) = data.get("cleaned").and_then(|v| v.as_i64()) {
-            println!("{} Cleaned {} old state file(s)", color::success_indicator(), cleaned);
+            println!(
+                "{} Cleaned {} old state file(s)",
+                color::success_indicator(),
+                cleaned
+            );
             return;
         }
 
@@ -571,7 +974,8 @@ Aliases: goto, navigate
 Global Options:
   --json               Output as JSON
   --session <name>     Use specific session
-  --headers <json>     Set HTTP headers (scoped to this origin)
+  --headers <json>     Set HTTP headers (scoped to this origin; @file reads JSON from a file)
+  --header <name:value>  Set one HTTP header (repeatable, overrides --headers)
   --headed             Show browser window
 
 Examples:
@@ -580,57 +984,73 @@ Examples:
   agent-browser open localhost:3000
   agent-browser open api.example.com --headers '{"Authorization": "Bearer token"}'
     # ^ Headers only sent to api.example.com, not other domains
+  agent-browser open api.example.com --headers @./auth-headers.json
+  agent-browser open api.example.com --header "Authorization: Bearer token" --header "X-Env: staging"
 "##
         }
         "back" => {
             r##"
 agent-browser back - Navigate back in history
 
-Usage: agent-browser back
+Usage: agent-browser back [--wait-until <state>]
 
 Goes back one page in the browser history, equivalent to clicking
 the browser's back button.
 
+Options:
+  --wait-until <state> load, domcontentloaded, or networkidle
+
 Global Options:
   --json               Output as JSON
   --session <name>     Use specific session
 
 Examples:
   agent-browser back
+  agent-browser back --wait-until networkidle
 "##
         }
         "forward" => {
             r##"
 agent-browser forward - Navigate forward in history
 
-Usage: agent-browser forward
+Usage: agent-browser forward [--wait-until <state>]
 
 Goes forward one page in the browser history, equivalent to clicking
 the browser's forward button.
 
+Options:
+  --wait-until <state> load, domcontentloaded, or networkidle
+
 Global Options:
   --json               Output as JSON
   --session <name>     Use specific session
 
 Examples:
   agent-browser forward
+  agent-browser forward --wait-until networkidle
 "##
         }
         "reload" => {
             r##"
 agent-browser reload - Reload the current page
 
-Usage: agent-browser reload
+Usage: agent-browser reload [--hard] [--wait-until <state>]
 
 Reloads the current page, equivalent to pressing F5 or clicking
 the browser's reload button.
 
+Options:
+  --hard               Bypass the browser cache
+  --wait-until <state> load, domcontentloaded, or networkidle
+
 Global Options:
   --json               Output as JSON
   --session <name>     Use specific session
 
 Examples:
   agent-browser reload
+  agent-browser reload --hard
+  agent-browser reload --wait-until networkidle
 "##
         }
 
@@ -639,7 +1059,7 @@ Examples:
             r##"
 agent-browser click - Click an element
 
-Usage: agent-browser click <selector> [--new-tab]
+Usage: agent-browser click <selector> [--new-tab] [--no-wait-after]
 
 Clicks on the specified element. The selector can be a CSS selector,
 XPath, or an element reference from snapshot (e.g., @e1).
@@ -647,6 +1067,7 @@ XPath, or an element reference from snapshot (e.g., @e1).
 Options:
   --new-tab            Open link in a new tab instead of navigating current tab
                        (only works on elements with href attribute)
+  --no-wait-after      Don't wait for navigations started by the action
 
 Global Options:
   --json               Output as JSON
@@ -658,17 +1079,21 @@ Examples:
   agent-browser click "button.primary"
   agent-browser click "//button[@type='submit']"
   agent-browser click @e3 --new-tab
+  agent-browser click "#logout" --no-wait-after
 "##
         }
         "dblclick" => {
             r##"
 agent-browser dblclick - Double-click an element
 
-Usage: agent-browser dblclick <selector>
+Usage: agent-browser dblclick <selector> [--no-wait-after]
 
 Double-clicks on the specified element. Useful for text selection
 or triggering double-click handlers.
 
+Options:
+  --no-wait-after      Don't wait for navigations started by the action
+
 Global Options:
   --json               Output as JSON
   --session <name>     Use specific session
@@ -682,11 +1107,14 @@ Examples:
             r##"
 agent-browser fill - Clear and fill an input field
 
-Usage: agent-browser fill <selector> <text>
+Usage: agent-browser fill <selector> <text> [--no-wait-after]
 
 Clears the input field and fills it with the specified text.
 This replaces any existing content in the field.
 
+Options:
+  --no-wait-after      Don't wait for navigations started by the action
+
 Global Options:
   --json               Output as JSON
   --session <name>     Use specific session
@@ -701,11 +1129,14 @@ Examples:
             r##"
 agent-browser type - Type text into an element
 
-Usage: agent-browser type <selector> <text>
+Usage: agent-browser type <selector> <text> [--no-wait-after]
 
 Types text into the specified element character by character.
 Unlike fill, this does not clear existing content first.
 
+Options:
+  --no-wait-after      Don't wait for navigations started by the action
+
 Global Options:
   --json               Output as JSON
   --session <name>     Use specific session
@@ -794,8 +1225,16 @@ Examples:
 agent-browser select - Select a dropdown option
 
 Usage: agent-browser select <selector> <value...>
+       agent-browser select <selector> --by-index <n>
+       agent-browser select <selector> --by-label <text>
+
+Selects one or more options in a <select> dropdown by value. Use
+--by-index or --by-label instead of values to pick a single option by
+its position or visible text.
 
-Selects one or more options in a <select> dropdown by value.
+Options:
+  --by-index <n>       Select the option at a zero-based index
+  --by-label <text>    Select the option with this visible label
 
 Global Options:
   --json               Output as JSON
@@ -805,6 +1244,8 @@ Examples:
   agent-browser select "#country" "US"
   agent-browser select @e5 "option2"
   agent-browser select "#menu" "opt1" "opt2" "opt3"
+  agent-browser select "#menu" --by-index 2
+  agent-browser select "#country" --by-label "United States"
 "##
         }
         "drag" => {
@@ -828,9 +1269,14 @@ Examples:
             r##"
 agent-browser upload - Upload files
 
-Usage: agent-browser upload <selector> <files...>
+Usage: agent-browser upload <selector> <files...> [--base64 <name> <content>]
+
+Uploads one or more files to a file input element. File arguments may be
+glob patterns (*, ?, [...]), which expand to every matching file.
 
-Uploads one or more files to a file input element.
+Options:
+  --base64 <name> <content>  Upload inline base64 content as a file named
+                             <name> (repeatable)
 
 Global Options:
   --json               Output as JSON
@@ -839,13 +1285,15 @@ Global Options:
 Examples:
   agent-browser upload "#file-input" ./document.pdf
   agent-browser upload @e3 ./image1.png ./image2.png
+  agent-browser upload "#file-input" "./fixtures/*.csv"
+  agent-browser upload "#file-input" --base64 test.txt aGVsbG8=
 "##
         }
         "download" => {
             r##"
 agent-browser download - Download a file by clicking an element
 
-Usage: agent-browser download <selector> <path>
+Usage: agent-browser download <selector> <path> [--wait] [--timeout <ms>]
 
 Clicks an element that triggers a download and saves the file to the specified path.
 
@@ -853,6 +1301,12 @@ Arguments:
   selector             Element to click (CSS selector or @ref)
   path                 Path where the downloaded file will be saved
 
+Options:
+  --wait               Listen for the download before clicking and wait for it
+                       (alias: --wait-for-download)
+  --timeout <ms>       How long to wait for the download
+  --path <path>        Alternative to the positional path
+
 Global Options:
   --json               Output as JSON
   --session <name>     Use specific session
@@ -861,6 +1315,7 @@ Examples:
   agent-browser download "#download-btn" ./file.pdf
   agent-browser download @e5 ./report.xlsx
   agent-browser download "a[href$='.zip']" ./archive.zip
+  agent-browser download "#export" ./data.csv --wait --timeout 60000
 "##
         }
 
@@ -869,9 +1324,11 @@ Examples:
             r##"
 agent-browser press - Press a key or key combination
 
-Usage: agent-browser press <key>
+Usage: agent-browser press <key> [--selector <sel>]
 
 Presses a key or key combination. Supports special keys and modifiers.
+Pass several space-separated keys in one quoted argument to press them
+in sequence.
 
 Aliases: key
 
@@ -884,6 +1341,9 @@ Special Keys:
 Modifiers (combine with +):
   Control, Alt, Shift, Meta
 
+Options:
+  --selector <sel>     Focus an element before pressing
+
 Global Options:
   --json               Output as JSON
   --session <name>     Use specific session
@@ -894,6 +1354,8 @@ Examples:
   agent-browser press Control+a
   agent-browser press Control+Shift+s
   agent-browser press Escape
+  agent-browser press "Tab Tab Enter"
+  agent-browser press Enter --selector "#search"
 "##
         }
         "keydown" => {
@@ -1033,10 +1495,31 @@ Modes:
   --fn <expression>    Wait for JavaScript expression to be truthy
   --text <text>        Wait for text to appear on page
   --download [path]    Wait for a download to complete (optionally save to path)
+  --element-hidden <selector>
+                       Wait for element to become hidden
+  --element-detached <selector>
+                       Wait for element to be removed from the DOM
+  --navigation         Wait for the next navigation to complete
+  --request <pattern>  Wait for a request matching the URL pattern
+  --response <pattern> Wait for a response matching the URL pattern
+  --console <pattern>  Wait for a console message matching the pattern
+
+Navigation Options (with --navigation):
+  --wait-until <state> load, domcontentloaded, or networkidle
+
+Request Options (with --request/--response):
+  --timeout <ms>       Maximum time to wait in milliseconds
+
+Console Options (with --console):
+  --type <type>        Only match messages of this type (log, error, warn, info)
 
 Download Options (with --download):
   --timeout <ms>       Timeout in milliseconds for download to start
 
+Polling Options (with <selector>, --fn, --text, ...):
+  --polling-interval <ms>
+                       How often to re-check the condition (default: daemon's)
+
 Global Options:
   --json               Output as JSON
   --session <name>     Use specific session
@@ -1047,9 +1530,17 @@ Examples:
   agent-browser wait --url "**/dashboard"
   agent-browser wait --load networkidle
   agent-browser wait --fn "window.appReady === true"
+  agent-browser wait --fn "window.jobDone" --polling-interval 2000 --timeout 60000
   agent-browser wait --text "Welcome back"
   agent-browser wait --download ./file.pdf
   agent-browser wait --download ./report.xlsx --timeout 30000
+  agent-browser wait --element-hidden "#loading-spinner"
+  agent-browser wait --element-detached ".modal"
+  agent-browser wait --navigation --wait-until networkidle
+  agent-browser wait --request "**/api/login"
+  agent-browser wait --response "**/api/users" --timeout 10000
+  agent-browser wait --console "App ready"
+  agent-browser wait --console "failed" --type error
 "##
         }
 
@@ -1058,13 +1549,15 @@ Examples:
             r##"
 agent-browser screenshot - Take a screenshot
 
-Usage: agent-browser screenshot [path]
+Usage: agent-browser screenshot [selector] [path] [--mask <sel>]...
 
 Captures a screenshot of the current page. If no path is provided,
 saves to a temporary directory with a generated filename.
 
 Options:
   --full, -f           Capture full page (not just viewport)
+  --mask <sel>         Black out matching elements before capture
+                       (repeatable, e.g. for passwords or card numbers)
   --annotate           Overlay numbered labels on interactive elements.
                        Each label [N] corresponds to ref @eN from snapshot.
                        Prints a legend mapping labels to element roles/names.
@@ -1078,6 +1571,7 @@ Examples:
   agent-browser screenshot
   agent-browser screenshot ./screenshot.png
   agent-browser screenshot --full ./full-page.png
+  agent-browser screenshot ./page.png --mask "#password" --mask ".card-number"
   agent-browser screenshot --annotate              # Labeled screenshot + legend
   agent-browser screenshot --annotate ./page.png   # Save annotated screenshot
   agent-browser screenshot --annotate --json       # JSON output with annotations
@@ -1118,6 +1612,8 @@ Options:
   -c, --compact        Remove empty structural elements
   -d, --depth <n>      Limit tree depth
   -s, --selector <sel> Scope snapshot to CSS selector
+  --only-roles <list>  Only include nodes with these ARIA roles (comma-separated,
+                       applied after --selector scoping)
 
 Global Options:
   --json               Output as JSON
@@ -1129,6 +1625,7 @@ Examples:
   agent-browser snapshot -i -C         # Interactive + cursor-interactive elements
   agent-browser snapshot --compact --depth 5
   agent-browser snapshot -s "#main-content"
+  agent-browser snapshot --only-roles button,link,textbox
 "##
         }
 
@@ -1195,12 +1692,16 @@ Retrieves various types of information from elements or the page.
 
 Subcommands:
   text <selector>            Get text content of element
+  all-text <selector>        Get text of every matching element, one per line
+  nth <n> text <selector>    Get text of the nth match (0-based; -1 is last)
   html <selector>            Get inner HTML of element
   value <selector>           Get value of input element
-  attr <selector> <name>     Get attribute value
+  attr <selector> <name>     Get attribute value (exit code 1 if absent)
+  aria <selector> [name]     Get one ARIA attribute, or all of them
   title                      Get page title
   url                        Get current URL
   count <selector>           Count matching elements
+        [--fail-on-zero]     Exit with code 1 when nothing matches
   box <selector>             Get bounding box (x, y, width, height)
   styles <selector>          Get computed styles of elements
 
@@ -1210,12 +1711,17 @@ Global Options:
 
 Examples:
   agent-browser get text @e1
+  agent-browser get all-text ".item"
+  agent-browser get nth -1 text ".item"
   agent-browser get html "#content"
   agent-browser get value "#email-input"
   agent-browser get attr "#link" href
+  agent-browser get aria "#menu-button" aria-expanded
+  agent-browser get aria @e2
   agent-browser get title
   agent-browser get url
   agent-browser get count "li.item"
+  agent-browser get count ".error" --fail-on-zero
   agent-browser get box "#header"
   agent-browser get styles "button"
   agent-browser get styles @e1
@@ -1235,6 +1741,14 @@ Subcommands:
   visible <selector>   Check if element is visible
   enabled <selector>   Check if element is enabled (not disabled)
   checked <selector>   Check if checkbox/radio is checked
+  hidden <selector>    Check if element is hidden
+  editable <selector>  Check if element is editable
+  disabled <selector>  Check if element is disabled
+  focused <selector>   Check if element has focus
+  empty <selector>     Check if element has no content
+
+Options:
+  --not                Invert the check; exits 1 when the inverted result is false
 
 Global Options:
   --json               Output as JSON
@@ -1244,53 +1758,415 @@ Examples:
   agent-browser is visible "#modal"
   agent-browser is enabled "#submit-btn"
   agent-browser is checked "#agree-checkbox"
+  agent-browser is focused "#search"
+  agent-browser is empty "#results"
+  agent-browser is visible "#spinner" --not
 "##
         }
 
-        // === Find ===
-        "find" => {
+        // === Clipboard ===
+        "clipboard" => {
             r##"
-agent-browser find - Find and interact with elements by locator
-
-Usage: agent-browser find <locator> <value> [action] [text]
-
-Finds elements using semantic locators and optionally performs an action.
-
-Locators:
-  role <role>              Find by ARIA role (--name <n>, --exact)
-  text <text>              Find by text content (--exact)
-  label <label>            Find by associated label (--exact)
-  placeholder <text>       Find by placeholder text (--exact)
-  alt <text>               Find by alt text (--exact)
-  title <text>             Find by title attribute (--exact)
-  testid <id>              Find by data-testid attribute
-  first <selector>         First matching element
-  last <selector>          Last matching element
-  nth <index> <selector>   Nth matching element (0-based)
+agent-browser clipboard - Read and write the browser clipboard
 
-Actions (default: click):
-  click, fill, type, hover, focus, check, uncheck
+Usage: agent-browser clipboard <operation> [args]
 
-Options:
-  --name <name>        Filter role by accessible name
-  --exact              Require exact text match
+Operations:
+  copy <text>          Write text to the clipboard
+  paste <selector>     Paste the clipboard contents into an element
+  get                  Print the clipboard contents
 
 Global Options:
   --json               Output as JSON
   --session <name>     Use specific session
 
 Examples:
-  agent-browser find role button click --name Submit
-  agent-browser find text "Sign In" click
-  agent-browser find label "Email" fill "user@example.com"
-  agent-browser find placeholder "Search..." type "query"
-  agent-browser find testid "login-form" click
-  agent-browser find first "li.item" click
-  agent-browser find nth 2 ".card" hover
+  agent-browser clipboard copy "hello world"
+  agent-browser clipboard paste "#search"
+  agent-browser clipboard get
 "##
         }
 
-        // === Mouse ===
+        // === Performance ===
+        "performance" => {
+            r##"
+agent-browser performance - Read Core Web Vitals and User Timing data
+
+Usage: agent-browser performance <operation> [args]
+
+Operations:
+  metrics                            Print LCP, FID, CLS, TTFB, FCP and navigation timings
+  mark <name>                        Create a User Timing mark
+  measure <name> [start] [end]       Measure between two marks (defaults to
+                                     navigation start and now)
+
+Metrics are printed one per line. Timings are in milliseconds, CLS is
+unitless, and metrics the page has not produced yet (e.g. FID before any
+interaction) are shown as n/a.
+
+Global Options:
+  --json               Output as JSON
+  --session <name>     Use specific session
+
+Examples:
+  agent-browser performance metrics
+  agent-browser performance mark checkout-start
+  agent-browser click "#pay"
+  agent-browser performance mark checkout-end
+  agent-browser performance measure checkout checkout-start checkout-end
+"##
+        }
+
+        // === Mock ===
+        "mock" => {
+            r##"
+agent-browser mock - Mock network routes from a directory of fixture files
+
+Usage: agent-browser mock <operation> [args]
+
+Operations:
+  load <fixture-dir>   Load every *.json fixture in the directory as a route
+  clear                Remove all mock routes
+  status               Show how many mock rules are active
+
+Fixture format (one route per file):
+  {
+    "url": "**/api/users",          URL pattern to intercept (required)
+    "status": 200,                  HTTP status (default 200)
+    "body": [{ "id": 1 }],          Response body (JSON value or string)
+    "contentType": "application/json",
+    "headers": { "x-mock": "1" },   Extra response headers
+    "delay": 500,                   Delay the response in milliseconds
+    "abort": false                  Abort the request instead of responding
+  }
+
+Global Options:
+  --json               Output as JSON
+  --session <name>     Use specific session
+
+Examples:
+  agent-browser mock load ./fixtures
+  agent-browser mock status
+  agent-browser mock clear
+"##
+        }
+
+        // === Foreach ===
+        "foreach" => {
+            r##"
+agent-browser foreach - Run a command on every element matching a selector
+
+Usage: agent-browser foreach <selector> <command> [args...] [--output <template>]
+
+The command runs once per matching element, with that element as its
+target. Supported commands: click, dblclick, fill, type, hover, focus,
+check, uncheck, select, scrollintoview, screenshot, highlight, get, is.
+
+Options:
+  --output <template>  Output path per element (e.g. for screenshot)
+
+Template variables (in args and --output):
+  {{index}}            Zero-based position of the element
+  {{text}}             Text content of the element
+  {{ref}}              Ref of the element (e.g. e12)
+
+Global Options:
+  --json               Output as JSON
+  --session <name>     Use specific session
+
+Examples:
+  agent-browser foreach .checkbox check
+  agent-browser foreach .card screenshot --output ./cards/{{index}}.png
+  agent-browser foreach "input.qty" fill "{{index}}"
+  agent-browser foreach "li a" get text
+"##
+        }
+
+        // === Inject ===
+        "inject" => {
+            r##"
+agent-browser inject - Inject a script or stylesheet into pages
+
+Usage: agent-browser inject script <path> [--url <pattern>]
+       agent-browser inject style <path> [--url <pattern>]
+
+Reads a local JavaScript or CSS file and injects it into the page. The file is
+read by the CLI, so the path is relative to the current directory.
+
+Operations:
+  script <path>        Inject a JavaScript file
+  style <path>         Inject a CSS file
+
+Options:
+  --url <pattern>      Only inject into pages whose URL matches the pattern
+
+Global Options:
+  --json               Output as JSON
+  --session <name>     Use specific session
+
+Examples:
+  agent-browser inject script ./utils.js
+  agent-browser inject style ./overrides.css
+  agent-browser inject style ./no-animations.css --url "**/checkout/*"
+"##
+        }
+
+        // === Version ===
+        "version" => {
+            r##"
+agent-browser version - Show CLI version and daemon protocol version
+
+Usage: agent-browser version
+       agent-browser --version | -V
+
+Prints the CLI version and the version of the JSON protocol it uses to talk
+to the daemon. Does not start the browser.
+
+Global Options:
+  --json               Output as JSON: {"version": "...", "protocol": N}
+
+Examples:
+  agent-browser version
+  agent-browser version --json
+"##
+        }
+
+        // === Schema ===
+        "schema" => {
+            r##"
+agent-browser schema - Print the JSON protocol schema
+
+Usage: agent-browser schema
+
+Asks the daemon for a JSON Schema document describing every action and its
+fields. Useful when driving the daemon protocol from another program.
+
+Global Options:
+  --json               Output as JSON
+  --session <name>     Use specific session
+
+Examples:
+  agent-browser schema > agent-browser.schema.json
+"##
+        }
+
+        // === Completions ===
+        "completions" => {
+            r##"
+agent-browser completions - Generate shell tab completion
+
+Usage: agent-browser completions <bash|zsh|fish|powershell>
+
+Prints a completion script covering all commands, their subcommands and
+global flags. Path-valued flags (--config, --profile, --state, ...) complete
+file names.
+
+Examples:
+  # bash (add to ~/.bashrc)
+  source <(agent-browser completions bash)
+
+  # zsh (add to ~/.zshrc)
+  source <(agent-browser completions zsh)
+
+  # fish
+  agent-browser completions fish > ~/.config/fish/completions/agent-browser.fish
+
+  # PowerShell (add to $PROFILE)
+  agent-browser completions powershell | Out-String | Invoke-Expression
+"##
+        }
+
+        // === Config ===
+        "config" => {
+            r##"
+agent-browser config - Check config files
+
+Usage: agent-browser config validate [--strict]
+
+Loads the config the same way every command does (--config, or the user and
+project files) and warns about keys agent-browser does not recognize, such as
+misspellings. Ends with "Valid" or "Invalid". Runs locally without a browser.
+
+Options:
+  --strict             Treat unknown keys as errors (exit code 1)
+
+Global Options:
+  --json               Output {"success":...,"unknownKeys":[...]}
+  --config <path>      Validate this file instead of the defaults
+
+Examples:
+  agent-browser config validate
+  agent-browser config validate --strict --config ./ci-config.json
+"##
+        }
+
+        // === Daemon ===
+        "daemon" => {
+            r##"
+agent-browser daemon - Control the background daemon
+
+Usage: agent-browser daemon <start|stop|status|restart|logs>
+
+Other commands start the daemon automatically. These subcommands manage it
+explicitly for the current session (--session or AGENT_BROWSER_SESSION).
+
+Subcommands:
+  start                Start the daemon (launch options such as --headed apply)
+  stop                 Stop the daemon (SIGTERM on Unix, TerminateProcess on Windows)
+  status               Show whether the daemon is running, its PID and uptime
+  restart              Stop, then start the daemon
+  logs                 Print the daemon's stderr since it was last started
+
+Global Options:
+  --json               Output as JSON
+  --session <name>     Use specific session
+
+Examples:
+  agent-browser daemon status
+  agent-browser daemon restart --headed
+  agent-browser --session work daemon logs
+"##
+        }
+
+        // === Run ===
+        "run" => {
+            r##"
+agent-browser run - Run commands from a script file
+
+Usage: agent-browser run <script-file>
+
+Reads a file with one agent-browser command per line (without the
+"agent-browser" prefix) and sends them to the browser as a single batch,
+executed in order. Blank lines and lines starting with # are ignored.
+Arguments can be quoted with single or double quotes.
+
+Every line is checked before anything runs; a line that fails to parse
+is reported with its line number.
+
+Global Options:
+  --json               Output as JSON
+  --session <name>     Use specific session
+
+Examples:
+  agent-browser run ./login.ab
+
+  # login.ab
+  open https://example.com/login
+  fill "#email" "user@example.com"
+  click "button[type=submit]"
+  wait --url "**/dashboard"
+"##
+        }
+
+        // === Assert ===
+        "assert" => {
+            r##"
+agent-browser assert - Assert a condition for test scripts
+
+Usage: agent-browser assert <subcommand> [args]
+
+Checks a condition on the current page. Prints the expected and actual
+values and exits with code 1 when the assertion fails.
+
+Subcommands:
+  visible <selector>           Element is visible
+  text <selector> <expected>   Element text equals the expected text
+  count <selector> <n>         Selector matches exactly n elements
+  url <pattern>                Page URL matches the pattern
+  title <expected>             Page title equals the expected text
+
+Global Options:
+  --json               Output as JSON (includes "expected" and "actual")
+  --session <name>     Use specific session
+
+Examples:
+  agent-browser assert visible "#dashboard"
+  agent-browser assert text h1 "Welcome back"
+  agent-browser assert count ".cart-item" 3
+  agent-browser assert url "**/checkout"
+  agent-browser assert title "Order confirmed"
+"##
+        }
+
+        // === Validate ===
+        "validate" => {
+            r##"
+agent-browser validate - Check that a selector matches elements
+
+Usage: agent-browser validate <selector> [--min <n>] [--max <n>]
+
+Counts the elements matching the selector without interacting with them.
+Exits with code 1 when no element matches (or the count is outside the
+--min/--max bounds), and 0 otherwise. Unlike "is visible", hidden elements
+count as existing.
+
+Options:
+  --min <n>            Require at least n matches (default 1)
+  --max <n>            Require at most n matches
+
+Global Options:
+  --json               Output as JSON (includes "count")
+  --session <name>     Use specific session
+
+Examples:
+  agent-browser validate "#submit-button"
+  agent-browser validate ".cart-item" --min 2 --max 5
+  agent-browser validate ".error" --max 0
+"##
+        }
+
+        // === Find ===
+        "find" => {
+            r##"
+agent-browser find - Find and interact with elements by locator
+
+Usage: agent-browser find <locator> <value> [action] [text]
+
+Finds elements using semantic locators and optionally performs an action.
+
+Locators:
+  role <role>              Find by ARIA role (--name <n>, --exact)
+  text <text>              Find by text content (--exact)
+  label <label>            Find by associated label (--exact)
+  placeholder <text>       Find by placeholder text (--exact)
+  alt <text>               Find by alt text (--exact)
+  title <text>             Find by title attribute (--exact)
+  testid <id>              Find by data-testid attribute
+  first <selector>         First matching element
+  last <selector>          Last matching element
+  nth <index> <selector>   Nth matching element (0-based)
+  filter <selector>        Narrow a selector (--has-text <text>, --has <sel>)
+  has <selector> <sub>     Elements containing a matching descendant
+
+Actions (default: click):
+  click, fill, type, hover, focus, check, uncheck
+
+Options:
+  --name <name>        Filter role by accessible name
+  --exact              Require exact text match
+  --count <n>          Count matches and exit 1 unless exactly n elements
+                       match (only with the count action, the default)
+  --timeout <ms>       Timeout for this find
+
+Global Options:
+  --json               Output as JSON
+  --session <name>     Use specific session
+
+Examples:
+  agent-browser find role button click --name Submit
+  agent-browser find text "Sign In" click
+  agent-browser find label "Email" fill "user@example.com"
+  agent-browser find placeholder "Search..." type "query"
+  agent-browser find testid "login-form" click
+  agent-browser find first "li.item" click
+  agent-browser find nth 2 ".card" hover
+  agent-browser find filter "li.item" --has-text "Apple"
+  agent-browser find has ".card" "button.buy"
+  agent-browser find role row --count 5
+"##
+        }
+
+        // === Mouse ===
         "mouse" => {
             r##"
 agent-browser mouse - Low-level mouse operations
@@ -1301,9 +2177,13 @@ Performs low-level mouse operations for precise control.
 
 Subcommands:
   move <x> <y>         Move mouse to coordinates
+                       (--steps <n> to interpolate intermediate moves)
   down [button]        Press mouse button (left, right, middle)
   up [button]          Release mouse button
   wheel <dy> [dx]      Scroll mouse wheel
+  click <x> <y>        Click at coordinates
+  dblclick <x> <y>     Double-click at coordinates
+  rightclick <x> <y>   Right-click at coordinates
 
 Global Options:
   --json               Output as JSON
@@ -1311,11 +2191,14 @@ Global Options:
 
 Examples:
   agent-browser mouse move 100 200
+  agent-browser mouse move 400 300 --steps 20
   agent-browser mouse down
   agent-browser mouse up
   agent-browser mouse down right
   agent-browser mouse wheel 100
   agent-browser mouse wheel -50 0
+  agent-browser mouse click 400 300
+  agent-browser mouse rightclick 120 80
 "##
         }
 
@@ -1330,6 +2213,13 @@ Configures various browser settings and emulation options.
 
 Settings:
   viewport <w> <h>           Set viewport size
+    --preset <name>          Use a named size instead: iPhoneSE, iPhone15,
+                             iPhone15Pro, iPhone15ProMax, Pixel7, GalaxyS23,
+                             iPad, iPadPro, Laptop, Desktop720, Desktop1080,
+                             Desktop1440 (case-insensitive)
+    --device-scale-factor <n>  Device pixel ratio (e.g., 2 for retina)
+    --is-mobile              Emulate a mobile viewport
+    --has-touch              Enable touch events
   device <name>              Emulate device (e.g., "iPhone 12")
   geo <lat> <lng>            Set geolocation
   offline [on|off]           Toggle offline mode
@@ -1337,6 +2227,13 @@ Settings:
   credentials <user> <pass>  Set HTTP authentication
   media [dark|light]         Set color scheme preference
         [reduced-motion]     Enable reduced motion
+        [--forced-colors active|none]   Emulate forced colors (high contrast)
+        [--contrast more|no-preference] Emulate a contrast preference
+  timeout <ms>               Default timeout for actions (0 = no timeout)
+  timezone <tz>              Emulate timezone (e.g., "America/New_York")
+  locale <locale>            Emulate locale (e.g., "fr-FR")
+  permissions <origin> <perm...>
+                             Grant permissions to an origin
 
 Global Options:
   --json               Output as JSON
@@ -1344,6 +2241,9 @@ Global Options:
 
 Examples:
   agent-browser set viewport 1920 1080
+  agent-browser set viewport 390 844 --device-scale-factor 3 --is-mobile --has-touch
+  agent-browser set viewport --device-scale-factor 2
+  agent-browser set viewport --preset iPhone15
   agent-browser set device "iPhone 12"
   agent-browser set geo 37.7749 -122.4194
   agent-browser set offline on
@@ -1351,6 +2251,11 @@ Examples:
   agent-browser set credentials admin secret123
   agent-browser set media dark
   agent-browser set media light reduced-motion
+  agent-browser set media --forced-colors active --contrast more
+  agent-browser set timeout 10000
+  agent-browser set timezone "Europe/Paris"
+  agent-browser set locale fr-FR
+  agent-browser set permissions https://example.com geolocation notifications
 "##
         }
 
@@ -1367,10 +2272,29 @@ Subcommands:
   route <url> [options]      Intercept requests matching URL pattern
     --abort                  Abort matching requests
     --body <json>            Respond with custom body
+    --file <path>            Respond with the contents of a file
+    --content-type <mime>    Content-Type of the mocked response
+    --status <code>          Respond with HTTP status (100-599)
+    --delay <ms>             Delay the response by milliseconds
+    --redirect <url>         Redirect to URL (status 302 unless --status given)
+    --headers <json>         Add or override response headers
   unroute [url]              Remove route (all if no URL)
+  throttle <preset>          Emulate network conditions
+                             (slow-3g, fast-3g, offline, none)
+  throttle [options]         Custom network conditions
+    --download <kbps>        Download throughput
+    --upload <kbps>          Upload throughput
+    --latency <ms>           Added round-trip latency
+  har save <path>            Save captured traffic as a HAR file
+  har load <path>            Replay responses from a HAR file
   requests [options]         List captured requests
     --clear                  Clear request log
+    --follow                 Stream requests as they happen until Ctrl-C
     --filter <pattern>       Filter by URL pattern
+    --url <glob>             Only requests whose URL matches the glob
+    --method <method>        Only requests with this HTTP method
+    --status <code>          Only requests with this response status
+    --format <fmt>           Output format: table (default), json, ndjson
 
 Global Options:
   --json               Output as JSON
@@ -1379,9 +2303,21 @@ Global Options:
 Examples:
   agent-browser network route "**/api/*" --abort
   agent-browser network route "**/data.json" --body '{"mock": true}'
+  agent-browser network route "**/api/users" --status 500 --delay 2000
+  agent-browser network route "**/api/users" --file ./fixtures/users.json
+  agent-browser network route "**/old" --redirect "https://example.com/new"
+  agent-browser network route "**/api/*" --headers '{"Access-Control-Allow-Origin": "*"}'
   agent-browser network unroute
+  agent-browser network throttle slow-3g
+  agent-browser network throttle --download 500 --upload 250 --latency 300
+  agent-browser network throttle none
+  agent-browser network har save ./traffic.har
+  agent-browser network har load ./traffic.har
   agent-browser network requests
   agent-browser network requests --filter "api"
+  agent-browser network requests --url "**/api/*" --method POST --status 500
+  agent-browser network requests --output-format ndjson
+  agent-browser network requests --follow --url "**/api/*"
   agent-browser network requests --clear
 "##
         }
@@ -1401,7 +2337,13 @@ Types:
 
 Operations:
   get [key]            Get all storage or specific key
+    --fail-if-missing  Exit with code 1 if the key is not set
   set <key> <value>    Set a key-value pair
+  delete <key>         Remove a single key
+  list                 List all keys
+  dump                 Get all key-value pairs
+  export <file>        Save all key-value pairs to a JSON file
+  import <file>        Set key-value pairs from a JSON object file
   clear                Clear all storage
 
 Global Options:
@@ -1412,8 +2354,14 @@ Examples:
   agent-browser storage local
   agent-browser storage local get authToken
   agent-browser storage local set theme "dark"
+  agent-browser storage local delete authToken
+  agent-browser storage local list
+  agent-browser storage session dump
+  agent-browser storage local export storage.json
+  agent-browser storage local import storage.json
   agent-browser storage local clear
   agent-browser storage session get userId
+  agent-browser storage local get token --fail-if-missing
 "##
         }
 
@@ -1427,8 +2375,11 @@ Usage: agent-browser cookies [operation] [args]
 Manage browser cookies for the current context.
 
 Operations:
-  get                                Get all cookies (default)
+  get [name]                         Get all cookies (default), or one by name
   set <name> <value> [options]       Set a cookie with optional properties
+  delete <name> [options]            Delete a single cookie
+  import <file>                      Set cookies from a JSON array file
+  export <file>                      Save all cookies to a JSON file
   clear                              Clear all cookies
 
 Cookie Set Options:
@@ -1440,6 +2391,10 @@ Cookie Set Options:
   --sameSite <Strict|Lax|None>       SameSite policy
   --expires <timestamp>              Expiration time (Unix timestamp in seconds)
 
+Cookie Delete Options:
+  --domain <domain>                  Only delete the cookie for this domain
+  --path <path>                      Only delete the cookie for this path
+
 Note: If --url, --domain, and --path are all omitted, the cookie will be set
 for the current page URL.
 
@@ -1466,6 +2421,16 @@ Examples:
   # Get all cookies
   agent-browser cookies
 
+  # Get a single cookie by name
+  agent-browser cookies get session_id
+
+  # Delete one cookie
+  agent-browser cookies delete session_id --domain example.com
+
+  # Share cookies between sessions
+  agent-browser cookies export cookies.json
+  agent-browser --session other cookies import cookies.json
+
   # Clear all cookies
   agent-browser cookies clear
 "##
@@ -1482,9 +2447,17 @@ Manage browser tabs in the current window.
 
 Operations:
   list                 List all tabs (default)
+  info                 Show title, URL and index of the current tab
   new [url]            Open new tab
+  new --background     Open new tab without switching to it
+  duplicate [index]    Duplicate a tab (current if no index)
   close [index]        Close tab (current if no index)
+  close --all          Close all tabs
+  close --others       Close all tabs except the current one
   <index>              Switch to tab by index
+  switch <index>       Switch to tab by index
+  switch --url <pat>   Switch to the first tab whose URL matches
+  switch --title <pat> Switch to the first tab whose title matches
 
 Global Options:
   --json               Output as JSON
@@ -1493,11 +2466,17 @@ Global Options:
 Examples:
   agent-browser tab
   agent-browser tab list
+  agent-browser tab info
   agent-browser tab new
   agent-browser tab new https://example.com
+  agent-browser tab new https://example.com --background
+  agent-browser tab duplicate
   agent-browser tab 2
+  agent-browser tab switch --url "**/dashboard"
+  agent-browser tab switch --title "Inbox"
   agent-browser tab close
   agent-browser tab close 1
+  agent-browser tab close --others
 "##
         }
 
@@ -1506,12 +2485,16 @@ Examples:
             r##"
 agent-browser window - Manage browser windows
 
-Usage: agent-browser window <operation>
+Usage: agent-browser window <operation> [args]
 
 Manage browser windows.
 
 Operations:
   new                  Open new browser window
+  list                 List open windows
+  switch <index>       Switch to window by index
+  close [index]        Close window (current if no index)
+  resize <w> <h>       Resize the current window
 
 Global Options:
   --json               Output as JSON
@@ -1519,6 +2502,10 @@ Global Options:
 
 Examples:
   agent-browser window new
+  agent-browser window list
+  agent-browser window switch 1
+  agent-browser window close 1
+  agent-browser window resize 1280 800
 "##
         }
 
@@ -1527,13 +2514,20 @@ Examples:
             r##"
 agent-browser frame - Switch frame context
 
-Usage: agent-browser frame <selector|main>
+Usage: agent-browser frame <selector|main|list>
+       agent-browser frame --url <pattern>
+       agent-browser frame --name <name>
 
 Switch to an iframe or back to the main frame.
 
 Arguments:
   <selector>           CSS selector for iframe
   main                 Switch back to main frame
+  list                 List frames on the page
+
+Options:
+  --url <pattern>      Switch to the frame whose URL matches
+  --name <name>        Switch to the frame with this name
 
 Global Options:
   --json               Output as JSON
@@ -1543,6 +2537,9 @@ Examples:
   agent-browser frame "#embed-iframe"
   agent-browser frame "iframe[name='content']"
   agent-browser frame main
+  agent-browser frame list
+  agent-browser frame --url "**/checkout"
+  agent-browser frame --name payment
 "##
         }
 
@@ -1558,6 +2555,10 @@ Respond to browser dialogs (alert, confirm, prompt).
 Operations:
   accept [text]        Accept dialog, optionally with prompt text
   dismiss              Dismiss/cancel dialog
+  auto accept          Accept future dialogs automatically (--text <value> for prompts)
+  auto dismiss         Dismiss future dialogs automatically
+  auto off             Stop handling dialogs automatically
+  get                  Print the open dialog's type, message and prompt default
 
 Global Options:
   --json               Output as JSON
@@ -1567,6 +2568,9 @@ Examples:
   agent-browser dialog accept
   agent-browser dialog accept "my input"
   agent-browser dialog dismiss
+  agent-browser dialog auto accept --text "yes"
+  agent-browser dialog auto off
+  agent-browser dialog get
 "##
         }
 
@@ -1582,6 +2586,8 @@ Record a trace for debugging with Playwright Trace Viewer.
 Operations:
   start [path]         Start recording trace
   stop [path]          Stop recording and save trace
+  open [path]          Open a trace in the viewer (last recorded by default)
+  status               Show whether a trace is being recorded
 
 Global Options:
   --json               Output as JSON
@@ -1592,6 +2598,8 @@ Examples:
   agent-browser trace start ./my-trace
   agent-browser trace stop
   agent-browser trace stop ./debug-trace.zip
+  agent-browser trace open ./debug-trace.zip
+  agent-browser trace status
 "##
         }
 
@@ -1609,6 +2617,8 @@ Perfetto UI (https://ui.perfetto.dev/), or other trace analysis tools.
 Operations:
   start                Start profiling
   stop [path]          Stop profiling and save to file
+  snapshot <path>      Capture a heap memory snapshot (.heapsnapshot)
+  status               Show whether profiling is active
 
 Start Options:
   --categories <list>  Comma-separated trace categories (default includes
@@ -1629,6 +2639,9 @@ Examples:
   agent-browser profiler start --categories "devtools.timeline,v8.execute,blink.user_timing"
   agent-browser profiler stop ./custom-trace.json
 
+  # Heap snapshot (independent of CPU profiling)
+  agent-browser profiler snapshot ./heap.heapsnapshot
+
 The output file can be viewed in:
   - Chrome DevTools: Performance panel > Load profile
   - Perfetto: https://ui.perfetto.dev/
@@ -1678,12 +2691,15 @@ Examples:
             r##"
 agent-browser console - View console logs
 
-Usage: agent-browser console [--clear]
+Usage: agent-browser console [--clear] [--follow] [--filter <level>] [--filter-text <regex>]
 
 View browser console output (log, warn, error, info).
 
 Options:
   --clear              Clear console log buffer
+  --follow             Stream new messages as they arrive until Ctrl-C
+  --filter <level>     Only show one level: log, info, warn, error, debug
+  --filter-text <re>   Only show messages matching a regular expression
 
 Global Options:
   --json               Output as JSON
@@ -1692,18 +2708,23 @@ Global Options:
 Examples:
   agent-browser console
   agent-browser console --clear
+  agent-browser console --filter error
+  agent-browser console --filter-text "fetch failed"
+  agent-browser console --follow --filter error
 "##
         }
         "errors" => {
             r##"
 agent-browser errors - View page errors
 
-Usage: agent-browser errors [--clear]
+Usage: agent-browser errors [--clear] [--follow] [--type <type>]
 
 View JavaScript errors and uncaught exceptions.
 
 Options:
   --clear              Clear error buffer
+  --follow             Stream new errors as they happen until Ctrl-C
+  --type <type>        Only show errors of this type (e.g. TypeError)
 
 Global Options:
   --json               Output as JSON
@@ -1712,6 +2733,8 @@ Global Options:
 Examples:
   agent-browser errors
   agent-browser errors --clear
+  agent-browser errors --type TypeError
+  agent-browser errors --follow
 "##
         }
 
@@ -1720,10 +2743,21 @@ Examples:
             r##"
 agent-browser highlight - Highlight an element
 
-Usage: agent-browser highlight <selector>
+Usage: agent-browser highlight <selector> [options]
+       agent-browser highlight all <selector> [options]
+       agent-browser highlight clear
 
 Visually highlights an element on the page for debugging.
 
+Options:
+  --highlight-color <#RRGGBB>  Highlight color (default: #FF0000)
+  --label <text>               Show a label badge next to the element
+  --duration <ms>              Remove the highlight after this many milliseconds
+
+Subcommands:
+  all <selector>       Highlight every element matching the selector
+  clear                Remove all highlights
+
 Global Options:
   --json               Output as JSON
   --session <name>     Use specific session
@@ -1731,6 +2765,40 @@ Global Options:
 Examples:
   agent-browser highlight "#target-element"
   agent-browser highlight @e5
+  agent-browser highlight @e5 --highlight-color #00AA00 --label "Submit"
+  agent-browser highlight .banner --duration 2000
+  agent-browser highlight all .card --highlight-color #0066FF
+  agent-browser highlight clear
+"##
+        }
+
+        // === Accessibility ===
+        "accessibility" => {
+            r##"
+agent-browser accessibility - Inspect and check page accessibility
+
+Usage: agent-browser accessibility [snapshot]
+       agent-browser accessibility check [--standard <standard>]
+
+Dump the raw accessibility tree as JSON, or run an accessibility audit and
+list rule violations with their impact.
+
+Operations:
+  snapshot             Print the accessibility tree as JSON (default)
+  check                Report accessibility violations
+
+Check Options:
+  --standard <std>     Only check rules for one standard:
+                       wcag2a, wcag2aa, wcag21a, wcag21aa
+
+Global Options:
+  --json               Output as JSON
+  --session <name>     Use specific session
+
+Examples:
+  agent-browser accessibility
+  agent-browser accessibility check
+  agent-browser accessibility check --standard wcag2aa
 "##
         }
 
@@ -1744,13 +2812,14 @@ Usage: agent-browser state <operation> [args]
 Save, restore, list, and manage browser state (cookies, localStorage, sessionStorage).
 
 Operations:
-  save <path>                        Save current state to file
-  load <path>                        Load state from file
+  save <path> [--encrypt [password]] Save current state to file
+  load <path> [--decrypt [password]] Load state from file
   list                               List saved state files
   show <filename>                    Show state summary
   rename <old-name> <new-name>       Rename state file
   clear [session-name] [--all]       Clear saved states
   clean --older-than <days>          Delete expired state files
+  diff <baseline> <current>          Compare two state files
 
 Automatic State Persistence:
   Use --session-name to auto-save/restore state across restarts:
@@ -1760,6 +2829,8 @@ Automatic State Persistence:
 State Encryption:
   Set AGENT_BROWSER_ENCRYPTION_KEY (64-char hex) for AES-256-GCM encryption.
   Generate a key: openssl rand -hex 32
+  For password-protected files, set AGENT_BROWSER_STATE_PASSWORD and pass a
+  bare --encrypt/--decrypt in CI so the password stays out of shell history.
 
 Global Options:
   --json               Output as JSON
@@ -1768,11 +2839,13 @@ Global Options:
 Examples:
   agent-browser state save ./auth-state.json
   agent-browser state load ./auth-state.json
+  agent-browser state save ./auth-state.json --encrypt "$PASSWORD"
   agent-browser state list
   agent-browser state show myapp-default.json
   agent-browser state rename old-name new-name
   agent-browser state clear --all
   agent-browser state clean --older-than 7
+  agent-browser state diff ./before.json ./after.json
 "##
         }
 
@@ -1781,14 +2854,20 @@ Examples:
             r##"
 agent-browser session - Manage sessions
 
-Usage: agent-browser session [operation]
+Usage: agent-browser session [operation] [name]
 
 Manage isolated browser sessions. Each session has its own browser
 instance with separate cookies, storage, and state.
 
 Operations:
   (none)               Show current session name
-  list                 List all active sessions
+  list                 List all active sessions with their start time
+  list --clean         Also remove files left behind by dead sessions
+  info [name]          Show PID, uptime and socket path (current session by default)
+  create <name>        Validate a session name before using it
+  switch <name>        Print the env var that makes <name> the default
+  kill <name>          Stop a running session's daemon
+  delete <name>        Remove leftover files of a stopped session
 
 Environment:
   AGENT_BROWSER_SESSION    Default session name
@@ -1800,6 +2879,9 @@ Global Options:
 Examples:
   agent-browser session
   agent-browser session list
+  agent-browser session info
+  agent-browser session kill test
+  agent-browser session delete test
   agent-browser --session test open example.com
 "##
         }
@@ -1886,7 +2968,7 @@ Examples:
             r##"
 agent-browser swipe - Swipe gesture (iOS)
 
-Usage: agent-browser swipe <direction> [distance]
+Usage: agent-browser swipe <direction> [distance] [--selector <sel>]
 
 Performs a swipe gesture on iOS Safari. The direction determines
 which way the content moves (swipe up scrolls down, etc.).
@@ -1896,13 +2978,36 @@ Arguments:
   distance     Optional distance in pixels (default: 300)
 
 Options:
+  --selector <sel>     Swipe within an element instead of the viewport
   --json               Output as JSON
   --session <name>     Use specific session
 
 Examples:
   agent-browser -p ios swipe up
   agent-browser -p ios swipe down 500
-  agent-browser -p ios swipe left
+  agent-browser -p ios swipe left --selector "#carousel"
+"##
+        }
+        "pinch" => {
+            r##"
+agent-browser pinch - Pinch gesture (iOS)
+
+Usage: agent-browser pinch <in|out> [--selector <sel>] [--scale <n>]
+
+Performs a two-finger pinch gesture. 'in' zooms out, 'out' zooms in.
+
+Arguments:
+  direction    in or out
+
+Options:
+  --selector <sel>     Pinch on an element instead of the viewport
+  --scale <n>          Zoom factor, must be positive (e.g. 0.5, 2)
+  --json               Output as JSON
+  --session <name>     Use specific session
+
+Examples:
+  agent-browser -p ios pinch out
+  agent-browser -p ios pinch in --selector "#map" --scale 0.5
 "##
         }
         "device" => {
@@ -1943,6 +3048,9 @@ Snapshot Diff:
     -s, --selector <sel>     Scope snapshot to a CSS selector or @ref
     -c, --compact            Use compact snapshot format
     -d, --depth <n>          Limit snapshot tree depth
+        --ignore-refs        Ignore @ref renumbering when comparing
+        --only-additions     Show only added lines
+        --only-removals      Show only removed lines
 
   Without --baseline, compares against the last snapshot taken in this session.
 
@@ -1956,6 +3064,10 @@ Screenshot Diff:
     -t, --threshold <0-1>    Color distance threshold (default: 0.1)
     -s, --selector <sel>     Scope screenshot to element
         --full               Full page screenshot
+        --mask <sel>         Black out matching elements (repeatable)
+        --ignore-colors      Compare luminance only, ignoring color differences
+        --ignore-antialiasing  Ignore pixels that differ only by antialiasing
+        --max-diff-pixels <n>  Pass when at most n pixels differ (not with --threshold)
 
 URL Diff:
 
@@ -1963,6 +3075,8 @@ URL Diff:
 
   Options:
     --screenshot             Also compare screenshots (default: snapshot only)
+    --output-dir <dir>       Save before.png, after.png and diff.png to dir
+                             (created if missing; requires --screenshot)
     --full                   Full page screenshots
     --wait-until <strategy>  Navigation wait strategy: load, domcontentloaded, networkidle (default: load)
     -s, --selector <sel>     Scope snapshots to a CSS selector or @ref
@@ -1976,10 +3090,13 @@ Global Options:
 Examples:
   agent-browser diff snapshot
   agent-browser diff snapshot --baseline before.txt
+  agent-browser diff snapshot --ignore-refs --only-additions
   agent-browser diff screenshot --baseline before.png
   agent-browser diff screenshot --baseline before.png --output diff.png --threshold 0.2
+  agent-browser diff screenshot --baseline before.png --ignore-antialiasing --max-diff-pixels 50
   agent-browser diff url https://staging.example.com https://prod.example.com
   agent-browser diff url https://v1.example.com https://v2.example.com --screenshot
+  agent-browser diff url https://v1.example.com https://v2.example.com --screenshot --output-dir ./diff
 "##
         }
 
@@ -1998,25 +3115,28 @@ Usage: agent-browser <command> [args] [options]
 
 Core Commands:
   open <url>                 Navigate to URL
-  click <sel>                Click element (or @ref)
+  click <sel>                Click element (or @ref; --no-wait-after to fire and forget)
   dblclick <sel>             Double-click element
   type <sel> <text>          Type into element
   fill <sel> <text>          Clear and fill
-  press <key>                Press key (Enter, Tab, Control+a)
+  press <key>                Press key (Enter, Tab, Control+a, "Tab Tab Enter")
   keyboard type <text>       Type text with real keystrokes (no selector)
   keyboard inserttext <text> Insert text without key events
   hover <sel>                Hover element
   focus <sel>                Focus element
   check <sel>                Check checkbox
   uncheck <sel>              Uncheck checkbox
-  select <sel> <val...>      Select dropdown option
+  select <sel> <val...>      Select dropdown option (or --by-index <n>, --by-label <text>)
   drag <src> <dst>           Drag and drop
   upload <sel> <files...>    Upload files
-  download <sel> <path>      Download file by clicking element
+  download <sel> <path>      Download file by clicking element (--wait to await it)
   scroll <dir> [px]          Scroll (up/down/left/right)
   scrollintoview <sel>       Scroll element into view
   wait <sel|ms>              Wait for element or time
-  screenshot [path]          Take screenshot
+  wait --request <url>       Wait for a matching request (--response for responses)
+  wait --console <pattern>   Wait for a matching console message
+                             (--polling-interval <ms> sets the re-check rate)
+  screenshot [path]          Take screenshot (--mask <sel> to redact, repeatable)
   pdf <path>                 Save as PDF
   snapshot                   Accessibility tree with refs (for AI)
   eval <js>                  Run JavaScript
@@ -2024,72 +3144,130 @@ Core Commands:
   close                      Close browser
 
 Navigation:
-  back                       Go back
-  forward                    Go forward
-  reload                     Reload page
+  back                       Go back (--wait-until <state> to wait for load)
+  forward                    Go forward (--wait-until <state> to wait for load)
+  reload [--hard]            Reload page (--hard bypasses the cache)
 
 Get Info:  agent-browser get <what> [selector]
-  text, html, value, attr <name>, title, url, count, box, styles
+  text, all-text, nth <n> text, html, value, attr <name>, aria [name], title, url, count,
+  box, styles
 
 Check State:  agent-browser is <what> <selector>
-  visible, enabled, checked
+  visible, enabled, checked, hidden, editable, disabled, focused, empty
+
+Assert:  agent-browser assert <what> [args]  (exits 1 on failure)
+  visible <sel>, text <sel> <expected>, count <sel> <n>, url <pattern>, title <expected>
+
+Validate:  agent-browser validate <sel> [--min <n>] [--max <n>]  (exits 1 if nothing matches)
 
 Find Elements:  agent-browser find <locator> <value> <action> [text]
-  role, text, label, placeholder, alt, title, testid, first, last, nth
+  role, text, label, placeholder, alt, title, testid, first, last, nth, filter, has
 
 Mouse:  agent-browser mouse <action> [args]
-  move <x> <y>, down [btn], up [btn], wheel <dy> [dx]
+  move <x> <y> [--steps <n>], down [btn], up [btn], wheel <dy> [dx]
+  click <x> <y>, dblclick <x> <y>, rightclick <x> <y>
+
+Clipboard:  agent-browser clipboard <action> [args]
+  copy <text>, paste <selector>, get
+
+Inject:  agent-browser inject <script|style> <path> [--url <pattern>]
+
+Performance:  agent-browser performance <action> [args]
+  metrics, mark <name>, measure <name> [start-mark] [end-mark]
+
+Foreach:  agent-browser foreach <selector> <command> [args...] [--output <template>]
+  Runs the command on every match ({{{{index}}}}, {{{{text}}}}, {{{{ref}}}} in args)
+
+Run:  agent-browser run <script-file>  (one command per line, # comments)
 
 Browser Settings:  agent-browser set <setting> [value]
-  viewport <w> <h>, device <name>, geo <lat> <lng>
+  viewport <w> <h> [--device-scale-factor <n>] [--is-mobile] [--has-touch]
+  device <name>, geo <lat> <lng>
   offline [on|off], headers <json>, credentials <user> <pass>
   media [dark|light] [reduced-motion]
+  timezone <tz>, locale <locale>, permissions <origin> <perm...>
+  timeout <ms> (0 = no timeout)
 
 Network:  agent-browser network <action>
-  route <url> [--abort|--body <json>]
+  route <url> [--abort|--body <json>|--file <path>] [--content-type <mime>]
+    [--status <code>] [--delay <ms>] [--redirect <url>] [--headers <json>]
   unroute [url]
-  requests [--clear] [--filter <pattern>]
+  requests [--clear] [--follow] [--filter <pattern>] [--url <glob>] [--method <m>] [--status <code>]
+           [--output-format table|json|ndjson]
+  throttle <slow-3g|fast-3g|offline|none> | --download <kbps> --upload <kbps> --latency <ms>
+  mock load <fixture-dir> | clear | status
+    (each *.json fixture is one route: url, status, body, contentType, headers, delay, abort)
+  har save|load <path>
 
 Storage:
-  cookies [get|set|clear]    Manage cookies (set supports --url, --domain, --path, --httpOnly, --secure, --sameSite, --expires)
-  storage <local|session>    Manage web storage
+  cookies [get [name]|set|delete|import|export|clear]  Manage cookies (set supports --url, --domain, --path, --httpOnly, --secure, --sameSite, --expires)
+  storage <local|session>    Manage web storage (get, set, delete, list, dump, export, import, clear)
 
 Tabs:
   tab [new|list|close|<n>]   Manage tabs
+  tab switch --url <pat>     Switch tab by URL (or --title <pat>)
+  tab close --all|--others   Close all tabs, or all but the current one
+  tab info                   Show current tab title, URL and index
+  tab new --background       Open a tab without focusing it
+  tab duplicate [n]          Duplicate the current tab (or tab n)
+  window [new|list|switch|close|resize]  Manage windows
+  frame <sel|main|list>      Switch frame (or --url <pat>, --name <name>)
 
 Diff:
   diff snapshot              Compare current vs last snapshot
+                             (--ignore-refs, --only-additions, --only-removals)
   diff screenshot --baseline Compare current vs baseline image
+                             (--ignore-colors, --ignore-antialiasing, --max-diff-pixels <n>)
   diff url <u1> <u2>         Compare two pages
 
 Debug:
   trace start|stop [path]    Record Playwright trace
+  trace open [path]|status   View a trace, or check if one is recording
   profiler start|stop [path] Record Chrome DevTools profile
+  profiler snapshot <path>   Save heap memory snapshot
+  profiler status            Show profiling state
   record start <path> [url]  Start video recording (WebM)
   record stop                Stop and save video
-  console [--clear]          View console logs
-  errors [--clear]           View page errors
+  console [--clear|--follow] View console logs
+                             (--filter <level>, --filter-text <regex>)
+  errors [--clear|--follow]  View page errors (--type <type>)
   highlight <sel>            Highlight element
+                             (--highlight-color <#RRGGBB>, --label <text>, --duration <ms>)
+  highlight all <sel>        Highlight every matching element
+  highlight clear            Remove all highlights
+  accessibility [snapshot]   Print accessibility tree as JSON
+  accessibility check        List accessibility violations
+                             (--standard wcag2a|wcag2aa|wcag21a|wcag21aa)
 
 Sessions:
   session                    Show current session name
-  session list               List active sessions
+  session list [--clean]     List active sessions (--clean removes stale files)
+  session info [name]        Show session PID, uptime and socket path
+  session <create|switch|kill|delete> <name>  Manage a named session
+  daemon <start|stop|status|restart|logs>     Control the session's daemon
 
 Setup:
   install                    Install browser binaries
   install --with-deps        Also install system dependencies (Linux)
+  completions <shell>        Print shell completion script
+                             (bash, zsh, fish, powershell)
+  version                    Show CLI and daemon protocol versions
+  schema                     Print JSON Schema for every daemon action
+  config validate [--strict] Check config files for unknown keys
 
 Snapshot Options:
   -i, --interactive          Only interactive elements
   -c, --compact              Remove empty structural elements
   -d, --depth <n>            Limit tree depth
   -s, --selector <sel>       Scope to CSS selector
+  --only-roles <list>        Only nodes with these ARIA roles (comma-separated)
 
 Options:
   --session <name>           Isolated session (or AGENT_BROWSER_SESSION env)
   --profile <path>           Persistent browser profile (or AGENT_BROWSER_PROFILE env)
   --state <path>             Load storage state from JSON file (or AGENT_BROWSER_STATE env)
-  --headers <json>           HTTP headers scoped to URL's origin (for auth)
+  --headers <json|@file>     HTTP headers scoped to URL's origin (for auth)
+  --header <name:value>      Single HTTP header for open (repeatable, overrides --headers)
   --executable-path <path>   Custom browser executable (or AGENT_BROWSER_EXECUTABLE_PATH)
   --extension <path>         Load browser extensions (repeatable)
   --args <args>              Browser launch args, comma or newline separated (or AGENT_BROWSER_ARGS)
@@ -2104,6 +3282,9 @@ Options:
   -p, --provider <name>      Browser provider: ios, browserbase, kernel, browseruse
   --device <name>            iOS device name (e.g., "iPhone 15 Pro")
   --json                     JSON output
+  --ndjson                   One JSON event per line (navigate, log, request, ...)
+  --format <fmt>             human, json, ndjson or table (cookies, tabs and
+                             requests as aligned columns)
   --full, -f                 Full page screenshot
   --annotate                 Annotated screenshot with numbered labels and legend
   --headed                   Show browser window (not headless)
@@ -2111,8 +3292,23 @@ Options:
   --auto-connect             Auto-discover and connect to running Chrome
   --color-scheme <scheme>    Color scheme: dark, light, no-preference (or AGENT_BROWSER_COLOR_SCHEME)
   --download-path <path>     Default download directory (or AGENT_BROWSER_DOWNLOAD_PATH)
+  --timeout <ms>             Timeout for this command (overrides set timeout)
+                             (or AGENT_BROWSER_TIMEOUT env)
+  --retry <n>                Retry up to n times if the daemon cannot be reached
+                             (or AGENT_BROWSER_RETRY)
+  --retry-delay <ms>         Delay between retries (default: 1000, or AGENT_BROWSER_RETRY_DELAY)
+  --connect-timeout <ms>     Daemon connect timeout (default: 30000, 0 = none)
+  --send-timeout <ms>        Daemon response timeout (default: 60000 or the command's
+                             timeout + 5000, 0 = none)
+  --output-file <path>       Also write output to a file (plain text, or JSON with --json)
   --session-name <name>      Auto-save/restore session state (cookies, localStorage)
   --config <path>            Use a custom config file (or AGENT_BROWSER_CONFIG env)
+  --color, --no-color        Force colored output on or off (default: on for a terminal)
+  -q, --quiet                Print nothing for successful commands (or AGENT_BROWSER_QUIET)
+  --screenshot-on-failure    Save a screenshot when a command fails (path printed to stderr)
+  --screenshot-dir <path>    Directory for failure screenshots (default: system temp dir)
+  --dry-run                  Print the command JSON instead of sending it to the daemon
+  --fail-on-console-error    Exit 1 if the page logged JavaScript errors
   --debug                    Debug output
   --version, -V              Show version
 
@@ -2130,7 +3326,13 @@ Configuration:
     --headed           (same as --headed true)
     --headed false     (disables "headed": true from config)
 
+  Options that take a value also accept --option=value (e.g. --session=work).
+
   Extensions from user and project configs are merged (not replaced).
+  A config can inherit from another with "extends": "<path>" (relative to that file).
+  defaultWaitUntil, defaultTimeout and defaultScreenshotFormat set defaults for open
+  and screenshot.
+  "aliases": {{"login": "open https://myapp.com/login"}} adds shortcuts ($1, $2 = arguments).
 
   Example agent-browser.json:
     {{"headed": true, "proxy": "http://localhost:8080", "profile": "./browser-data"}}
@@ -2140,6 +3342,7 @@ Environment:
   AGENT_BROWSER_SESSION          Session name (default: "default")
   AGENT_BROWSER_SESSION_NAME     Auto-save/restore state persistence name
   AGENT_BROWSER_ENCRYPTION_KEY   64-char hex key for AES-256-GCM state encryption
+  AGENT_BROWSER_STATE_PASSWORD   Password for a bare state save --encrypt / load --decrypt
   AGENT_BROWSER_STATE_EXPIRE_DAYS Auto-delete states older than N days (default: 30)
   AGENT_BROWSER_EXECUTABLE_PATH  Custom browser executable path
   AGENT_BROWSER_EXTENSIONS       Comma-separated browser extension paths
@@ -2155,6 +3358,15 @@ Environment:
   AGENT_BROWSER_COLOR_SCHEME     Color scheme preference (dark, light, no-preference)
   AGENT_BROWSER_DOWNLOAD_PATH    Default download directory for browser downloads
   AGENT_BROWSER_DEFAULT_TIMEOUT  Default Playwright timeout in ms (default: 25000)
+  AGENT_BROWSER_TIMEOUT          Per-command timeout in ms (same as --timeout)
+  AGENT_BROWSER_RETRY            Retry reaching the daemon up to N times (same as --retry)
+  AGENT_BROWSER_RETRY_DELAY      Delay between retries in ms (default: 1000)
+  AGENT_BROWSER_CONNECT_TIMEOUT  Daemon connect timeout in ms (default: 30000)
+  AGENT_BROWSER_SEND_TIMEOUT     Daemon response timeout in ms (default: 60000 or the command's timeout + 5000)
+  AGENT_BROWSER_QUIET            Suppress output for successful commands (same as --quiet)
+  AGENT_BROWSER_FAIL_ON_CONSOLE_ERROR  Exit 1 on page JavaScript errors (same as --fail-on-console-error)
+  NO_COLOR                       Disable colored output (any value)
+  FORCE_COLOR                    Color even when piped (0 or false disables)
   AGENT_BROWSER_SESSION_NAME     Auto-save/load state persistence name
   AGENT_BROWSER_STATE_EXPIRE_DAYS Auto-delete saved states older than N days (default: 30)
   AGENT_BROWSER_ENCRYPTION_KEY   64-char hex key for AES-256-GCM session encryption
@@ -2197,84 +3409,1597 @@ iOS Simulator (requires Xcode and Appium):
   agent-browser -p ios --device "iPhone 15 Pro" open url   # Specific device
   agent-browser -p ios device list                         # List simulators
   agent-browser -p ios swipe up                            # Swipe gesture
+  agent-browser -p ios pinch out                           # Pinch gesture
   agent-browser -p ios tap @e1                             # Touch element
 "#
     );
 }
 
-fn print_snapshot_diff(data: &serde_json::Map<String, serde_json::Value>) {
-    let changed = data
-        .get("changed")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
-    if !changed {
-        println!("{} No changes detected", color::success_indicator());
-        return;
-    }
-    if let Some(diff) = data.get("diff").and_then(|v| v.as_str()) {
-        for line in diff.lines() {
-            if line.starts_with("+ ") {
-                println!("{}", color::green(line));
-            } else if line.starts_with("- ") {
-                println!("{}", color::red(line));
+/// Prints `network requests` output in an explicit `--output-format` (json or ndjson).
+/// Table output and errors fall through to `print_response`.
+pub fn print_requests(resp: &Response, format: &str, action: Option<&str>) {
+    let requests = resp
+        .data
+        .as_ref()
+        .and_then(|d| d.get("requests"))
+        .and_then(|v| v.as_array());
+    match (format, requests) {
+        ("json", Some(requests)) if resp.success => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(requests).unwrap_or_default()
+            );
+        }
+        ("ndjson", Some(requests)) if resp.success => {
+            for req in requests {
+                println!("{}", serde_json::to_string(req).unwrap_or_default());
+            }
+        }
+        _ => print_response(resp, OutputFormat::Human, false, action),
+    }
+}
+
+/// Value of a `get attr` response: `data.value` when the daemon sent the
+/// attribute name alongside it, otherwise `data.attribute` itself.
+fn attribute_value(data: &serde_json::Value) -> &serde_json::Value {
+    data.get("value")
+        .or_else(|| data.get("attribute"))
+        .unwrap_or(&serde_json::Value::Null)
+}
+
+/// True if a successful response reports an attribute the element does not
+/// have. The CLI treats this as a failure (exit code 1).
+pub fn is_missing_attribute(resp: &Response) -> bool {
+    resp.success
+        && resp
+            .data
+            .as_ref()
+            .filter(|d| d.get("attribute").is_some())
+            .is_some_and(|d| attribute_value(d).is_null())
+}
+
+/// Formats an open dialog as `type: message`, plus the default value of a prompt.
+fn format_dialog(dialog: &serde_json::Value) -> String {
+    let field = |name: &str| dialog.get(name).and_then(|v| v.as_str()).unwrap_or("");
+    let mut out = format!("{}: {}\n", field("type"), field("message"));
+    if let Some(default) = dialog.get("defaultValue").and_then(|v| v.as_str()) {
+        out.push_str(&format!("Default value: {}\n", default));
+    }
+    out
+}
+
+/// Formats Core Web Vitals and navigation timings as `name: value` lines.
+/// CLS is unitless; every other numeric metric is a duration in milliseconds.
+fn format_metrics(metrics: &serde_json::Value) -> String {
+    let mut out = String::new();
+    for (name, value) in metrics.as_object().into_iter().flatten() {
+        let formatted = match value {
+            serde_json::Value::Null => "n/a".to_string(),
+            serde_json::Value::Number(n) => {
+                let n = n.as_f64().unwrap_or_default();
+                if name.eq_ignore_ascii_case("cls") {
+                    format!("{:.3}", n)
+                } else {
+                    format!("{:.1} ms", n)
+                }
+            }
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        out.push_str(&format!("{}: {}\n", name, formatted));
+    }
+    out
+}
+
+/// Formats a style (or ARIA) object as one `property: value` line per entry.
+fn format_style_lines(styles: &serde_json::Value) -> String {
+    let mut out = String::new();
+    for (property, value) in styles.as_object().into_iter().flatten() {
+        match value {
+            serde_json::Value::String(s) => out.push_str(&format!("{}: {}\n", property, s)),
+            other => out.push_str(&format!("{}: {}\n", property, other)),
+        }
+    }
+    out
+}
+
+/// Builds an `--ndjson` event: `{"type": kind}` followed by the fields of `fields`.
+fn ndjson_event(kind: &str, fields: &serde_json::Value) -> serde_json::Value {
+    let mut event = serde_json::Map::new();
+    event.insert("type".to_string(), serde_json::json!(kind));
+    if let Some(obj) = fields.as_object() {
+        for (key, value) in obj.iter().filter(|(k, _)| k.as_str() != "type") {
+            event.insert(key.clone(), value.clone());
+        }
+    }
+    serde_json::Value::Object(event)
+}
+
+/// NDJSON event for a console message: its level and text.
+fn log_ndjson_event(log: &serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "type": "log",
+        "level": log.get("type").and_then(|v| v.as_str()).unwrap_or("log"),
+        "text": log.get("text").and_then(|v| v.as_str()).unwrap_or("")
+    })
+}
+
+/// A console message as printed by `console`: level prefix, then the text.
+fn format_log_line(log: &serde_json::Value) -> String {
+    let level = log.get("type").and_then(|v| v.as_str()).unwrap_or("log");
+    let text = log.get("text").and_then(|v| v.as_str()).unwrap_or("");
+    format!("{} {}", color::console_level_prefix(level), text)
+}
+
+/// A page error as printed by `errors`.
+fn format_page_error_line(err: &serde_json::Value) -> String {
+    let msg = err.get("message").and_then(|v| v.as_str()).unwrap_or("");
+    format!("{} {}", color::error_indicator(), msg)
+}
+
+/// Splits a response into the events printed one per line by `--ndjson`.
+/// Lists (console messages, page errors, requests, cookies, tabs) give one
+/// event per item; anything else is a single `result` event.
+fn ndjson_events(resp: &Response, action: Option<&str>) -> Vec<serde_json::Value> {
+    use serde_json::json;
+
+    if !resp.success {
+        let error = resp.error.as_deref().unwrap_or("Unknown error");
+        return vec![json!({ "type": "error", "error": error })];
+    }
+    let Some(data) = &resp.data else {
+        return vec![json!({ "type": "result", "action": action })];
+    };
+    // Empty lists fall through to a single `result` event so every command
+    // produces at least one line.
+    let list = |key: &str| {
+        data.get(key)
+            .and_then(|v| v.as_array())
+            .filter(|items| !items.is_empty())
+    };
+
+    let url = data.get("url").and_then(|v| v.as_str());
+    if let (Some("navigate" | "open"), Some(url)) = (action, url) {
+        let mut event = json!({ "type": "navigate", "url": url });
+        if let Some(title) = data.get("title").and_then(|v| v.as_str()) {
+            event["title"] = json!(title);
+        }
+        return vec![event];
+    }
+    if let Some(logs) = list("messages") {
+        return logs.iter().map(log_ndjson_event).collect();
+    }
+    if let Some(errors) = list("errors") {
+        return errors
+            .iter()
+            .map(|e| ndjson_event("page_error", e))
+            .collect();
+    }
+    if let Some(requests) = list("requests") {
+        return requests
+            .iter()
+            .map(|r| ndjson_event("request", r))
+            .collect();
+    }
+    if let Some(cookies) = list("cookies") {
+        return cookies.iter().map(|c| ndjson_event("cookie", c)).collect();
+    }
+    if let Some(tabs) = list("tabs") {
+        return tabs
+            .iter()
+            .enumerate()
+            .map(|(i, tab)| {
+                let mut event = ndjson_event("tab", tab);
+                if event.get("index").is_none() {
+                    event["index"] = json!(i);
+                }
+                event
+            })
+            .collect();
+    }
+    vec![json!({ "type": "result", "action": action, "data": data })]
+}
+
+/// Formats a single cookie as `name=value`, noting its expiry when it has one.
+fn format_cookie(cookie: &serde_json::Value) -> String {
+    let name = cookie.get("name").and_then(|v| v.as_str()).unwrap_or("");
+    let value = cookie.get("value").and_then(|v| v.as_str()).unwrap_or("");
+    match cookie.get("expires").and_then(|v| v.as_f64()) {
+        Some(expires) if expires > 0.0 => {
+            format!("{}={} (expires {})", name, value, expires as i64)
+        }
+        _ => format!("{}={}", name, value),
+    }
+}
+
+/// Formats `device list` output: connected devices, then simulators, numbered
+/// in one sequence. Entries may also be plain device names.
+fn format_device_list(devices: &[serde_json::Value]) -> String {
+    if devices.is_empty() {
+        return "No iOS devices available. Open Xcode to download simulator runtimes.\n"
+            .to_string();
+    }
+    let is_real = |d: &&serde_json::Value| {
+        d.get("isRealDevice")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    };
+    let real_devices: Vec<_> = devices.iter().filter(is_real).collect();
+    let simulators: Vec<_> = devices.iter().filter(|d| !is_real(d)).collect();
+
+    let mut out = String::new();
+    let mut number = 0;
+    for (heading, group) in [
+        ("Connected Devices:", real_devices),
+        ("Simulators:", simulators),
+    ] {
+        if group.is_empty() {
+            continue;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("{}\n\n", heading));
+        for device in group {
+            number += 1;
+            let Some(obj) = device.as_object() else {
+                let name = device.as_str().unwrap_or("Unknown");
+                out.push_str(&format!("  {}. {}\n", number, name));
+                continue;
+            };
+            let field = |key: &str| obj.get(key).and_then(|v| v.as_str()).unwrap_or("");
+            let name = Some(field("name"))
+                .filter(|n| !n.is_empty())
+                .unwrap_or("Unknown");
+            // Real devices are always connected; simulators show whether they are booted
+            let indicator = if is_real(&device) || field("state") == "Booted" {
+                color::green("●")
             } else {
-                println!("{}", color::dim(line));
+                color::dim("○")
+            };
+            let runtime = field("runtime");
+            if runtime.is_empty() {
+                out.push_str(&format!("  {}. {} {}\n", number, indicator, name));
+            } else {
+                out.push_str(&format!(
+                    "  {}. {} {} ({})\n",
+                    number, indicator, name, runtime
+                ));
+            }
+            let udid = field("udid");
+            if !udid.is_empty() {
+                out.push_str(&format!("     {}\n", color::dim(udid)));
             }
         }
+    }
+    out
+}
+
+/// Formats `device info` output as one labelled field per line.
+fn format_device_info(info: &serde_json::Value) -> String {
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|k| info.get(*k).and_then(|v| v.as_str()))
+            .unwrap_or("-")
+    };
+    format!(
+        "Name:  {}\nUDID:  {}\nOS:    {}\nState: {}\n",
+        field(&["name"]),
+        field(&["udid"]),
+        field(&["osVersion", "runtime"]),
+        field(&["state"])
+    )
+}
+
+/// Formats `tab info` output as one labelled field per line.
+fn format_tab_info(tab: &serde_json::Value) -> String {
+    let title = tab
+        .get("title")
+        .and_then(|v| v.as_str())
+        .unwrap_or("Untitled");
+    let url = tab.get("url").and_then(|v| v.as_str()).unwrap_or("");
+    let index = tab
+        .get("index")
+        .and_then(|v| v.as_u64())
+        .map(|i| i.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let active = tab.get("active").and_then(|v| v.as_bool()).unwrap_or(false);
+    format!(
+        "Title:  {}\nURL:    {}\nIndex:  {}\nActive: {}\n",
+        title,
+        url,
+        index,
+        if active { "yes" } else { "no" }
+    )
+}
+
+/// Lays out rows as left-aligned columns separated by two spaces. Widths are
+/// counted in characters; the last column is not padded.
+fn format_columns(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut widths = vec![0usize; columns];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in rows {
+        let mut line = String::new();
+        for (i, cell) in row.iter().enumerate() {
+            if i + 1 == row.len() {
+                line.push_str(cell);
+            } else {
+                line.push_str(cell);
+                let padding = widths[i] - cell.chars().count() + 2;
+                line.push_str(&" ".repeat(padding));
+            }
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// `--format table` rendering for cookies, tabs and network requests.
+/// Returns None for other responses (and for empty lists), which are printed
+/// as in human mode.
+fn format_table_response(data: &serde_json::Value) -> Option<String> {
+    let list = |key: &str| {
+        data.get(key)
+            .and_then(|v| v.as_array())
+            .filter(|items| !items.is_empty())
+    };
+    if let Some(cookies) = list("cookies") {
+        return Some(format_cookies_table(cookies));
+    }
+    if let Some(tabs) = list("tabs") {
+        return Some(format_tabs_table(tabs));
+    }
+    list("requests").map(|requests| format_requests_table(requests))
+}
+
+/// Formats cookies as `NAME VALUE DOMAIN PATH EXPIRES` columns.
+fn format_cookies_table(cookies: &[serde_json::Value]) -> String {
+    let field = |cookie: &serde_json::Value, key: &str| {
+        cookie
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    let mut rows = vec![vec![
+        "NAME".to_string(),
+        "VALUE".to_string(),
+        "DOMAIN".to_string(),
+        "PATH".to_string(),
+        "EXPIRES".to_string(),
+    ]];
+    for cookie in cookies {
+        let expires = match cookie.get("expires").and_then(|v| v.as_f64()) {
+            Some(expires) if expires > 0.0 => (expires as i64).to_string(),
+            _ => "session".to_string(),
+        };
+        rows.push(vec![
+            field(cookie, "name"),
+            field(cookie, "value"),
+            field(cookie, "domain"),
+            field(cookie, "path"),
+            expires,
+        ]);
+    }
+    format_columns(&rows)
+}
+
+/// Formats tabs as `INDEX ACTIVE TITLE URL` columns.
+fn format_tabs_table(tabs: &[serde_json::Value]) -> String {
+    let mut rows = vec![vec![
+        "INDEX".to_string(),
+        "ACTIVE".to_string(),
+        "TITLE".to_string(),
+        "URL".to_string(),
+    ]];
+    for (i, tab) in tabs.iter().enumerate() {
+        let active = tab.get("active").and_then(|v| v.as_bool()).unwrap_or(false);
+        rows.push(vec![
+            i.to_string(),
+            if active { "*" } else { "" }.to_string(),
+            tab.get("title")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Untitled")
+                .to_string(),
+            tab.get("url")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+        ]);
+    }
+    format_columns(&rows)
+}
+
+/// Formats captured requests as aligned `METHOD STATUS URL DURATION` columns.
+fn format_requests_table(requests: &[serde_json::Value]) -> String {
+    let mut rows = vec![vec![
+        "METHOD".to_string(),
+        "STATUS".to_string(),
+        "URL".to_string(),
+        "DURATION".to_string(),
+    ]];
+    rows.extend(requests.iter().map(request_row));
+    format_columns(&rows)
+}
+
+/// Method, status, URL and duration of a captured request.
+fn request_row(req: &serde_json::Value) -> Vec<String> {
+    let method = req.get("method").and_then(|v| v.as_str()).unwrap_or("GET");
+    let status = req
+        .get("status")
+        .and_then(|v| v.as_u64())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let url = req.get("url").and_then(|v| v.as_str()).unwrap_or("");
+    let duration = req
+        .get("duration")
+        .and_then(|v| v.as_f64())
+        .map(|d| format!("{}ms", d.round() as u64))
+        .unwrap_or_else(|| "-".to_string());
+    vec![method.to_string(), status, url.to_string(), duration]
+}
+
+/// Prints one event of a `--follow` stream as soon as it arrives. `kind` is
+/// the NDJSON event type (`request`, `log` or `page_error`). JSON output
+/// prints the event as is, NDJSON as the matching `--ndjson` event, and human
+/// output as the same line the non-streaming command prints.
+pub fn print_stream_event(event: &serde_json::Value, kind: &str, format: OutputFormat) {
+    match (format, kind) {
+        (OutputFormat::Json, _) => println!("{}", event),
+        (OutputFormat::Ndjson, "log") => println!("{}", log_ndjson_event(event)),
+        (OutputFormat::Ndjson, _) => println!("{}", ndjson_event(kind, event)),
+        (_, "request") => println!("{}", request_row(event).join(" ")),
+        (_, "log") => println!("{}", format_log_line(event)),
+        (_, "page_error") => println!("{}", format_page_error_line(event)),
+        _ => println!("{}", event),
+    }
+}
+
+/// Formats `state diff` output: one line per entry, prefixed `+` for added,
+/// `-` for removed and `~` for changed. Sections may be arrays of descriptions
+/// or objects keyed by cookie/storage name.
+fn format_violations(violations: &[serde_json::Value]) -> String {
+    if violations.is_empty() {
+        return format!(
+            "{} No accessibility violations\n",
+            color::success_indicator()
+        );
+    }
+    let mut out = String::new();
+    for violation in violations {
+        let id = violation
+            .get("id")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown");
+        let impact = violation
+            .get("impact")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown");
+        let description = violation
+            .get("description")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let impact_label = match impact {
+            "critical" | "serious" => color::red(impact),
+            "moderate" => color::yellow(impact),
+            _ => color::dim(impact),
+        };
+        out.push_str(&format!(
+            "[{}] {}: {}\n",
+            impact_label,
+            color::bold(id),
+            description
+        ));
+    }
+    out.push_str(&format!("\n{} violation(s)\n", violations.len()));
+    out
+}
+
+fn format_state_diff(diff: &serde_json::Value) -> String {
+    let entries = |key: &str| -> Vec<String> {
+        match diff.get(key) {
+            Some(serde_json::Value::Array(items)) => items
+                .iter()
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .unwrap_or_else(|| v.to_string())
+                })
+                .collect(),
+            Some(serde_json::Value::Object(map)) => map
+                .iter()
+                .map(|(k, v)| match v.as_str() {
+                    Some(s) => format!("{}: {}", k, s),
+                    None => format!("{}: {}", k, v),
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+    let added = entries("added");
+    let removed = entries("removed");
+    let changed = entries("changed");
+    if added.is_empty() && removed.is_empty() && changed.is_empty() {
+        return format!("{} No differences\n", color::success_indicator());
+    }
+
+    let mut out = String::new();
+    for entry in &added {
+        out.push_str(&format!("{}\n", color::green(&format!("+ {}", entry))));
+    }
+    for entry in &removed {
+        out.push_str(&format!("{}\n", color::red(&format!("- {}", entry))));
+    }
+    for entry in &changed {
+        out.push_str(&format!("{}\n", color::yellow(&format!("~ {}", entry))));
+    }
+    out.push_str(&format!(
+        "\n{} added, {} removed, {} changed\n",
+        color::green(&added.len().to_string()),
+        color::red(&removed.len().to_string()),
+        color::yellow(&changed.len().to_string())
+    ));
+    out
+}
+
+/// How a line of snapshot diff output is colored.
+#[derive(Debug, PartialEq, Eq)]
+enum DiffLine {
+    /// `+++` / `---` file headers
+    Header,
+    Added,
+    Removed,
+    /// `@@ ... @@` hunk headers
+    Hunk,
+    Context,
+}
+
+fn classify_diff_line(line: &str) -> DiffLine {
+    if line.starts_with("+++") || line.starts_with("---") {
+        DiffLine::Header
+    } else if line.starts_with('+') {
+        DiffLine::Added
+    } else if line.starts_with('-') {
+        DiffLine::Removed
+    } else if line.starts_with('@') {
+        DiffLine::Hunk
+    } else {
+        DiffLine::Context
+    }
+}
+
+fn colorize_diff_line(line: &str) -> String {
+    match classify_diff_line(line) {
+        DiffLine::Header => color::bold(line),
+        DiffLine::Added => color::green(line),
+        DiffLine::Removed => color::red(line),
+        DiffLine::Hunk => color::blue(line),
+        DiffLine::Context => color::dim(line),
+    }
+}
+
+/// Formats a `diff snapshot` result. `data.diff` is a unified diff string;
+/// without a `changed` flag, any non-empty diff counts as a change.
+fn format_snapshot_diff(data: &serde_json::Map<String, serde_json::Value>) -> String {
+    let diff = data.get("diff").and_then(|v| v.as_str()).unwrap_or("");
+    let changed = data
+        .get("changed")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(!diff.trim().is_empty());
+    if !changed {
+        return format!("{} No changes detected\n", color::success_indicator());
+    }
+    let mut out = String::new();
+    for line in diff.lines() {
+        out.push_str(&colorize_diff_line(line));
+        out.push('\n');
+    }
+    if data.contains_key("additions") || data.contains_key("removals") {
         let additions = data.get("additions").and_then(|v| v.as_i64()).unwrap_or(0);
         let removals = data.get("removals").and_then(|v| v.as_i64()).unwrap_or(0);
         let unchanged = data.get("unchanged").and_then(|v| v.as_i64()).unwrap_or(0);
-        println!(
-            "\n{} additions, {} removals, {} unchanged",
+        out.push_str(&format!(
+            "\n{} additions, {} removals, {} unchanged\n",
             color::green(&additions.to_string()),
             color::red(&removals.to_string()),
             unchanged
-        );
+        ));
     }
+    out
 }
 
-fn print_screenshot_diff(data: &serde_json::Map<String, serde_json::Value>) {
-    let mismatch = data
-        .get("mismatchPercentage")
+fn print_snapshot_diff(data: &serde_json::Map<String, serde_json::Value>) {
+    print!("{}", format_snapshot_diff(data));
+}
+
+/// Formats a `diff screenshot` result. Accepts the daemon's
+/// `mismatchPercentage` / `differentPixels` or `diffPercent` / `diffPixels`.
+/// The summary is green when the images are within the threshold: `match`
+/// when given, else at most `maxDiffPixels` (default 0) pixels differ.
+fn format_screenshot_diff(data: &serde_json::Map<String, serde_json::Value>) -> String {
+    let field = |primary: &str, alias: &str| data.get(primary).or_else(|| data.get(alias));
+    let percent = field("mismatchPercentage", "diffPercent")
         .and_then(|v| v.as_f64())
         .unwrap_or(0.0);
-    let is_match = data
+    let different = field("differentPixels", "diffPixels")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let max_diff_pixels = data
+        .get("maxDiffPixels")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let within_threshold = data
         .get("match")
         .and_then(|v| v.as_bool())
-        .unwrap_or(false);
+        .unwrap_or(different <= max_diff_pixels);
     let dim_mismatch = data
         .get("dimensionMismatch")
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
-    if dim_mismatch {
-        println!(
-            "{} Images have different dimensions",
+
+    let mut out = if dim_mismatch {
+        format!(
+            "{} Images have different dimensions\n",
             color::error_indicator()
-        );
-    } else if is_match {
-        println!("{} Images match (0% difference)", color::success_indicator());
+        )
+    } else if within_threshold && different == 0 {
+        format!(
+            "{} Images match (0% difference)\n",
+            color::success_indicator()
+        )
+    } else if within_threshold {
+        format!(
+            "{} {:.2}% pixels differ (within threshold)\n",
+            color::success_indicator(),
+            percent
+        )
     } else {
-        println!(
-            "{} {:.2}% pixels differ",
+        format!(
+            "{} {:.2}% pixels differ\n",
             color::error_indicator(),
-            mismatch
-        );
-    }
+            percent
+        )
+    };
     if let Some(diff_path) = data.get("diffPath").and_then(|v| v.as_str()) {
-        println!("  Diff image: {}", color::green(diff_path));
+        out.push_str(&format!("  Diff image: {}\n", color::green(diff_path)));
     }
-    let total = data.get("totalPixels").and_then(|v| v.as_i64()).unwrap_or(0);
-    let different = data
-        .get("differentPixels")
-        .and_then(|v| v.as_i64())
-        .unwrap_or(0);
-    println!(
-        "  {} different / {} total pixels",
-        color::red(&different.to_string()),
-        total
-    );
+    let count = different.to_string();
+    let count = if within_threshold {
+        color::green(&count)
+    } else {
+        color::red(&count)
+    };
+    match data.get("totalPixels").and_then(|v| v.as_u64()) {
+        Some(total) => out.push_str(&format!("  {} different / {} total pixels\n", count, total)),
+        None => out.push_str(&format!("  {} different pixels\n", count)),
+    }
+    out
+}
+
+fn print_screenshot_diff(data: &serde_json::Map<String, serde_json::Value>) {
+    print!("{}", format_screenshot_diff(data));
+}
+
+pub fn print_version(json_mode: bool) {
+    print!("{}", format_version(json_mode));
 }
 
-pub fn print_version() {
-    println!("agent-browser {}", env!("CARGO_PKG_VERSION"));
+fn format_version(json_mode: bool) -> String {
+    if json_mode {
+        let resp = serde_json::json!({
+            "success": true,
+            "data": {
+                "version": env!("CARGO_PKG_VERSION"),
+                "protocol": PROTOCOL_VERSION,
+            }
+        });
+        format!("{}\n", resp)
+    } else {
+        format!(
+            "agent-browser {}\ndaemon protocol: {}\n",
+            env!("CARGO_PKG_VERSION"),
+            PROTOCOL_VERSION
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_requests_table_columns() {
+        let requests = vec![
+            json!({ "method": "GET", "status": 200, "url": "https://example.com/", "duration": 120.4 }),
+            json!({ "method": "POST", "status": 201, "url": "https://example.com/api", "duration": 35 }),
+        ];
+        let table = format_requests_table(&requests);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "METHOD  STATUS  URL                      DURATION"
+        );
+        assert_eq!(lines[1], "GET     200     https://example.com/     120ms");
+        assert_eq!(lines[2], "POST    201     https://example.com/api  35ms");
+    }
+
+    #[test]
+    fn test_format_cookie_with_expiry() {
+        let cookie = json!({ "name": "session_token", "value": "abc", "expires": 1735689600.5 });
+        assert_eq!(
+            format_cookie(&cookie),
+            "session_token=abc (expires 1735689600)"
+        );
+    }
+
+    #[test]
+    fn test_format_cookie_session() {
+        let cookie = json!({ "name": "session_token", "value": "abc", "expires": -1 });
+        assert_eq!(format_cookie(&cookie), "session_token=abc");
+    }
+
+    #[test]
+    fn test_format_state_diff_sections() {
+        let diff = json!({
+            "added": ["cookie session_id"],
+            "removed": { "localStorage.theme": "dark" },
+            "changed": ["cookie csrf"]
+        });
+        let out = format_state_diff(&diff);
+        assert!(out.contains("+ cookie session_id"));
+        assert!(out.contains("- localStorage.theme: dark"));
+        assert!(out.contains("~ cookie csrf"));
+        assert!(out.contains("added"));
+        assert!(out.contains("changed"));
+    }
+
+    #[test]
+    fn test_format_state_diff_no_differences() {
+        let diff = json!({ "added": [], "removed": [], "changed": {} });
+        assert!(format_state_diff(&diff).contains("No differences"));
+    }
+
+    #[test]
+    fn test_format_tab_info_human() {
+        let tab = json!({ "title": "Inbox", "url": "https://mail.example.com/", "index": 2, "active": true });
+        assert_eq!(
+            format_tab_info(&tab),
+            "Title:  Inbox\nURL:    https://mail.example.com/\nIndex:  2\nActive: yes\n"
+        );
+    }
+
+    #[test]
+    fn test_format_tab_info_missing_fields() {
+        let text = format_tab_info(&json!({}));
+        assert!(text.contains("Title:  Untitled"));
+        assert!(text.contains("Index:  -"));
+        assert!(text.contains("Active: no"));
+    }
+
+    #[test]
+    fn test_tab_info_json_mode_serializes_full_object() {
+        let tab = json!({ "title": "Inbox", "url": "https://mail.example.com/", "index": 2, "active": true });
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "tab": tab.clone() })),
+            error: None,
+        };
+        let value: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&resp).unwrap()).unwrap();
+        assert_eq!(value["data"]["tab"], tab);
+        print_response(&resp, OutputFormat::Json, false, Some("tab_info"));
+    }
+
+    #[test]
+    fn test_format_requests_table_missing_fields() {
+        let requests = vec![json!({ "url": "https://example.com/pending" })];
+        let table = format_requests_table(&requests);
+        let row = table.lines().nth(1).unwrap();
+        assert!(row.starts_with("GET     -       https://example.com/pending"));
+        assert!(row.ends_with("-"));
+    }
+
+    #[test]
+    fn test_format_violations_lists_rules() {
+        let violations = vec![
+            json!({ "id": "color-contrast", "impact": "serious", "description": "Text has low contrast" }),
+            json!({ "id": "image-alt", "impact": "critical", "description": "Images need alt text" }),
+        ];
+        let out = format_violations(&violations);
+        assert!(out.contains("color-contrast"));
+        assert!(out.contains("serious"));
+        assert!(out.contains("Text has low contrast"));
+        assert!(out.contains("image-alt"));
+        assert!(out.contains("2 violation(s)"));
+    }
+
+    #[test]
+    fn test_format_violations_empty() {
+        assert!(format_violations(&[]).contains("No accessibility violations"));
+    }
+
+    #[test]
+    fn test_format_version_human() {
+        let out = format_version(false);
+        assert_eq!(
+            out,
+            format!(
+                "agent-browser {}\ndaemon protocol: {}\n",
+                env!("CARGO_PKG_VERSION"),
+                PROTOCOL_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn test_format_version_json() {
+        let out: serde_json::Value = serde_json::from_str(&format_version(true)).unwrap();
+        assert_eq!(out["success"], true);
+        assert_eq!(out["data"]["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(out["data"]["protocol"], PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn test_strip_ansi_removes_color_codes() {
+        let colored = "\x1b[32m✓\x1b[0m \x1b[1mExample\x1b[0m";
+        assert_eq!(strip_ansi(colored), "✓ Example");
+        assert_eq!(strip_ansi("plain text\n"), "plain text\n");
+    }
+
+    #[test]
+    fn test_capture_collects_response_output() {
+        start_capture();
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "url": "https://example.com" })),
+            error: None,
+        };
+        print_response(&resp, OutputFormat::Human, false, Some("get_url"));
+        print_response(&resp, OutputFormat::Json, false, Some("get_url"));
+        let captured = take_capture().unwrap();
+        assert!(captured.starts_with("https://example.com\n"));
+        assert!(captured.contains(r#""success":true"#));
+        assert_eq!(take_capture(), None);
+    }
+
+    #[test]
+    fn test_write_output_file_creates_parent_dirs() {
+        let dir = std::env::temp_dir().join("ab-test-output-file");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("nested/out.txt");
+        write_output_file(path.to_str().unwrap(), "hello\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hello\n");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_write_output_file_reports_failure() {
+        let dir = std::env::temp_dir().join("ab-test-output-file-dir");
+        fs::create_dir_all(&dir).unwrap();
+        let err = write_output_file(dir.to_str().unwrap(), "x").unwrap_err();
+        assert!(err.contains("Failed to write output file"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    fn captured_output(resp: &Response, json_mode: bool, quiet: bool) -> String {
+        start_capture();
+        let format = if json_mode {
+            OutputFormat::Json
+        } else {
+            OutputFormat::Human
+        };
+        print_response(resp, format, quiet, Some("click"));
+        take_capture().unwrap()
+    }
+
+    #[test]
+    fn test_quiet_suppresses_success_output() {
+        let resp = Response {
+            success: true,
+            data: Some(json!({})),
+            error: None,
+        };
+        assert_eq!(captured_output(&resp, false, true), "");
+        assert!(captured_output(&resp, false, false).contains("Done"));
+    }
+
+    #[test]
+    fn test_quiet_keeps_errors() {
+        let resp = Response {
+            success: false,
+            data: None,
+            error: Some("Element not found".to_string()),
+        };
+        assert!(captured_output(&resp, false, true).contains("Element not found"));
+    }
+
+    #[test]
+    fn test_quiet_with_json_still_prints_json() {
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "clicked": true })),
+            error: None,
+        };
+        let out = captured_output(&resp, true, true);
+        assert!(out.contains(r#""success":true"#));
+    }
+
+    fn ndjson_lines(resp: &Response, action: Option<&str>) -> Vec<serde_json::Value> {
+        start_capture();
+        print_response(resp, OutputFormat::Ndjson, false, action);
+        take_capture()
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    fn ok(data: serde_json::Value) -> Response {
+        Response {
+            success: true,
+            data: Some(data),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_ndjson_navigate_event() {
+        let resp = ok(json!({ "url": "https://example.com", "title": "Example" }));
+        assert_eq!(
+            ndjson_lines(&resp, Some("navigate")),
+            vec![json!({ "type": "navigate", "url": "https://example.com", "title": "Example" })]
+        );
+    }
+
+    #[test]
+    fn test_ndjson_url_from_other_actions_is_result() {
+        let resp = ok(json!({ "url": "https://example.com" }));
+        assert_eq!(
+            ndjson_lines(&resp, Some("url")),
+            vec![json!({
+                "type": "result",
+                "action": "url",
+                "data": { "url": "https://example.com" }
+            })]
+        );
+    }
+
+    #[test]
+    fn test_ndjson_empty_list_is_result() {
+        let resp = ok(json!({ "messages": [] }));
+        assert_eq!(
+            ndjson_lines(&resp, Some("console")),
+            vec![json!({ "type": "result", "action": "console", "data": { "messages": [] } })]
+        );
+    }
+
+    #[test]
+    fn test_ndjson_one_log_event_per_message() {
+        let resp = ok(json!({ "messages": [
+            { "type": "warning", "text": "slow" },
+            { "type": "error", "text": "boom" }
+        ] }));
+        assert_eq!(
+            ndjson_lines(&resp, Some("console")),
+            vec![
+                json!({ "type": "log", "level": "warning", "text": "slow" }),
+                json!({ "type": "log", "level": "error", "text": "boom" })
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ndjson_page_errors() {
+        let resp = ok(json!({ "errors": [{ "message": "x is undefined" }] }));
+        assert_eq!(
+            ndjson_lines(&resp, Some("errors")),
+            vec![json!({ "type": "page_error", "message": "x is undefined" })]
+        );
+    }
+
+    #[test]
+    fn test_ndjson_requests_keep_fields() {
+        let resp = ok(json!({ "requests": [
+            { "method": "GET", "url": "https://a.test/", "resourceType": "document" },
+            { "method": "POST", "url": "https://a.test/api", "resourceType": "fetch" }
+        ] }));
+        let lines = ndjson_lines(&resp, Some("requests"));
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["type"], "request");
+        assert_eq!(lines[1]["method"], "POST");
+        assert_eq!(lines[1]["resourceType"], "fetch");
+    }
+
+    #[test]
+    fn test_ndjson_cookies_and_tabs() {
+        let resp = ok(json!({ "cookies": [{ "name": "sid", "value": "1" }] }));
+        assert_eq!(
+            ndjson_lines(&resp, Some("cookies_get")),
+            vec![json!({ "type": "cookie", "name": "sid", "value": "1" })]
+        );
+        let resp = ok(json!({ "tabs": [
+            { "title": "A", "url": "https://a.test", "active": true },
+            { "title": "B", "url": "https://b.test", "active": false }
+        ] }));
+        let lines = ndjson_lines(&resp, Some("tab_list"));
+        assert_eq!(lines[1]["type"], "tab");
+        assert_eq!(lines[1]["index"], 1);
+        assert_eq!(lines[0]["active"], true);
+    }
+
+    #[test]
+    fn test_ndjson_other_data_is_one_result_event() {
+        let resp = ok(json!({ "snapshot": "- heading \"Hi\"" }));
+        assert_eq!(
+            ndjson_lines(&resp, Some("snapshot")),
+            vec![json!({
+                "type": "result",
+                "action": "snapshot",
+                "data": { "snapshot": "- heading \"Hi\"" }
+            })]
+        );
+    }
+
+    #[test]
+    fn test_ndjson_error_event() {
+        let resp = Response {
+            success: false,
+            data: None,
+            error: Some("Element not found".to_string()),
+        };
+        assert_eq!(
+            ndjson_lines(&resp, Some("click")),
+            vec![json!({ "type": "error", "error": "Element not found" })]
+        );
+    }
+
+    #[test]
+    fn test_ndjson_ignores_quiet() {
+        let resp = ok(json!({ "url": "https://example.com" }));
+        start_capture();
+        print_response(&resp, OutputFormat::Ndjson, true, Some("navigate"));
+        assert_eq!(
+            take_capture().unwrap(),
+            "{\"type\":\"navigate\",\"url\":\"https://example.com\"}\n"
+        );
+    }
+
+    fn row(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_format_columns_aligns_to_widest_cell() {
+        let rows = vec![row(&["A", "BB", "C"]), row(&["long", "x", "y"])];
+        assert_eq!(format_columns(&rows), "A     BB  C\nlong  x   y\n");
+    }
+
+    #[test]
+    fn test_format_columns_last_column_not_padded() {
+        let rows = vec![row(&["K", "short"]), row(&["K", "much longer value"])];
+        for line in format_columns(&rows).lines() {
+            assert_eq!(line, line.trim_end());
+        }
+    }
+
+    #[test]
+    fn test_format_columns_counts_chars_not_bytes() {
+        let rows = vec![row(&["héllo", "1"]), row(&["ab", "2"])];
+        assert_eq!(format_columns(&rows), "héllo  1\nab     2\n");
+    }
+
+    #[test]
+    fn test_format_columns_short_rows() {
+        let rows = vec![row(&["NAME", "VALUE"]), row(&["only"])];
+        assert_eq!(format_columns(&rows), "NAME  VALUE\nonly\n");
+    }
+
+    #[test]
+    fn test_format_columns_empty_cells_keep_alignment() {
+        let rows = vec![row(&["A", "", "C"]), row(&["B", "xyz", "D"])];
+        assert_eq!(format_columns(&rows), "A       C\nB  xyz  D\n");
+    }
+
+    #[test]
+    fn test_format_columns_single_column_and_no_rows() {
+        assert_eq!(
+            format_columns(&[row(&["one"]), row(&["three"])]),
+            "one\nthree\n"
+        );
+        assert_eq!(format_columns(&[]), "");
+    }
+
+    #[test]
+    fn test_format_cookies_table() {
+        let cookies = vec![
+            json!({ "name": "sid", "value": "abc123", "domain": ".example.com", "path": "/", "expires": 1735689600.0 }),
+            json!({ "name": "theme", "value": "dark", "domain": "example.com", "path": "/app", "expires": -1 }),
+        ];
+        assert_eq!(
+            format_cookies_table(&cookies),
+            "NAME   VALUE   DOMAIN        PATH  EXPIRES\n\
+             sid    abc123  .example.com  /     1735689600\n\
+             theme  dark    example.com   /app  session\n"
+        );
+    }
+
+    #[test]
+    fn test_format_tabs_table_marks_active() {
+        let tabs = vec![
+            json!({ "title": "Home", "url": "https://a.test/", "active": false }),
+            json!({ "title": "Docs", "url": "https://a.test/docs", "active": true }),
+        ];
+        assert_eq!(
+            format_tabs_table(&tabs),
+            "INDEX  ACTIVE  TITLE  URL\n\
+             0              Home   https://a.test/\n\
+             1      *       Docs   https://a.test/docs\n"
+        );
+    }
+
+    #[test]
+    fn test_format_table_response_only_for_lists() {
+        let requests =
+            json!({ "requests": [{ "method": "GET", "status": 200, "url": "https://a.test/" }] });
+        let table = format_table_response(&requests).unwrap();
+        assert!(table.starts_with("METHOD  STATUS  URL"));
+        assert_eq!(format_table_response(&json!({ "cookies": [] })), None);
+        assert_eq!(format_table_response(&json!({ "title": "Example" })), None);
+    }
+
+    #[test]
+    fn test_print_response_table_format() {
+        let resp = Response {
+            success: true,
+            data: Some(
+                json!({ "tabs": [{ "title": "A", "url": "https://a.test", "active": true }] }),
+            ),
+            error: None,
+        };
+        start_capture();
+        print_response(&resp, OutputFormat::Table, false, Some("tab_list"));
+        let out = take_capture().unwrap();
+        assert!(out.starts_with("INDEX  ACTIVE  TITLE  URL\n"));
+
+        // Responses without a table layout print as in human mode
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "title": "Example" })),
+            error: None,
+        };
+        start_capture();
+        print_response(&resp, OutputFormat::Table, false, Some("get_title"));
+        assert_eq!(take_capture().unwrap(), "Example\n");
+    }
+
+    #[test]
+    fn test_output_format_names_round_trip() {
+        for name in OutputFormat::NAMES {
+            assert_eq!(OutputFormat::from_name(name).unwrap().name(), *name);
+        }
+        assert_eq!(OutputFormat::from_name("yaml"), None);
+    }
+
+    fn human_output(data: serde_json::Value, action: &str) -> String {
+        let resp = Response {
+            success: true,
+            data: Some(data),
+            error: None,
+        };
+        start_capture();
+        print_response(&resp, OutputFormat::Human, false, Some(action));
+        take_capture().unwrap()
+    }
+
+    #[test]
+    fn test_print_attribute_value() {
+        let out = human_output(
+            json!({ "attribute": "href", "value": "/login" }),
+            "getattribute",
+        );
+        assert_eq!(out, "/login\n");
+    }
+
+    #[test]
+    fn test_print_bare_attribute_string() {
+        assert_eq!(
+            human_output(json!({ "attribute": "submit" }), "getattribute"),
+            "submit\n"
+        );
+    }
+
+    #[test]
+    fn test_print_missing_attribute() {
+        let out = human_output(
+            json!({ "attribute": "href", "value": null }),
+            "getattribute",
+        );
+        assert!(out.contains("Element has no 'href' attribute"));
+        assert!(!out.contains("Done"));
+        let out = human_output(json!({ "attribute": null }), "getattribute");
+        assert!(out.contains("Element has no such attribute"));
+    }
+
+    #[test]
+    fn test_is_missing_attribute() {
+        let resp = |data: serde_json::Value| Response {
+            success: true,
+            data: Some(data),
+            error: None,
+        };
+        assert!(is_missing_attribute(&resp(
+            json!({ "attribute": "href", "value": null })
+        )));
+        assert!(is_missing_attribute(&resp(json!({ "attribute": null }))));
+        assert!(!is_missing_attribute(&resp(
+            json!({ "attribute": "href", "value": "" })
+        )));
+        assert!(!is_missing_attribute(&resp(json!({ "text": "hi" }))));
+    }
+
+    #[test]
+    fn test_print_property_pretty_json() {
+        assert_eq!(
+            human_output(json!({ "property": true }), "getproperty"),
+            "true\n"
+        );
+        let out = human_output(json!({ "property": { "a": 1 } }), "getproperty");
+        assert_eq!(out, "{\n  \"a\": 1\n}\n");
+        assert_eq!(
+            human_output(json!({ "property": "text" }), "getproperty"),
+            "\"text\"\n"
+        );
+    }
+
+    #[test]
+    fn test_print_property_null_is_not_done() {
+        assert_eq!(
+            human_output(json!({ "property": null }), "getproperty"),
+            "null\n"
+        );
+    }
+
+    #[test]
+    fn test_print_styles_as_property_lines() {
+        let out = human_output(
+            json!({ "styles": { "color": "rgb(0, 0, 0)", "opacity": 1 } }),
+            "styles",
+        );
+        assert_eq!(out, "color: rgb(0, 0, 0)\nopacity: 1\n");
+    }
+
+    #[test]
+    fn test_print_computed_style_object_and_string() {
+        let out = human_output(json!({ "computedStyle": { "display": "flex" } }), "styles");
+        assert_eq!(out, "display: flex\n");
+        let out = human_output(json!({ "computedStyle": "block" }), "styles");
+        assert_eq!(out, "block\n");
+    }
+
+    fn diff_obj(data: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+        data.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_classify_diff_line() {
+        assert_eq!(classify_diff_line("+ button \"Save\""), DiffLine::Added);
+        assert_eq!(classify_diff_line("+added"), DiffLine::Added);
+        assert_eq!(classify_diff_line("- heading"), DiffLine::Removed);
+        assert_eq!(classify_diff_line("@@ -1,3 +1,4 @@"), DiffLine::Hunk);
+        assert_eq!(classify_diff_line("+++ after"), DiffLine::Header);
+        assert_eq!(classify_diff_line("--- before"), DiffLine::Header);
+        assert_eq!(classify_diff_line("  unchanged"), DiffLine::Context);
+    }
+
+    #[test]
+    fn test_colorize_diff_line_keeps_text() {
+        for line in ["+ a", "- b", "@@ -1 +1 @@", "  c"] {
+            assert_eq!(strip_ansi(&colorize_diff_line(line)), line);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_diff_without_changed_flag() {
+        let out = format_snapshot_diff(&diff_obj(json!({ "diff": "@@ -1 +1 @@\n-old\n+new" })));
+        assert_eq!(strip_ansi(&out), "@@ -1 +1 @@\n-old\n+new\n");
+        let out = format_snapshot_diff(&diff_obj(json!({ "diff": "" })));
+        assert!(out.contains("No changes detected"));
+    }
+
+    #[test]
+    fn test_snapshot_diff_with_stats() {
+        let out = format_snapshot_diff(&diff_obj(json!({
+            "diff": "  a\n+ b",
+            "changed": true,
+            "additions": 1,
+            "removals": 0,
+            "unchanged": 1
+        })));
+        assert!(strip_ansi(&out).ends_with("\n1 additions, 0 removals, 1 unchanged\n"));
+    }
+
+    #[test]
+    fn test_screenshot_diff_aliases_above_threshold() {
+        let out = format_screenshot_diff(&diff_obj(
+            json!({ "diffPercent": 3.456, "diffPixels": 120 }),
+        ));
+        let out = strip_ansi(&out);
+        assert!(out.contains("3.46% pixels differ\n"));
+        assert!(!out.contains("within threshold"));
+        assert!(out.contains("  120 different pixels\n"));
+    }
+
+    #[test]
+    fn test_screenshot_diff_within_max_diff_pixels() {
+        let out = format_screenshot_diff(&diff_obj(json!({
+            "diffPercent": 0.01,
+            "diffPixels": 5,
+            "maxDiffPixels": 10
+        })));
+        assert!(strip_ansi(&out).contains("0.01% pixels differ (within threshold)"));
+    }
+
+    #[test]
+    fn test_screenshot_diff_daemon_shape() {
+        let out = format_screenshot_diff(&diff_obj(json!({
+            "diffPath": "/tmp/diff.png",
+            "totalPixels": 1000,
+            "differentPixels": 0,
+            "mismatchPercentage": 0,
+            "match": true
+        })));
+        assert_eq!(
+            strip_ansi(&out),
+            "✓ Images match (0% difference)\n  Diff image: /tmp/diff.png\n  0 different / 1000 total pixels\n"
+        );
+    }
+
+    #[test]
+    fn test_print_diff_url_flattened() {
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "diff": "+ new", "diffPercent": 1.5, "diffPixels": 30 })),
+            error: None,
+        };
+        start_capture();
+        print_response(&resp, OutputFormat::Human, false, Some("diff_url"));
+        let out = strip_ansi(&take_capture().unwrap());
+        assert!(out.starts_with("Snapshot diff:\n+ new\n"));
+        assert!(out.contains("Screenshot diff:"));
+        assert!(out.contains("1.50% pixels differ"));
+    }
+
+    #[test]
+    fn test_device_list_numbers_devices_across_groups() {
+        let devices = vec![
+            json!({ "name": "iPhone 15 Pro", "udid": "SIM-1", "state": "Booted", "runtime": "iOS 17.2" }),
+            json!({ "name": "My iPhone", "udid": "REAL-1", "state": "Connected", "runtime": "17.1", "isRealDevice": true }),
+            json!({ "name": "iPad Air", "udid": "SIM-2", "state": "Shutdown", "runtime": "iOS 17.2" }),
+        ];
+        assert_eq!(
+            strip_ansi(&format_device_list(&devices)),
+            "Connected Devices:\n\n  1. ● My iPhone (17.1)\n     REAL-1\n\n\
+             Simulators:\n\n  2. ● iPhone 15 Pro (iOS 17.2)\n     SIM-1\n  3. ○ iPad Air (iOS 17.2)\n     SIM-2\n"
+        );
+    }
+
+    #[test]
+    fn test_device_list_accepts_plain_names() {
+        let devices = vec![json!("iPhone 15"), json!("iPhone SE")];
+        assert_eq!(
+            format_device_list(&devices),
+            "Simulators:\n\n  1. iPhone 15\n  2. iPhone SE\n"
+        );
+    }
+
+    #[test]
+    fn test_device_list_empty() {
+        assert!(format_device_list(&[]).starts_with("No iOS devices available"));
+    }
+
+    #[test]
+    fn test_print_response_device_list_is_not_done() {
+        let resp = Response {
+            success: true,
+            data: Some(
+                json!({ "devices": [{ "name": "iPhone 15", "udid": "U1", "runtime": "iOS 17.0" }] }),
+            ),
+            error: None,
+        };
+        start_capture();
+        print_response(&resp, OutputFormat::Human, false, Some("device_list"));
+        let out = strip_ansi(&take_capture().unwrap());
+        assert!(out.contains("1. ○ iPhone 15 (iOS 17.0)"));
+        assert!(!out.contains("Done"));
+    }
+
+    #[test]
+    fn test_device_info() {
+        let info = json!({ "name": "iPhone 15", "udid": "ABC-123", "osVersion": "17.2", "state": "Booted" });
+        assert_eq!(
+            format_device_info(&info),
+            "Name:  iPhone 15\nUDID:  ABC-123\nOS:    17.2\nState: Booted\n"
+        );
+    }
+
+    #[test]
+    fn test_device_info_falls_back_to_runtime() {
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "deviceInfo": { "name": "iPad", "runtime": "iOS 16.4" } })),
+            error: None,
+        };
+        start_capture();
+        print_response(&resp, OutputFormat::Human, false, Some("device_info"));
+        assert_eq!(
+            take_capture().unwrap(),
+            "Name:  iPad\nUDID:  -\nOS:    iOS 16.4\nState: -\n"
+        );
+    }
+
+    #[test]
+    fn test_print_response_texts_one_per_line() {
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "texts": ["Apple", "Banana", "Cherry"] })),
+            error: None,
+        };
+        start_capture();
+        print_response(&resp, OutputFormat::Human, false, Some("alltext"));
+        assert_eq!(take_capture().unwrap(), "Apple\nBanana\nCherry\n");
+    }
+
+    #[test]
+    fn test_print_response_texts_empty_and_null_entries() {
+        let resp = Response {
+            success: true,
+            data: Some(json!({ "texts": ["First", null, ""] })),
+            error: None,
+        };
+        start_capture();
+        print_response(&resp, OutputFormat::Human, false, Some("alltext"));
+        assert_eq!(take_capture().unwrap(), "First\n\n\n");
+
+        let empty = Response {
+            success: true,
+            data: Some(json!({ "texts": [] })),
+            error: None,
+        };
+        start_capture();
+        print_response(&empty, OutputFormat::Human, false, Some("alltext"));
+        assert_eq!(take_capture().unwrap(), "");
+    }
+
+    #[test]
+    fn test_print_stream_event_log_and_page_error() {
+        let log = json!({ "type": "warn", "text": "Deprecated API" });
+        let error = json!({ "message": "x is not defined" });
+        start_capture();
+        print_stream_event(&log, "log", OutputFormat::Ndjson);
+        print_stream_event(&error, "page_error", OutputFormat::Ndjson);
+        print_stream_event(&log, "log", OutputFormat::Human);
+        print_stream_event(&error, "page_error", OutputFormat::Human);
+        let captured = strip_ansi(&take_capture().unwrap());
+        let lines: Vec<&str> = captured.lines().collect();
+        assert_eq!(
+            lines[0],
+            r#"{"level":"warn","text":"Deprecated API","type":"log"}"#
+        );
+        assert_eq!(
+            lines[1],
+            r#"{"message":"x is not defined","type":"page_error"}"#
+        );
+        assert!(lines[2].ends_with("Deprecated API"));
+        assert!(lines[3].ends_with("x is not defined"));
+    }
+
+    #[test]
+    fn test_print_response_dialog() {
+        let resp = |data: serde_json::Value| Response {
+            success: true,
+            data: Some(data),
+            error: None,
+        };
+        start_capture();
+        for data in [
+            json!({ "dialog": { "type": "confirm", "message": "Delete item 3?" } }),
+            json!({ "dialog": { "type": "prompt", "message": "Name?", "defaultValue": "guest" } }),
+            json!({ "dialog": null }),
+        ] {
+            print_response(&resp(data), OutputFormat::Human, false, Some("dialog_get"));
+        }
+        assert_eq!(
+            take_capture().unwrap(),
+            "confirm: Delete item 3?\nprompt: Name?\nDefault value: guest\nNo dialog is open\n"
+        );
+    }
+
+    #[test]
+    fn test_print_response_mock_rules_count() {
+        let resp = |data: serde_json::Value| Response {
+            success: true,
+            data: Some(data),
+            error: None,
+        };
+        start_capture();
+        for data in [
+            json!({ "mockRules": 3 }),
+            json!({ "mockRules": [{ "url": "**/api" }] }),
+            json!({ "mockRules": [] }),
+        ] {
+            print_response(&resp(data), OutputFormat::Human, false, Some("mock_status"));
+        }
+        assert_eq!(
+            take_capture().unwrap(),
+            "3 active mock rules\n1 active mock rule\n0 active mock rules\n"
+        );
+    }
+
+    #[test]
+    fn test_print_response_performance_metrics() {
+        let resp = |data: serde_json::Value| Response {
+            success: true,
+            data: Some(data),
+            error: None,
+        };
+        start_capture();
+        print_response(
+            &resp(json!({ "metrics": { "lcp": 1234.56, "cls": 0.0512, "fid": null, "ttfb": 80 } })),
+            OutputFormat::Human,
+            false,
+            Some("performance_metrics"),
+        );
+        print_response(
+            &resp(json!({ "metrics": {} })),
+            OutputFormat::Human,
+            false,
+            Some("performance_metrics"),
+        );
+        assert_eq!(
+            take_capture().unwrap(),
+            "cls: 0.051\nfid: n/a\nlcp: 1234.6 ms\nttfb: 80.0 ms\nNo performance metrics\n"
+        );
+    }
+
+    #[test]
+    fn test_print_response_aria_value_and_object() {
+        let resp = |data: serde_json::Value| Response {
+            success: true,
+            data: Some(data),
+            error: None,
+        };
+        start_capture();
+        print_response(
+            &resp(json!({ "attribute": "aria-expanded", "aria": "true" })),
+            OutputFormat::Human,
+            false,
+            Some("getaria"),
+        );
+        print_response(
+            &resp(json!({ "aria": { "aria-label": "Menu", "aria-expanded": false } })),
+            OutputFormat::Human,
+            false,
+            Some("getaria"),
+        );
+        print_response(
+            &resp(json!({ "aria": {} })),
+            OutputFormat::Human,
+            false,
+            Some("getaria"),
+        );
+        assert_eq!(
+            take_capture().unwrap(),
+            "true\naria-expanded: false\naria-label: Menu\nNo ARIA attributes\n"
+        );
+    }
 }